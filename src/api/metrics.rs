@@ -213,6 +213,56 @@ pub async fn metrics_handler(State(state): State<AppState>) -> impl IntoResponse
         }
     }
 
+    writeln!(out, "# TYPE hls_monitor_live_edge_latency_seconds gauge").unwrap();
+    writeln!(
+        out,
+        "# HELP hls_monitor_live_edge_latency_seconds Time between the live edge PDT and the last fetch per variant"
+    )
+    .unwrap();
+    for (id, m) in &monitors {
+        for stream in m.get_stream_status().await {
+            for v in &stream.variants {
+                if let Some(latency) = v.live_edge_latency_secs {
+                    writeln!(
+                        out,
+                        "hls_monitor_live_edge_latency_seconds{{monitor_id=\"{}\",stream_id=\"{}\",variant=\"{}\",media_type=\"{}\"}} {:.3}",
+                        id,
+                        escape_label_value(&stream.stream_id),
+                        escape_label_value(&v.variant_key),
+                        escape_label_value(&v.media_type),
+                        latency
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
+    writeln!(out, "# TYPE hls_monitor_clock_drift_ms_per_hour gauge").unwrap();
+    writeln!(
+        out,
+        "# HELP hls_monitor_clock_drift_ms_per_hour Encoder clock drift of PDT against EXTINF per variant"
+    )
+    .unwrap();
+    for (id, m) in &monitors {
+        for stream in m.get_stream_status().await {
+            for v in &stream.variants {
+                if let Some(drift) = v.clock_drift_ms_per_hour {
+                    writeln!(
+                        out,
+                        "hls_monitor_clock_drift_ms_per_hour{{monitor_id=\"{}\",stream_id=\"{}\",variant=\"{}\",media_type=\"{}\"}} {:.3}",
+                        id,
+                        escape_label_value(&stream.stream_id),
+                        escape_label_value(&v.variant_key),
+                        escape_label_value(&v.media_type),
+                        drift
+                    )
+                    .unwrap();
+                }
+            }
+        }
+    }
+
     writeln!(out, "# EOF").unwrap();
 
     (
@@ -1,3 +1,4 @@
+use std::collections::BTreeMap;
 use std::sync::Arc;
 
 use axum::extract::{Path, Query, State};
@@ -8,7 +9,9 @@ use axum::{Json, Router};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::{HttpLoader, Monitor, MonitorConfig, MonitorEvent, StreamItem, StreamStatus};
+use crate::{
+    AdDialect, HttpLoader, Monitor, MonitorConfig, MonitorEvent, PollMode, ProbeMethod, StreamItem, StreamStatus,
+};
 
 const MAX_STREAMS_PER_MONITOR: usize = 100;
 
@@ -51,18 +54,42 @@ pub struct CreateMonitorRequest {
     pub streams: Vec<StreamInput>,
     pub stale_limit: Option<u64>,
     pub poll_interval: Option<u64>,
+    pub poll_mode: Option<PollMode>,
     #[serde(default)]
     pub scte35: bool,
+    pub ad_dialect: Option<AdDialect>,
     pub target_duration_tolerance: Option<f64>,
     pub mseq_gap_threshold: Option<u64>,
     pub variant_sync_drift_threshold: Option<u64>,
+    pub variant_pdt_drift_threshold: Option<f64>,
+    pub max_live_latency: Option<f64>,
+    pub clock_drift_threshold: Option<f64>,
     pub variant_failure_threshold: Option<u32>,
     pub segment_duration_anomaly_ratio: Option<f64>,
     pub max_concurrent_fetches: Option<usize>,
+    pub max_concurrent_streams: Option<usize>,
     #[serde(default)]
     pub spec_stale: bool,
     #[serde(default)]
     pub authoring_spec: bool,
+    #[serde(default)]
+    pub probe_segments: bool,
+    pub probe_method: Option<ProbeMethod>,
+    pub probe_newest: Option<usize>,
+    pub probe_fraction: Option<f64>,
+    #[serde(default)]
+    pub bitrate_check: bool,
+    pub bitrate_margin: Option<f64>,
+    #[serde(default)]
+    pub segment_inspection: bool,
+    pub timestamp_tolerance: Option<f64>,
+    pub ad_break_tolerance: Option<f64>,
+    #[serde(default)]
+    pub key_check: bool,
+    #[serde(default)]
+    pub key_headers: BTreeMap<String, String>,
+    #[serde(default)]
+    pub interstitial_fetch: bool,
 }
 
 #[derive(Serialize)]
@@ -71,6 +98,7 @@ pub struct CreateMonitorResponse {
     pub streams: Vec<StreamItem>,
     pub stale_limit_ms: u64,
     pub poll_interval_ms: u64,
+    pub poll_mode: PollMode,
     pub scte35: bool,
     pub spec_stale: bool,
     pub authoring_spec: bool,
@@ -94,6 +122,7 @@ pub struct MonitorDetail {
     pub streams: Vec<StreamItem>,
     pub stale_limit_ms: u64,
     pub poll_interval_ms: u64,
+    pub poll_mode: PollMode,
     pub scte35: bool,
     pub spec_stale: bool,
     pub authoring_spec: bool,
@@ -241,12 +270,18 @@ async fn create_monitor(
 
     let config = {
         let mut c = MonitorConfig::default().with_scte35(body.scte35);
+        if let Some(v) = body.ad_dialect {
+            c = c.with_ad_dialect(v);
+        }
         if let Some(sl) = body.stale_limit {
             c = c.with_stale_limit(sl);
         }
         if let Some(pi) = body.poll_interval {
             c = c.with_poll_interval(pi);
         }
+        if let Some(mode) = body.poll_mode {
+            c = c.with_poll_mode(mode);
+        }
         if let Some(v) = body.target_duration_tolerance {
             c = c.with_target_duration_tolerance(v);
         }
@@ -256,6 +291,15 @@ async fn create_monitor(
         if let Some(v) = body.variant_sync_drift_threshold {
             c = c.with_variant_sync_drift_threshold(v);
         }
+        if let Some(v) = body.variant_pdt_drift_threshold {
+            c = c.with_variant_pdt_drift_threshold(v);
+        }
+        if let Some(v) = body.max_live_latency {
+            c = c.with_max_live_latency(v);
+        }
+        if let Some(v) = body.clock_drift_threshold {
+            c = c.with_clock_drift_threshold(v);
+        }
         if let Some(v) = body.variant_failure_threshold {
             c = c.with_variant_failure_threshold(v);
         }
@@ -265,17 +309,57 @@ async fn create_monitor(
         if let Some(v) = body.max_concurrent_fetches {
             c = c.with_max_concurrent_fetches(v);
         }
+        if let Some(v) = body.max_concurrent_streams {
+            c = c.with_max_concurrent_streams(v);
+        }
         if body.spec_stale {
             c = c.with_spec_stale(true);
         }
         if body.authoring_spec {
             c = c.with_authoring_spec(true);
         }
+        if body.probe_segments {
+            c = c.with_probe_segments(true);
+        }
+        if let Some(v) = body.probe_method {
+            c = c.with_probe_method(v);
+        }
+        if let Some(v) = body.probe_newest {
+            c = c.with_probe_newest(v);
+        }
+        if let Some(v) = body.probe_fraction {
+            c = c.with_probe_fraction(v);
+        }
+        if body.bitrate_check {
+            c = c.with_bitrate_check(true);
+        }
+        if let Some(v) = body.bitrate_margin {
+            c = c.with_bitrate_margin(v);
+        }
+        if body.segment_inspection {
+            c = c.with_segment_inspection(true);
+        }
+        if let Some(v) = body.timestamp_tolerance {
+            c = c.with_timestamp_tolerance(v);
+        }
+        if let Some(v) = body.ad_break_tolerance {
+            c = c.with_ad_break_tolerance(v);
+        }
+        if body.key_check {
+            c = c.with_key_check(true);
+        }
+        if body.interstitial_fetch {
+            c = c.with_interstitial_fetch(true);
+        }
+        for (name, value) in &body.key_headers {
+            c = c.with_key_header(name, value);
+        }
         c
     };
 
     let stale_limit_ms = config.stale_limit.as_millis() as u64;
     let poll_interval_ms = config.poll_interval.as_millis() as u64;
+    let poll_mode = config.poll_mode;
 
     let items: Vec<StreamItem> = body
         .streams
@@ -295,6 +379,7 @@ async fn create_monitor(
         streams: items,
         stale_limit_ms,
         poll_interval_ms,
+        poll_mode,
         scte35: body.scte35,
         spec_stale: body.spec_stale,
         authoring_spec: body.authoring_spec,
@@ -341,6 +426,7 @@ async fn get_monitor(
         streams: m.streams().await,
         stale_limit_ms: m.config().stale_limit.as_millis() as u64,
         poll_interval_ms: m.config().poll_interval.as_millis() as u64,
+        poll_mode: m.config().poll_mode,
         scte35: m.config().scte35_enabled,
         spec_stale: m.config().spec_stale,
         authoring_spec: m.config().authoring_spec,
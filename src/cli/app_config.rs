@@ -25,12 +25,13 @@
 //! ]
 //! ```
 
+use std::collections::BTreeMap;
 use std::net::SocketAddr;
 use std::path::Path;
 
 use serde::Deserialize;
 
-use crate::{MonitorConfig, StreamItem, WebhookConfig};
+use crate::{AdDialect, MonitorConfig, PollMode, ProbeMethod, StreamItem, WebhookConfig};
 
 #[derive(Debug, Clone, Deserialize)]
 #[serde(deny_unknown_fields)]
@@ -88,9 +89,15 @@ pub struct DefaultsConfig {
     #[serde(default)]
     pub poll_interval_ms: Option<u64>,
 
+    #[serde(default)]
+    pub poll_mode: Option<PollMode>,
+
     #[serde(default)]
     pub scte35: bool,
 
+    #[serde(default)]
+    pub ad_dialect: Option<AdDialect>,
+
     #[serde(default = "default_error_limit")]
     pub error_limit: usize,
 
@@ -106,6 +113,15 @@ pub struct DefaultsConfig {
     #[serde(default)]
     pub variant_sync_drift_threshold: Option<u64>,
 
+    #[serde(default)]
+    pub variant_pdt_drift_threshold: Option<f64>,
+
+    #[serde(default)]
+    pub max_live_latency: Option<f64>,
+
+    #[serde(default)]
+    pub clock_drift_threshold: Option<f64>,
+
     #[serde(default)]
     pub variant_failure_threshold: Option<u32>,
 
@@ -115,11 +131,50 @@ pub struct DefaultsConfig {
     #[serde(default)]
     pub max_concurrent_fetches: Option<usize>,
 
+    #[serde(default)]
+    pub max_concurrent_streams: Option<usize>,
+
     #[serde(default)]
     pub spec_stale: bool,
 
     #[serde(default)]
     pub authoring_spec: bool,
+
+    #[serde(default)]
+    pub probe_segments: bool,
+
+    #[serde(default)]
+    pub probe_method: Option<ProbeMethod>,
+
+    #[serde(default)]
+    pub probe_newest: Option<usize>,
+
+    #[serde(default)]
+    pub probe_fraction: Option<f64>,
+
+    #[serde(default)]
+    pub bitrate_check: bool,
+
+    #[serde(default)]
+    pub bitrate_margin: Option<f64>,
+
+    #[serde(default)]
+    pub segment_inspection: bool,
+
+    #[serde(default)]
+    pub timestamp_tolerance: Option<f64>,
+
+    #[serde(default)]
+    pub ad_break_tolerance: Option<f64>,
+
+    #[serde(default)]
+    pub key_check: bool,
+
+    #[serde(default)]
+    pub key_headers: BTreeMap<String, String>,
+
+    #[serde(default)]
+    pub interstitial_fetch: bool,
 }
 
 impl Default for DefaultsConfig {
@@ -127,17 +182,35 @@ impl Default for DefaultsConfig {
         Self {
             stale_limit_ms: default_stale_limit_ms(),
             poll_interval_ms: None,
+            poll_mode: None,
             scte35: false,
+            ad_dialect: None,
             error_limit: default_error_limit(),
             event_limit: default_event_limit(),
             target_duration_tolerance: None,
             mseq_gap_threshold: None,
             variant_sync_drift_threshold: None,
+            variant_pdt_drift_threshold: None,
+            max_live_latency: None,
+            clock_drift_threshold: None,
             variant_failure_threshold: None,
             segment_duration_anomaly_ratio: None,
             max_concurrent_fetches: None,
+            max_concurrent_streams: None,
             spec_stale: false,
             authoring_spec: false,
+            probe_segments: false,
+            probe_method: None,
+            probe_newest: None,
+            probe_fraction: None,
+            bitrate_check: false,
+            bitrate_margin: None,
+            segment_inspection: false,
+            timestamp_tolerance: None,
+            ad_break_tolerance: None,
+            key_check: false,
+            key_headers: BTreeMap::new(),
+            interstitial_fetch: false,
         }
     }
 }
@@ -161,9 +234,15 @@ impl DefaultsConfig {
             .with_scte35(self.scte35)
             .with_error_limit(self.error_limit)
             .with_event_limit(self.event_limit);
+        if let Some(v) = self.ad_dialect {
+            c = c.with_ad_dialect(v);
+        }
         if let Some(pi) = self.poll_interval_ms {
             c = c.with_poll_interval(pi);
         }
+        if let Some(mode) = self.poll_mode {
+            c = c.with_poll_mode(mode);
+        }
         if let Some(v) = self.target_duration_tolerance {
             c = c.with_target_duration_tolerance(v);
         }
@@ -173,6 +252,15 @@ impl DefaultsConfig {
         if let Some(v) = self.variant_sync_drift_threshold {
             c = c.with_variant_sync_drift_threshold(v);
         }
+        if let Some(v) = self.variant_pdt_drift_threshold {
+            c = c.with_variant_pdt_drift_threshold(v);
+        }
+        if let Some(v) = self.max_live_latency {
+            c = c.with_max_live_latency(v);
+        }
+        if let Some(v) = self.clock_drift_threshold {
+            c = c.with_clock_drift_threshold(v);
+        }
         if let Some(v) = self.variant_failure_threshold {
             c = c.with_variant_failure_threshold(v);
         }
@@ -182,12 +270,51 @@ impl DefaultsConfig {
         if let Some(v) = self.max_concurrent_fetches {
             c = c.with_max_concurrent_fetches(v);
         }
+        if let Some(v) = self.max_concurrent_streams {
+            c = c.with_max_concurrent_streams(v);
+        }
         if self.spec_stale {
             c = c.with_spec_stale(true);
         }
         if self.authoring_spec {
             c = c.with_authoring_spec(true);
         }
+        if self.probe_segments {
+            c = c.with_probe_segments(true);
+        }
+        if let Some(v) = self.probe_method {
+            c = c.with_probe_method(v);
+        }
+        if let Some(v) = self.probe_newest {
+            c = c.with_probe_newest(v);
+        }
+        if let Some(v) = self.probe_fraction {
+            c = c.with_probe_fraction(v);
+        }
+        if self.bitrate_check {
+            c = c.with_bitrate_check(true);
+        }
+        if let Some(v) = self.bitrate_margin {
+            c = c.with_bitrate_margin(v);
+        }
+        if self.segment_inspection {
+            c = c.with_segment_inspection(true);
+        }
+        if let Some(v) = self.timestamp_tolerance {
+            c = c.with_timestamp_tolerance(v);
+        }
+        if let Some(v) = self.ad_break_tolerance {
+            c = c.with_ad_break_tolerance(v);
+        }
+        if self.key_check {
+            c = c.with_key_check(true);
+        }
+        if self.interstitial_fetch {
+            c = c.with_interstitial_fetch(true);
+        }
+        for (name, value) in &self.key_headers {
+            c = c.with_key_header(name, value);
+        }
         c
     }
 }
@@ -198,15 +325,34 @@ pub struct MonitorDef {
     pub id: String,
     pub stale_limit_ms: Option<u64>,
     pub poll_interval_ms: Option<u64>,
+    pub poll_mode: Option<PollMode>,
     pub scte35: Option<bool>,
+    pub ad_dialect: Option<AdDialect>,
     pub target_duration_tolerance: Option<f64>,
     pub mseq_gap_threshold: Option<u64>,
     pub variant_sync_drift_threshold: Option<u64>,
+    pub variant_pdt_drift_threshold: Option<f64>,
+    pub max_live_latency: Option<f64>,
+    pub clock_drift_threshold: Option<f64>,
     pub variant_failure_threshold: Option<u32>,
     pub segment_duration_anomaly_ratio: Option<f64>,
     pub max_concurrent_fetches: Option<usize>,
+    pub max_concurrent_streams: Option<usize>,
     pub spec_stale: Option<bool>,
     pub authoring_spec: Option<bool>,
+    pub probe_segments: Option<bool>,
+    pub probe_method: Option<ProbeMethod>,
+    pub probe_newest: Option<usize>,
+    pub probe_fraction: Option<f64>,
+    pub bitrate_check: Option<bool>,
+    pub bitrate_margin: Option<f64>,
+    pub segment_inspection: Option<bool>,
+    pub timestamp_tolerance: Option<f64>,
+    pub ad_break_tolerance: Option<f64>,
+    pub key_check: Option<bool>,
+    /// Added to (and overriding) `[defaults] key_headers`.
+    pub key_headers: Option<BTreeMap<String, String>>,
+    pub interstitial_fetch: Option<bool>,
 
     #[serde(default)]
     pub streams: Vec<StreamDef>,
@@ -228,9 +374,15 @@ impl MonitorDef {
         if let Some(pi) = self.poll_interval_ms {
             c = c.with_poll_interval(pi);
         }
+        if let Some(mode) = self.poll_mode {
+            c = c.with_poll_mode(mode);
+        }
         if let Some(scte) = self.scte35 {
             c = c.with_scte35(scte);
         }
+        if let Some(v) = self.ad_dialect {
+            c = c.with_ad_dialect(v);
+        }
         if let Some(v) = self.target_duration_tolerance {
             c = c.with_target_duration_tolerance(v);
         }
@@ -240,6 +392,15 @@ impl MonitorDef {
         if let Some(v) = self.variant_sync_drift_threshold {
             c = c.with_variant_sync_drift_threshold(v);
         }
+        if let Some(v) = self.variant_pdt_drift_threshold {
+            c = c.with_variant_pdt_drift_threshold(v);
+        }
+        if let Some(v) = self.max_live_latency {
+            c = c.with_max_live_latency(v);
+        }
+        if let Some(v) = self.clock_drift_threshold {
+            c = c.with_clock_drift_threshold(v);
+        }
         if let Some(v) = self.variant_failure_threshold {
             c = c.with_variant_failure_threshold(v);
         }
@@ -249,12 +410,51 @@ impl MonitorDef {
         if let Some(v) = self.max_concurrent_fetches {
             c = c.with_max_concurrent_fetches(v);
         }
+        if let Some(v) = self.max_concurrent_streams {
+            c = c.with_max_concurrent_streams(v);
+        }
         if let Some(v) = self.spec_stale {
             c = c.with_spec_stale(v);
         }
         if let Some(v) = self.authoring_spec {
             c = c.with_authoring_spec(v);
         }
+        if let Some(v) = self.probe_segments {
+            c = c.with_probe_segments(v);
+        }
+        if let Some(v) = self.probe_method {
+            c = c.with_probe_method(v);
+        }
+        if let Some(v) = self.probe_newest {
+            c = c.with_probe_newest(v);
+        }
+        if let Some(v) = self.probe_fraction {
+            c = c.with_probe_fraction(v);
+        }
+        if let Some(v) = self.bitrate_check {
+            c = c.with_bitrate_check(v);
+        }
+        if let Some(v) = self.bitrate_margin {
+            c = c.with_bitrate_margin(v);
+        }
+        if let Some(v) = self.segment_inspection {
+            c = c.with_segment_inspection(v);
+        }
+        if let Some(v) = self.timestamp_tolerance {
+            c = c.with_timestamp_tolerance(v);
+        }
+        if let Some(v) = self.ad_break_tolerance {
+            c = c.with_ad_break_tolerance(v);
+        }
+        if let Some(v) = self.key_check {
+            c = c.with_key_check(v);
+        }
+        if let Some(v) = self.interstitial_fetch {
+            c = c.with_interstitial_fetch(v);
+        }
+        for (name, value) in self.key_headers.iter().flatten() {
+            c = c.with_key_header(name, value);
+        }
         c
     }
 
@@ -387,6 +587,7 @@ streams = [
 [[monitor]]
 id = "channel-2"
 scte35 = false
+poll_mode = "adaptive"
 streams = [
   { url = "https://cdn3.example.com/master.m3u8" },
 ]
@@ -415,6 +616,8 @@ streams = [
 
         let m2_config = config.monitor[1].to_monitor_config(&config.defaults);
         assert!(!m2_config.scte35_enabled);
+        assert_eq!(m2_config.poll_mode, PollMode::Adaptive);
+        assert_eq!(m1_config.poll_mode, PollMode::Fixed);
     }
 
     #[test]
@@ -10,8 +10,8 @@ use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use tracing_subscriber::{fmt, EnvFilter};
 
 use crate::{
-    notification_channel, EventKind, HttpLoader, Monitor, MonitorConfig, MonitorError, StreamItem,
-    WebhookDispatcher,
+    notification_channel, AdDialect, EventKind, HttpLoader, Monitor, MonitorConfig, MonitorError,
+    PollMode, ProbeMethod, StreamItem, WebhookDispatcher,
 };
 
 fn version_string() -> &'static str {
@@ -40,6 +40,10 @@ struct CheckArgs {
     #[arg(long, default_value_t = false)]
     scte35: bool,
 
+    /// Ad marker dialect: "auto", "cue", "oatcls", "scte35" or "daterange" [default: auto].
+    #[arg(long)]
+    ad_dialect: Option<AdDialect>,
+
     /// HTTP request timeout in milliseconds [default: 10000].
     #[arg(long)]
     request_timeout: Option<u64>,
@@ -56,6 +60,18 @@ struct CheckArgs {
     #[arg(long)]
     variant_sync_drift_threshold: Option<u64>,
 
+    /// Max seconds between the PDTs variants give the same media sequence [default: 0.5].
+    #[arg(long)]
+    variant_pdt_drift_threshold: Option<f64>,
+
+    /// Max seconds the live edge (last PDT plus segment durations) may lag the wall clock [default: unchecked].
+    #[arg(long)]
+    max_live_latency: Option<f64>,
+
+    /// Max encoder clock drift, PDT against EXTINF, in ms per hour [default: 1000].
+    #[arg(long)]
+    clock_drift_threshold: Option<f64>,
+
     /// Consecutive fetch failures before a variant is reported unavailable [default: 3].
     #[arg(long)]
     variant_failure_threshold: Option<u32>,
@@ -75,11 +91,62 @@ struct CheckArgs {
     /// Enable Apple HLS Authoring Specification checks.
     #[arg(long, default_value_t = false)]
     authoring_spec: bool,
+
+    /// Probe newly appeared segments for availability.
+    #[arg(long, default_value_t = false)]
+    probe_segments: bool,
+
+    /// Segment probe request: "head" or "ranged_get" [default: head].
+    #[arg(long)]
+    probe_method: Option<ProbeMethod>,
+
+    /// Newest new segments probed per variant and poll [default: 1].
+    #[arg(long)]
+    probe_newest: Option<usize>,
+
+    /// Probe a random fraction (0-1] of new segments instead of the newest.
+    #[arg(long)]
+    probe_fraction: Option<f64>,
+
+    /// Measure segment bitrates against BANDWIDTH / AVERAGE-BANDWIDTH.
+    #[arg(long, default_value_t = false)]
+    bitrate_check: bool,
+
+    /// Fraction a measured bitrate may exceed the advertised one [default: 0.1].
+    #[arg(long)]
+    bitrate_margin: Option<f64>,
+
+    /// Download new segments and check timestamp and continuity-counter continuity.
+    #[arg(long, default_value_t = false)]
+    segment_inspection: bool,
+
+    /// Seconds segment timestamps may deviate from EXTINF [default: 0.1].
+    #[arg(long)]
+    timestamp_tolerance: Option<f64>,
+
+    /// Seconds an ad break may run shorter or longer than announced [default: 1.0].
+    #[arg(long)]
+    ad_break_tolerance: Option<f64>,
+
+    /// Fetch AES-128 keys and check that they are 16 bytes.
+    #[arg(long, default_value_t = false)]
+    key_check: bool,
+
+    /// Header sent with key requests, as "Name: value" (repeatable).
+    #[arg(long = "key-header", value_parser = parse_header)]
+    key_headers: Vec<(String, String)>,
+
+    /// Fetch interstitial asset lists and asset playlists and check that they parse.
+    #[arg(long, default_value_t = false)]
+    interstitial_fetch: bool,
 }
 
 impl CheckArgs {
     fn to_monitor_config(&self) -> MonitorConfig {
         let mut config = MonitorConfig::default().with_scte35(self.scte35);
+        if let Some(v) = self.ad_dialect {
+            config = config.with_ad_dialect(v);
+        }
         if let Some(ms) = self.request_timeout {
             config.request_timeout = std::time::Duration::from_millis(ms);
         }
@@ -92,6 +159,15 @@ impl CheckArgs {
         if let Some(v) = self.variant_sync_drift_threshold {
             config = config.with_variant_sync_drift_threshold(v);
         }
+        if let Some(v) = self.variant_pdt_drift_threshold {
+            config = config.with_variant_pdt_drift_threshold(v);
+        }
+        if let Some(v) = self.max_live_latency {
+            config = config.with_max_live_latency(v);
+        }
+        if let Some(v) = self.clock_drift_threshold {
+            config = config.with_clock_drift_threshold(v);
+        }
         if let Some(v) = self.variant_failure_threshold {
             config = config.with_variant_failure_threshold(v);
         }
@@ -103,10 +179,43 @@ impl CheckArgs {
         }
         config = config.with_spec_stale(self.spec_stale);
         config = config.with_authoring_spec(self.authoring_spec);
+        config = config.with_probe_segments(self.probe_segments);
+        if let Some(v) = self.probe_method {
+            config = config.with_probe_method(v);
+        }
+        if let Some(v) = self.probe_newest {
+            config = config.with_probe_newest(v);
+        }
+        if let Some(v) = self.probe_fraction {
+            config = config.with_probe_fraction(v);
+        }
+        config = config.with_bitrate_check(self.bitrate_check);
+        if let Some(v) = self.bitrate_margin {
+            config = config.with_bitrate_margin(v);
+        }
+        config = config.with_segment_inspection(self.segment_inspection);
+        if let Some(v) = self.timestamp_tolerance {
+            config = config.with_timestamp_tolerance(v);
+        }
+        if let Some(v) = self.ad_break_tolerance {
+            config = config.with_ad_break_tolerance(v);
+        }
+        config = config.with_key_check(self.key_check);
+        for (name, value) in &self.key_headers {
+            config = config.with_key_header(name, value);
+        }
+        config = config.with_interstitial_fetch(self.interstitial_fetch);
         config
     }
 }
 
+fn parse_header(s: &str) -> Result<(String, String), String> {
+    match s.split_once(':') {
+        Some((name, value)) if !name.trim().is_empty() => Ok((name.trim().to_string(), value.trim().to_string())),
+        _ => Err(format!("expected \"Name: value\", got {:?}", s)),
+    }
+}
+
 #[derive(Subcommand)]
 enum Commands {
     /// Start the HTTP API server.
@@ -144,6 +253,10 @@ enum Commands {
         #[arg(long)]
         poll_interval: Option<u64>,
 
+        /// Reload scheduling: "fixed" (poll interval), "adaptive" (target duration) or "blocking" (LL-HLS _HLS_msn/_HLS_part).
+        #[arg(long, default_value_t = PollMode::Fixed)]
+        poll_mode: PollMode,
+
         /// Optional webhook URL to POST notifications to.
         #[arg(long)]
         webhook_url: Option<String>,
@@ -172,6 +285,7 @@ pub async fn run() {
             url,
             stale_limit,
             poll_interval,
+            poll_mode,
             webhook_url,
             checks,
         } => {
@@ -180,7 +294,7 @@ pub async fn run() {
                     EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("warn")),
                 )
                 .init();
-            run_watch(url, stale_limit, poll_interval, webhook_url, checks).await;
+            run_watch(url, stale_limit, poll_interval, poll_mode, webhook_url, checks).await;
         }
     }
 }
@@ -356,11 +470,15 @@ async fn run_watch(
     url: String,
     stale_limit: u64,
     poll_interval: Option<u64>,
+    poll_mode: PollMode,
     webhook_url: Option<String>,
     checks: CheckArgs,
 ) {
     let config = {
-        let mut c = checks.to_monitor_config().with_stale_limit(stale_limit);
+        let mut c = checks
+            .to_monitor_config()
+            .with_stale_limit(stale_limit)
+            .with_poll_mode(poll_mode);
         if let Some(pi) = poll_interval {
             c = c.with_poll_interval(pi);
         }
@@ -412,8 +530,12 @@ async fn run_watch(
             style(&url).bold()
         ))
         .ok();
+    let poll_desc = match poll_mode {
+        PollMode::Fixed => format!("{}ms", poll_ms),
+        PollMode::Adaptive | PollMode::Blocking => poll_mode.to_string(),
+    };
     multi
-        .println(format!("  {} {}ms", style("poll:  ").dim(), poll_ms))
+        .println(format!("  {} {}", style("poll:  ").dim(), poll_desc))
         .ok();
     multi
         .println(format!("  {} {}ms", style("stale: ").dim(), stale_limit))
@@ -1,7 +1,134 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::str::FromStr;
 use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
+/// How a monitor schedules playlist reloads for each stream.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PollMode {
+    /// Reload every `poll_interval` with ±1/7 jitter.
+    #[default]
+    Fixed,
+    /// Reload once per EXT-X-TARGETDURATION, or half of it after an unchanged
+    /// reload (RFC 8216 §6.3.4).
+    Adaptive,
+    /// LL-HLS blocking reloads: request the next expected segment/part with
+    /// `_HLS_msn`/`_HLS_part` and let the origin hold the request.
+    Blocking,
+}
+
+impl fmt::Display for PollMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Fixed => write!(f, "fixed"),
+            Self::Adaptive => write!(f, "adaptive"),
+            Self::Blocking => write!(f, "blocking"),
+        }
+    }
+}
+
+impl FromStr for PollMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "fixed" => Ok(Self::Fixed),
+            "adaptive" => Ok(Self::Adaptive),
+            "blocking" => Ok(Self::Blocking),
+            other => Err(format!(
+                "invalid poll mode '{}': must be 'fixed', 'adaptive' or 'blocking'",
+                other
+            )),
+        }
+    }
+}
+
+/// Request used to check that a segment is available on the origin.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ProbeMethod {
+    /// `HEAD` request; the origin must answer 200.
+    #[default]
+    Head,
+    /// `GET` with `Range: bytes=0-0`; the origin may answer 200 or 206.
+    RangedGet,
+}
+
+impl fmt::Display for ProbeMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Head => write!(f, "head"),
+            Self::RangedGet => write!(f, "ranged_get"),
+        }
+    }
+}
+
+impl FromStr for ProbeMethod {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "head" => Ok(Self::Head),
+            "ranged_get" => Ok(Self::RangedGet),
+            other => Err(format!(
+                "invalid probe method '{}': must be 'head' or 'ranged_get'",
+                other
+            )),
+        }
+    }
+}
+
+/// Packager dialect used to signal ad breaks in media playlists.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AdDialect {
+    /// Recognize every dialect below.
+    #[default]
+    Auto,
+    /// `EXT-X-CUE-OUT` / `EXT-X-CUE-OUT-CONT` / `EXT-X-CUE-IN` (Elemental).
+    Cue,
+    /// `EXT-OATCLS-SCTE35` with `EXT-X-ASSET`; direction and duration come
+    /// from the SCTE-35 payload.
+    Oatcls,
+    /// `EXT-X-SCTE35` with `CUE-OUT` / `CUE-IN` attributes.
+    Scte35,
+    /// `EXT-X-DATERANGE` with `SCTE35-OUT` / `SCTE35-IN`.
+    Daterange,
+}
+
+impl fmt::Display for AdDialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Auto => write!(f, "auto"),
+            Self::Cue => write!(f, "cue"),
+            Self::Oatcls => write!(f, "oatcls"),
+            Self::Scte35 => write!(f, "scte35"),
+            Self::Daterange => write!(f, "daterange"),
+        }
+    }
+}
+
+impl FromStr for AdDialect {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Self::Auto),
+            "cue" => Ok(Self::Cue),
+            "oatcls" => Ok(Self::Oatcls),
+            "scte35" => Ok(Self::Scte35),
+            "daterange" => Ok(Self::Daterange),
+            other => Err(format!(
+                "invalid ad dialect '{}': must be 'auto', 'cue', 'oatcls', 'scte35' or 'daterange'",
+                other
+            )),
+        }
+    }
+}
+
 /// Configuration for an HLS monitor instance.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitorConfig {
@@ -9,6 +136,8 @@ pub struct MonitorConfig {
     pub stale_limit: Duration,
     /// Polling interval between manifest fetches (default: stale_limit / 2).
     pub poll_interval: Duration,
+    /// How reloads are scheduled per stream (default: fixed `poll_interval`).
+    pub poll_mode: PollMode,
     /// Maximum number of errors to retain per stream (ring buffer capacity).
     pub error_limit: usize,
     /// HTTP request timeout for manifest fetches.
@@ -19,16 +148,52 @@ pub struct MonitorConfig {
     pub retry_backoff: Duration,
     /// Whether to enable SCTE-35/CUE marker validation.
     pub scte35_enabled: bool,
+    /// Ad marker dialect to recognize (default: all of them).
+    pub ad_dialect: AdDialect,
     /// Maximum number of events to retain per stream (ring buffer capacity).
     pub event_limit: usize,
     pub target_duration_tolerance: f64,
     pub mseq_gap_threshold: u64,
     pub variant_sync_drift_threshold: u64,
+    /// Max seconds between the PDTs variants give the same media sequence (default: 0.5).
+    pub variant_pdt_drift_threshold: f64,
+    /// Max seconds the live edge may lag the wall clock; unchecked when unset.
+    pub max_live_latency: Option<f64>,
+    /// Max encoder clock drift in ms per hour of PDT (default: 1000).
+    pub clock_drift_threshold: f64,
     pub variant_failure_threshold: u32,
     pub segment_duration_anomaly_ratio: f64,
     pub max_concurrent_fetches: usize,
+    /// Maximum number of streams polled at the same time by one monitor.
+    pub max_concurrent_streams: usize,
     pub spec_stale: bool,
     pub authoring_spec: bool,
+    /// Whether to probe newly appeared segments for availability.
+    pub probe_segments: bool,
+    /// Request used for segment probes (default: HEAD).
+    pub probe_method: ProbeMethod,
+    /// Number of newest new segments probed per variant and poll (default: 1).
+    pub probe_newest: usize,
+    /// Fraction of new segments probed at random; overrides `probe_newest` when set.
+    pub probe_fraction: Option<f64>,
+    /// Whether to measure segment bitrates against BANDWIDTH/AVERAGE-BANDWIDTH.
+    pub bitrate_check: bool,
+    /// Fraction a measured bitrate may exceed the advertised one (default: 0.1).
+    pub bitrate_margin: f64,
+    /// Whether to download new segments and inspect their timestamps.
+    pub segment_inspection: bool,
+    /// Seconds segment timestamps may deviate from EXTINF (default: 0.1).
+    pub timestamp_tolerance: f64,
+    /// Seconds an ad break may run shorter or longer than announced (default: 1.0).
+    pub ad_break_tolerance: f64,
+    /// Whether to fetch AES-128 keys and check that they are 16 bytes.
+    pub key_check: bool,
+    /// Extra request headers for key fetches, e.g. `Authorization`.
+    /// Never serialized, since they usually hold credentials.
+    #[serde(default, skip_serializing)]
+    pub key_headers: BTreeMap<String, String>,
+    /// Whether to fetch interstitial asset lists and asset playlists.
+    pub interstitial_fetch: bool,
 }
 
 impl Default for MonitorConfig {
@@ -37,20 +202,38 @@ impl Default for MonitorConfig {
         Self {
             stale_limit,
             poll_interval: stale_limit / 2,
+            poll_mode: PollMode::Fixed,
             error_limit: 100,
             request_timeout: Duration::from_secs(10),
             max_retries: 3,
             retry_backoff: Duration::from_millis(100),
             scte35_enabled: false,
+            ad_dialect: AdDialect::Auto,
             event_limit: 200,
             target_duration_tolerance: 0.5,
             mseq_gap_threshold: 5,
             variant_sync_drift_threshold: 3,
+            variant_pdt_drift_threshold: 0.5,
+            max_live_latency: None,
+            clock_drift_threshold: 1000.0,
             variant_failure_threshold: 3,
             segment_duration_anomaly_ratio: 0.5,
             max_concurrent_fetches: 4,
+            max_concurrent_streams: 8,
             spec_stale: false,
             authoring_spec: false,
+            probe_segments: false,
+            probe_method: ProbeMethod::Head,
+            probe_newest: 1,
+            probe_fraction: None,
+            bitrate_check: false,
+            bitrate_margin: 0.1,
+            segment_inspection: false,
+            timestamp_tolerance: 0.1,
+            ad_break_tolerance: 1.0,
+            key_check: false,
+            key_headers: BTreeMap::new(),
+            interstitial_fetch: false,
         }
     }
 }
@@ -73,6 +256,11 @@ impl MonitorConfig {
         self
     }
 
+    pub fn with_poll_mode(mut self, mode: PollMode) -> Self {
+        self.poll_mode = mode;
+        self
+    }
+
     pub fn with_error_limit(mut self, limit: usize) -> Self {
         self.error_limit = limit;
         self
@@ -83,6 +271,11 @@ impl MonitorConfig {
         self
     }
 
+    pub fn with_ad_dialect(mut self, dialect: AdDialect) -> Self {
+        self.ad_dialect = dialect;
+        self
+    }
+
     pub fn with_event_limit(mut self, limit: usize) -> Self {
         self.event_limit = limit;
         self
@@ -103,6 +296,21 @@ impl MonitorConfig {
         self
     }
 
+    pub fn with_variant_pdt_drift_threshold(mut self, seconds: f64) -> Self {
+        self.variant_pdt_drift_threshold = seconds.max(0.0);
+        self
+    }
+
+    pub fn with_max_live_latency(mut self, seconds: f64) -> Self {
+        self.max_live_latency = Some(seconds.max(0.0));
+        self
+    }
+
+    pub fn with_clock_drift_threshold(mut self, ms_per_hour: f64) -> Self {
+        self.clock_drift_threshold = ms_per_hour.max(0.0);
+        self
+    }
+
     pub fn with_variant_failure_threshold(mut self, threshold: u32) -> Self {
         self.variant_failure_threshold = threshold;
         self
@@ -118,6 +326,11 @@ impl MonitorConfig {
         self
     }
 
+    pub fn with_max_concurrent_streams(mut self, max: usize) -> Self {
+        self.max_concurrent_streams = max.max(1);
+        self
+    }
+
     pub fn with_spec_stale(mut self, enabled: bool) -> Self {
         self.spec_stale = enabled;
         self
@@ -127,6 +340,66 @@ impl MonitorConfig {
         self.authoring_spec = enabled;
         self
     }
+
+    pub fn with_probe_segments(mut self, enabled: bool) -> Self {
+        self.probe_segments = enabled;
+        self
+    }
+
+    pub fn with_probe_method(mut self, method: ProbeMethod) -> Self {
+        self.probe_method = method;
+        self
+    }
+
+    pub fn with_probe_newest(mut self, count: usize) -> Self {
+        self.probe_newest = count.max(1);
+        self
+    }
+
+    pub fn with_probe_fraction(mut self, fraction: f64) -> Self {
+        self.probe_fraction = Some(fraction.clamp(f64::MIN_POSITIVE, 1.0));
+        self
+    }
+
+    pub fn with_bitrate_check(mut self, enabled: bool) -> Self {
+        self.bitrate_check = enabled;
+        self
+    }
+
+    pub fn with_bitrate_margin(mut self, margin: f64) -> Self {
+        self.bitrate_margin = margin.max(0.0);
+        self
+    }
+
+    pub fn with_segment_inspection(mut self, enabled: bool) -> Self {
+        self.segment_inspection = enabled;
+        self
+    }
+
+    pub fn with_timestamp_tolerance(mut self, seconds: f64) -> Self {
+        self.timestamp_tolerance = seconds.max(0.0);
+        self
+    }
+
+    pub fn with_ad_break_tolerance(mut self, seconds: f64) -> Self {
+        self.ad_break_tolerance = seconds.max(0.0);
+        self
+    }
+
+    pub fn with_key_check(mut self, enabled: bool) -> Self {
+        self.key_check = enabled;
+        self
+    }
+
+    pub fn with_interstitial_fetch(mut self, enabled: bool) -> Self {
+        self.interstitial_fetch = enabled;
+        self
+    }
+
+    pub fn with_key_header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.key_headers.insert(name.into(), value.into());
+        self
+    }
 }
 
 #[cfg(test)]
@@ -166,6 +439,59 @@ mod tests {
         assert_eq!(c.max_concurrent_fetches, 1);
     }
 
+    #[test]
+    fn max_concurrent_streams_clamped_to_at_least_1() {
+        let c = MonitorConfig::default().with_max_concurrent_streams(0);
+        assert_eq!(c.max_concurrent_streams, 1);
+    }
+
+    #[test]
+    fn poll_mode_parses_from_str() {
+        assert_eq!("fixed".parse::<PollMode>(), Ok(PollMode::Fixed));
+        assert_eq!("adaptive".parse::<PollMode>(), Ok(PollMode::Adaptive));
+        assert_eq!("blocking".parse::<PollMode>(), Ok(PollMode::Blocking));
+        assert!("sometimes".parse::<PollMode>().is_err());
+    }
+
+    #[test]
+    fn probe_method_parses_from_str() {
+        assert_eq!("head".parse::<ProbeMethod>(), Ok(ProbeMethod::Head));
+        assert_eq!("ranged_get".parse::<ProbeMethod>(), Ok(ProbeMethod::RangedGet));
+        assert!("options".parse::<ProbeMethod>().is_err());
+    }
+
+    #[test]
+    fn probe_sampling_clamped() {
+        let c = MonitorConfig::default().with_probe_newest(0);
+        assert_eq!(c.probe_newest, 1);
+
+        let c = MonitorConfig::default().with_probe_fraction(2.0);
+        assert_eq!(c.probe_fraction, Some(1.0));
+
+        let c = MonitorConfig::default().with_probe_fraction(-1.0);
+        assert!(c.probe_fraction.is_some_and(|f| f > 0.0));
+    }
+
+    #[test]
+    fn bitrate_margin_clamped_to_non_negative() {
+        let c = MonitorConfig::default().with_bitrate_margin(-0.2);
+        assert_eq!(c.bitrate_margin, 0.0);
+    }
+
+    #[test]
+    fn timestamp_tolerance_clamped_to_non_negative() {
+        let c = MonitorConfig::default().with_timestamp_tolerance(-1.0);
+        assert_eq!(c.timestamp_tolerance, 0.0);
+    }
+
+    #[test]
+    fn key_headers_are_not_serialized() {
+        let c = MonitorConfig::default().with_key_header("Authorization", "Bearer secret");
+        assert_eq!(c.key_headers["Authorization"], "Bearer secret");
+        let json = serde_json::to_string(&c).unwrap();
+        assert!(!json.contains("secret"), "{}", json);
+    }
+
     #[test]
     fn valid_values_pass_through() {
         let c = MonitorConfig::default()
@@ -4,11 +4,12 @@ pub mod api;
 pub mod cli;
 pub mod config;
 pub mod loader;
+pub mod media;
 pub mod monitor;
 pub mod webhook;
 
-pub use config::MonitorConfig;
-pub use loader::{HttpLoader, LoadError, LoadResponse, ManifestLoader};
+pub use config::{AdDialect, MonitorConfig, PollMode, ProbeMethod};
+pub use loader::{DeliveryDirectives, HttpLoader, LoadError, LoadResponse, ManifestLoader, ProbeResponse};
 pub use monitor::{
     DateRangeSnapshot, ErrorRing, ErrorType, EventKind, EventRing, Monitor, MonitorError,
     MonitorEvent, MonitorState, StreamItem, StreamStatus, VariantStatus,
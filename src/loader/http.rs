@@ -1,10 +1,12 @@
-use std::time::Duration;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
 
 use async_trait::async_trait;
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
 use tracing::{debug, warn};
 
-use super::{LoadError, LoadResponse, ManifestLoader};
+use super::{DeliveryDirectives, LoadError, LoadResponse, ManifestLoader, ProbeResponse};
+use crate::config::ProbeMethod;
 
 /// HTTP-based manifest loader with connection pooling, retries, and backoff.
 #[derive(Debug, Clone)]
@@ -61,6 +63,80 @@ impl Default for HttpLoader {
     }
 }
 
+/// How much longer than its hold limit a blocking reload may take before it times out.
+const BLOCKING_TIMEOUT_MARGIN: Duration = Duration::from_secs(5);
+
+/// Send one manifest request; `attempt` and `is_last` describe its place in a retry loop.
+async fn get_manifest(request: RequestBuilder, uri: &str, attempt: u32, is_last: bool) -> Result<LoadResponse, LoadError> {
+    let started = Instant::now();
+    match request.send().await {
+        Ok(response) => {
+            if response.status().is_success() {
+                let content_type = response
+                    .headers()
+                    .get(reqwest::header::CONTENT_TYPE)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|s| s.to_string());
+                let content_encoding = response
+                    .headers()
+                    .get(reqwest::header::CONTENT_ENCODING)
+                    .and_then(|v| v.to_str().ok())
+                    .map(|s| s.to_string());
+                match response.text().await {
+                    Ok(body) => Ok(LoadResponse {
+                        body,
+                        content_type,
+                        content_encoding,
+                        elapsed: Some(started.elapsed()),
+                    }),
+                    Err(e) => Err(LoadError::Network {
+                        url: uri.to_string(),
+                        reason: e.to_string(),
+                        is_last_retry: is_last,
+                    }),
+                }
+            } else {
+                let status = response.status().as_u16();
+                let message = response.status().canonical_reason()
+                    .unwrap_or("Unknown")
+                    .to_string();
+                warn!(uri, status, attempt, "Manifest fetch returned error status");
+                Err(LoadError::Http {
+                    url: uri.to_string(),
+                    status,
+                    message,
+                    is_last_retry: is_last,
+                })
+            }
+        }
+        Err(e) => {
+            if e.is_timeout() {
+                warn!(uri, attempt, "Manifest fetch timed out");
+                Err(LoadError::Timeout {
+                    url: uri.to_string(),
+                    is_last_retry: is_last,
+                })
+            } else {
+                warn!(uri, attempt, error = %e, "Manifest fetch network error");
+                Err(LoadError::Network {
+                    url: uri.to_string(),
+                    reason: e.to_string(),
+                    is_last_retry: is_last,
+                })
+            }
+        }
+    }
+}
+
+/// Map a client error of a request that is not retried (segment probes and downloads).
+fn single_attempt_error(uri: &str, e: reqwest::Error) -> LoadError {
+    if e.is_timeout() {
+        LoadError::Timeout { url: uri.to_string(), is_last_retry: true }
+    } else {
+        LoadError::Network { url: uri.to_string(), reason: e.to_string(), is_last_retry: true }
+    }
+}
+
 #[async_trait]
 impl ManifestLoader for HttpLoader {
     async fn load(&self, uri: &str) -> Result<LoadResponse, LoadError> {
@@ -75,75 +151,89 @@ impl ManifestLoader for HttpLoader {
                 tokio::time::sleep(backoff).await;
             }
 
-            match self.client.get(uri).send().await {
-                Ok(response) => {
-                    if response.status().is_success() {
-                        let content_type = response
-                            .headers()
-                            .get(reqwest::header::CONTENT_TYPE)
-                            .and_then(|v| v.to_str().ok())
-                            .map(|s| s.to_string());
-                        let content_encoding = response
-                            .headers()
-                            .get(reqwest::header::CONTENT_ENCODING)
-                            .and_then(|v| v.to_str().ok())
-                            .map(|s| s.to_string());
-                        match response.text().await {
-                            Ok(body) => return Ok(LoadResponse { body, content_type, content_encoding }),
-                            Err(e) => {
-                                last_error = Some(LoadError::Network {
-                                    url: uri.to_string(),
-                                    reason: e.to_string(),
-                                    is_last_retry: is_last,
-                                });
-                            }
-                        }
-                    } else {
-                        let status = response.status().as_u16();
-                        let message = response.status().canonical_reason()
-                            .unwrap_or("Unknown")
-                            .to_string();
-                        warn!(uri, status, attempt, "Manifest fetch returned error status");
-                        let err = LoadError::Http {
-                            url: uri.to_string(),
-                            status,
-                            message,
-                            is_last_retry: is_last,
-                        };
-
-                        if (400..500).contains(&status) && status != 429 {
-                            return Err(err);
-                        }
-                        last_error = Some(err);
-                    }
-                }
-                Err(e) => {
-                    if e.is_timeout() {
-                        warn!(uri, attempt, "Manifest fetch timed out");
-                        last_error = Some(LoadError::Timeout {
-                            url: uri.to_string(),
-                            is_last_retry: is_last,
-                        });
-                    } else {
-                        warn!(uri, attempt, error = %e, "Manifest fetch network error");
-                        last_error = Some(LoadError::Network {
-                            url: uri.to_string(),
-                            reason: e.to_string(),
-                            is_last_retry: is_last,
-                        });
-                    }
+            match get_manifest(self.client.get(uri), uri, attempt, is_last).await {
+                Ok(resp) => return Ok(resp),
+                Err(err @ LoadError::Http { status, .. }) if (400..500).contains(&status) && status != 429 => {
+                    return Err(err);
                 }
+                Err(err) => last_error = Some(err),
             }
         }
 
         Err(last_error.expect("Loop must have produced an error"))
     }
+
+    async fn load_with_directives(
+        &self,
+        uri: &str,
+        directives: &DeliveryDirectives,
+    ) -> Result<LoadResponse, LoadError> {
+        let uri = directives.apply(uri);
+        match directives.hold_limit.filter(|_| directives.msn.is_some()) {
+            // The origin may hold a blocking reload up to the limit, so the
+            // request outlives it and is not retried: a late answer is then
+            // measured instead of timing out and starting over.
+            Some(limit) => {
+                let request = self.client.get(&uri).timeout(limit + BLOCKING_TIMEOUT_MARGIN);
+                get_manifest(request, &uri, 0, true).await
+            }
+            None => self.load(&uri).await,
+        }
+    }
+
+    async fn probe(&self, uri: &str, method: ProbeMethod) -> Result<ProbeResponse, LoadError> {
+        let request = match method {
+            ProbeMethod::Head => self.client.head(uri),
+            ProbeMethod::RangedGet => self.client.get(uri).header(reqwest::header::RANGE, "bytes=0-0"),
+        };
+        let response = request.send().await.map_err(|e| single_attempt_error(uri, e))?;
+
+        let header = |name| {
+            response
+                .headers()
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+        };
+        // A ranged answer carries the full length after the slash: "bytes 0-0/12345".
+        let content_length = header(reqwest::header::CONTENT_RANGE)
+            .and_then(|v| v.rsplit_once('/'))
+            .and_then(|(_, total)| total.trim().parse().ok())
+            .or_else(|| header(reqwest::header::CONTENT_LENGTH).and_then(|v| v.trim().parse().ok()));
+
+        Ok(ProbeResponse { status: response.status().as_u16(), content_length })
+    }
+
+    async fn fetch_segment(&self, uri: &str) -> Result<Vec<u8>, LoadError> {
+        get_bytes(self.client.get(uri), uri).await
+    }
+
+    async fn fetch_key(&self, uri: &str, headers: &BTreeMap<String, String>) -> Result<Vec<u8>, LoadError> {
+        let request = headers
+            .iter()
+            .fold(self.client.get(uri), |request, (name, value)| request.header(name, value));
+        get_bytes(request, uri).await
+    }
+}
+
+/// Send a single GET and return the body of a successful response.
+async fn get_bytes(request: RequestBuilder, uri: &str) -> Result<Vec<u8>, LoadError> {
+    let response = request.send().await.map_err(|e| single_attempt_error(uri, e))?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(LoadError::Http {
+            url: uri.to_string(),
+            status: status.as_u16(),
+            message: status.canonical_reason().unwrap_or("Unknown").to_string(),
+            is_last_retry: true,
+        });
+    }
+    response.bytes().await.map(|b| b.to_vec()).map_err(|e| single_attempt_error(uri, e))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use wiremock::matchers::{method, path};
+    use wiremock::matchers::{header, method, path};
     use wiremock::{Mock, MockServer, ResponseTemplate};
 
     #[tokio::test]
@@ -216,6 +306,35 @@ mod tests {
         assert!(result.unwrap_err().is_last_retry());
     }
 
+    #[tokio::test]
+    async fn blocking_reload_outlives_request_timeout_and_is_not_retried() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/held.m3u8"))
+            .respond_with(ResponseTemplate::new(200).set_body_string("#EXTM3U\nHELD").set_delay(Duration::from_millis(400)))
+            .mount(&server)
+            .await;
+        Mock::given(method("GET"))
+            .and(path("/broken.m3u8"))
+            .respond_with(ResponseTemplate::new(500))
+            .expect(1)
+            .mount(&server)
+            .await;
+
+        let loader = HttpLoader::new(Duration::from_millis(100), 3, Duration::from_millis(10));
+        let directives = DeliveryDirectives {
+            msn: Some(5),
+            part: None,
+            skip: false,
+            hold_limit: Some(Duration::from_millis(300)),
+        };
+        let held = loader.load_with_directives(&format!("{}/held.m3u8", server.uri()), &directives).await;
+        assert!(held.unwrap().body.contains("HELD"));
+
+        let broken = loader.load_with_directives(&format!("{}/broken.m3u8", server.uri()), &directives).await;
+        assert_eq!(broken.unwrap_err().status_code(), Some(500));
+    }
+
     #[tokio::test]
     async fn load_captures_content_type_header() {
         let server = MockServer::start().await;
@@ -233,4 +352,80 @@ mod tests {
         assert_eq!(resp.content_type.as_deref(), Some("application/vnd.apple.mpegurl"));
         assert!(resp.body.contains("#EXTM3U"));
     }
+
+    #[tokio::test]
+    async fn probe_head_reports_status_and_length() {
+        let server = MockServer::start().await;
+        Mock::given(method("HEAD"))
+            .and(path("/seg1.ts"))
+            .respond_with(ResponseTemplate::new(200).insert_header("content-length", "1880"))
+            .mount(&server)
+            .await;
+        Mock::given(method("HEAD"))
+            .and(path("/seg2.ts"))
+            .respond_with(ResponseTemplate::new(404))
+            .mount(&server)
+            .await;
+
+        let loader = HttpLoader::new(Duration::from_secs(5), 2, Duration::from_millis(10));
+        let ok = loader.probe(&format!("{}/seg1.ts", server.uri()), ProbeMethod::Head).await.unwrap();
+        assert_eq!(ok, ProbeResponse { status: 200, content_length: Some(1880) });
+        let missing = loader.probe(&format!("{}/seg2.ts", server.uri()), ProbeMethod::Head).await.unwrap();
+        assert_eq!(missing.status, 404);
+    }
+
+    #[tokio::test]
+    async fn probe_ranged_get_reads_content_range_total() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/seg1.ts"))
+            .and(header("range", "bytes=0-0"))
+            .respond_with(
+                ResponseTemplate::new(206)
+                    .insert_header("content-range", "bytes 0-0/4096")
+                    .set_body_bytes(vec![0x47]),
+            )
+            .mount(&server)
+            .await;
+
+        let loader = HttpLoader::new(Duration::from_secs(5), 0, Duration::from_millis(10));
+        let resp = loader.probe(&format!("{}/seg1.ts", server.uri()), ProbeMethod::RangedGet).await.unwrap();
+        assert_eq!(resp, ProbeResponse { status: 206, content_length: Some(4096) });
+    }
+
+    #[tokio::test]
+    async fn fetch_segment_returns_raw_bytes() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/seg1.ts"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![0x47, 0x40, 0x00, 0xff]))
+            .mount(&server)
+            .await;
+
+        let loader = HttpLoader::new(Duration::from_secs(5), 0, Duration::from_millis(10));
+        let bytes = loader.fetch_segment(&format!("{}/seg1.ts", server.uri())).await.unwrap();
+        assert_eq!(bytes, vec![0x47, 0x40, 0x00, 0xff]);
+
+        let err = loader.fetch_segment(&format!("{}/seg2.ts", server.uri())).await.unwrap_err();
+        assert_eq!(err.status_code(), Some(404));
+    }
+
+    #[tokio::test]
+    async fn fetch_key_sends_headers() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/key.bin"))
+            .and(header("authorization", "Bearer abc"))
+            .respond_with(ResponseTemplate::new(200).set_body_bytes(vec![7u8; 16]))
+            .mount(&server)
+            .await;
+
+        let loader = HttpLoader::new(Duration::from_secs(5), 0, Duration::from_millis(10));
+        let url = format!("{}/key.bin", server.uri());
+        let headers = BTreeMap::from([("Authorization".to_string(), "Bearer abc".to_string())]);
+        assert_eq!(loader.fetch_key(&url, &headers).await.unwrap(), vec![7u8; 16]);
+
+        let err = loader.fetch_key(&url, &BTreeMap::new()).await.unwrap_err();
+        assert_eq!(err.status_code(), Some(404));
+    }
 }
@@ -2,14 +2,30 @@ mod http;
 
 pub use http::HttpLoader;
 
+use std::collections::BTreeMap;
+use std::time::Duration;
+
 use async_trait::async_trait;
 use thiserror::Error;
 
+use crate::config::ProbeMethod;
+
 #[derive(Debug, Clone)]
 pub struct LoadResponse {
     pub body: String,
     pub content_type: Option<String>,
     pub content_encoding: Option<String>,
+    /// How long the request that returned this response took, excluding
+    /// earlier failed attempts and retry backoff. `None` if not measured.
+    pub elapsed: Option<Duration>,
+}
+
+/// Outcome of a segment availability probe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProbeResponse {
+    pub status: u16,
+    /// Total resource length from Content-Range or Content-Length, when known.
+    pub content_length: Option<u64>,
 }
 
 #[derive(Debug, Error)]
@@ -51,6 +67,57 @@ impl LoadError {
     }
 }
 
+/// LL-HLS delivery directives sent as query parameters on a playlist request.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct DeliveryDirectives {
+    /// `_HLS_msn`: block until this media sequence number is available.
+    pub msn: Option<u64>,
+    /// `_HLS_part`: block until this part of segment `msn` is available.
+    pub part: Option<u64>,
+    /// `_HLS_skip=YES`: ask for a playlist delta update.
+    pub skip: bool,
+    /// How long the origin may hold a blocking reload. Not sent; loaders use
+    /// it to give the request time to be answered.
+    pub hold_limit: Option<Duration>,
+}
+
+impl DeliveryDirectives {
+    pub fn is_empty(&self) -> bool {
+        self.msn.is_none() && self.part.is_none() && !self.skip
+    }
+
+    /// Append the directives to `uri` as query parameters.
+    ///
+    /// `_HLS_part` is only meaningful alongside `_HLS_msn` and is dropped otherwise.
+    pub fn apply(&self, uri: &str) -> String {
+        let mut params = Vec::new();
+        if let Some(msn) = self.msn {
+            params.push(format!("_HLS_msn={}", msn));
+            if let Some(part) = self.part {
+                params.push(format!("_HLS_part={}", part));
+            }
+        }
+        if self.skip {
+            params.push("_HLS_skip=YES".to_string());
+        }
+        if params.is_empty() {
+            return uri.to_string();
+        }
+
+        let (base, fragment) = match uri.split_once('#') {
+            Some((b, f)) => (b, Some(f)),
+            None => (uri, None),
+        };
+        let sep = if base.contains('?') { '&' } else { '?' };
+        let mut out = format!("{}{}{}", base, sep, params.join("&"));
+        if let Some(f) = fragment {
+            out.push('#');
+            out.push_str(f);
+        }
+        out
+    }
+}
+
 /// Trait for loading HLS manifests from a URI.
 ///
 /// Implementations handle HTTP fetching, retries, and returning raw manifest text.
@@ -58,4 +125,70 @@ impl LoadError {
 #[async_trait]
 pub trait ManifestLoader: Send + Sync {
     async fn load(&self, uri: &str) -> Result<LoadResponse, LoadError>;
+
+    /// Load a playlist with LL-HLS delivery directives (blocking reload).
+    ///
+    /// The default implementation appends the directives to the URI and calls [`load`](Self::load).
+    async fn load_with_directives(
+        &self,
+        uri: &str,
+        directives: &DeliveryDirectives,
+    ) -> Result<LoadResponse, LoadError> {
+        self.load(&directives.apply(uri)).await
+    }
+
+    /// Check that a media segment is available without downloading it.
+    ///
+    /// Any HTTP status is returned as `Ok`; `Err` is reserved for requests that
+    /// got no response. The default implementation fetches the resource with
+    /// [`load`](Self::load) and reports its length.
+    async fn probe(&self, uri: &str, _method: ProbeMethod) -> Result<ProbeResponse, LoadError> {
+        match self.load(uri).await {
+            Ok(resp) => Ok(ProbeResponse { status: 200, content_length: Some(resp.body.len() as u64) }),
+            Err(LoadError::Http { status, .. }) => Ok(ProbeResponse { status, content_length: None }),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Download a media segment as raw bytes.
+    ///
+    /// The default implementation returns the body of [`load`](Self::load).
+    async fn fetch_segment(&self, uri: &str) -> Result<Vec<u8>, LoadError> {
+        self.load(uri).await.map(|resp| resp.body.into_bytes())
+    }
+
+    /// Download a decryption key, sending `headers` (e.g. `Authorization`) with the request.
+    ///
+    /// The default implementation ignores the headers and calls [`fetch_segment`](Self::fetch_segment).
+    async fn fetch_key(&self, uri: &str, _headers: &BTreeMap<String, String>) -> Result<Vec<u8>, LoadError> {
+        self.fetch_segment(uri).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn directives_append_query() {
+        let d = DeliveryDirectives { msn: Some(12), part: Some(3), skip: false, hold_limit: None };
+        assert_eq!(d.apply("http://a/b.m3u8"), "http://a/b.m3u8?_HLS_msn=12&_HLS_part=3");
+        assert_eq!(d.apply("http://a/b.m3u8?token=x"), "http://a/b.m3u8?token=x&_HLS_msn=12&_HLS_part=3");
+    }
+
+    #[test]
+    fn directives_without_msn_leave_uri_untouched() {
+        let d = DeliveryDirectives { msn: None, part: Some(1), skip: false, hold_limit: None };
+        assert_eq!(d.apply("http://a/b.m3u8"), "http://a/b.m3u8");
+        let d = DeliveryDirectives { msn: Some(5), part: None, skip: false, hold_limit: None };
+        assert_eq!(d.apply("http://a/b.m3u8#x"), "http://a/b.m3u8?_HLS_msn=5#x");
+    }
+
+    #[test]
+    fn directives_skip() {
+        let d = DeliveryDirectives { msn: None, part: None, skip: true, hold_limit: None };
+        assert_eq!(d.apply("http://a/b.m3u8"), "http://a/b.m3u8?_HLS_skip=YES");
+        let d = DeliveryDirectives { msn: Some(5), part: Some(0), skip: true, hold_limit: None };
+        assert_eq!(d.apply("http://a/b.m3u8"), "http://a/b.m3u8?_HLS_msn=5&_HLS_part=0&_HLS_skip=YES");
+    }
 }
@@ -0,0 +1,379 @@
+//! Minimal ISO-BMFF (fragmented MP4 / CMAF) box reader (ISO/IEC 14496-12).
+//!
+//! Init segments yield the track IDs, timescales and default sample durations
+//! of `moov`; media segments yield `tfdt` and the summed `trun` sample
+//! durations of every track fragment.
+
+use std::collections::BTreeMap;
+
+use thiserror::Error;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Fmp4Error {
+    #[error("not an ISO-BMFF segment")]
+    NotIsoBmff,
+    #[error("truncated '{0}' box")]
+    Truncated(String),
+    #[error("no '{0}' box")]
+    Missing(&'static str),
+}
+
+/// A track declared in the init segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitTrack {
+    pub track_id: u32,
+    pub timescale: u32,
+    pub handler: String,
+    /// `trex` default_sample_duration.
+    pub default_sample_duration: Option<u32>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InitSegment {
+    pub tracks: BTreeMap<u32, InitTrack>,
+}
+
+/// All fragments of one track in a media segment.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TrackRun {
+    pub track_id: u32,
+    /// `baseMediaDecodeTime` of the first fragment.
+    pub base_media_decode_time: Option<u64>,
+    pub sample_count: u64,
+    /// Sum of the sample durations given in `trun` or `tfhd`.
+    pub duration: u64,
+    /// Samples whose duration comes from the init segment's `trex`.
+    pub samples_without_duration: u64,
+}
+
+impl TrackRun {
+    /// Total duration in the track timescale, filling in `trex` defaults.
+    pub fn total_duration(&self, trex_default: Option<u32>) -> Option<u64> {
+        if self.samples_without_duration == 0 {
+            return Some(self.duration);
+        }
+        trex_default.map(|d| self.duration + self.samples_without_duration * u64::from(d))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FragmentSummary {
+    pub tracks: BTreeMap<u32, TrackRun>,
+    /// `sidx` reference_ID and timescale.
+    pub sidx: Option<(u32, u32)>,
+}
+
+/// Whether `data` starts with an ISO-BMFF box header.
+pub fn looks_like_iso_bmff(data: &[u8]) -> bool {
+    data.get(4..8)
+        .is_some_and(|t| matches!(t, b"ftyp" | b"styp" | b"moof" | b"sidx" | b"moov" | b"emsg" | b"prft"))
+}
+
+/// Iterate over the boxes of a buffer as `(type, payload)`.
+fn boxes(mut data: &[u8]) -> impl Iterator<Item = Result<([u8; 4], &[u8]), Fmp4Error>> {
+    std::iter::from_fn(move || {
+        if data.len() < 8 {
+            return None;
+        }
+        let kind: [u8; 4] = data[4..8].try_into().ok()?;
+        let truncated = || Fmp4Error::Truncated(String::from_utf8_lossy(&kind).into_owned());
+        let (header, size) = match u32::from_be_bytes(data[..4].try_into().ok()?) {
+            0 => (8, data.len()),
+            1 => match data.get(8..16) {
+                Some(b) => (16, u64::from_be_bytes(b.try_into().ok()?) as usize),
+                None => {
+                    data = &[];
+                    return Some(Err(truncated()));
+                }
+            },
+            n => (8, n as usize),
+        };
+        if size < header || size > data.len() {
+            data = &[];
+            return Some(Err(truncated()));
+        }
+        let payload = &data[header..size];
+        data = &data[size..];
+        Some(Ok((kind, payload)))
+    })
+}
+
+/// First child box of type `kind`.
+fn child<'a>(data: &'a [u8], kind: &[u8; 4]) -> Result<Option<&'a [u8]>, Fmp4Error> {
+    for b in boxes(data) {
+        let (k, payload) = b?;
+        if &k == kind {
+            return Ok(Some(payload));
+        }
+    }
+    Ok(None)
+}
+
+/// Version and flags of a full box, and its body.
+fn full_box(payload: &[u8]) -> Option<(u8, u32, &[u8])> {
+    let header = payload.get(..4)?;
+    let flags = u32::from_be_bytes([0, header[1], header[2], header[3]]);
+    Some((header[0], flags, &payload[4..]))
+}
+
+fn read_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn read_u64(data: &[u8], at: usize) -> Option<u64> {
+    Some(u64::from_be_bytes(data.get(at..at + 8)?.try_into().ok()?))
+}
+
+/// Read the `moov` of an init segment.
+pub fn parse_init(data: &[u8]) -> Result<InitSegment, Fmp4Error> {
+    if !looks_like_iso_bmff(data) {
+        return Err(Fmp4Error::NotIsoBmff);
+    }
+    let moov = child(data, b"moov")?.ok_or(Fmp4Error::Missing("moov"))?;
+    let truncated = |kind: &str| Fmp4Error::Truncated(kind.to_string());
+
+    let mut init = InitSegment::default();
+    for b in boxes(moov) {
+        let (kind, trak) = b?;
+        if &kind != b"trak" {
+            continue;
+        }
+        let tkhd = child(trak, b"tkhd")?.ok_or(Fmp4Error::Missing("tkhd"))?;
+        let (version, _, body) = full_box(tkhd).ok_or_else(|| truncated("tkhd"))?;
+        let track_id = read_u32(body, if version == 1 { 16 } else { 8 }).ok_or_else(|| truncated("tkhd"))?;
+
+        let mdia = child(trak, b"mdia")?.ok_or(Fmp4Error::Missing("mdia"))?;
+        let mdhd = child(mdia, b"mdhd")?.ok_or(Fmp4Error::Missing("mdhd"))?;
+        let (version, _, body) = full_box(mdhd).ok_or_else(|| truncated("mdhd"))?;
+        let timescale = read_u32(body, if version == 1 { 16 } else { 8 }).ok_or_else(|| truncated("mdhd"))?;
+        let handler = child(mdia, b"hdlr")?
+            .and_then(full_box)
+            .and_then(|(_, _, body)| body.get(4..8))
+            .map(|h| String::from_utf8_lossy(h).into_owned())
+            .unwrap_or_default();
+
+        init.tracks.insert(
+            track_id,
+            InitTrack { track_id, timescale, handler, default_sample_duration: None },
+        );
+    }
+
+    if let Some(mvex) = child(moov, b"mvex")? {
+        for b in boxes(mvex) {
+            let (kind, trex) = b?;
+            if &kind != b"trex" {
+                continue;
+            }
+            let (_, _, body) = full_box(trex).ok_or_else(|| truncated("trex"))?;
+            let track_id = read_u32(body, 0).ok_or_else(|| truncated("trex"))?;
+            let duration = read_u32(body, 8).ok_or_else(|| truncated("trex"))?;
+            if let Some(track) = init.tracks.get_mut(&track_id) {
+                track.default_sample_duration = Some(duration);
+            }
+        }
+    }
+
+    Ok(init)
+}
+
+/// Read every `moof` of a media segment.
+pub fn parse_fragment(data: &[u8]) -> Result<FragmentSummary, Fmp4Error> {
+    if !looks_like_iso_bmff(data) {
+        return Err(Fmp4Error::NotIsoBmff);
+    }
+    let truncated = |kind: &str| Fmp4Error::Truncated(kind.to_string());
+
+    let mut summary = FragmentSummary::default();
+    let mut found_moof = false;
+    for b in boxes(data) {
+        let (kind, payload) = b?;
+        match &kind {
+            b"sidx" if summary.sidx.is_none() => {
+                let (_, _, body) = full_box(payload).ok_or_else(|| truncated("sidx"))?;
+                let reference_id = read_u32(body, 0).ok_or_else(|| truncated("sidx"))?;
+                let timescale = read_u32(body, 4).ok_or_else(|| truncated("sidx"))?;
+                summary.sidx = Some((reference_id, timescale));
+            }
+            b"moof" => {
+                found_moof = true;
+                for b in boxes(payload) {
+                    let (kind, traf) = b?;
+                    if &kind == b"traf" {
+                        read_traf(traf, &mut summary)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    if !found_moof {
+        return Err(Fmp4Error::Missing("moof"));
+    }
+    Ok(summary)
+}
+
+fn read_traf(traf: &[u8], summary: &mut FragmentSummary) -> Result<(), Fmp4Error> {
+    let truncated = |kind: &str| Fmp4Error::Truncated(kind.to_string());
+
+    let tfhd = child(traf, b"tfhd")?.ok_or(Fmp4Error::Missing("tfhd"))?;
+    let (_, tfhd_flags, body) = full_box(tfhd).ok_or_else(|| truncated("tfhd"))?;
+    let track_id = read_u32(body, 0).ok_or_else(|| truncated("tfhd"))?;
+    let mut at = 4;
+    if tfhd_flags & 0x01 != 0 {
+        at += 8; // base_data_offset
+    }
+    if tfhd_flags & 0x02 != 0 {
+        at += 4; // sample_description_index
+    }
+    let default_duration = if tfhd_flags & 0x08 != 0 {
+        Some(read_u32(body, at).ok_or_else(|| truncated("tfhd"))?)
+    } else {
+        None
+    };
+
+    let run = summary.tracks.entry(track_id).or_insert_with(|| TrackRun { track_id, ..Default::default() });
+
+    if let Some(tfdt) = child(traf, b"tfdt")? {
+        let (version, _, body) = full_box(tfdt).ok_or_else(|| truncated("tfdt"))?;
+        let decode_time = if version == 1 {
+            read_u64(body, 0)
+        } else {
+            read_u32(body, 0).map(u64::from)
+        }
+        .ok_or_else(|| truncated("tfdt"))?;
+        run.base_media_decode_time.get_or_insert(decode_time);
+    }
+
+    for b in boxes(traf) {
+        let (kind, trun) = b?;
+        if &kind != b"trun" {
+            continue;
+        }
+        let (_, flags, body) = full_box(trun).ok_or_else(|| truncated("trun"))?;
+        let sample_count = read_u32(body, 0).ok_or_else(|| truncated("trun"))?;
+        let mut at = 4;
+        if flags & 0x001 != 0 {
+            at += 4; // data_offset
+        }
+        if flags & 0x004 != 0 {
+            at += 4; // first_sample_flags
+        }
+        let per_sample = [0x100, 0x200, 0x400, 0x800].iter().filter(|f| flags & **f != 0).count() * 4;
+
+        run.sample_count += u64::from(sample_count);
+        for i in 0..sample_count as usize {
+            if flags & 0x100 != 0 {
+                let duration = read_u32(body, at + i * per_sample).ok_or_else(|| truncated("trun"))?;
+                run.duration += u64::from(duration);
+            } else if let Some(d) = default_duration {
+                run.duration += u64::from(d);
+            } else {
+                run.samples_without_duration += 1;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Builders for synthetic fMP4 segments, shared with the inspection tests.
+#[cfg(test)]
+pub(crate) mod testing {
+    fn bx(kind: &[u8; 4], payload: &[u8]) -> Vec<u8> {
+        let mut out = ((payload.len() + 8) as u32).to_be_bytes().to_vec();
+        out.extend_from_slice(kind);
+        out.extend_from_slice(payload);
+        out
+    }
+
+    fn full(kind: &[u8; 4], version: u8, flags: u32, body: &[u8]) -> Vec<u8> {
+        let mut payload = vec![version, (flags >> 16) as u8, (flags >> 8) as u8, flags as u8];
+        payload.extend_from_slice(body);
+        bx(kind, &payload)
+    }
+
+    /// Init segment with `(track_id, timescale, handler)` tracks.
+    pub fn init(tracks: &[(u32, u32, &[u8; 4])]) -> Vec<u8> {
+        let mut moov = Vec::new();
+        let mut mvex = Vec::new();
+        for &(id, timescale, handler) in tracks {
+            let mut tkhd = vec![0u8; 8];
+            tkhd.extend_from_slice(&id.to_be_bytes());
+            tkhd.extend_from_slice(&[0u8; 68]);
+            let mut mdhd = vec![0u8; 8];
+            mdhd.extend_from_slice(&timescale.to_be_bytes());
+            mdhd.extend_from_slice(&[0u8; 8]);
+            let mut hdlr = vec![0u8; 4];
+            hdlr.extend_from_slice(handler);
+            hdlr.extend_from_slice(&[0u8; 13]);
+            let mdia = [full(b"mdhd", 0, 0, &mdhd), full(b"hdlr", 0, 0, &hdlr)].concat();
+            moov.extend(bx(b"trak", &[full(b"tkhd", 0, 3, &tkhd), bx(b"mdia", &mdia)].concat()));
+
+            let mut trex = id.to_be_bytes().to_vec();
+            trex.extend_from_slice(&[0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+            mvex.extend(full(b"trex", 0, 0, &trex));
+        }
+        moov.extend(bx(b"mvex", &mvex));
+        [bx(b"ftyp", b"iso6\0\0\0\0iso6cmfc"), bx(b"moov", &moov)].concat()
+    }
+
+    /// Media segment with one `moof` holding `(track_id, tfdt, sample_durations)` fragments.
+    pub fn fragment(trafs: &[(u32, u64, &[u32])]) -> Vec<u8> {
+        let mut moof = full(b"mfhd", 0, 0, &1u32.to_be_bytes());
+        for &(id, tfdt, durations) in trafs {
+            let tfhd = full(b"tfhd", 0, 0x020000, &id.to_be_bytes());
+            let tfdt = full(b"tfdt", 1, 0, &tfdt.to_be_bytes());
+            let mut trun = (durations.len() as u32).to_be_bytes().to_vec();
+            for d in durations {
+                trun.extend_from_slice(&d.to_be_bytes());
+            }
+            let trun = full(b"trun", 0, 0x100, &trun);
+            moof.extend(bx(b"traf", &[tfhd, tfdt, trun].concat()));
+        }
+        [bx(b"styp", b"msdhmsdh"), bx(b"moof", &moof), bx(b"mdat", &[0u8; 16])].concat()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::*;
+    use super::*;
+
+    #[test]
+    fn reads_init_tracks() {
+        let init = parse_init(&init(&[(1, 90_000, b"vide"), (2, 48_000, b"soun")])).unwrap();
+        assert_eq!(init.tracks.len(), 2);
+        assert_eq!(init.tracks[&1].timescale, 90_000);
+        assert_eq!(init.tracks[&1].handler, "vide");
+        assert_eq!(init.tracks[&2].timescale, 48_000);
+        assert_eq!(init.tracks[&2].default_sample_duration, Some(0));
+    }
+
+    #[test]
+    fn reads_fragment_timing() {
+        let data = fragment(&[(1, 180_000, &[3600; 50]), (2, 96_000, &[1024; 94])]);
+        let summary = parse_fragment(&data).unwrap();
+        let video = &summary.tracks[&1];
+        assert_eq!(video.base_media_decode_time, Some(180_000));
+        assert_eq!(video.sample_count, 50);
+        assert_eq!(video.total_duration(None), Some(180_000));
+        assert_eq!(summary.tracks[&2].duration, 96_256);
+    }
+
+    #[test]
+    fn fills_missing_durations_from_trex() {
+        let run = TrackRun { track_id: 1, samples_without_duration: 10, ..Default::default() };
+        assert_eq!(run.total_duration(None), None);
+        assert_eq!(run.total_duration(Some(3000)), Some(30_000));
+    }
+
+    #[test]
+    fn rejects_other_data() {
+        assert_eq!(parse_fragment(&[0x47, 0x40, 0x00, 0x10, 0, 0, 0, 0]), Err(Fmp4Error::NotIsoBmff));
+        assert_eq!(parse_init(&fragment(&[(1, 0, &[1])])), Err(Fmp4Error::Missing("moov")));
+        let mut truncated = fragment(&[(1, 0, &[1])]);
+        truncated.truncate(30);
+        assert!(matches!(parse_fragment(&truncated), Err(Fmp4Error::Truncated(_))));
+    }
+}
@@ -0,0 +1,6 @@
+//! Binary parsers: the segment containers used by segment inspection and the
+//! SCTE-35 splice information carried in playlists.
+
+pub mod fmp4;
+pub mod scte35;
+pub mod ts;
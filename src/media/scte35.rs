@@ -0,0 +1,534 @@
+//! SCTE-35 `splice_info_section` decoder (ANSI/SCTE 35).
+//!
+//! Payloads arrive in playlists as base64 (`EXT-OATCLS-SCTE35`,
+//! `EXT-X-SCTE35:CUE=`) or as `0x` hex (`EXT-X-DATERANGE` SCTE35-OUT/IN/CMD).
+//! `splice_insert` and `time_signal` are decoded in full, together with any
+//! `segmentation_descriptor`; other commands are identified by type only.
+
+use std::fmt;
+
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Ticks per second of `pts_time`, `break_duration` and `segmentation_duration`.
+pub const CLOCK_HZ: u64 = 90_000;
+const TABLE_ID: u8 = 0xfc;
+/// `CUEI`, the identifier of the descriptors defined by SCTE 35 itself.
+const CUEI: u32 = 0x4355_4549;
+const SEGMENTATION_DESCRIPTOR: u8 = 0x02;
+
+#[derive(Debug, Clone, Error, PartialEq, Eq)]
+pub enum Scte35Error {
+    #[error("payload is neither base64 nor 0x-prefixed hex: {0}")]
+    InvalidEncoding(String),
+    #[error("table_id 0x{0:02x} is not a splice_info_section")]
+    BadTableId(u8),
+    #[error("splice_info_section truncated in {0}")]
+    Truncated(&'static str),
+    #[error("encrypted splice commands are not supported")]
+    Encrypted,
+}
+
+/// A decoded `splice_info_section`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpliceInfo {
+    pub protocol_version: u8,
+    pub pts_adjustment: u64,
+    pub tier: u16,
+    pub command: SpliceCommand,
+    pub segmentation: Vec<SegmentationDescriptor>,
+    pub crc_32: u32,
+    /// Whether `crc_32` matches the CRC-32/MPEG-2 of the section.
+    pub crc_valid: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum SpliceCommand {
+    SpliceNull,
+    SpliceSchedule,
+    SpliceInsert(SpliceInsert),
+    TimeSignal { pts_time: Option<u64> },
+    BandwidthReservation,
+    PrivateCommand,
+    Unknown { command_type: u8 },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SpliceInsert {
+    pub event_id: u32,
+    pub cancel: bool,
+    pub out_of_network: bool,
+    pub program_splice: bool,
+    pub splice_immediate: bool,
+    /// Splice time of a program splice, when not immediate.
+    pub pts_time: Option<u64>,
+    pub break_duration: Option<BreakDuration>,
+    pub unique_program_id: u16,
+    pub avail_num: u8,
+    pub avails_expected: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BreakDuration {
+    pub auto_return: bool,
+    /// 90 kHz ticks.
+    pub duration: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SegmentationDescriptor {
+    pub event_id: u32,
+    pub cancel: bool,
+    pub type_id: u8,
+    /// 90 kHz ticks.
+    pub duration: Option<u64>,
+    pub upid_type: u8,
+    /// The UPID bytes as lowercase hex.
+    pub upid: String,
+    pub segment_num: u8,
+    pub segments_expected: u8,
+}
+
+impl SegmentationDescriptor {
+    /// `Some(true)` for the start of a break or placement opportunity,
+    /// `Some(false)` for its end, `None` for other segmentation types.
+    pub fn break_start(&self) -> Option<bool> {
+        match self.type_id {
+            0x22 | 0x30 | 0x32 | 0x34 | 0x36 | 0x44 | 0x46 => Some(true),
+            0x23 | 0x31 | 0x33 | 0x35 | 0x37 | 0x45 | 0x47 => Some(false),
+            _ => None,
+        }
+    }
+}
+
+impl SpliceInfo {
+    /// Whether the signal leaves the network (`true`) or returns to it.
+    ///
+    /// Taken from `out_of_network_indicator` of a `splice_insert`, or from the
+    /// segmentation type of a `time_signal`.
+    pub fn out_of_network(&self) -> Option<bool> {
+        match &self.command {
+            SpliceCommand::SpliceInsert(insert) if !insert.cancel => Some(insert.out_of_network),
+            SpliceCommand::TimeSignal { .. } => self
+                .segmentation
+                .iter()
+                .filter(|d| !d.cancel)
+                .find_map(SegmentationDescriptor::break_start),
+            _ => None,
+        }
+    }
+
+    /// Signalled break length in seconds, from `break_duration` or the first
+    /// `segmentation_duration`.
+    pub fn duration_secs(&self) -> Option<f64> {
+        let ticks = match &self.command {
+            SpliceCommand::SpliceInsert(insert) => insert.break_duration.map(|b| b.duration),
+            _ => None,
+        };
+        ticks
+            .or_else(|| self.segmentation.iter().find_map(|d| d.duration))
+            .map(|t| t as f64 / CLOCK_HZ as f64)
+    }
+}
+
+impl fmt::Display for SpliceInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.command {
+            SpliceCommand::SpliceInsert(insert) => {
+                write!(f, "splice_insert event_id={}", insert.event_id)?;
+                if insert.cancel {
+                    return write!(f, " cancel");
+                }
+                write!(f, " out_of_network={}", insert.out_of_network)?;
+                if let Some(pts) = insert.pts_time {
+                    write!(f, " pts_time={}", pts)?;
+                }
+                if let Some(b) = insert.break_duration {
+                    write!(
+                        f,
+                        " break_duration={:.3}s auto_return={}",
+                        b.duration as f64 / CLOCK_HZ as f64,
+                        b.auto_return
+                    )?;
+                }
+            }
+            SpliceCommand::TimeSignal { pts_time } => {
+                write!(f, "time_signal")?;
+                if let Some(pts) = pts_time {
+                    write!(f, " pts_time={}", pts)?;
+                }
+            }
+            SpliceCommand::SpliceNull => write!(f, "splice_null")?,
+            SpliceCommand::SpliceSchedule => write!(f, "splice_schedule")?,
+            SpliceCommand::BandwidthReservation => write!(f, "bandwidth_reservation")?,
+            SpliceCommand::PrivateCommand => write!(f, "private_command")?,
+            SpliceCommand::Unknown { command_type } => write!(f, "splice_command_type=0x{:02x}", command_type)?,
+        }
+        for d in &self.segmentation {
+            write!(f, " segmentation_type=0x{:02x} segmentation_event_id={}", d.type_id, d.event_id)?;
+            if let Some(ticks) = d.duration {
+                write!(f, " segmentation_duration={:.3}s", ticks as f64 / CLOCK_HZ as f64)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Decode a base64 or `0x` hex payload.
+pub fn decode(payload: &str) -> Result<SpliceInfo, Scte35Error> {
+    let payload = payload.trim();
+    let bytes = match payload.strip_prefix("0x").or_else(|| payload.strip_prefix("0X")) {
+        Some(hex) => decode_hex(hex).ok_or_else(|| Scte35Error::InvalidEncoding(payload.to_string()))?,
+        None => base64::engine::general_purpose::STANDARD
+            .decode(payload)
+            .map_err(|_| Scte35Error::InvalidEncoding(payload.to_string()))?,
+    };
+    parse(&bytes)
+}
+
+fn decode_hex(hex: &str) -> Option<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return None;
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+/// CRC-32/MPEG-2, as used by `splice_info_section`.
+pub fn crc32_mpeg2(data: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffffu32;
+    for &byte in data {
+        crc ^= (byte as u32) << 24;
+        for _ in 0..8 {
+            crc = if crc & 0x8000_0000 != 0 {
+                (crc << 1) ^ 0x04c1_1db7
+            } else {
+                crc << 1
+            };
+        }
+    }
+    crc
+}
+
+/// MSB-first bit reader over a byte slice.
+struct Bits<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Bits<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self { data, pos: 0 }
+    }
+
+    fn read(&mut self, n: usize, what: &'static str) -> Result<u64, Scte35Error> {
+        if self.pos + n > self.data.len() * 8 {
+            return Err(Scte35Error::Truncated(what));
+        }
+        let mut value = 0u64;
+        for _ in 0..n {
+            let bit = (self.data[self.pos / 8] >> (7 - self.pos % 8)) & 1;
+            value = (value << 1) | bit as u64;
+            self.pos += 1;
+        }
+        Ok(value)
+    }
+
+    fn flag(&mut self, what: &'static str) -> Result<bool, Scte35Error> {
+        Ok(self.read(1, what)? == 1)
+    }
+
+    fn skip(&mut self, n: usize, what: &'static str) -> Result<(), Scte35Error> {
+        if self.pos + n > self.data.len() * 8 {
+            return Err(Scte35Error::Truncated(what));
+        }
+        self.pos += n;
+        Ok(())
+    }
+
+    fn bytes(&mut self, n: usize, what: &'static str) -> Result<&'a [u8], Scte35Error> {
+        let start = self.pos / 8;
+        self.skip(n * 8, what)?;
+        Ok(&self.data[start..start + n])
+    }
+}
+
+/// Parse a binary `splice_info_section`.
+pub fn parse(data: &[u8]) -> Result<SpliceInfo, Scte35Error> {
+    let mut bits = Bits::new(data);
+    let table_id = bits.read(8, "header")? as u8;
+    if table_id != TABLE_ID {
+        return Err(Scte35Error::BadTableId(table_id));
+    }
+    bits.skip(4, "header")?;
+    let section_length = bits.read(12, "header")? as usize;
+    let section = data
+        .get(..3 + section_length)
+        .filter(|s| s.len() >= 4)
+        .ok_or(Scte35Error::Truncated("section"))?;
+    let mut bits = Bits::new(section);
+    bits.skip(24, "header")?;
+
+    let protocol_version = bits.read(8, "header")? as u8;
+    if bits.flag("header")? {
+        return Err(Scte35Error::Encrypted);
+    }
+    bits.skip(6, "header")?;
+    let pts_adjustment = bits.read(33, "header")?;
+    bits.skip(8, "header")?;
+    let tier = bits.read(12, "header")? as u16;
+    let command_length = bits.read(12, "header")? as usize;
+    let command_type = bits.read(8, "header")? as u8;
+
+    let command_start = bits.pos;
+    let command = match command_type {
+        0x00 => SpliceCommand::SpliceNull,
+        0x04 => SpliceCommand::SpliceSchedule,
+        0x05 => SpliceCommand::SpliceInsert(parse_splice_insert(&mut bits)?),
+        0x06 => SpliceCommand::TimeSignal {
+            pts_time: parse_splice_time(&mut bits)?,
+        },
+        0x07 => SpliceCommand::BandwidthReservation,
+        0xff => SpliceCommand::PrivateCommand,
+        other => SpliceCommand::Unknown { command_type: other },
+    };
+    // 0xfff is the legacy "unknown length"; only the decoded commands can be skipped then.
+    if command_length != 0xfff {
+        bits.pos = command_start;
+        bits.skip(command_length * 8, "splice_command")?;
+    } else if matches!(command, SpliceCommand::Unknown { .. } | SpliceCommand::SpliceSchedule | SpliceCommand::PrivateCommand) {
+        return Err(Scte35Error::Truncated("splice_command"));
+    }
+
+    let loop_length = bits.read(16, "descriptor_loop")? as usize;
+    let descriptors = bits.bytes(loop_length, "descriptor_loop")?;
+    let segmentation = parse_descriptors(descriptors)?;
+
+    let crc_at = section.len() - 4;
+    let crc_32 = u32::from_be_bytes(section[crc_at..].try_into().expect("4 bytes"));
+    Ok(SpliceInfo {
+        protocol_version,
+        pts_adjustment,
+        tier,
+        command,
+        segmentation,
+        crc_32,
+        crc_valid: crc32_mpeg2(&section[..crc_at]) == crc_32,
+    })
+}
+
+fn parse_splice_time(bits: &mut Bits) -> Result<Option<u64>, Scte35Error> {
+    if bits.flag("splice_time")? {
+        bits.skip(6, "splice_time")?;
+        Ok(Some(bits.read(33, "splice_time")?))
+    } else {
+        bits.skip(7, "splice_time")?;
+        Ok(None)
+    }
+}
+
+fn parse_splice_insert(bits: &mut Bits) -> Result<SpliceInsert, Scte35Error> {
+    let what = "splice_insert";
+    let mut insert = SpliceInsert {
+        event_id: bits.read(32, what)? as u32,
+        cancel: bits.flag(what)?,
+        out_of_network: false,
+        program_splice: false,
+        splice_immediate: false,
+        pts_time: None,
+        break_duration: None,
+        unique_program_id: 0,
+        avail_num: 0,
+        avails_expected: 0,
+    };
+    bits.skip(7, what)?;
+    if insert.cancel {
+        return Ok(insert);
+    }
+    insert.out_of_network = bits.flag(what)?;
+    insert.program_splice = bits.flag(what)?;
+    let duration_flag = bits.flag(what)?;
+    insert.splice_immediate = bits.flag(what)?;
+    bits.skip(4, what)?;
+    if insert.program_splice {
+        if !insert.splice_immediate {
+            insert.pts_time = parse_splice_time(bits)?;
+        }
+    } else {
+        let components = bits.read(8, what)?;
+        for _ in 0..components {
+            bits.skip(8, what)?;
+            if !insert.splice_immediate {
+                parse_splice_time(bits)?;
+            }
+        }
+    }
+    if duration_flag {
+        let auto_return = bits.flag("break_duration")?;
+        bits.skip(6, "break_duration")?;
+        insert.break_duration = Some(BreakDuration {
+            auto_return,
+            duration: bits.read(33, "break_duration")?,
+        });
+    }
+    insert.unique_program_id = bits.read(16, what)? as u16;
+    insert.avail_num = bits.read(8, what)? as u8;
+    insert.avails_expected = bits.read(8, what)? as u8;
+    Ok(insert)
+}
+
+fn parse_descriptors(data: &[u8]) -> Result<Vec<SegmentationDescriptor>, Scte35Error> {
+    let mut found = Vec::new();
+    let mut bits = Bits::new(data);
+    while bits.pos < data.len() * 8 {
+        let tag = bits.read(8, "splice_descriptor")? as u8;
+        let length = bits.read(8, "splice_descriptor")? as usize;
+        let body = bits.bytes(length, "splice_descriptor")?;
+        if tag != SEGMENTATION_DESCRIPTOR || body.len() < 4 {
+            continue;
+        }
+        let mut body = Bits::new(body);
+        if body.read(32, "segmentation_descriptor")? as u32 != CUEI {
+            continue;
+        }
+        found.push(parse_segmentation(&mut body)?);
+    }
+    Ok(found)
+}
+
+fn parse_segmentation(bits: &mut Bits) -> Result<SegmentationDescriptor, Scte35Error> {
+    let what = "segmentation_descriptor";
+    let mut d = SegmentationDescriptor {
+        event_id: bits.read(32, what)? as u32,
+        cancel: bits.flag(what)?,
+        type_id: 0,
+        duration: None,
+        upid_type: 0,
+        upid: String::new(),
+        segment_num: 0,
+        segments_expected: 0,
+    };
+    bits.skip(7, what)?;
+    if d.cancel {
+        return Ok(d);
+    }
+    let program_segmentation = bits.flag(what)?;
+    let duration_flag = bits.flag(what)?;
+    bits.skip(6, what)?;
+    if !program_segmentation {
+        let components = bits.read(8, what)? as usize;
+        bits.skip(components * 48, what)?;
+    }
+    if duration_flag {
+        d.duration = Some(bits.read(40, what)?);
+    }
+    d.upid_type = bits.read(8, what)? as u8;
+    let upid_length = bits.read(8, what)? as usize;
+    d.upid = bits.bytes(upid_length, what)?.iter().map(|b| format!("{:02x}", b)).collect();
+    d.type_id = bits.read(8, what)? as u8;
+    d.segment_num = bits.read(8, what)? as u8;
+    d.segments_expected = bits.read(8, what)? as u8;
+    Ok(d)
+}
+
+/// Builders for splice_info_section payloads in tests.
+#[cfg(test)]
+pub(crate) mod testing {
+    use base64::Engine;
+
+    use super::{crc32_mpeg2, CLOCK_HZ};
+
+    fn section(command_type: u8, command: &[u8]) -> Vec<u8> {
+        let section_length = 11 + command.len() + 2 + 4;
+        let mut out = vec![0xfc, 0x30 | (section_length >> 8) as u8, section_length as u8, 0x00];
+        out.extend_from_slice(&[0x00, 0x00, 0x00, 0x00, 0x00]);
+        out.extend_from_slice(&[0x00, 0xff, 0xf0 | (command.len() >> 8) as u8, command.len() as u8]);
+        out.push(command_type);
+        out.extend_from_slice(command);
+        out.extend_from_slice(&[0x00, 0x00]);
+        let crc = crc32_mpeg2(&out);
+        out.extend_from_slice(&crc.to_be_bytes());
+        out
+    }
+
+    /// Base64 `splice_insert` with an immediate program splice.
+    pub fn splice_insert(event_id: u32, out_of_network: bool, duration_secs: Option<f64>) -> String {
+        let mut cmd = event_id.to_be_bytes().to_vec();
+        cmd.push(0x7f);
+        let duration_flag = if duration_secs.is_some() { 0x20 } else { 0 };
+        cmd.push(if out_of_network { 0x80 } else { 0 } | 0x40 | duration_flag | 0x10 | 0x0f);
+        if let Some(secs) = duration_secs {
+            let ticks = (secs * CLOCK_HZ as f64).round() as u64;
+            cmd.push(0x80 | 0x7e | (ticks >> 32) as u8);
+            cmd.extend_from_slice(&(ticks as u32).to_be_bytes());
+        }
+        cmd.extend_from_slice(&[0x00, 0x01, 0x00, 0x00]);
+        base64::engine::general_purpose::STANDARD.encode(section(0x05, &cmd))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_spec_splice_insert() {
+        // ANSI/SCTE 35 sample 14.2: splice_insert with a 60.3 s auto-return break.
+        let info = decode("/DAvAAAAAAAA///wFAVIAACPf+/+c2nALv4AUsz1AAAAAAAKAAhDVUVJAAABNWLbowo=").unwrap();
+        assert!(info.crc_valid);
+        let SpliceCommand::SpliceInsert(insert) = &info.command else {
+            panic!("expected splice_insert, got {:?}", info.command);
+        };
+        assert_eq!(insert.event_id, 0x4800_008f);
+        assert!(insert.out_of_network);
+        assert_eq!(insert.pts_time, Some(0x0_7369_c02e));
+        assert_eq!(
+            insert.break_duration,
+            Some(BreakDuration {
+                auto_return: true,
+                duration: 0x0_0052_ccf5
+            })
+        );
+        assert_eq!(info.out_of_network(), Some(true));
+        assert!((info.duration_secs().unwrap() - 60.293).abs() < 0.001);
+    }
+
+    #[test]
+    fn decodes_spec_time_signal_with_segmentation() {
+        // ANSI/SCTE 35 sample 14.1: time_signal, provider placement opportunity start.
+        let info = decode("/DA0AAAAAAAA///wBQb+cr0AUAAeAhxDVUVJSAAAjn/PAAGlmbAICAAAAAAsoKGKNAIAmsnRfg==").unwrap();
+        assert!(info.crc_valid);
+        assert_eq!(info.command, SpliceCommand::TimeSignal { pts_time: Some(0x0_72bd_0050) });
+        assert_eq!(info.segmentation.len(), 1);
+        let seg = &info.segmentation[0];
+        assert_eq!(seg.type_id, 0x34);
+        assert_eq!(seg.upid_type, 0x08);
+        assert_eq!(info.out_of_network(), Some(true));
+        assert!((info.duration_secs().unwrap() - 307.0).abs() < 0.001);
+    }
+
+    #[test]
+    fn decodes_hex_and_flags_bad_crc() {
+        let b64 = testing::splice_insert(7, false, None);
+        let mut bytes = base64::engine::general_purpose::STANDARD.decode(&b64).unwrap();
+        let hex: String = bytes.iter().map(|b| format!("{:02X}", b)).collect();
+        let info = decode(&format!("0x{}", hex)).unwrap();
+        assert!(info.crc_valid);
+        assert_eq!(info.out_of_network(), Some(false));
+
+        let last = bytes.len() - 1;
+        bytes[last] ^= 0xff;
+        assert!(!parse(&bytes).unwrap().crc_valid);
+    }
+
+    #[test]
+    fn rejects_malformed_payloads() {
+        assert!(matches!(decode("not base64!"), Err(Scte35Error::InvalidEncoding(_))));
+        assert_eq!(decode("0xFF00"), Err(Scte35Error::BadTableId(0xff)));
+        assert!(matches!(decode("0xFC3020"), Err(Scte35Error::Truncated(_))));
+    }
+}
@@ -0,0 +1,373 @@
+//! Minimal MPEG-2 Transport Stream reader (ISO/IEC 13818-1).
+//!
+//! Only what segment inspection needs is decoded: the PAT and PMT of the
+//! first program, PES timestamps, PCRs and continuity counters.
+
+use std::collections::{BTreeMap, HashMap};
+
+use thiserror::Error;
+
+pub const PACKET_SIZE: usize = 188;
+const SYNC_BYTE: u8 = 0x47;
+const PAT_PID: u16 = 0x0000;
+const NULL_PID: u16 = 0x1fff;
+
+/// Ticks per second of PTS/DTS and of the PCR base.
+pub const CLOCK_HZ: u64 = 90_000;
+/// PTS and the PCR base are 33-bit counters.
+pub const TIMESTAMP_WRAP: u64 = 1 << 33;
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum TsError {
+    #[error("not an MPEG-TS segment (no sync byte at offset 0)")]
+    NotTransportStream,
+    #[error("lost sync at packet {0}")]
+    LostSync(usize),
+}
+
+/// An elementary stream announced in the PMT.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ElementaryStream {
+    pub pid: u16,
+    pub stream_type: u8,
+    pub packets: u64,
+    pub first_pts: Option<u64>,
+    pub last_pts: Option<u64>,
+}
+
+/// A packet whose continuity counter did not follow the previous one on its PID.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContinuityError {
+    pub pid: u16,
+    pub packet: usize,
+    pub expected: u8,
+    pub found: u8,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TsSummary {
+    pub has_pat: bool,
+    pub has_pmt: bool,
+    /// Streams of the first program, by PID.
+    pub streams: Vec<ElementaryStream>,
+    pub pcr_pid: Option<u16>,
+    /// First and last PCR base (90 kHz) on the PCR PID.
+    pub first_pcr: Option<u64>,
+    pub last_pcr: Option<u64>,
+    pub continuity_errors: Vec<ContinuityError>,
+}
+
+impl TsSummary {
+    pub fn stream(&self, pid: u16) -> Option<&ElementaryStream> {
+        self.streams.iter().find(|s| s.pid == pid)
+    }
+}
+
+/// Signed difference `to - from` of two 33-bit timestamps, in ticks.
+pub fn timestamp_delta(from: u64, to: u64) -> i64 {
+    let diff = to.wrapping_sub(from) % TIMESTAMP_WRAP;
+    if diff >= TIMESTAMP_WRAP / 2 {
+        diff as i64 - TIMESTAMP_WRAP as i64
+    } else {
+        diff as i64
+    }
+}
+
+/// Read a whole segment.
+pub fn parse(data: &[u8]) -> Result<TsSummary, TsError> {
+    if data.first() != Some(&SYNC_BYTE) {
+        return Err(TsError::NotTransportStream);
+    }
+
+    let mut summary = TsSummary::default();
+    let mut pmt_pid: Option<u16> = None;
+    let mut pmt_streams: BTreeMap<u16, u8> = BTreeMap::new();
+    let mut counters: HashMap<u16, u8> = HashMap::new();
+    let mut packets: HashMap<u16, u64> = HashMap::new();
+    let mut pts: HashMap<u16, (u64, u64)> = HashMap::new();
+    let mut pcrs: HashMap<u16, (u64, u64)> = HashMap::new();
+
+    for (index, packet) in data.chunks_exact(PACKET_SIZE).enumerate() {
+        if packet[0] != SYNC_BYTE {
+            return Err(TsError::LostSync(index));
+        }
+        let pusi = packet[1] & 0x40 != 0;
+        let pid = (u16::from(packet[1] & 0x1f) << 8) | u16::from(packet[2]);
+        let adaptation_field_control = (packet[3] >> 4) & 0x03;
+        let cc = packet[3] & 0x0f;
+        if pid == NULL_PID {
+            continue;
+        }
+        *packets.entry(pid).or_default() += 1;
+
+        let mut payload_start = 4;
+        let mut discontinuity_indicator = false;
+        if adaptation_field_control & 0x02 != 0 {
+            let length = usize::from(packet[4]);
+            if length > 0 {
+                let flags = packet[5];
+                discontinuity_indicator = flags & 0x80 != 0;
+                if flags & 0x10 != 0 && length >= 7 {
+                    let pcr = read_pcr_base(&packet[6..11]);
+                    pcrs.entry(pid).and_modify(|e| e.1 = pcr).or_insert((pcr, pcr));
+                }
+            }
+            payload_start = 5 + length;
+        }
+
+        let has_payload = adaptation_field_control & 0x01 != 0;
+        if has_payload {
+            if let Some(&prev) = counters.get(&pid) {
+                let expected = (prev + 1) & 0x0f;
+                // A single repeated packet keeps its counter.
+                if cc != expected && cc != prev && !discontinuity_indicator {
+                    summary.continuity_errors.push(ContinuityError { pid, packet: index, expected, found: cc });
+                }
+            }
+            counters.insert(pid, cc);
+        }
+
+        if !has_payload || !pusi || payload_start >= PACKET_SIZE {
+            continue;
+        }
+        let payload = &packet[payload_start..];
+
+        if pid == PAT_PID {
+            if let Some(pid) = parse_pat(payload) {
+                summary.has_pat = true;
+                pmt_pid.get_or_insert(pid);
+            }
+        } else if Some(pid) == pmt_pid {
+            if let Some((pcr_pid, streams)) = parse_pmt(payload) {
+                summary.has_pmt = true;
+                summary.pcr_pid = Some(pcr_pid);
+                pmt_streams = streams;
+            }
+        } else if let Some(ts) = read_pes_pts(payload) {
+            pts.entry(pid).and_modify(|e| e.1 = ts).or_insert((ts, ts));
+        }
+    }
+
+    summary.streams = pmt_streams
+        .into_iter()
+        .map(|(pid, stream_type)| ElementaryStream {
+            pid,
+            stream_type,
+            packets: packets.get(&pid).copied().unwrap_or(0),
+            first_pts: pts.get(&pid).map(|p| p.0),
+            last_pts: pts.get(&pid).map(|p| p.1),
+        })
+        .collect();
+    if let Some(&(first, last)) = summary.pcr_pid.and_then(|pid| pcrs.get(&pid)) {
+        summary.first_pcr = Some(first);
+        summary.last_pcr = Some(last);
+    }
+
+    Ok(summary)
+}
+
+/// Start of the PSI section after the pointer field, with its declared end
+/// (excluding the CRC).
+fn psi_section(payload: &[u8], table_id: u8) -> Option<&[u8]> {
+    let pointer = usize::from(*payload.first()?);
+    let section = payload.get(1 + pointer..)?;
+    if *section.first()? != table_id {
+        return None;
+    }
+    let length = (usize::from(*section.get(1)? & 0x0f) << 8) | usize::from(*section.get(2)?);
+    let end = (3 + length).checked_sub(4)?;
+    section.get(..end)
+}
+
+/// PID of the first program's PMT.
+fn parse_pat(payload: &[u8]) -> Option<u16> {
+    let section = psi_section(payload, 0x00)?;
+    section.get(8..)?.chunks_exact(4).find_map(|entry| {
+        let program = u16::from_be_bytes([entry[0], entry[1]]);
+        let pid = (u16::from(entry[2] & 0x1f) << 8) | u16::from(entry[3]);
+        // Program 0 points at the network information table.
+        (program != 0).then_some(pid)
+    })
+}
+
+/// PCR PID and `pid -> stream_type` of every elementary stream.
+fn parse_pmt(payload: &[u8]) -> Option<(u16, BTreeMap<u16, u8>)> {
+    let section = psi_section(payload, 0x02)?;
+    let pcr_pid = (u16::from(*section.get(8)? & 0x1f) << 8) | u16::from(*section.get(9)?);
+    let program_info_length = (usize::from(*section.get(10)? & 0x0f) << 8) | usize::from(*section.get(11)?);
+
+    let mut streams = BTreeMap::new();
+    let mut i = 12 + program_info_length;
+    while i + 5 <= section.len() {
+        let stream_type = section[i];
+        let pid = (u16::from(section[i + 1] & 0x1f) << 8) | u16::from(section[i + 2]);
+        let es_info_length = (usize::from(section[i + 3] & 0x0f) << 8) | usize::from(section[i + 4]);
+        streams.insert(pid, stream_type);
+        i += 5 + es_info_length;
+    }
+    Some((pcr_pid, streams))
+}
+
+/// PTS of a PES packet header, if it carries one.
+fn read_pes_pts(payload: &[u8]) -> Option<u64> {
+    if payload.get(..3)? != [0x00, 0x00, 0x01] {
+        return None;
+    }
+    let flags = *payload.get(7)?;
+    if flags & 0x80 == 0 {
+        return None;
+    }
+    let b = payload.get(9..14)?;
+    Some(
+        (u64::from(b[0] >> 1 & 0x07) << 30)
+            | (u64::from(b[1]) << 22)
+            | (u64::from(b[2] >> 1) << 15)
+            | (u64::from(b[3]) << 7)
+            | u64::from(b[4] >> 1),
+    )
+}
+
+fn read_pcr_base(b: &[u8]) -> u64 {
+    (u64::from(b[0]) << 25) | (u64::from(b[1]) << 17) | (u64::from(b[2]) << 9) | (u64::from(b[3]) << 1) | u64::from(b[4] >> 7)
+}
+
+/// Builders for synthetic transport streams, shared with the inspection tests.
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+
+    pub const PMT_PID: u16 = 0x1000;
+    pub const VIDEO_PID: u16 = 0x0100;
+    pub const AUDIO_PID: u16 = 0x0101;
+
+    fn packet(pid: u16, pusi: bool, cc: u8, adaptation: Option<Vec<u8>>, payload: &[u8]) -> Vec<u8> {
+        let mut p = vec![SYNC_BYTE, (pusi as u8) << 6 | (pid >> 8) as u8 & 0x1f, pid as u8];
+        let afc = match (&adaptation, payload.is_empty()) {
+            (Some(_), true) => 0x20,
+            (Some(_), false) => 0x30,
+            (None, _) => 0x10,
+        };
+        p.push(afc | (cc & 0x0f));
+        if let Some(af) = adaptation {
+            p.push(af.len() as u8);
+            p.extend(af);
+        }
+        p.extend_from_slice(payload);
+        p.resize(PACKET_SIZE, 0xff);
+        p
+    }
+
+    fn section(table_id: u8, body: &[u8]) -> Vec<u8> {
+        // pointer_field, table_id, section_length, body, dummy CRC
+        let length = body.len() + 4;
+        let mut s = vec![0x00, table_id, 0xb0 | (length >> 8) as u8, length as u8];
+        s.extend_from_slice(body);
+        s.extend_from_slice(&[0, 0, 0, 0]);
+        s
+    }
+
+    pub fn pat() -> Vec<u8> {
+        let body = [0x00, 0x01, 0xc1, 0x00, 0x00, 0x00, 0x01, 0xe0 | (PMT_PID >> 8) as u8, PMT_PID as u8];
+        packet(PAT_PID, true, 0, None, &section(0x00, &body))
+    }
+
+    pub fn pmt(pids: &[(u16, u8)]) -> Vec<u8> {
+        let mut body = vec![0x00, 0x01, 0xc1, 0x00, 0x00, 0xe0 | (VIDEO_PID >> 8) as u8, VIDEO_PID as u8, 0xf0, 0x00];
+        for &(pid, stream_type) in pids {
+            body.extend_from_slice(&[stream_type, 0xe0 | (pid >> 8) as u8, pid as u8, 0xf0, 0x00]);
+        }
+        packet(PMT_PID, true, 0, None, &section(0x02, &body))
+    }
+
+    pub fn pes(pid: u16, cc: u8, pts: u64, pcr: Option<u64>) -> Vec<u8> {
+        let pts_bytes = [
+            0x21 | ((pts >> 29) & 0x0e) as u8,
+            (pts >> 22) as u8,
+            0x01 | ((pts >> 14) & 0xfe) as u8,
+            (pts >> 7) as u8,
+            0x01 | ((pts << 1) & 0xfe) as u8,
+        ];
+        let mut payload = vec![0x00, 0x00, 0x01, 0xe0, 0x00, 0x00, 0x80, 0x80, 0x05];
+        payload.extend_from_slice(&pts_bytes);
+        let adaptation = pcr.map(|pcr| {
+            vec![0x10, (pcr >> 25) as u8, (pcr >> 17) as u8, (pcr >> 9) as u8, (pcr >> 1) as u8, ((pcr & 1) << 7) as u8 | 0x7e, 0x00]
+        });
+        packet(pid, true, cc, adaptation, &payload)
+    }
+
+    pub fn continuation(pid: u16, cc: u8) -> Vec<u8> {
+        packet(pid, false, cc, None, &[0xaa; 16])
+    }
+
+    /// A segment with one video and one audio PES per `frames` step of
+    /// `frame_ticks`, starting at `start` and with counters starting at `cc`.
+    pub fn segment(start: u64, frames: u64, frame_ticks: u64, cc: u8) -> Vec<u8> {
+        let mut out = pat();
+        out.extend(pmt(&[(VIDEO_PID, 0x1b), (AUDIO_PID, 0x0f)]));
+        for i in 0..frames {
+            let ts = start + i * frame_ticks;
+            let c = cc.wrapping_add(i as u8);
+            out.extend(pes(VIDEO_PID, c, ts, Some(ts)));
+            out.extend(pes(AUDIO_PID, c, ts, None));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::testing::*;
+    use super::*;
+
+    #[test]
+    fn rejects_non_ts_data() {
+        assert_eq!(parse(b"\x00\x00\x00\x18ftypiso6"), Err(TsError::NotTransportStream));
+        let mut data = segment(0, 2, 3000, 0);
+        data[PACKET_SIZE * 2] = 0x00;
+        assert_eq!(parse(&data), Err(TsError::LostSync(2)));
+    }
+
+    #[test]
+    fn reads_program_and_timestamps() {
+        let summary = parse(&segment(900_000, 4, 3000, 0)).unwrap();
+        assert!(summary.has_pat && summary.has_pmt);
+        assert_eq!(summary.pcr_pid, Some(VIDEO_PID));
+        let video = summary.stream(VIDEO_PID).unwrap();
+        assert_eq!(video.stream_type, 0x1b);
+        assert_eq!(video.packets, 4);
+        assert_eq!(video.first_pts, Some(900_000));
+        assert_eq!(video.last_pts, Some(909_000));
+        assert_eq!(summary.first_pcr, Some(900_000));
+        assert_eq!(summary.last_pcr, Some(909_000));
+        assert!(summary.continuity_errors.is_empty());
+    }
+
+    #[test]
+    fn detects_continuity_counter_jump() {
+        let mut data = segment(0, 2, 3000, 0);
+        data.extend(continuation(AUDIO_PID, 2));
+        data.extend(continuation(AUDIO_PID, 2)); // duplicate is allowed
+        data.extend(continuation(AUDIO_PID, 5));
+        let summary = parse(&data).unwrap();
+        assert_eq!(
+            summary.continuity_errors,
+            vec![ContinuityError { pid: AUDIO_PID, packet: 8, expected: 3, found: 5 }]
+        );
+    }
+
+    #[test]
+    fn counts_pids_without_packets() {
+        let mut data = pat();
+        data.extend(pmt(&[(VIDEO_PID, 0x1b), (AUDIO_PID, 0x0f)]));
+        data.extend(pes(VIDEO_PID, 0, 0, Some(0)));
+        let summary = parse(&data).unwrap();
+        assert_eq!(summary.stream(AUDIO_PID).unwrap().packets, 0);
+    }
+
+    #[test]
+    fn timestamp_delta_handles_wrap() {
+        assert_eq!(timestamp_delta(100, 400), 300);
+        assert_eq!(timestamp_delta(400, 100), -300);
+        assert_eq!(timestamp_delta(TIMESTAMP_WRAP - 100, 200), 300);
+    }
+}
@@ -0,0 +1,297 @@
+//! Planned vs. actual ad break duration.
+//!
+//! A break opens on a `CUE-OUT` segment and closes on the next `CUE-IN`
+//! segment. Its planned duration is the one announced on the `CUE-OUT`
+//! segment; its actual duration is the sum of the `EXTINF` durations of the
+//! segments in between, counted once each as they are published across polls.
+
+use std::ops::Range;
+
+use super::error::{ErrorType, MonitorError};
+use super::state::{CheckContext, PlaylistSnapshot};
+
+/// A break that has started but not yet returned to the network.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdBreak {
+    pub start_mseq: u64,
+    pub planned: Option<f64>,
+    pub elapsed: f64,
+    /// An over-run is reported once, as soon as it happens.
+    pub overrun_reported: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum BreakOutcome {
+    /// The open break has already run longer than planned.
+    Overrun {
+        start_mseq: u64,
+        planned: f64,
+        elapsed: f64,
+    },
+    Completed {
+        start_mseq: u64,
+        end_mseq: u64,
+        planned: Option<f64>,
+        actual: f64,
+        /// The actual duration is outside the tolerance and was not already
+        /// reported as an over-run.
+        mismatch: bool,
+    },
+}
+
+impl BreakOutcome {
+    /// Event text for a completed break.
+    pub fn describe(&self) -> Option<String> {
+        let BreakOutcome::Completed {
+            start_mseq,
+            end_mseq,
+            planned,
+            actual,
+            ..
+        } = self
+        else {
+            return None;
+        };
+        let planned = planned.map_or_else(|| "unknown".to_string(), |p| format!("{:.1}s", p));
+        Some(format!(
+            "Ad break mseq {}..{} ran {:.1}s, planned {}",
+            start_mseq, end_mseq, actual, planned
+        ))
+    }
+
+    pub fn to_error(&self, ctx: &CheckContext) -> Option<MonitorError> {
+        let details = match *self {
+            BreakOutcome::Overrun {
+                start_mseq,
+                planned,
+                elapsed,
+            } => format!(
+                "Ad break from mseq({}) over-ran: {:.3}s elapsed, planned {:.3}s, no CUE-IN yet",
+                start_mseq, elapsed, planned
+            ),
+            BreakOutcome::Completed {
+                start_mseq,
+                end_mseq,
+                planned: Some(planned),
+                actual,
+                mismatch: true,
+            } => format!(
+                "Ad break mseq({})..mseq({}) {}: actual {:.3}s, planned {:.3}s",
+                start_mseq,
+                end_mseq,
+                if actual < planned { "under-ran" } else { "over-ran" },
+                actual,
+                planned
+            ),
+            _ => return None,
+        };
+        Some(MonitorError::new(
+            ErrorType::AdBreakDurationMismatch,
+            &ctx.media_type,
+            &ctx.variant_key,
+            details,
+            &ctx.stream_url,
+            &ctx.stream_id,
+        ))
+    }
+}
+
+/// Advance the open break over the segments of `curr` past `prev_window`,
+/// closing it at a `CUE-IN` and opening a new one at a `CUE-OUT`.
+pub fn advance(
+    open: Option<AdBreak>,
+    prev_window: Option<Range<u64>>,
+    curr: &PlaylistSnapshot,
+    tolerance: f64,
+) -> (Option<AdBreak>, Vec<BreakOutcome>) {
+    let mut open = open;
+    let mut outcomes = Vec::new();
+
+    for (mseq, seg) in curr.segments_after(prev_window) {
+        if seg.cue_in || seg.cue_out {
+            if let Some(b) = open.take() {
+                let mismatch = b.planned.is_some_and(|p| {
+                    let off = b.elapsed - p;
+                    off < -tolerance || (off > tolerance && !b.overrun_reported)
+                });
+                outcomes.push(BreakOutcome::Completed {
+                    start_mseq: b.start_mseq,
+                    end_mseq: mseq,
+                    planned: b.planned,
+                    actual: b.elapsed,
+                    mismatch,
+                });
+            }
+        }
+        if seg.cue_out {
+            open = Some(AdBreak {
+                start_mseq: mseq,
+                planned: seg.cue_out_duration,
+                elapsed: 0.0,
+                overrun_reported: false,
+            });
+        }
+        if let Some(b) = open.as_mut() {
+            b.elapsed += seg.duration;
+            if let Some(planned) = b.planned.filter(|p| !b.overrun_reported && b.elapsed > p + tolerance) {
+                b.overrun_reported = true;
+                outcomes.push(BreakOutcome::Overrun {
+                    start_mseq: b.start_mseq,
+                    planned,
+                    elapsed: b.elapsed,
+                });
+            }
+        }
+    }
+
+    (open, outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::state::SegmentSnapshot;
+
+    fn seg(uri: &str, duration: f64, cue_out: Option<f64>, cue_in: bool) -> SegmentSnapshot {
+        SegmentSnapshot {
+            uri: uri.to_string(),
+            duration,
+            discontinuity: false,
+            cue_out: cue_out.is_some(),
+            cue_in,
+            cue_out_cont: None,
+            gap: false,
+            program_date_time: None,
+            daterange: None,
+            parts: vec![],
+            byte_range_length: None,
+            map: None,
+            scte35: vec![],
+            cue_out_duration: cue_out.filter(|d| *d > 0.0),
+            ad_markers: vec![],
+        }
+    }
+
+    fn snap(mseq: u64, segments: Vec<SegmentSnapshot>) -> PlaylistSnapshot {
+        PlaylistSnapshot {
+            media_sequence: mseq,
+            discontinuity_sequence: 0,
+            segments,
+            duration: 0.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            has_cue_out: false,
+            cue_out_duration: None,
+            target_duration: 6.0,
+            playlist_type: None,
+            version: Some(3),
+            has_gaps: false,
+            has_endlist: false,
+            i_frames_only: false,
+            has_byte_range: false,
+            has_map: false,
+            has_key_iv: false,
+            has_key_format: false,
+            keys: vec![],
+            part_target: None,
+            pending_parts: vec![],
+            preload_hints: vec![],
+            server_control: None,
+            skipped_segments: None,
+            rendition_reports: vec![],
+            key_uri: None,
+        }
+    }
+
+    fn window(snapshot: &PlaylistSnapshot) -> Option<Range<u64>> {
+        let start = snapshot.media_sequence;
+        Some(start..start + snapshot.segments.len() as u64)
+    }
+
+    #[test]
+    fn break_tracked_across_polls_and_completed() {
+        let first = snap(0, vec![seg("a.ts", 6.0, None, false), seg("ad0.ts", 6.0, Some(18.0), false)]);
+        let (open, outcomes) = advance(None, None, &first, 1.0);
+        assert!(outcomes.is_empty());
+        assert_eq!(open.as_ref().map(|b| (b.start_mseq, b.elapsed)), Some((1, 6.0)));
+
+        // ad0.ts is not counted twice.
+        let second = snap(1, vec![seg("ad0.ts", 6.0, Some(18.0), false), seg("ad1.ts", 6.0, None, false)]);
+        let (open, outcomes) = advance(open, window(&first), &second, 1.0);
+        assert!(outcomes.is_empty());
+        assert_eq!(open.as_ref().unwrap().elapsed, 12.0);
+
+        let third = snap(2, vec![seg("ad1.ts", 6.0, None, false), seg("ad2.ts", 6.0, None, false), seg("b.ts", 6.0, None, true)]);
+        let (open, outcomes) = advance(open, window(&second), &third, 1.0);
+        assert!(open.is_none());
+        assert_eq!(
+            outcomes,
+            vec![BreakOutcome::Completed {
+                start_mseq: 1,
+                end_mseq: 4,
+                planned: Some(18.0),
+                actual: 18.0,
+                mismatch: false,
+            }]
+        );
+        assert_eq!(outcomes[0].describe().unwrap(), "Ad break mseq 1..4 ran 18.0s, planned 18.0s");
+    }
+
+    #[test]
+    fn under_run_is_a_mismatch() {
+        let curr = snap(0, vec![seg("ad0.ts", 6.0, Some(30.0), false), seg("b.ts", 6.0, None, true)]);
+        let (open, outcomes) = advance(None, None, &curr, 1.0);
+        assert!(open.is_none());
+        let ctx = CheckContext {
+            stream_url: "http://example.com/".to_string(),
+            stream_id: "stream_1".to_string(),
+            media_type: "VIDEO".to_string(),
+            variant_key: "1200000".to_string(),
+        };
+        let error = outcomes[0].to_error(&ctx).unwrap();
+        assert_eq!(error.error_type, ErrorType::AdBreakDurationMismatch);
+        assert!(error.details.contains("under-ran: actual 6.000s, planned 30.000s"), "{}", error.details);
+    }
+
+    #[test]
+    fn over_run_reported_once_while_open() {
+        let curr = snap(
+            0,
+            vec![
+                seg("ad0.ts", 6.0, Some(6.0), false),
+                seg("ad1.ts", 6.0, None, false),
+                seg("ad2.ts", 6.0, None, false),
+                seg("b.ts", 6.0, None, true),
+            ],
+        );
+        let (_, outcomes) = advance(None, None, &curr, 1.0);
+        assert_eq!(outcomes.len(), 2);
+        assert!(matches!(outcomes[0], BreakOutcome::Overrun { elapsed, .. } if elapsed == 12.0));
+        assert!(matches!(outcomes[1], BreakOutcome::Completed { mismatch: false, actual, .. } if actual == 18.0));
+    }
+
+    #[test]
+    fn break_without_announced_duration_is_not_a_mismatch() {
+        let curr = snap(0, vec![seg("ad0.ts", 6.0, Some(0.0), false), seg("b.ts", 6.0, None, true)]);
+        let (_, outcomes) = advance(None, None, &curr, 1.0);
+        assert!(matches!(outcomes[0], BreakOutcome::Completed { planned: None, mismatch: false, .. }));
+        assert!(outcomes[0].describe().unwrap().ends_with("planned unknown"));
+    }
+
+    #[test]
+    fn byte_range_segments_counted_once_each() {
+        // Every segment of a single-file playlist shares one URI.
+        let first = snap(0, vec![seg("main.ts", 6.0, None, false), seg("main.ts", 6.0, Some(18.0), false)]);
+        let (open, _) = advance(None, None, &first, 1.0);
+        assert_eq!(open.as_ref().unwrap().elapsed, 6.0);
+
+        let second = snap(1, vec![seg("main.ts", 6.0, Some(18.0), false), seg("main.ts", 6.0, None, false)]);
+        let (open, _) = advance(open, window(&first), &second, 1.0);
+        assert_eq!(open.as_ref().unwrap().elapsed, 12.0);
+
+        let third = snap(2, vec![seg("main.ts", 6.0, None, false), seg("main.ts", 6.0, None, false), seg("main.ts", 6.0, None, true)]);
+        let (open, outcomes) = advance(open, window(&second), &third, 1.0);
+        assert!(open.is_none());
+        assert!(matches!(outcomes[..], [BreakOutcome::Completed { start_mseq: 1, end_mseq: 4, actual, mismatch: false, .. }] if actual == 18.0));
+    }
+}
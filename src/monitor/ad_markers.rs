@@ -0,0 +1,329 @@
+//! Ad break markers normalized across packager dialects.
+//!
+//! Packagers signal the same break in different ways: `EXT-X-CUE-OUT`/`IN`,
+//! `EXT-OATCLS-SCTE35` with `EXT-X-ASSET`, `EXT-X-SCTE35`, or an
+//! `EXT-X-DATERANGE` carrying `SCTE35-OUT`/`IN`. Each is turned into an
+//! [`AdMarker`] here, so break tracking, events and checks only look at the
+//! segment's `cue_out` / `cue_in` / `cue_out_cont`, whatever the dialect.
+
+use m3u8_rs::MediaSegment;
+
+use crate::config::AdDialect;
+use crate::media::scte35;
+
+use super::state::{Scte35Marker, Scte35Source};
+use super::tags;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdSignal {
+    /// The break starts at this segment.
+    Out,
+    /// The segment is inside a break that started earlier.
+    Cont,
+    /// The segment returns to the network.
+    In,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdMarker {
+    pub dialect: AdDialect,
+    pub signal: AdSignal,
+    /// Announced break duration in seconds.
+    pub duration: Option<f64>,
+    /// Seconds into the break, for `Cont`.
+    pub elapsed: Option<f64>,
+    /// Tag value as written, shown in continuation events.
+    pub text: Option<String>,
+}
+
+impl AdMarker {
+    fn new(dialect: AdDialect, signal: AdSignal) -> Self {
+        Self {
+            dialect,
+            signal,
+            duration: None,
+            elapsed: None,
+            text: None,
+        }
+    }
+}
+
+fn tag<'a>(seg: &'a MediaSegment, name: &str) -> Option<&'a m3u8_rs::ExtTag> {
+    seg.unknown_tags.iter().find(|t| t.tag == name)
+}
+
+fn parse_secs(value: &str) -> Option<f64> {
+    value.trim().parse::<f64>().ok()
+}
+
+/// `EXT-X-CUE-OUT`, `EXT-X-CUE-OUT-CONT` and `EXT-X-CUE-IN`.
+fn cue_markers(seg: &MediaSegment) -> Vec<AdMarker> {
+    let mut markers = Vec::new();
+    if let Some(t) = tag(seg, "X-CUE-OUT") {
+        let mut m = AdMarker::new(AdDialect::Cue, AdSignal::Out);
+        // `:30` or `:DURATION=30`
+        m.duration = t.rest.as_deref().and_then(|rest| {
+            if rest.contains('=') {
+                parse_secs(tags::parse_attribute_list(rest).get("DURATION")?)
+            } else {
+                parse_secs(rest)
+            }
+        });
+        markers.push(m);
+    }
+    if let Some(t) = tag(seg, "X-CUE-OUT-CONT") {
+        let mut m = AdMarker::new(AdDialect::Cue, AdSignal::Cont);
+        // `:10/30` or `:ElapsedTime=10,Duration=30,...`
+        if let Some(rest) = t.rest.as_deref() {
+            if rest.contains('=') {
+                let attrs = tags::parse_attribute_list(rest);
+                m.elapsed = attrs.get("ElapsedTime").and_then(|v| parse_secs(v));
+                m.duration = attrs.get("Duration").and_then(|v| parse_secs(v));
+            } else if let Some((elapsed, duration)) = rest.split_once('/') {
+                m.elapsed = parse_secs(elapsed);
+                m.duration = parse_secs(duration);
+            } else {
+                m.elapsed = parse_secs(rest);
+            }
+        }
+        m.text = t.rest.clone();
+        markers.push(m);
+    }
+    if tag(seg, "X-CUE-IN").is_some() {
+        markers.push(AdMarker::new(AdDialect::Cue, AdSignal::In));
+    }
+    markers
+}
+
+/// Collect the SCTE-35 payloads of a segment's DATERANGE, `EXT-OATCLS-SCTE35`
+/// and `EXT-X-SCTE35` tags, with the break direction and duration each tag states.
+pub fn scte35_markers(seg: &MediaSegment) -> Vec<Scte35Marker> {
+    let cues = cue_markers(seg);
+    let cue_out = cues.iter().find(|m| m.signal == AdSignal::Out);
+    let segment_out = if cue_out.is_some() {
+        Some(true)
+    } else if cues.iter().any(|m| m.signal == AdSignal::In) {
+        Some(false)
+    } else {
+        None
+    };
+    let cue_out_duration = cue_out.and_then(|m| m.duration);
+    let marker = |source, payload: &str, tag_out, tag_duration| Scte35Marker {
+        source,
+        payload: payload.to_string(),
+        signal: scte35::decode(payload),
+        tag_out,
+        tag_duration,
+    };
+
+    let mut markers = Vec::new();
+    if let Some(dr) = &seg.daterange {
+        let attrs = dr.other_attributes.as_ref();
+        for (name, source, tag_out) in [
+            ("SCTE35-OUT", Scte35Source::DateRangeOut, Some(true)),
+            ("SCTE35-IN", Scte35Source::DateRangeIn, Some(false)),
+            ("SCTE35-CMD", Scte35Source::DateRangeCmd, None),
+        ] {
+            if let Some(value) = attrs.and_then(|a| a.get(name)) {
+                let duration = if tag_out == Some(true) {
+                    dr.duration.or(dr.planned_duration)
+                } else {
+                    None
+                };
+                markers.push(marker(source, value.as_str(), tag_out, duration));
+            }
+        }
+    }
+    for t in &seg.unknown_tags {
+        let Some(rest) = t.rest.as_deref() else {
+            continue;
+        };
+        match t.tag.as_str() {
+            "OATCLS-SCTE35" => {
+                markers.push(marker(Scte35Source::Oatcls, rest, segment_out, cue_out_duration));
+            }
+            "X-SCTE35" => {
+                let attrs = tags::parse_attribute_list(rest);
+                let Some(cue) = attrs.get("CUE") else {
+                    continue;
+                };
+                let tag_out = match (attrs.get("CUE-OUT").map(String::as_str), attrs.get("CUE-IN").map(String::as_str)) {
+                    (Some("YES"), _) => Some(true),
+                    (_, Some("YES")) => Some(false),
+                    (Some(_), _) => None,
+                    _ => segment_out,
+                };
+                let duration = attrs.get("DURATION").and_then(|d| parse_secs(d)).or(cue_out_duration);
+                markers.push(marker(Scte35Source::XScte35, cue, tag_out, duration));
+            }
+            _ => {}
+        }
+    }
+    markers
+}
+
+/// Ad markers of a segment in `dialect`, or in every dialect for `Auto`.
+///
+/// `scte35` are the segment's decoded payloads, used for the DATERANGE,
+/// OATCLS and `EXT-X-SCTE35` dialects and as the fallback break duration.
+pub fn from_segment(seg: &MediaSegment, scte35: &[Scte35Marker], dialect: AdDialect) -> Vec<AdMarker> {
+    let payload_duration = |source| {
+        scte35
+            .iter()
+            .filter(|m| m.source == source)
+            .find_map(|m| m.signal.as_ref().ok()?.duration_secs())
+    };
+    let payload_out = |source| {
+        scte35
+            .iter()
+            .filter(|m| m.source == source)
+            .find_map(|m| m.signal.as_ref().ok()?.out_of_network())
+    };
+
+    let mut markers = cue_markers(seg);
+    if let Some(out) = markers.iter_mut().find(|m| m.signal == AdSignal::Out) {
+        // A bare `EXT-X-CUE-OUT` takes its duration from an accompanying payload.
+        out.duration = out.duration.or_else(|| {
+            scte35
+                .iter()
+                .filter(|m| m.tag_out == Some(true))
+                .find_map(|m| m.tag_duration.or_else(|| m.signal.as_ref().ok()?.duration_secs()))
+        });
+    }
+
+    for m in scte35.iter().filter(|m| m.source == Scte35Source::DateRangeOut) {
+        let mut marker = AdMarker::new(AdDialect::Daterange, AdSignal::Out);
+        marker.duration = m.tag_duration.or_else(|| m.signal.as_ref().ok()?.duration_secs());
+        markers.push(marker);
+    }
+    if scte35.iter().any(|m| m.source == Scte35Source::DateRangeIn) {
+        markers.push(AdMarker::new(AdDialect::Daterange, AdSignal::In));
+    }
+
+    if let Some(out) = payload_out(Scte35Source::Oatcls) {
+        let mut marker = AdMarker::new(AdDialect::Oatcls, if out { AdSignal::Out } else { AdSignal::In });
+        if out {
+            marker.duration = payload_duration(Scte35Source::Oatcls);
+        }
+        marker.text = tag(seg, "X-ASSET").and_then(|t| t.rest.clone());
+        markers.push(marker);
+    }
+
+    if let Some(rest) = tag(seg, "X-SCTE35").and_then(|t| t.rest.as_deref()) {
+        let attrs = tags::parse_attribute_list(rest);
+        let signal = match (attrs.get("CUE-OUT").map(String::as_str), attrs.get("CUE-IN").map(String::as_str)) {
+            (Some("YES"), _) => Some(AdSignal::Out),
+            (Some("CONT"), _) => Some(AdSignal::Cont),
+            (_, Some("YES")) => Some(AdSignal::In),
+            _ => match payload_out(Scte35Source::XScte35) {
+                Some(true) => Some(AdSignal::Out),
+                Some(false) => Some(AdSignal::In),
+                None => None,
+            },
+        };
+        if let Some(signal) = signal {
+            let mut marker = AdMarker::new(AdDialect::Scte35, signal);
+            if signal != AdSignal::In {
+                marker.duration = attrs
+                    .get("DURATION")
+                    .and_then(|d| parse_secs(d))
+                    .or_else(|| payload_duration(Scte35Source::XScte35));
+            }
+            if signal == AdSignal::Cont {
+                marker.elapsed = attrs.get("ELAPSED").and_then(|e| parse_secs(e));
+                marker.text = Some(rest.to_string());
+            }
+            markers.push(marker);
+        }
+    }
+
+    markers.retain(|m| dialect == AdDialect::Auto || m.dialect == dialect);
+    markers
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::scte35::testing;
+
+    fn segment(tags: &str) -> MediaSegment {
+        let body = format!("#EXTM3U\n#EXT-X-TARGETDURATION:10\n{}#EXTINF:10.0,\nseg.ts\n", tags);
+        match m3u8_rs::parse_media_playlist_res(body.as_bytes()) {
+            Ok(pl) => pl.segments.into_iter().next().unwrap(),
+            Err(e) => panic!("{:?}", e),
+        }
+    }
+
+    fn markers(tags: &str, dialect: AdDialect) -> Vec<AdMarker> {
+        let seg = segment(tags);
+        let scte35 = scte35_markers(&seg);
+        from_segment(&seg, &scte35, dialect)
+    }
+
+    fn signals(markers: &[AdMarker]) -> Vec<(AdDialect, AdSignal, Option<f64>)> {
+        markers.iter().map(|m| (m.dialect, m.signal, m.duration)).collect()
+    }
+
+    #[test]
+    fn cue_dialect() {
+        let out = markers("#EXT-X-CUE-OUT:DURATION=30\n", AdDialect::Auto);
+        assert_eq!(signals(&out), vec![(AdDialect::Cue, AdSignal::Out, Some(30.0))]);
+
+        let cont = markers("#EXT-X-CUE-OUT-CONT:ElapsedTime=10.0,Duration=30\n", AdDialect::Auto);
+        assert_eq!(cont[0].signal, AdSignal::Cont);
+        assert_eq!((cont[0].elapsed, cont[0].duration), (Some(10.0), Some(30.0)));
+        assert_eq!(markers("#EXT-X-CUE-OUT-CONT:10/30\n", AdDialect::Cue)[0].elapsed, Some(10.0));
+
+        let back = markers("#EXT-X-CUE-IN\n", AdDialect::Auto);
+        assert_eq!(signals(&back), vec![(AdDialect::Cue, AdSignal::In, None)]);
+    }
+
+    #[test]
+    fn oatcls_dialect_reads_the_payload() {
+        let tags = format!(
+            "#EXT-OATCLS-SCTE35:{}\n#EXT-X-ASSET:CAID=0x0000000020ED1BE1\n",
+            testing::splice_insert(1, true, Some(30.0))
+        );
+        let out = markers(&tags, AdDialect::Oatcls);
+        assert_eq!(signals(&out), vec![(AdDialect::Oatcls, AdSignal::Out, Some(30.0))]);
+        assert_eq!(out[0].text.as_deref(), Some("CAID=0x0000000020ED1BE1"));
+
+        let tags = format!("#EXT-OATCLS-SCTE35:{}\n", testing::splice_insert(1, false, None));
+        assert_eq!(markers(&tags, AdDialect::Oatcls)[0].signal, AdSignal::In);
+    }
+
+    #[test]
+    fn scte35_and_daterange_dialects() {
+        let cue = testing::splice_insert(1, true, Some(15.0));
+        let tags = format!("#EXT-X-SCTE35:CUE=\"{}\",CUE-OUT=YES\n", cue);
+        assert_eq!(signals(&markers(&tags, AdDialect::Scte35)), vec![(AdDialect::Scte35, AdSignal::Out, Some(15.0))]);
+
+        let tags = format!("#EXT-X-SCTE35:CUE=\"{}\",CUE-OUT=CONT,ELAPSED=5,DURATION=15\n", cue);
+        let cont = markers(&tags, AdDialect::Scte35);
+        assert_eq!((cont[0].signal, cont[0].elapsed), (AdSignal::Cont, Some(5.0)));
+
+        let tags = "#EXT-X-DATERANGE:ID=\"1\",START-DATE=\"2024-01-01T00:00:00Z\",PLANNED-DURATION=20,SCTE35-OUT=0xFC\n";
+        assert_eq!(
+            signals(&markers(tags, AdDialect::Daterange)),
+            vec![(AdDialect::Daterange, AdSignal::Out, Some(20.0))]
+        );
+    }
+
+    #[test]
+    fn dialect_filter_and_duration_fallback() {
+        let tags = format!(
+            "#EXT-OATCLS-SCTE35:{}\n#EXT-X-CUE-OUT\n",
+            testing::splice_insert(1, true, Some(30.0))
+        );
+        // The bare CUE-OUT borrows the payload's break_duration.
+        assert_eq!(
+            signals(&markers(&tags, AdDialect::Auto)),
+            vec![
+                (AdDialect::Cue, AdSignal::Out, Some(30.0)),
+                (AdDialect::Oatcls, AdSignal::Out, Some(30.0)),
+            ]
+        );
+        assert!(markers(&tags, AdDialect::Daterange).is_empty());
+        assert!(markers("#EXT-X-CUE-OUT:30\n", AdDialect::Scte35).is_empty());
+    }
+}
@@ -0,0 +1,232 @@
+//! Measured segment bitrate vs. the advertised `BANDWIDTH` / `AVERAGE-BANDWIDTH`.
+//!
+//! Segment sizes come from `EXT-X-BYTERANGE` when present, otherwise from the
+//! Content-Length of a probe request, and as a last resort from downloading
+//! the segment. The bitrate of a segment is its size over its `EXTINF`
+//! duration.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+
+use futures::stream::{self, StreamExt};
+use tracing::debug;
+
+use crate::config::MonitorConfig;
+use crate::loader::ManifestLoader;
+
+use super::error::{ErrorType, MonitorError};
+use super::state::CheckContext;
+
+/// Number of most recent segments the rolling average is computed over.
+pub const BITRATE_WINDOW: usize = 5;
+
+/// Bitrates a variant declares in the master playlist, in bits per second.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Advertised {
+    pub bandwidth: u64,
+    pub average_bandwidth: Option<u64>,
+}
+
+/// A segment whose size is to be measured.
+#[derive(Debug, Clone)]
+pub struct MeasureTarget {
+    pub url: String,
+    pub duration: f64,
+    pub byte_range_length: Option<u64>,
+    pub variant_key: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BitrateSample {
+    pub uri: String,
+    pub bytes: u64,
+    pub duration: f64,
+}
+
+impl BitrateSample {
+    pub fn bits_per_second(&self) -> f64 {
+        self.bytes as f64 * 8.0 / self.duration
+    }
+}
+
+/// Measure the size of every target, keeping the input order.
+///
+/// Segments that cannot be measured are left out; reporting unavailable
+/// segments is the job of the availability probe.
+pub async fn measure(
+    loader: &Arc<dyn ManifestLoader>,
+    targets: Vec<MeasureTarget>,
+    config: &MonitorConfig,
+) -> Vec<(MeasureTarget, BitrateSample)> {
+    let method = config.probe_method;
+    let measured: Vec<_> = stream::iter(targets)
+        .map(|target| {
+            let loader = Arc::clone(loader);
+            async move {
+                let bytes = match target.byte_range_length {
+                    Some(len) => Some(len),
+                    None => match loader.probe(&target.url, method).await {
+                        Ok(resp) if resp.content_length.is_some_and(|len| len > 0) => resp.content_length,
+                        _ => match loader.fetch_segment(&target.url).await {
+                            Ok(body) => Some(body.len() as u64),
+                            Err(e) => {
+                                debug!(url = %target.url, error = %e, "Could not measure segment size");
+                                None
+                            }
+                        },
+                    },
+                };
+                (target, bytes)
+            }
+        })
+        .buffered(config.max_concurrent_fetches.max(1))
+        .collect()
+        .await;
+
+    measured
+        .into_iter()
+        .filter_map(|(target, bytes)| {
+            let sample = BitrateSample {
+                uri: target.url.clone(),
+                bytes: bytes.filter(|b| *b > 0)?,
+                duration: target.duration,
+            };
+            Some((target, sample))
+        })
+        .collect()
+}
+
+/// Add new samples to a variant's rolling window and compare them with what
+/// the variant advertises.
+///
+/// Every sample is checked against BANDWIDTH (the peak segment bitrate); the
+/// window average is checked against AVERAGE-BANDWIDTH once the window is full.
+/// Both allow `margin` (a fraction) of overshoot.
+pub fn check_bitrate(
+    window: &mut VecDeque<BitrateSample>,
+    samples: Vec<BitrateSample>,
+    advertised: Advertised,
+    margin: f64,
+    ctx: &CheckContext,
+) -> Vec<MonitorError> {
+    let mut errors = Vec::new();
+    let error = |error_type, details: String| {
+        MonitorError::new(
+            error_type,
+            &ctx.media_type,
+            &ctx.variant_key,
+            details,
+            &ctx.stream_url,
+            &ctx.stream_id,
+        )
+    };
+
+    let mut added = false;
+    for sample in samples.into_iter().filter(|s| s.duration > 0.0) {
+        let peak_limit = advertised.bandwidth as f64 * (1.0 + margin);
+        let bps = sample.bits_per_second();
+        if bps > peak_limit {
+            errors.push(error(
+                ErrorType::BitrateExceeded,
+                format!(
+                    "Segment '{}' measured {:.0} bps ({} bytes over {:.3}s), above BANDWIDTH={}",
+                    sample.uri, bps, sample.bytes, sample.duration, advertised.bandwidth
+                ),
+            ));
+        }
+        window.push_back(sample);
+        added = true;
+    }
+    while window.len() > BITRATE_WINDOW {
+        window.pop_front();
+    }
+
+    if let Some(average_bandwidth) = advertised.average_bandwidth {
+        if added && window.len() == BITRATE_WINDOW {
+            let bits: f64 = window.iter().map(|s| s.bytes as f64 * 8.0).sum();
+            let seconds: f64 = window.iter().map(|s| s.duration).sum();
+            let average = bits / seconds;
+            if average > average_bandwidth as f64 * (1.0 + margin) {
+                errors.push(error(
+                    ErrorType::AverageBitrateExceeded,
+                    format!(
+                        "Average of the last {} segments measured {:.0} bps, above AVERAGE-BANDWIDTH={}",
+                        BITRATE_WINDOW, average, average_bandwidth
+                    ),
+                ));
+            }
+        }
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> CheckContext {
+        CheckContext {
+            stream_url: "http://example.com/".to_string(),
+            stream_id: "stream_1".to_string(),
+            media_type: "VIDEO".to_string(),
+            variant_key: "1000000".to_string(),
+        }
+    }
+
+    /// A 2s segment measuring `kbps` kilobits per second.
+    fn sample(i: usize, kbps: u64) -> BitrateSample {
+        BitrateSample {
+            uri: format!("s{}.ts", i),
+            bytes: kbps * 1000 * 2 / 8,
+            duration: 2.0,
+        }
+    }
+
+    const ADVERTISED: Advertised = Advertised {
+        bandwidth: 1_000_000,
+        average_bandwidth: Some(800_000),
+    };
+
+    #[test]
+    fn no_error_within_advertised_bitrates() {
+        let mut window = VecDeque::new();
+        let samples = (0..BITRATE_WINDOW).map(|i| sample(i, 800)).collect();
+        let errors = check_bitrate(&mut window, samples, ADVERTISED, 0.0, &ctx());
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(window.len(), BITRATE_WINDOW);
+    }
+
+    #[test]
+    fn detects_peak_above_bandwidth() {
+        let mut window = VecDeque::new();
+        let errors = check_bitrate(&mut window, vec![sample(0, 1200)], ADVERTISED, 0.1, &ctx());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error_type, ErrorType::BitrateExceeded);
+        assert!(errors[0].details.contains("1200000 bps"));
+
+        let errors = check_bitrate(&mut window, vec![sample(1, 1050)], ADVERTISED, 0.1, &ctx());
+        assert!(errors.is_empty(), "margin should allow 5% overshoot: {:?}", errors);
+    }
+
+    #[test]
+    fn detects_rolling_average_above_average_bandwidth() {
+        let mut window = VecDeque::new();
+        let samples: Vec<_> = (0..BITRATE_WINDOW - 1).map(|i| sample(i, 950)).collect();
+        assert!(check_bitrate(&mut window, samples, ADVERTISED, 0.1, &ctx()).is_empty());
+
+        let errors = check_bitrate(&mut window, vec![sample(9, 950)], ADVERTISED, 0.1, &ctx());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error_type, ErrorType::AverageBitrateExceeded);
+        assert!(errors[0].details.contains("950000 bps"));
+    }
+
+    #[test]
+    fn window_keeps_most_recent_samples() {
+        let mut window = VecDeque::new();
+        let samples = (0..BITRATE_WINDOW + 3).map(|i| sample(i, 500)).collect();
+        check_bitrate(&mut window, samples, ADVERTISED, 0.0, &ctx());
+        assert_eq!(window.len(), BITRATE_WINDOW);
+        assert_eq!(window.front().unwrap().uri, "s3.ts");
+    }
+}
@@ -0,0 +1,292 @@
+//! Blocking playlist reload (`_HLS_msn` / `_HLS_part`, RFC 8216bis §6.2.5.2).
+//!
+//! In blocking mode each reload asks the origin to hold the request until the
+//! next segment or part exists. The directives are derived from the previous
+//! `VariantState`, and each response is checked against what was asked for.
+
+use std::time::{Duration, Instant};
+
+use crate::loader::{DeliveryDirectives, LoadError, LoadResponse};
+
+use super::error::{ErrorType, MonitorError};
+use super::state::{CheckContext, PlaylistSnapshot, VariantState};
+
+/// Multiple of the part target (or target duration) an origin may hold a
+/// blocking reload before it is considered overdue.
+const HOLD_LIMIT_FACTOR: f64 = 3.0;
+
+/// How long the origin may hold a blocking reload of a variant: 3× the part
+/// target, or 3× the target duration for playlists without parts.
+pub fn hold_limit(prev: &VariantState) -> Option<Duration> {
+    let unit = prev.part_target.unwrap_or(prev.target_duration);
+    (unit > 0.0).then(|| Duration::from_secs_f64(unit * HOLD_LIMIT_FACTOR))
+}
+
+/// Directives for the next blocking reload of a variant: the part after the
+/// last one seen, or the next segment for playlists without parts.
+pub fn next_directives(prev: &VariantState) -> DeliveryDirectives {
+    let msn = prev.media_sequence + prev.segment_uris.len() as u64;
+    let part = prev.part_target.map(|_| prev.pending_parts.len() as u64);
+    DeliveryDirectives { msn: Some(msn), part, skip: false, hold_limit: hold_limit(prev) }
+}
+
+/// How long the origin held a reload that was started at `started`.
+///
+/// Only the attempt that got the response counts; retries after failed
+/// attempts and their backoff are not part of the hold.
+pub fn hold_time(result: &Result<LoadResponse, LoadError>, started: Instant) -> Duration {
+    result
+        .as_ref()
+        .ok()
+        .and_then(|resp| resp.elapsed)
+        .unwrap_or_else(|| started.elapsed())
+}
+
+/// Whether `curr` contains the segment/part that `directives` asked for.
+fn satisfies(directives: &DeliveryDirectives, curr: &PlaylistSnapshot) -> bool {
+    let Some(msn) = directives.msn else {
+        return true;
+    };
+    let published_end = curr.media_sequence + curr.segments.len() as u64;
+    if published_end > msn || curr.has_endlist {
+        return true;
+    }
+    match directives.part {
+        Some(part) => published_end == msn && curr.pending_parts.len() as u64 > part,
+        None => false,
+    }
+}
+
+/// Validate a blocking reload response against the directives it was sent with.
+///
+/// Reports an origin that answered before the limit without the requested
+/// segment/part, and one that held the request past 3× the part target
+/// (3× the target duration for playlists without parts).
+pub fn check_blocking_reload(
+    directives: &DeliveryDirectives,
+    hold: Duration,
+    prev: &VariantState,
+    curr: &PlaylistSnapshot,
+    ctx: &CheckContext,
+) -> Vec<MonitorError> {
+    let (Some(limit), Some(_)) = (hold_limit(prev), directives.msn) else {
+        return vec![];
+    };
+    let requested = match directives.part {
+        Some(part) => format!("msn={} part={}", directives.msn.unwrap_or(0), part),
+        None => format!("msn={}", directives.msn.unwrap_or(0)),
+    };
+
+    if hold > limit {
+        return vec![MonitorError::new(
+            ErrorType::BlockingReloadOverdue,
+            &ctx.media_type,
+            &ctx.variant_key,
+            format!(
+                "Blocking reload for {} held {}ms, limit {}ms",
+                requested,
+                hold.as_millis(),
+                limit.as_millis()
+            ),
+            &ctx.stream_url,
+            &ctx.stream_id,
+        )];
+    }
+
+    if !satisfies(directives, curr) {
+        return vec![MonitorError::new(
+            ErrorType::BlockingReloadEarly,
+            &ctx.media_type,
+            &ctx.variant_key,
+            format!(
+                "Blocking reload for {} answered after {}ms without it (mseq {}, {} segments, {} pending parts)",
+                requested,
+                hold.as_millis(),
+                curr.media_sequence,
+                curr.segments.len(),
+                curr.pending_parts.len()
+            ),
+            &ctx.stream_url,
+            &ctx.stream_id,
+        )];
+    }
+
+    vec![]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::state::{PartSnapshot, SegmentSnapshot};
+
+    fn ctx() -> CheckContext {
+        CheckContext {
+            stream_url: "http://example.com/".to_string(),
+            stream_id: "stream_1".to_string(),
+            media_type: "VIDEO".to_string(),
+            variant_key: "1200000".to_string(),
+        }
+    }
+
+    fn make_prev(part_target: Option<f64>, pending: usize) -> VariantState {
+        VariantState {
+            media_type: "VIDEO".to_string(),
+            media_sequence: 100,
+            segment_uris: vec!["s100.mp4".into(), "s101.mp4".into()],
+            discontinuity_sequence: 0,
+            next_is_discontinuity: false,
+            prev_segments: vec![],
+            duration: 4.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            in_cue_out: false,
+            cue_out_duration: None,
+            version: None,
+            target_duration: 2.0,
+            playlist_type: None,
+            has_endlist: false,
+            part_target,
+            pending_parts: (0..pending).map(|i| format!("s102.p{}.mp4", i)).collect(),
+            last_part_uri: None,
+            preload_hint_uri: None,
+            can_skip_until: None,
+            can_block_reload: false,
+            last_part: None,
+            rendition_reports: vec![],
+            map: None,
+            key_uri: None,
+            ad_break: None,
+            ad_boundaries: vec![],
+            live_edge_latency: None,
+            clock_drift: None,
+            dateranges: Default::default(),
+        }
+    }
+
+    fn make_snap(segments: usize, pending: usize) -> PlaylistSnapshot {
+        PlaylistSnapshot {
+            media_sequence: 100,
+            discontinuity_sequence: 0,
+            segments: (0..segments)
+                .map(|i| SegmentSnapshot {
+                    uri: format!("s{}.mp4", 100 + i),
+                    duration: 2.0,
+                    discontinuity: false,
+                    cue_out: false,
+                    cue_in: false,
+                    cue_out_cont: None,
+                    gap: false,
+                    program_date_time: None,
+                    daterange: None,
+                    parts: vec![],
+                    byte_range_length: None,
+                    map: None,
+                    scte35: vec![],
+                    cue_out_duration: None,
+                    ad_markers: vec![],
+                })
+                .collect(),
+            duration: 2.0 * segments as f64,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            has_cue_out: false,
+            cue_out_duration: None,
+            target_duration: 2.0,
+            playlist_type: None,
+            version: None,
+            has_gaps: false,
+            has_endlist: false,
+            i_frames_only: false,
+            has_byte_range: false,
+            has_map: false,
+            has_key_iv: false,
+            has_key_format: false,
+            keys: vec![],
+            part_target: Some(0.5),
+            pending_parts: (0..pending)
+                .map(|i| PartSnapshot {
+                    uri: format!("p{}.mp4", i),
+                    duration: 0.5,
+                    independent: false,
+                    gap: false,
+                    byterange_length: None,
+                    byterange_offset: None,
+                })
+                .collect(),
+            preload_hints: vec![],
+            server_control: None,
+            skipped_segments: None,
+            rendition_reports: vec![],
+            key_uri: None,
+        }
+    }
+
+    #[test]
+    fn directives_target_next_part() {
+        let d = next_directives(&make_prev(Some(0.5), 2));
+        assert_eq!(
+            d,
+            DeliveryDirectives { msn: Some(102), part: Some(2), skip: false, hold_limit: Some(Duration::from_millis(1500)) }
+        );
+    }
+
+    #[test]
+    fn directives_target_next_segment_without_parts() {
+        let d = next_directives(&make_prev(None, 0));
+        assert_eq!(
+            d,
+            DeliveryDirectives { msn: Some(102), part: None, skip: false, hold_limit: Some(Duration::from_secs(6)) }
+        );
+    }
+
+    #[test]
+    fn hold_excludes_failed_attempts() {
+        let started = Instant::now() - Duration::from_secs(5);
+        let resp = LoadResponse {
+            body: String::new(),
+            content_type: None,
+            content_encoding: None,
+            elapsed: Some(Duration::from_millis(800)),
+        };
+        assert_eq!(hold_time(&Ok(resp), started), Duration::from_millis(800));
+
+        let err = Err(LoadError::Timeout { url: "a.m3u8".into(), is_last_retry: true });
+        assert!(hold_time(&err, started) >= Duration::from_secs(5));
+    }
+
+    #[test]
+    fn no_error_when_part_delivered_in_time() {
+        let prev = make_prev(Some(0.5), 2);
+        let d = next_directives(&prev);
+        let errors = check_blocking_reload(&d, Duration::from_millis(400), &prev, &make_snap(2, 3), &ctx());
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn no_error_when_segment_published() {
+        let prev = make_prev(Some(0.5), 3);
+        let d = next_directives(&prev);
+        let errors = check_blocking_reload(&d, Duration::from_millis(400), &prev, &make_snap(3, 0), &ctx());
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn detects_early_stale_answer() {
+        let prev = make_prev(Some(0.5), 2);
+        let d = next_directives(&prev);
+        let errors = check_blocking_reload(&d, Duration::from_millis(5), &prev, &make_snap(2, 2), &ctx());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error_type, ErrorType::BlockingReloadEarly);
+        assert!(errors[0].details.contains("msn=102 part=2"));
+    }
+
+    #[test]
+    fn detects_overdue_hold() {
+        let prev = make_prev(Some(0.5), 2);
+        let d = next_directives(&prev);
+        let errors = check_blocking_reload(&d, Duration::from_millis(1600), &prev, &make_snap(2, 3), &ctx());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error_type, ErrorType::BlockingReloadOverdue);
+        assert!(errors[0].details.contains("limit 1500ms"));
+    }
+}
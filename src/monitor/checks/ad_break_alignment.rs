@@ -0,0 +1,277 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use crate::monitor::ad_markers::AdSignal;
+use crate::monitor::checks::stream_check::{StreamCheck, StreamCheckContext};
+use crate::monitor::error::{ErrorType, MonitorError};
+use crate::monitor::state::{AdBoundary, VariantState};
+
+/// Video and audio renditions must enter and leave each ad break at the same
+/// media sequence, with the same PDT and announced break duration, so that an
+/// ABR or audio switch never lands on the wrong side of a break.
+///
+/// Only the media sequences listed by every rendition on this poll are
+/// compared; a boundary a rendition has not published yet is not a mismatch.
+pub struct AdBreakAlignmentCheck {
+    tolerance: f64,
+}
+
+impl AdBreakAlignmentCheck {
+    pub fn new(tolerance: f64) -> Self {
+        Self { tolerance }
+    }
+}
+
+fn label(signal: AdSignal) -> &'static str {
+    match signal {
+        AdSignal::Out => "CUE-OUT",
+        AdSignal::Cont => "CUE-OUT-CONT",
+        AdSignal::In => "CUE-IN",
+    }
+}
+
+impl StreamCheck for AdBreakAlignmentCheck {
+    fn name(&self) -> &'static str {
+        "AdBreakAlignment"
+    }
+
+    fn check(
+        &self,
+        variants: &HashMap<String, VariantState>,
+        ctx: &StreamCheckContext,
+    ) -> Vec<MonitorError> {
+        let renditions: BTreeMap<&str, &VariantState> = variants
+            .iter()
+            .filter(|(_, s)| (s.media_type == "VIDEO" || s.media_type == "AUDIO") && !s.segment_uris.is_empty())
+            .map(|(key, s)| (key.as_str(), s))
+            .collect();
+        if renditions.len() < 2 {
+            return Vec::new();
+        }
+
+        let start = renditions.values().map(|s| s.media_sequence).max().unwrap_or(0);
+        let end = renditions
+            .values()
+            .map(|s| s.media_sequence + s.segment_uris.len() as u64)
+            .min()
+            .unwrap_or(0);
+        let in_window = |b: &&AdBoundary| b.mseq >= start && b.mseq < end;
+
+        let points: BTreeSet<(u64, &'static str)> = renditions
+            .values()
+            .flat_map(|s| s.ad_boundaries.iter().filter(in_window))
+            .map(|b| (b.mseq, label(b.signal)))
+            .collect();
+
+        let mut errors = Vec::new();
+        let mut push = |details: String| {
+            errors.push(MonitorError::new(
+                ErrorType::AdBreakMisalignment,
+                "ALL",
+                "ALL",
+                details,
+                &ctx.stream_url,
+                &ctx.stream_id,
+            ));
+        };
+
+        for (mseq, tag) in points {
+            let mut present = Vec::new();
+            let mut missing = Vec::new();
+            for (key, state) in &renditions {
+                match state.ad_boundaries.iter().find(|b| b.mseq == mseq && label(b.signal) == tag) {
+                    Some(b) => present.push((*key, b)),
+                    None => missing.push((*key, *state)),
+                }
+            }
+
+            if !missing.is_empty() {
+                let breakdown: Vec<String> = missing
+                    .iter()
+                    .map(|(key, state)| {
+                        let nearest = state
+                            .ad_boundaries
+                            .iter()
+                            .filter(in_window)
+                            .filter(|b| label(b.signal) == tag)
+                            .min_by_key(|b| b.mseq.abs_diff(mseq));
+                        match nearest {
+                            Some(b) => format!("'{}' at mseq({})", key, b.mseq),
+                            None => format!("'{}' has none", key),
+                        }
+                    })
+                    .collect();
+                let keys: Vec<String> = present.iter().map(|(key, _)| format!("'{}'", key)).collect();
+                push(format!(
+                    "{} at mseq({}) in {} but not in every rendition: {}",
+                    tag,
+                    mseq,
+                    keys.join(", "),
+                    breakdown.join(", "),
+                ));
+                continue;
+            }
+
+            let pdts: Vec<_> = present
+                .iter()
+                .filter_map(|(key, b)| Some((*key, b.program_date_time?)))
+                .collect();
+            if let (Some(min), Some(max)) = (pdts.iter().map(|(_, t)| *t).min(), pdts.iter().map(|(_, t)| *t).max()) {
+                let spread = (max - min).num_milliseconds() as f64 / 1000.0;
+                if spread > self.tolerance {
+                    let breakdown: Vec<String> = pdts
+                        .iter()
+                        .map(|(key, t)| format!("'{}'={}", key, t.to_rfc3339()))
+                        .collect();
+                    push(format!(
+                        "{} at mseq({}) has PDTs {:.3}s apart across renditions: {}",
+                        tag,
+                        mseq,
+                        spread,
+                        breakdown.join(", "),
+                    ));
+                }
+            }
+
+            let durations: Vec<(&str, f64)> = present
+                .iter()
+                .filter_map(|(key, b)| Some((*key, b.duration?)))
+                .collect();
+            let min = durations.iter().map(|(_, d)| *d).fold(f64::INFINITY, f64::min);
+            let max = durations.iter().map(|(_, d)| *d).fold(f64::NEG_INFINITY, f64::max);
+            if durations.len() >= 2 && max - min > self.tolerance {
+                let breakdown: Vec<String> = durations
+                    .iter()
+                    .map(|(key, d)| format!("'{}'={:.3}s", key, d))
+                    .collect();
+                push(format!(
+                    "{} at mseq({}) announces different break durations across renditions: {}",
+                    tag,
+                    mseq,
+                    breakdown.join(", "),
+                ));
+            }
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx() -> StreamCheckContext {
+        StreamCheckContext {
+            stream_url: "http://example.com/master.m3u8".to_string(),
+            stream_id: "stream_1".to_string(),
+            variant_failures: HashMap::new(),
+            variant_urls: HashMap::new(),
+        }
+    }
+
+    fn boundary(mseq: u64, signal: AdSignal, pdt_secs: i64, duration: Option<f64>) -> AdBoundary {
+        AdBoundary {
+            mseq,
+            signal,
+            program_date_time: chrono::DateTime::from_timestamp(1_700_000_000 + pdt_secs, 0).map(|t| t.fixed_offset()),
+            duration,
+        }
+    }
+
+    fn make_variant(media_type: &str, mseq: u64, count: usize, ad_boundaries: Vec<AdBoundary>) -> VariantState {
+        VariantState {
+            media_type: media_type.to_string(),
+            media_sequence: mseq,
+            segment_uris: (0..count).map(|i| format!("seg{}.ts", mseq + i as u64)).collect(),
+            discontinuity_sequence: 0,
+            next_is_discontinuity: false,
+            prev_segments: vec![],
+            duration: 0.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            in_cue_out: false,
+            cue_out_duration: None,
+            version: None,
+            target_duration: 6.0,
+            playlist_type: None,
+            has_endlist: false,
+            part_target: None,
+            pending_parts: vec![],
+            last_part_uri: None,
+            preload_hint_uri: None,
+            can_skip_until: None,
+            can_block_reload: false,
+            last_part: None,
+            rendition_reports: vec![],
+            map: None,
+            key_uri: None,
+            ad_break: None,
+            ad_boundaries,
+            live_edge_latency: None,
+            clock_drift: None,
+            dateranges: Default::default(),
+        }
+    }
+
+    fn aligned() -> Vec<AdBoundary> {
+        vec![boundary(12, AdSignal::Out, 72, Some(18.0)), boundary(15, AdSignal::In, 90, None)]
+    }
+
+    #[test]
+    fn aligned_renditions_pass() {
+        let mut variants = HashMap::new();
+        variants.insert("1200000".to_string(), make_variant("VIDEO", 10, 6, aligned()));
+        variants.insert("audio_aac_en".to_string(), make_variant("AUDIO", 10, 6, aligned()));
+        assert!(AdBreakAlignmentCheck::new(1.0).check(&variants, &ctx()).is_empty());
+    }
+
+    #[test]
+    fn detects_break_start_at_different_mseq() {
+        let mut variants = HashMap::new();
+        variants.insert("1200000".to_string(), make_variant("VIDEO", 10, 6, aligned()));
+        variants.insert(
+            "audio_aac_en".to_string(),
+            make_variant("AUDIO", 10, 6, vec![boundary(13, AdSignal::Out, 78, Some(18.0)), boundary(15, AdSignal::In, 90, None)]),
+        );
+        let errors = AdBreakAlignmentCheck::new(1.0).check(&variants, &ctx());
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].error_type, ErrorType::AdBreakMisalignment);
+        assert_eq!(
+            errors[0].details,
+            "CUE-OUT at mseq(12) in '1200000' but not in every rendition: 'audio_aac_en' at mseq(13)"
+        );
+        assert!(errors[1].details.starts_with("CUE-OUT at mseq(13) in 'audio_aac_en'"));
+    }
+
+    #[test]
+    fn detects_pdt_and_duration_mismatch() {
+        let mut variants = HashMap::new();
+        variants.insert("1200000".to_string(), make_variant("VIDEO", 10, 6, aligned()));
+        variants.insert(
+            "audio_aac_en".to_string(),
+            make_variant("AUDIO", 10, 6, vec![boundary(12, AdSignal::Out, 75, Some(30.0)), boundary(15, AdSignal::In, 90, None)]),
+        );
+        let errors = AdBreakAlignmentCheck::new(1.0).check(&variants, &ctx());
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].details.contains("PDTs 3.000s apart"), "{}", errors[0].details);
+        assert!(
+            errors[1].details.contains("'1200000'=18.000s, 'audio_aac_en'=30.000s"),
+            "{}",
+            errors[1].details
+        );
+    }
+
+    #[test]
+    fn only_shared_window_and_av_renditions_are_compared() {
+        let mut variants = HashMap::new();
+        // The audio playlist has not published mseq 15 yet.
+        variants.insert("1200000".to_string(), make_variant("VIDEO", 10, 6, aligned()));
+        variants.insert(
+            "audio_aac_en".to_string(),
+            make_variant("AUDIO", 10, 5, vec![boundary(12, AdSignal::Out, 72, Some(18.0))]),
+        );
+        variants.insert("subs_en".to_string(), make_variant("SUBTITLES", 10, 6, vec![]));
+        variants.insert("iframe_200000".to_string(), make_variant("I-FRAME", 10, 6, vec![]));
+        assert!(AdBreakAlignmentCheck::new(1.0).check(&variants, &ctx()).is_empty());
+    }
+}
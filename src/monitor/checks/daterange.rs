@@ -156,6 +156,7 @@ impl Check for DateRangeCheck {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::collections::BTreeMap;
     use crate::monitor::state::{DateRangeSnapshot, SegmentSnapshot};
     use chrono::{DateTime, Duration, FixedOffset};
 
@@ -185,6 +186,21 @@ mod tests {
             target_duration: 10.0,
             playlist_type: None,
             has_endlist: false,
+            part_target: None,
+            pending_parts: vec![],
+            last_part_uri: None,
+            preload_hint_uri: None,
+            can_skip_until: None,
+            can_block_reload: false,
+            last_part: None,
+            rendition_reports: vec![],
+            map: None,
+            key_uri: None,
+            ad_break: None,
+            ad_boundaries: vec![],
+            live_edge_latency: None,
+            clock_drift: None,
+            dateranges: Default::default(),
         }
     }
 
@@ -208,6 +224,7 @@ mod tests {
             duration,
             end_on_next,
             planned_duration: None,
+            attributes: BTreeMap::new(),
         }
     }
 
@@ -222,6 +239,12 @@ mod tests {
             gap: false,
             program_date_time: None,
             daterange,
+            parts: vec![],
+            byte_range_length: None,
+            map: None,
+            scte35: vec![],
+            cue_out_duration: None,
+            ad_markers: vec![],
         }
     }
 
@@ -247,6 +270,13 @@ mod tests {
             has_key_iv: false,
             has_key_format: false,
             keys: vec![],
+            part_target: None,
+            pending_parts: vec![],
+            preload_hints: vec![],
+            server_control: None,
+            skipped_segments: None,
+            rendition_reports: vec![],
+            key_uri: None,
         }
     }
 
@@ -102,6 +102,12 @@ mod tests {
             gap: false,
             program_date_time: None,
             daterange: None,
+            parts: vec![],
+            byte_range_length: None,
+            map: None,
+            scte35: vec![],
+            cue_out_duration: None,
+            ad_markers: vec![],
         }
     }
 
@@ -109,6 +115,9 @@ mod tests {
         SegmentInfo {
             uri: uri.to_string(),
             discontinuity: disc,
+            duration: 10.0,
+            program_date_time: None,
+            tags: Default::default(),
         }
     }
 
@@ -135,6 +144,21 @@ mod tests {
             target_duration: 10.0,
             playlist_type: None,
             has_endlist: false,
+            part_target: None,
+            pending_parts: vec![],
+            last_part_uri: None,
+            preload_hint_uri: None,
+            can_skip_until: None,
+            can_block_reload: false,
+            last_part: None,
+            rendition_reports: vec![],
+            map: None,
+            key_uri: None,
+            ad_break: None,
+            ad_boundaries: vec![],
+            live_edge_latency: None,
+            clock_drift: None,
+            dateranges: Default::default(),
         }
     }
 
@@ -160,6 +184,13 @@ mod tests {
             has_key_iv: false,
             has_key_format: false,
             keys: vec![],
+            part_target: None,
+            pending_parts: vec![],
+            preload_hints: vec![],
+            server_control: None,
+            skipped_segments: None,
+            rendition_reports: vec![],
+            key_uri: None,
         }
     }
 
@@ -1,3 +1,5 @@
+use base64::Engine;
+
 use crate::monitor::error::{ErrorType, MonitorError};
 use crate::monitor::state::{CheckContext, PlaylistSnapshot, VariantState};
 
@@ -5,6 +7,27 @@ use super::Check;
 
 pub struct EncryptionConsistencyCheck;
 
+/// Describe what is wrong with an `skd://` or `data:` key URI, if anything.
+/// Other URIs point at a key server and are not checked here.
+fn sample_aes_uri_problem(uri: &str) -> Option<String> {
+    if let Some(id) = uri.strip_prefix("skd://") {
+        if id.is_empty() || id.chars().any(char::is_whitespace) {
+            return Some("skd:// URI has an empty or malformed key identifier".to_string());
+        }
+    } else if let Some(rest) = uri.strip_prefix("data:") {
+        let Some((header, data)) = rest.split_once(',') else {
+            return Some("data: URI has no ',' before its payload".to_string());
+        };
+        if data.is_empty() {
+            return Some("data: URI has an empty payload".to_string());
+        }
+        if header.ends_with(";base64") && base64::engine::general_purpose::STANDARD.decode(data).is_err() {
+            return Some("data: URI payload is not valid base64".to_string());
+        }
+    }
+    None
+}
+
 impl Check for EncryptionConsistencyCheck {
     fn name(&self) -> &'static str {
         "EncryptionConsistency"
@@ -41,6 +64,19 @@ impl Check for EncryptionConsistencyCheck {
                 ));
             }
 
+            if key.method.starts_with("SAMPLE-AES") {
+                if let Some(problem) = key.uri.as_deref().and_then(sample_aes_uri_problem) {
+                    errors.push(MonitorError::new(
+                        ErrorType::EncryptionViolation,
+                        &ctx.media_type,
+                        &ctx.variant_key,
+                        format!("METHOD={} key URI '{}': {}", key.method, key.uri.as_deref().unwrap_or_default(), problem),
+                        &ctx.stream_url,
+                        &ctx.stream_id,
+                    ));
+                }
+            }
+
             if key.method == "AES-128" && curr.has_map && !key.has_iv {
                 errors.push(MonitorError::new(
                     ErrorType::EncryptionViolation,
@@ -88,6 +124,21 @@ mod tests {
             target_duration: 10.0,
             playlist_type: None,
             has_endlist: false,
+            part_target: None,
+            pending_parts: vec![],
+            last_part_uri: None,
+            preload_hint_uri: None,
+            can_skip_until: None,
+            can_block_reload: false,
+            last_part: None,
+            rendition_reports: vec![],
+            map: None,
+            key_uri: None,
+            ad_break: None,
+            ad_boundaries: vec![],
+            live_edge_latency: None,
+            clock_drift: None,
+            dateranges: Default::default(),
         }
     }
 
@@ -105,6 +156,12 @@ mod tests {
                 gap: false,
                 program_date_time: None,
                 daterange: None,
+                parts: vec![],
+                byte_range_length: None,
+                map: None,
+                scte35: vec![],
+                cue_out_duration: None,
+                ad_markers: vec![],
             }],
             duration: 10.0,
             cue_out_count: 0,
@@ -122,6 +179,13 @@ mod tests {
             has_key_iv: false,
             has_key_format: false,
             keys,
+            part_target: None,
+            pending_parts: vec![],
+            preload_hints: vec![],
+            server_control: None,
+            skipped_segments: None,
+            rendition_reports: vec![],
+            key_uri: None,
         }
     }
 
@@ -142,6 +206,8 @@ mod tests {
                 has_uri: true,
                 has_iv: true,
                 has_keyformat: false,
+                uri: Some("key.bin".into()),
+                iv: Some("0x00000000000000000000000000000001".into()),
             }],
             false,
         );
@@ -158,6 +224,8 @@ mod tests {
                 has_uri: true,
                 has_iv: false,
                 has_keyformat: false,
+                uri: Some("key.bin".into()),
+                iv: None,
             }],
             false,
         );
@@ -176,6 +244,8 @@ mod tests {
                 has_uri: false,
                 has_iv: true,
                 has_keyformat: false,
+                uri: None,
+                iv: Some("0x00000000000000000000000000000001".into()),
             }],
             false,
         );
@@ -194,6 +264,8 @@ mod tests {
                 has_uri: true,
                 has_iv: false,
                 has_keyformat: false,
+                uri: Some("key.bin".into()),
+                iv: None,
             }],
             true,
         );
@@ -212,10 +284,53 @@ mod tests {
                 has_uri: true,
                 has_iv: true,
                 has_keyformat: false,
+                uri: Some("key.bin".into()),
+                iv: Some("0x00000000000000000000000000000001".into()),
             }],
             true,
         );
         let errors = check.check(&make_prev(), &snap, &ctx());
         assert!(errors.is_empty());
     }
+
+    fn sample_aes(uri: &str) -> KeySnapshot {
+        KeySnapshot {
+            method: "SAMPLE-AES".into(),
+            has_uri: true,
+            has_iv: false,
+            has_keyformat: true,
+            uri: Some(uri.into()),
+            iv: None,
+        }
+    }
+
+    #[test]
+    fn no_error_well_formed_sample_aes_uris() {
+        let check = EncryptionConsistencyCheck;
+        let snap = make_snap(
+            vec![
+                sample_aes("skd://key-id-1234"),
+                sample_aes("data:text/plain;base64,AAAAPnBzc2gAAAAA7e+LqXnWSs6jyCfc1R0h7QAAAB4iFnNoYWthX2NlYzJmNjRhYTc4OTBhMTFI49yVmwY="),
+                sample_aes("https://keys.example.com/key"),
+            ],
+            false,
+        );
+        let errors = check.check(&make_prev(), &snap, &ctx());
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn error_malformed_sample_aes_uris() {
+        let check = EncryptionConsistencyCheck;
+        let snap = make_snap(
+            vec![sample_aes("skd://"), sample_aes("data:text/plain;base64"), sample_aes("data:;base64,not base64!")],
+            false,
+        );
+        let errors = check.check(&make_prev(), &snap, &ctx());
+        assert_eq!(errors.len(), 3, "{:?}", errors);
+        assert!(errors.iter().all(|e| e.error_type == ErrorType::EncryptionViolation));
+        assert!(errors[0].details.contains("empty or malformed key identifier"));
+        assert!(errors[1].details.contains("no ','"));
+        assert!(errors[2].details.contains("not valid base64"));
+    }
 }
@@ -0,0 +1,183 @@
+use crate::monitor::error::{ErrorType, MonitorError};
+use crate::monitor::state::{CheckContext, PlaylistSnapshot, VariantState};
+
+use super::Check;
+
+/// Validates that `INDEPENDENT=YES` parts occur at least once per target duration.
+///
+/// Only applies to playlists that mark any part as independent; a playlist
+/// without the attribute gives no information about where decoding can start.
+pub struct IndependentPartCadenceCheck;
+
+impl Check for IndependentPartCadenceCheck {
+    fn name(&self) -> &'static str {
+        "IndependentPartCadence"
+    }
+
+    fn check(
+        &self,
+        _prev: &VariantState,
+        curr: &PlaylistSnapshot,
+        ctx: &CheckContext,
+    ) -> Vec<MonitorError> {
+        if curr.target_duration <= 0.0 || !curr.all_parts().any(|p| p.independent) {
+            return vec![];
+        }
+
+        let mut errors = Vec::new();
+        let mut last_independent: Option<&str> = None;
+        let mut since_independent = 0.0;
+
+        for part in curr.all_parts() {
+            if part.independent {
+                last_independent = Some(&part.uri);
+                since_independent = 0.0;
+            }
+            since_independent += part.duration;
+
+            // Parts before the first independent one have an unknown starting point.
+            if let Some(start) = last_independent {
+                if since_independent > curr.target_duration + 0.001 {
+                    errors.push(MonitorError::new(
+                        ErrorType::IndependentPartCadence,
+                        &ctx.media_type,
+                        &ctx.variant_key,
+                        format!(
+                            "No INDEPENDENT=YES part for {:.3}s after '{}' (target duration {:.0}s) at part '{}'",
+                            since_independent, start, curr.target_duration, part.uri
+                        ),
+                        &ctx.stream_url,
+                        &ctx.stream_id,
+                    ));
+                    // Report each run once.
+                    last_independent = None;
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::state::PartSnapshot;
+
+    fn ctx() -> CheckContext {
+        CheckContext {
+            stream_url: "http://example.com/".to_string(),
+            stream_id: "stream_1".to_string(),
+            media_type: "VIDEO".to_string(),
+            variant_key: "1200000".to_string(),
+        }
+    }
+
+    fn make_prev() -> VariantState {
+        VariantState {
+            media_type: "VIDEO".to_string(),
+            media_sequence: 100,
+            segment_uris: vec![],
+            discontinuity_sequence: 0,
+            next_is_discontinuity: false,
+            prev_segments: vec![],
+            duration: 0.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            in_cue_out: false,
+            cue_out_duration: None,
+            version: None,
+            target_duration: 2.0,
+            playlist_type: None,
+            has_endlist: false,
+            part_target: Some(0.5),
+            pending_parts: vec![],
+            last_part_uri: None,
+            preload_hint_uri: None,
+            can_skip_until: None,
+            can_block_reload: false,
+            last_part: None,
+            rendition_reports: vec![],
+            map: None,
+            key_uri: None,
+            ad_break: None,
+            ad_boundaries: vec![],
+            live_edge_latency: None,
+            clock_drift: None,
+            dateranges: Default::default(),
+        }
+    }
+
+    fn part(uri: &str, independent: bool) -> PartSnapshot {
+        PartSnapshot {
+            uri: uri.into(),
+            duration: 0.5,
+            independent,
+            gap: false,
+            byterange_length: None,
+            byterange_offset: None,
+        }
+    }
+
+    fn make_snap(pending: Vec<PartSnapshot>) -> PlaylistSnapshot {
+        PlaylistSnapshot {
+            media_sequence: 100,
+            discontinuity_sequence: 0,
+            segments: vec![],
+            duration: 0.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            has_cue_out: false,
+            cue_out_duration: None,
+            target_duration: 2.0,
+            playlist_type: None,
+            version: None,
+            has_gaps: false,
+            has_endlist: false,
+            i_frames_only: false,
+            has_byte_range: false,
+            has_map: false,
+            has_key_iv: false,
+            has_key_format: false,
+            keys: vec![],
+            part_target: Some(0.5),
+            pending_parts: pending,
+            preload_hints: vec![],
+            server_control: None,
+            skipped_segments: None,
+            rendition_reports: vec![],
+            key_uri: None,
+        }
+    }
+
+    #[test]
+    fn no_error_when_independent_not_used() {
+        let parts = (0..8).map(|i| part(&format!("p{}.mp4", i), false)).collect();
+        let errors = IndependentPartCadenceCheck.check(&make_prev(), &make_snap(parts), &ctx());
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn no_error_with_regular_cadence() {
+        let parts = (0..8).map(|i| part(&format!("p{}.mp4", i), i % 4 == 0)).collect();
+        let errors = IndependentPartCadenceCheck.check(&make_prev(), &make_snap(parts), &ctx());
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn detects_sparse_independent_parts() {
+        let parts = (0..10).map(|i| part(&format!("p{}.mp4", i), i == 0)).collect();
+        let errors = IndependentPartCadenceCheck.check(&make_prev(), &make_snap(parts), &ctx());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error_type, ErrorType::IndependentPartCadence);
+        assert!(errors[0].details.contains("p0.mp4"));
+        assert!(errors[0].details.contains("p4.mp4"));
+    }
+
+    #[test]
+    fn ignores_parts_before_first_independent() {
+        let parts = (0..8).map(|i| part(&format!("p{}.mp4", i), i == 6)).collect();
+        let errors = IndependentPartCadenceCheck.check(&make_prev(), &make_snap(parts), &ctx());
+        assert!(errors.is_empty());
+    }
+}
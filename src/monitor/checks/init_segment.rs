@@ -0,0 +1,226 @@
+use std::collections::HashSet;
+
+use crate::monitor::error::{ErrorType, MonitorError};
+use crate::monitor::state::{CheckContext, MapSnapshot, PlaylistSnapshot, VariantState};
+
+use super::Check;
+
+/// Flags an `EXT-X-MAP` change on a newly published segment that is not
+/// tagged with `EXT-X-DISCONTINUITY`.
+///
+/// New segments are compared with the segment before them, starting from the
+/// map of the last segment seen on the previous poll, so each change is
+/// reported once even while it stays in the playlist window.
+pub struct InitSegmentChangeCheck;
+
+impl Check for InitSegmentChangeCheck {
+    fn name(&self) -> &'static str {
+        "Init Segment Change"
+    }
+
+    fn check(
+        &self,
+        prev: &VariantState,
+        curr: &PlaylistSnapshot,
+        ctx: &CheckContext,
+    ) -> Vec<MonitorError> {
+        let mut errors = Vec::new();
+        let seen: HashSet<&str> = prev.segment_uris.iter().map(String::as_str).collect();
+        let mut current = prev.map.as_ref();
+
+        for (i, seg) in curr.segments.iter().enumerate() {
+            if seen.contains(seg.uri.as_str()) {
+                continue;
+            }
+            if seg.map.as_ref() != current && !seg.discontinuity {
+                errors.push(MonitorError::new(
+                    ErrorType::InitSegmentChange,
+                    &ctx.media_type,
+                    &ctx.variant_key,
+                    format!(
+                        "EXT-X-MAP changed from {} to {} without EXT-X-DISCONTINUITY at index({}) in mseq({}) \u{2014} segment: '{}'",
+                        MapSnapshot::describe(current),
+                        MapSnapshot::describe(seg.map.as_ref()),
+                        i,
+                        curr.media_sequence + i as u64,
+                        seg.uri
+                    ),
+                    &ctx.stream_url,
+                    &ctx.stream_id,
+                ));
+            }
+            current = seg.map.as_ref();
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::state::SegmentSnapshot;
+
+    fn ctx() -> CheckContext {
+        CheckContext {
+            stream_url: "http://example.com/".to_string(),
+            stream_id: "stream_1".to_string(),
+            media_type: "VIDEO".to_string(),
+            variant_key: "1200000".to_string(),
+        }
+    }
+
+    fn map(uri: &str) -> Option<MapSnapshot> {
+        Some(MapSnapshot {
+            uri: uri.to_string(),
+            byte_range_length: None,
+            byte_range_offset: None,
+        })
+    }
+
+    fn make_prev(uris: &[&str], last_map: Option<MapSnapshot>) -> VariantState {
+        VariantState {
+            media_type: "VIDEO".to_string(),
+            media_sequence: 0,
+            segment_uris: uris.iter().map(|u| u.to_string()).collect(),
+            discontinuity_sequence: 0,
+            next_is_discontinuity: false,
+            prev_segments: vec![],
+            duration: 10.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            in_cue_out: false,
+            cue_out_duration: None,
+            version: None,
+            target_duration: 10.0,
+            playlist_type: None,
+            has_endlist: false,
+            part_target: None,
+            pending_parts: vec![],
+            last_part_uri: None,
+            preload_hint_uri: None,
+            can_skip_until: None,
+            can_block_reload: false,
+            last_part: None,
+            rendition_reports: vec![],
+            map: last_map,
+            key_uri: None,
+            ad_break: None,
+            ad_boundaries: vec![],
+            live_edge_latency: None,
+            clock_drift: None,
+            dateranges: Default::default(),
+        }
+    }
+
+    fn make_segment(uri: &str, map: Option<MapSnapshot>, discontinuity: bool) -> SegmentSnapshot {
+        SegmentSnapshot {
+            uri: uri.into(),
+            duration: 10.0,
+            discontinuity,
+            cue_out: false,
+            cue_in: false,
+            cue_out_cont: None,
+            gap: false,
+            program_date_time: None,
+            daterange: None,
+            parts: vec![],
+            byte_range_length: None,
+            map,
+            scte35: vec![],
+            cue_out_duration: None,
+            ad_markers: vec![],
+        }
+    }
+
+    fn make_snap(segments: Vec<SegmentSnapshot>) -> PlaylistSnapshot {
+        PlaylistSnapshot {
+            media_sequence: 1,
+            discontinuity_sequence: 0,
+            segments,
+            duration: 30.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            has_cue_out: false,
+            cue_out_duration: None,
+            target_duration: 10.0,
+            playlist_type: None,
+            version: Some(6),
+            has_gaps: false,
+            has_endlist: false,
+            i_frames_only: false,
+            has_byte_range: false,
+            has_map: true,
+            has_key_iv: false,
+            has_key_format: false,
+            keys: vec![],
+            part_target: None,
+            pending_parts: vec![],
+            preload_hints: vec![],
+            server_control: None,
+            skipped_segments: None,
+            rendition_reports: vec![],
+            key_uri: None,
+        }
+    }
+
+    #[test]
+    fn no_error_when_map_is_unchanged() {
+        let prev = make_prev(&["a.m4s", "b.m4s"], map("init.mp4"));
+        let curr = make_snap(vec![
+            make_segment("b.m4s", map("init.mp4"), false),
+            make_segment("c.m4s", map("init.mp4"), false),
+        ]);
+        assert!(InitSegmentChangeCheck.check(&prev, &curr, &ctx()).is_empty());
+    }
+
+    #[test]
+    fn detects_change_without_discontinuity() {
+        let prev = make_prev(&["a.m4s", "b.m4s"], map("init.mp4"));
+        let curr = make_snap(vec![
+            make_segment("b.m4s", map("init.mp4"), false),
+            make_segment("c.m4s", map("init2.mp4"), false),
+        ]);
+        let errors = InitSegmentChangeCheck.check(&prev, &curr, &ctx());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error_type, ErrorType::InitSegmentChange);
+        assert!(errors[0].details.contains("from 'init.mp4' to 'init2.mp4'"), "{}", errors[0].details);
+        assert!(errors[0].details.contains("mseq(2)"));
+
+        // Already reported: c.m4s is no longer new on the next poll.
+        let prev = make_prev(&["b.m4s", "c.m4s"], map("init2.mp4"));
+        let curr = make_snap(vec![
+            make_segment("b.m4s", map("init.mp4"), false),
+            make_segment("c.m4s", map("init2.mp4"), false),
+            make_segment("d.m4s", map("init2.mp4"), false),
+        ]);
+        assert!(InitSegmentChangeCheck.check(&prev, &curr, &ctx()).is_empty());
+    }
+
+    #[test]
+    fn change_at_discontinuity_is_allowed() {
+        let prev = make_prev(&["a.m4s"], map("init.mp4"));
+        let curr = make_snap(vec![
+            make_segment("a.m4s", map("init.mp4"), false),
+            make_segment("ad1.m4s", map("ad-init.mp4"), true),
+            make_segment("ad2.m4s", map("ad-init.mp4"), false),
+        ]);
+        assert!(InitSegmentChangeCheck.check(&prev, &curr, &ctx()).is_empty());
+    }
+
+    #[test]
+    fn detects_byte_range_change() {
+        let ranged = |offset| {
+            Some(MapSnapshot {
+                uri: "main.mp4".to_string(),
+                byte_range_length: Some(720),
+                byte_range_offset: Some(offset),
+            })
+        };
+        let prev = make_prev(&["a.m4s"], ranged(0));
+        let curr = make_snap(vec![make_segment("b.m4s", ranged(1000), false)]);
+        let errors = InitSegmentChangeCheck.check(&prev, &curr, &ctx());
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].details.contains("BYTERANGE=720@0 to 'main.mp4' BYTERANGE=720@1000"), "{}", errors[0].details);
+    }
+}
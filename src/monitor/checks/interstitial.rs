@@ -0,0 +1,148 @@
+use crate::monitor::error::{ErrorType, MonitorError};
+use crate::monitor::state::{CheckContext, DateRangeSnapshot, PlaylistSnapshot, VariantState};
+
+use super::Check;
+
+/// `CLASS` of an HLS Interstitial `EXT-X-DATERANGE`.
+pub const INTERSTITIAL_CLASS: &str = "com.apple.hls.interstitial";
+
+/// Values allowed in `X-RESTRICT`.
+const RESTRICT_VALUES: [&str; 2] = ["SKIP", "JUMP"];
+
+/// Whether `dr` schedules an interstitial.
+pub fn is_interstitial(dr: &DateRangeSnapshot) -> bool {
+    dr.class.as_deref() == Some(INTERSTITIAL_CLASS)
+}
+
+/// Attribute rules an interstitial DATERANGE breaks.
+pub fn problems(dr: &DateRangeSnapshot) -> Vec<String> {
+    let mut problems = Vec::new();
+    let attr = |name: &str| dr.attributes.get(name).map(String::as_str);
+
+    match (attr("X-ASSET-URI"), attr("X-ASSET-LIST")) {
+        (Some(_), Some(_)) => problems.push("has both X-ASSET-URI and X-ASSET-LIST".to_string()),
+        (None, None) => problems.push("has neither X-ASSET-URI nor X-ASSET-LIST".to_string()),
+        _ => {}
+    }
+
+    if let Some(offset) = attr("X-RESUME-OFFSET") {
+        if !offset.parse::<f64>().is_ok_and(|o| o.is_finite() && o >= 0.0) {
+            problems.push(format!("X-RESUME-OFFSET '{}' is not a non-negative number of seconds", offset));
+        }
+    }
+
+    if let Some(limit) = attr("X-PLAYOUT-LIMIT") {
+        if !limit.parse::<f64>().is_ok_and(|l| l.is_finite() && l > 0.0) {
+            problems.push(format!("X-PLAYOUT-LIMIT '{}' is not a positive number of seconds", limit));
+        }
+    }
+
+    if let Some(restrict) = attr("X-RESTRICT") {
+        for value in restrict.split(',').map(str::trim).filter(|v| !v.is_empty()) {
+            if !RESTRICT_VALUES.contains(&value) {
+                problems.push(format!("X-RESTRICT has unknown value '{}', expected SKIP or JUMP", value));
+            }
+        }
+    }
+
+    problems
+}
+
+/// Validates the attributes of `EXT-X-DATERANGE` tags with
+/// `CLASS="com.apple.hls.interstitial"`.
+pub struct InterstitialCheck;
+
+impl Check for InterstitialCheck {
+    fn name(&self) -> &'static str {
+        "Interstitial"
+    }
+
+    fn check(
+        &self,
+        _prev: &VariantState,
+        curr: &PlaylistSnapshot,
+        ctx: &CheckContext,
+    ) -> Vec<MonitorError> {
+        let mut errors = Vec::new();
+
+        for (i, seg) in curr.segments.iter().enumerate() {
+            let Some(dr) = seg.daterange.as_ref().filter(|dr| is_interstitial(dr)) else {
+                continue;
+            };
+            for problem in problems(dr) {
+                errors.push(MonitorError::new(
+                    ErrorType::InterstitialViolation,
+                    &ctx.media_type,
+                    &ctx.variant_key,
+                    format!(
+                        "Interstitial '{}' at index({}) in mseq({}) {}",
+                        dr.id,
+                        i,
+                        curr.media_sequence + i as u64,
+                        problem
+                    ),
+                    &ctx.stream_url,
+                    &ctx.stream_id,
+                ));
+            }
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeMap;
+
+    fn interstitial(attributes: &[(&str, &str)]) -> DateRangeSnapshot {
+        DateRangeSnapshot {
+            id: "ad1".to_string(),
+            class: Some(INTERSTITIAL_CLASS.to_string()),
+            start_date: chrono::DateTime::parse_from_rfc3339("2025-01-01T00:00:00Z").unwrap(),
+            end_date: None,
+            duration: Some(30.0),
+            end_on_next: false,
+            planned_duration: None,
+            attributes: attributes.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect::<BTreeMap<_, _>>(),
+        }
+    }
+
+    #[test]
+    fn valid_interstitial_has_no_problems() {
+        let dr = interstitial(&[
+            ("X-ASSET-URI", "https://ads.example.com/ad1.m3u8"),
+            ("X-RESUME-OFFSET", "0"),
+            ("X-PLAYOUT-LIMIT", "30.0"),
+            ("X-RESTRICT", "SKIP,JUMP"),
+        ]);
+        assert!(problems(&dr).is_empty());
+        assert!(problems(&interstitial(&[("X-ASSET-LIST", "list.json")])).is_empty());
+    }
+
+    #[test]
+    fn requires_exactly_one_asset_attribute() {
+        assert_eq!(problems(&interstitial(&[])), vec!["has neither X-ASSET-URI nor X-ASSET-LIST"]);
+        let both = interstitial(&[("X-ASSET-URI", "a.m3u8"), ("X-ASSET-LIST", "list.json")]);
+        assert_eq!(problems(&both), vec!["has both X-ASSET-URI and X-ASSET-LIST"]);
+    }
+
+    #[test]
+    fn rejects_bad_offsets_limits_and_restrictions() {
+        let dr = interstitial(&[
+            ("X-ASSET-URI", "a.m3u8"),
+            ("X-RESUME-OFFSET", "-5"),
+            ("X-PLAYOUT-LIMIT", "0"),
+            ("X-RESTRICT", "SKIP,SEEK"),
+        ]);
+        assert_eq!(
+            problems(&dr),
+            vec![
+                "X-RESUME-OFFSET '-5' is not a non-negative number of seconds",
+                "X-PLAYOUT-LIMIT '0' is not a positive number of seconds",
+                "X-RESTRICT has unknown value 'SEEK', expected SKIP or JUMP",
+            ]
+        );
+    }
+}
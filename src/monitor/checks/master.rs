@@ -0,0 +1,575 @@
+//! Master playlist validation: rendition groups and the attributes of
+//! `EXT-X-STREAM-INF`, `EXT-X-I-FRAME-STREAM-INF` and `EXT-X-MEDIA`.
+
+use std::collections::{HashMap, HashSet};
+
+use m3u8_rs::AlternativeMediaType;
+
+use crate::config::MonitorConfig;
+use crate::monitor::error::{ErrorType, MonitorError};
+use crate::monitor::identity::VariantKeys;
+use crate::monitor::tags;
+
+/// Largest width or height accepted in `RESOLUTION`.
+const MAX_DIMENSION: u64 = 16384;
+
+/// Largest `FRAME-RATE` accepted.
+const MAX_FRAME_RATE: f64 = 240.0;
+
+/// Accessibility characteristics and the rendition types they describe.
+const ACCESSIBILITY_CHARACTERISTICS: [(&str, &[AlternativeMediaType]); 4] = [
+    (
+        "public.accessibility.transcribes-spoken-dialog",
+        &[AlternativeMediaType::Subtitles, AlternativeMediaType::ClosedCaptions],
+    ),
+    (
+        "public.accessibility.describes-music-and-sound",
+        &[AlternativeMediaType::Subtitles, AlternativeMediaType::ClosedCaptions],
+    ),
+    ("public.easy-to-read", &[AlternativeMediaType::Subtitles, AlternativeMediaType::ClosedCaptions]),
+    ("public.accessibility.describes-video", &[AlternativeMediaType::Audio]),
+];
+
+/// Resolve a URI in the master playlist against its URL.
+fn resolve(master_url: &str, uri: &str) -> Option<String> {
+    let base = url::Url::parse(master_url).ok()?;
+    base.join(uri).ok().map(|u| u.to_string())
+}
+
+/// What is wrong with one RFC 6381 codec string, or `None` if it is well formed.
+pub fn codec_problem(codec: &str) -> Option<String> {
+    let mut parts = codec.split('.');
+    let fourcc = parts.next().unwrap_or_default();
+    if fourcc.len() != 4 || !fourcc.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Some(format!("'{}' does not start with a four-character sample entry type", codec));
+    }
+    let params: Vec<&str> = parts.collect();
+    if params.iter().any(|p| p.is_empty() || !p.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')) {
+        return Some(format!("'{}' has an empty or malformed parameter", codec));
+    }
+
+    let hex = |p: &str| !p.is_empty() && p.chars().all(|c| c.is_ascii_hexdigit());
+    match fourcc {
+        "avc1" | "avc3" if params.len() != 1 || params[0].len() != 6 || !hex(params[0]) => {
+            Some(format!("'{}' is not {}.PPCCLL with six hex digits", codec, fourcc))
+        }
+        "mp4a" if params.is_empty() || params[0].len() != 2 || !hex(params[0]) => {
+            Some(format!("'{}' is not mp4a.OO with a two-digit hex object type", codec))
+        }
+        "mp4a" if params[0] == "40" && params.get(1).is_none_or(|p| p.parse::<u8>().is_err()) => {
+            Some(format!("'{}' is not mp4a.40.N with a decimal audio object type", codec))
+        }
+        "hvc1" | "hev1" if params.len() < 3 => {
+            Some(format!("'{}' is not {}.<profile>.<compatibility>.<tier and level>", codec, fourcc))
+        }
+        _ => None,
+    }
+}
+
+/// What is wrong with one `CHARACTERISTICS` entry of a rendition of `media_type`.
+fn characteristic_problem(characteristic: &str, media_type: &AlternativeMediaType) -> Option<String> {
+    let c = characteristic.trim();
+    if c.is_empty() || !c.contains('.') || !c.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '.' || ch == '-') {
+        return Some(format!("has malformed CHARACTERISTICS entry '{}', expected a Uniform Type Identifier", c));
+    }
+    let (_, types) = ACCESSIBILITY_CHARACTERISTICS.iter().find(|(name, _)| *name == c)?;
+    (!types.contains(media_type)).then(|| format!("has CHARACTERISTICS '{}', which does not apply to TYPE={}", c, media_type))
+}
+
+/// Attribute lists of the `EXT-X-MEDIA` tags in the master playlist text.
+///
+/// `m3u8_rs` reads an absent `AUTOSELECT` or `FORCED` as `NO` and drops an
+/// `EXT-X-MEDIA` with `FORCED` on a type other than SUBTITLES, so the rules on
+/// whether those attributes are present are checked against the raw tags.
+fn raw_media_tags(body: &str) -> impl Iterator<Item = HashMap<String, String>> + '_ {
+    body.lines()
+        .filter_map(|line| line.trim().strip_prefix("#EXT-X-MEDIA:"))
+        .map(tags::parse_attribute_list)
+}
+
+pub fn validate_master(
+    master: &m3u8_rs::MasterPlaylist,
+    body: &str,
+    stream_url: &str,
+    stream_id: &str,
+    config: &MonitorConfig,
+) -> Vec<MonitorError> {
+    let keys = VariantKeys::of(master);
+    let mut errors = keys.collision_errors(stream_url, stream_id);
+
+    let mut groups: HashMap<(String, String), Vec<&m3u8_rs::AlternativeMedia>> = HashMap::new();
+    for media in &master.alternatives {
+        let key = (media.media_type.to_string(), media.group_id.clone());
+        groups.entry(key).or_default().push(media);
+    }
+
+    for ((media_type, group_id), members) in &groups {
+        let mut names = HashSet::new();
+        let mut default_count = 0u32;
+
+        for member in members {
+            if !names.insert(&member.name) {
+                errors.push(MonitorError::new(
+                    ErrorType::RenditionGroupViolation,
+                    media_type.as_str(),
+                    group_id.as_str(),
+                    format!(
+                        "Rendition group '{}' has duplicate NAME '{}'",
+                        group_id, member.name
+                    ),
+                    stream_url,
+                    stream_id,
+                ));
+            }
+            if member.default {
+                default_count += 1;
+            }
+        }
+
+        if default_count > 1 {
+            errors.push(MonitorError::new(
+                ErrorType::RenditionGroupViolation,
+                media_type.as_str(),
+                group_id.as_str(),
+                format!(
+                    "Rendition group '{}' has {} members with DEFAULT=YES (max 1 allowed)",
+                    group_id, default_count
+                ),
+                stream_url,
+                stream_id,
+            ));
+        }
+    }
+
+    let group_exists = |media_type: &str, group_id: &str| {
+        groups.contains_key(&(media_type.to_string(), group_id.to_string()))
+    };
+    let stream_inf_urls: HashSet<String> = master
+        .variants
+        .iter()
+        .filter(|v| !v.is_i_frame)
+        .filter_map(|v| resolve(stream_url, &v.uri))
+        .collect();
+
+    for (variant, key) in master.variants.iter().zip(&keys.variants) {
+        let tag = if variant.is_i_frame { "EXT-X-I-FRAME-STREAM-INF" } else { "EXT-X-STREAM-INF" };
+        let mut report = |error_type: ErrorType, problem: String| {
+            errors.push(MonitorError::new(
+                error_type,
+                "MASTER",
+                key.as_str(),
+                format!("{} '{}' {}", tag, variant.uri, problem),
+                stream_url,
+                stream_id,
+            ));
+        };
+
+        let closed_captions = match &variant.closed_captions {
+            Some(m3u8_rs::ClosedCaptionGroupId::GroupId(id)) => Some(id),
+            _ => None,
+        };
+        for (attribute, media_type, group_id) in [
+            ("AUDIO", "AUDIO", variant.audio.as_ref()),
+            ("VIDEO", "VIDEO", variant.video.as_ref()),
+            ("SUBTITLES", "SUBTITLES", variant.subtitles.as_ref()),
+            ("CLOSED-CAPTIONS", "CLOSED-CAPTIONS", closed_captions),
+        ] {
+            if let Some(group_id) = group_id.filter(|id| !group_exists(media_type, id)) {
+                report(
+                    ErrorType::UndefinedRenditionGroup,
+                    format!("references {} group '{}' with no EXT-X-MEDIA of TYPE={}", attribute, group_id, media_type),
+                );
+            }
+        }
+
+        match &variant.codecs {
+            None if config.authoring_spec => report(ErrorType::MissingCodecs, "has no CODECS attribute".to_string()),
+            None => {}
+            Some(codecs) => {
+                for problem in codecs.split(',').filter_map(|c| codec_problem(c.trim())) {
+                    report(ErrorType::InvalidCodecs, format!("has invalid CODECS: {}", problem));
+                }
+            }
+        }
+
+        if let Some(res) = &variant.resolution {
+            if res.width == 0 || res.height == 0 || res.width > MAX_DIMENSION || res.height > MAX_DIMENSION {
+                report(
+                    ErrorType::InvalidResolution,
+                    format!("has RESOLUTION {}x{}, expected 1 to {} pixels per side", res.width, res.height, MAX_DIMENSION),
+                );
+            }
+        }
+
+        if let Some(rate) = variant.frame_rate {
+            if variant.is_i_frame {
+                report(ErrorType::InvalidFrameRate, "has FRAME-RATE, which is not allowed on I-frame streams".to_string());
+            } else if !(rate > 0.0 && rate <= MAX_FRAME_RATE) {
+                report(
+                    ErrorType::InvalidFrameRate,
+                    format!("has FRAME-RATE {}, expected more than 0 and at most {}", rate, MAX_FRAME_RATE),
+                );
+            }
+        }
+
+        if variant.is_i_frame {
+            if variant.uri.is_empty() {
+                report(ErrorType::UnresolvableIFrameUri, "has no URI".to_string());
+            } else {
+                match resolve(stream_url, &variant.uri) {
+                    None => report(
+                        ErrorType::UnresolvableIFrameUri,
+                        "URI does not resolve against the master playlist URL".to_string(),
+                    ),
+                    Some(url) if stream_inf_urls.contains(&url) => report(
+                        ErrorType::UnresolvableIFrameUri,
+                        "URI points at an EXT-X-STREAM-INF playlist rather than an I-frame playlist".to_string(),
+                    ),
+                    Some(_) => {}
+                }
+            }
+        }
+    }
+
+    for media in &master.alternatives {
+        let media_type = media.media_type.to_string();
+        let mut report = |error_type: ErrorType, problem: String| {
+            errors.push(MonitorError::new(
+                error_type,
+                media_type.as_str(),
+                media.group_id.as_str(),
+                format!("Rendition '{}' in group '{}' {}", media.name, media.group_id, problem),
+                stream_url,
+                stream_id,
+            ));
+        };
+
+        if media.forced {
+            if !media.autoselect {
+                report(ErrorType::ForcedViolation, "has FORCED=YES but not AUTOSELECT=YES".to_string());
+            }
+            if media.language.is_none() {
+                report(ErrorType::ForcedViolation, "has FORCED=YES but no LANGUAGE to match it against".to_string());
+            }
+        }
+
+        if let Some(characteristics) = &media.characteristics {
+            for problem in characteristics.split(',').filter_map(|c| characteristic_problem(c, &media.media_type)) {
+                report(ErrorType::CharacteristicsViolation, problem);
+            }
+        }
+    }
+
+    for attrs in raw_media_tags(body) {
+        let attr = |name: &str| attrs.get(name).map(String::as_str);
+        let media_type = attr("TYPE").unwrap_or_default();
+        let mut report = |error_type: ErrorType, problem: String| {
+            errors.push(MonitorError::new(
+                error_type,
+                media_type,
+                attr("GROUP-ID").unwrap_or_default(),
+                format!(
+                    "Rendition '{}' in group '{}' {}",
+                    attr("NAME").unwrap_or_default(),
+                    attr("GROUP-ID").unwrap_or_default(),
+                    problem
+                ),
+                stream_url,
+                stream_id,
+            ));
+        };
+
+        if let (Some("YES"), Some(autoselect)) = (attr("DEFAULT"), attr("AUTOSELECT")) {
+            if autoselect != "YES" {
+                report(ErrorType::AutoselectViolation, format!("has DEFAULT=YES but AUTOSELECT={}", autoselect));
+            }
+        }
+
+        if let Some(forced) = attr("FORCED").filter(|_| media_type != "SUBTITLES") {
+            report(
+                ErrorType::ForcedViolation,
+                format!("has FORCED={} but FORCED is only allowed on TYPE=SUBTITLES", forced),
+            );
+        }
+    }
+
+    if config.authoring_spec {
+        let missing_avg_bw = master
+            .variants
+            .iter()
+            .filter(|v| !v.is_i_frame && v.average_bandwidth.is_none())
+            .count();
+        if missing_avg_bw > 0 {
+            errors.push(MonitorError::new(
+                ErrorType::AuthoringSpecViolation,
+                "MASTER",
+                "master",
+                format!("{} variant(s) missing AVERAGE-BANDWIDTH attribute", missing_avg_bw),
+                stream_url,
+                stream_id,
+            ));
+        }
+
+        if !master.independent_segments {
+            errors.push(MonitorError::new(
+                ErrorType::AuthoringSpecViolation,
+                "MASTER",
+                "master",
+                "Master playlist missing EXT-X-INDEPENDENT-SEGMENTS tag",
+                stream_url,
+                stream_id,
+            ));
+        }
+
+        let has_cellular = master
+            .variants
+            .iter()
+            .any(|v| !v.is_i_frame && v.bandwidth <= 192_000);
+        if !has_cellular {
+            errors.push(MonitorError::new(
+                ErrorType::AuthoringSpecViolation,
+                "MASTER",
+                "master",
+                "No variant with BANDWIDTH ≤ 192 kb/s for cellular delivery",
+                stream_url,
+                stream_id,
+            ));
+        }
+    }
+
+    errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_master(text: &str) -> m3u8_rs::MasterPlaylist {
+        let (_, playlist) = m3u8_rs::parse_playlist(text.as_bytes()).unwrap();
+        match playlist {
+            m3u8_rs::Playlist::MasterPlaylist(m) => m,
+            _ => panic!("expected master playlist"),
+        }
+    }
+
+    fn authoring_errors(errors: &[MonitorError]) -> Vec<&MonitorError> {
+        errors
+            .iter()
+            .filter(|e| e.error_type == ErrorType::AuthoringSpecViolation)
+            .collect()
+    }
+
+    #[test]
+    fn test_authoring_avg_bandwidth_missing() {
+        let text = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=500000\n\
+            low.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1000000\n\
+            high.m3u8\n";
+        let master = parse_master(text);
+        let config = MonitorConfig::default().with_authoring_spec(true);
+        let errors = validate_master(&master, text, "http://x.com/m.m3u8", "s1", &config);
+        let ae = authoring_errors(&errors);
+        assert!(ae.iter().any(|e| e.details.contains("AVERAGE-BANDWIDTH")));
+    }
+
+    #[test]
+    fn test_authoring_avg_bandwidth_present() {
+        let text = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=500000,AVERAGE-BANDWIDTH=400000\n\
+            low.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1000000,AVERAGE-BANDWIDTH=800000\n\
+            high.m3u8\n";
+        let master = parse_master(text);
+        let config = MonitorConfig::default().with_authoring_spec(true);
+        let errors = validate_master(&master, text, "http://x.com/m.m3u8", "s1", &config);
+        let ae = authoring_errors(&errors);
+        assert!(!ae.iter().any(|e| e.details.contains("AVERAGE-BANDWIDTH")));
+    }
+
+    #[test]
+    fn test_authoring_independent_segments_missing() {
+        let text = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=150000\n\
+            low.m3u8\n";
+        let master = parse_master(text);
+        let config = MonitorConfig::default().with_authoring_spec(true);
+        let errors = validate_master(&master, text, "http://x.com/m.m3u8", "s1", &config);
+        let ae = authoring_errors(&errors);
+        assert!(ae.iter().any(|e| e.details.contains("INDEPENDENT-SEGMENTS")));
+    }
+
+    #[test]
+    fn test_authoring_independent_segments_present() {
+        let text = "#EXTM3U\n\
+            #EXT-X-INDEPENDENT-SEGMENTS\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=150000\n\
+            low.m3u8\n";
+        let master = parse_master(text);
+        let config = MonitorConfig::default().with_authoring_spec(true);
+        let errors = validate_master(&master, text, "http://x.com/m.m3u8", "s1", &config);
+        let ae = authoring_errors(&errors);
+        assert!(!ae.iter().any(|e| e.details.contains("INDEPENDENT-SEGMENTS")));
+    }
+
+    #[test]
+    fn test_authoring_no_cellular_variant() {
+        let text = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=500000\n\
+            mid.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1000000\n\
+            high.m3u8\n";
+        let master = parse_master(text);
+        let config = MonitorConfig::default().with_authoring_spec(true);
+        let errors = validate_master(&master, text, "http://x.com/m.m3u8", "s1", &config);
+        let ae = authoring_errors(&errors);
+        assert!(ae.iter().any(|e| e.details.contains("192 kb/s")));
+    }
+
+    #[test]
+    fn test_authoring_has_cellular_variant() {
+        let text = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=150000\n\
+            cellular.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1000000\n\
+            high.m3u8\n";
+        let master = parse_master(text);
+        let config = MonitorConfig::default().with_authoring_spec(true);
+        let errors = validate_master(&master, text, "http://x.com/m.m3u8", "s1", &config);
+        let ae = authoring_errors(&errors);
+        assert!(!ae.iter().any(|e| e.details.contains("192 kb/s")));
+    }
+
+    #[test]
+    fn test_authoring_disabled_no_errors() {
+        let text = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=500000\n\
+            low.m3u8\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=1000000\n\
+            high.m3u8\n";
+        let master = parse_master(text);
+        let config = MonitorConfig::default();
+        let errors = validate_master(&master, text, "http://x.com/m.m3u8", "s1", &config);
+        let ae = authoring_errors(&errors);
+        assert!(ae.is_empty());
+    }
+
+    fn of_type(errors: &[MonitorError], error_type: ErrorType) -> Vec<&str> {
+        errors.iter().filter(|e| e.error_type == error_type).map(|e| e.details.as_str()).collect()
+    }
+
+    #[test]
+    fn well_formed_master_passes() {
+        let text = "#EXTM3U\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\",LANGUAGE=\"en\",DEFAULT=YES,AUTOSELECT=YES,URI=\"en.m3u8\"\n\
+            #EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID=\"subs\",NAME=\"English (forced)\",LANGUAGE=\"en\",AUTOSELECT=YES,FORCED=YES,URI=\"forced.m3u8\"\n\
+            #EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID=\"subs\",NAME=\"English SDH\",LANGUAGE=\"en\",CHARACTERISTICS=\"public.accessibility.transcribes-spoken-dialog,public.accessibility.describes-music-and-sound\",URI=\"sdh.m3u8\"\n\
+            #EXT-X-MEDIA:TYPE=CLOSED-CAPTIONS,GROUP-ID=\"cc\",NAME=\"CC1\",INSTREAM-ID=\"CC1\"\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2000000,CODECS=\"avc1.64001f,mp4a.40.2\",RESOLUTION=1280x720,FRAME-RATE=29.97,AUDIO=\"aac\",SUBTITLES=\"subs\",CLOSED-CAPTIONS=\"cc\"\n\
+            video.m3u8\n\
+            #EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=200000,CODECS=\"hvc1.2.4.L123.B0\",RESOLUTION=1280x720,URI=\"iframe.m3u8\"\n";
+        let errors = validate_master(&parse_master(text), text, "http://x.com/m.m3u8", "s1", &MonitorConfig::default());
+        assert!(errors.is_empty(), "{:#?}", errors);
+    }
+
+    #[test]
+    fn detects_undefined_groups_and_missing_codecs() {
+        let text = "#EXTM3U\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\",URI=\"en.m3u8\"\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2000000,AUDIO=\"ac3\",SUBTITLES=\"aac\"\n\
+            video.m3u8\n";
+        let errors = validate_master(&parse_master(text), text, "http://x.com/m.m3u8", "s1", &MonitorConfig::default());
+        assert!(of_type(&errors, ErrorType::MissingCodecs).is_empty());
+
+        let config = MonitorConfig::default().with_authoring_spec(true);
+        let errors = validate_master(&parse_master(text), text, "http://x.com/m.m3u8", "s1", &config);
+        assert_eq!(
+            of_type(&errors, ErrorType::UndefinedRenditionGroup),
+            [
+                "EXT-X-STREAM-INF 'video.m3u8' references AUDIO group 'ac3' with no EXT-X-MEDIA of TYPE=AUDIO",
+                "EXT-X-STREAM-INF 'video.m3u8' references SUBTITLES group 'aac' with no EXT-X-MEDIA of TYPE=SUBTITLES",
+            ]
+        );
+        assert_eq!(of_type(&errors, ErrorType::MissingCodecs), ["EXT-X-STREAM-INF 'video.m3u8' has no CODECS attribute"]);
+        assert_eq!(errors[0].variant, "2000000_video");
+    }
+
+    #[test]
+    fn parses_rfc6381_codecs() {
+        for ok in ["avc1.64001f", "avc3.4D401E", "mp4a.40.2", "mp4a.6b", "hev1.1.6.L93.B0", "ec-3", "av01.0.04M.08", "stpp.ttml.im1t", "fLaC"] {
+            assert_eq!(codec_problem(ok), None, "{}", ok);
+        }
+        assert!(codec_problem("avc1.64001").unwrap().contains("six hex digits"));
+        assert!(codec_problem("mp4a.40").unwrap().contains("mp4a.40.N"));
+        assert!(codec_problem("h264").is_none(), "unknown sample entries are only checked for syntax");
+        assert!(codec_problem("avc").unwrap().contains("four-character"));
+        assert!(codec_problem("avc1..64").unwrap().contains("malformed parameter"));
+        assert!(codec_problem("").is_some());
+    }
+
+    #[test]
+    fn detects_bad_resolution_frame_rate_and_iframe_uri() {
+        let text = "#EXTM3U\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2000000,CODECS=\"avc1.64001f\",RESOLUTION=0x720,FRAME-RATE=1000\n\
+            video.m3u8\n\
+            #EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=200000,CODECS=\"avc1.64001f\",URI=\"video.m3u8\"\n";
+        let errors = validate_master(&parse_master(text), text, "http://x.com/m.m3u8", "s1", &MonitorConfig::default());
+        assert_eq!(
+            of_type(&errors, ErrorType::InvalidResolution),
+            ["EXT-X-STREAM-INF 'video.m3u8' has RESOLUTION 0x720, expected 1 to 16384 pixels per side"]
+        );
+        assert_eq!(
+            of_type(&errors, ErrorType::InvalidFrameRate),
+            ["EXT-X-STREAM-INF 'video.m3u8' has FRAME-RATE 1000, expected more than 0 and at most 240"]
+        );
+        assert_eq!(
+            of_type(&errors, ErrorType::UnresolvableIFrameUri),
+            ["EXT-X-I-FRAME-STREAM-INF 'video.m3u8' URI points at an EXT-X-STREAM-INF playlist rather than an I-frame playlist"]
+        );
+    }
+
+    #[test]
+    fn detects_autoselect_forced_and_characteristics_violations() {
+        let text = "#EXTM3U\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\",DEFAULT=YES,AUTOSELECT=NO,CHARACTERISTICS=\"public.accessibility.transcribes-spoken-dialog\",URI=\"en.m3u8\"\n\
+            #EXT-X-MEDIA:TYPE=SUBTITLES,GROUP-ID=\"subs\",NAME=\"Forced\",FORCED=YES,CHARACTERISTICS=\"not a uti\",URI=\"forced.m3u8\"\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2000000,CODECS=\"avc1.64001f,mp4a.40.2\",AUDIO=\"aac\",SUBTITLES=\"subs\"\n\
+            video.m3u8\n";
+        let errors = validate_master(&parse_master(text), text, "http://x.com/m.m3u8", "s1", &MonitorConfig::default());
+        assert_eq!(
+            of_type(&errors, ErrorType::AutoselectViolation),
+            ["Rendition 'English' in group 'aac' has DEFAULT=YES but AUTOSELECT=NO"]
+        );
+        assert_eq!(
+            of_type(&errors, ErrorType::ForcedViolation),
+            [
+                "Rendition 'Forced' in group 'subs' has FORCED=YES but not AUTOSELECT=YES",
+                "Rendition 'Forced' in group 'subs' has FORCED=YES but no LANGUAGE to match it against",
+            ]
+        );
+        assert_eq!(
+            of_type(&errors, ErrorType::CharacteristicsViolation),
+            [
+                "Rendition 'English' in group 'aac' has CHARACTERISTICS 'public.accessibility.transcribes-spoken-dialog', which does not apply to TYPE=AUDIO",
+                "Rendition 'Forced' in group 'subs' has malformed CHARACTERISTICS entry 'not a uti', expected a Uniform Type Identifier",
+            ]
+        );
+        assert_eq!(errors.iter().find(|e| e.error_type == ErrorType::AutoselectViolation).unwrap().media_type, "AUDIO");
+    }
+
+    #[test]
+    fn autoselect_and_forced_presence_read_from_raw_tags() {
+        let text = "#EXTM3U\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"Main\",LANGUAGE=\"en\",DEFAULT=YES,URI=\"en.m3u8\"\n\
+            #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"Commentary\",LANGUAGE=\"en\",FORCED=NO,URI=\"commentary.m3u8\"\n\
+            #EXT-X-STREAM-INF:BANDWIDTH=2000000,CODECS=\"avc1.64001f,mp4a.40.2\",AUDIO=\"aac\"\n\
+            video.m3u8\n";
+        let errors = validate_master(&parse_master(text), text, "http://x.com/m.m3u8", "s1", &MonitorConfig::default());
+        assert!(of_type(&errors, ErrorType::AutoselectViolation).is_empty(), "absent AUTOSELECT is not NO");
+        assert_eq!(
+            of_type(&errors, ErrorType::ForcedViolation),
+            ["Rendition 'Commentary' in group 'aac' has FORCED=NO but FORCED is only allowed on TYPE=SUBTITLES"]
+        );
+        assert_eq!(errors.len(), 1, "{:#?}", errors);
+    }
+}
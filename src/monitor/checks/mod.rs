@@ -24,6 +24,17 @@ pub mod variant_playlist_type;
 pub mod variant_discontinuity;
 pub mod version_compat;
 pub mod encryption;
+pub mod part_duration;
+pub mod independent_part;
+pub mod part_retention;
+pub mod preload_hint;
+pub mod server_control;
+pub mod rendition_report;
+pub mod init_segment;
+pub mod ad_break_alignment;
+pub mod variant_pdt_drift;
+pub mod interstitial;
+pub mod master;
 
 use super::error::MonitorError;
 use super::state::{CheckContext, PlaylistSnapshot, VariantState};
@@ -67,21 +78,36 @@ pub fn default_checks(config: &crate::config::MonitorConfig) -> Vec<Box<dyn Chec
     checks.push(Box::new(version::VersionCheck));
     checks.push(Box::new(program_date_time::ProgramDateTimeCheck));
     checks.push(Box::new(daterange::DateRangeCheck));
+    checks.push(Box::new(interstitial::InterstitialCheck));
     checks.push(Box::new(target_duration_change::TargetDurationChangeCheck));
     checks.push(Box::new(min_playlist_duration::MinPlaylistDurationCheck));
     checks.push(Box::new(endlist::EndlistCheck));
     checks.push(Box::new(version_compat::VersionCompatibilityCheck));
     checks.push(Box::new(encryption::EncryptionConsistencyCheck));
+    checks.push(Box::new(part_duration::PartDurationCheck));
+    checks.push(Box::new(independent_part::IndependentPartCadenceCheck));
+    checks.push(Box::new(part_retention::PartRetentionCheck));
+    checks.push(Box::new(preload_hint::PreloadHintCheck));
+    checks.push(Box::new(server_control::ServerControlCheck));
+    checks.push(Box::new(init_segment::InitSegmentChangeCheck));
 
     checks
 }
 
 pub fn default_stream_checks(config: &crate::config::MonitorConfig) -> Vec<Box<dyn stream_check::StreamCheck>> {
-    vec![
+    let mut checks: Vec<Box<dyn stream_check::StreamCheck>> = vec![
         Box::new(variant_sync_drift::VariantSyncDriftCheck::new(config.variant_sync_drift_threshold)),
+        Box::new(variant_pdt_drift::VariantPdtDriftCheck::new(config.variant_pdt_drift_threshold)),
         Box::new(variant_availability::VariantAvailabilityCheck::new(config.variant_failure_threshold)),
         Box::new(variant_target_duration::VariantTargetDurationConsistencyCheck),
         Box::new(variant_playlist_type::VariantPlaylistTypeConsistencyCheck),
         Box::new(variant_discontinuity::VariantDiscontinuityConsistencyCheck),
-    ]
+        Box::new(rendition_report::RenditionReportCheck),
+    ];
+
+    if config.scte35_enabled {
+        checks.push(Box::new(ad_break_alignment::AdBreakAlignmentCheck::new(config.ad_break_tolerance)));
+    }
+
+    checks
 }
@@ -0,0 +1,201 @@
+use crate::monitor::error::{ErrorType, MonitorError};
+use crate::monitor::state::{CheckContext, PlaylistSnapshot, VariantState};
+
+use super::Check;
+
+/// Rounding slack allowed when comparing part durations to PART-TARGET.
+const PART_DURATION_EPSILON: f64 = 0.001;
+
+/// Validates `EXT-X-PART` durations against `EXT-X-PART-INF:PART-TARGET`.
+pub struct PartDurationCheck;
+
+impl Check for PartDurationCheck {
+    fn name(&self) -> &'static str {
+        "PartDuration"
+    }
+
+    fn check(
+        &self,
+        _prev: &VariantState,
+        curr: &PlaylistSnapshot,
+        ctx: &CheckContext,
+    ) -> Vec<MonitorError> {
+        let mut parts = curr.all_parts().peekable();
+        if parts.peek().is_none() {
+            return vec![];
+        }
+
+        let Some(part_target) = curr.part_target else {
+            return vec![MonitorError::new(
+                ErrorType::PartTargetDurationExceeded,
+                &ctx.media_type,
+                &ctx.variant_key,
+                "Playlist contains EXT-X-PART but is missing EXT-X-PART-INF",
+                &ctx.stream_url,
+                &ctx.stream_id,
+            )];
+        };
+
+        parts
+            .filter(|p| p.duration > part_target + PART_DURATION_EPSILON)
+            .map(|p| {
+                MonitorError::new(
+                    ErrorType::PartTargetDurationExceeded,
+                    &ctx.media_type,
+                    &ctx.variant_key,
+                    format!(
+                        "Part '{}' duration {:.3}s exceeds PART-TARGET {:.3}s",
+                        p.uri, p.duration, part_target
+                    ),
+                    &ctx.stream_url,
+                    &ctx.stream_id,
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::state::{PartSnapshot, SegmentSnapshot};
+
+    fn ctx() -> CheckContext {
+        CheckContext {
+            stream_url: "http://example.com/".to_string(),
+            stream_id: "stream_1".to_string(),
+            media_type: "VIDEO".to_string(),
+            variant_key: "1200000".to_string(),
+        }
+    }
+
+    fn make_prev() -> VariantState {
+        VariantState {
+            media_type: "VIDEO".to_string(),
+            media_sequence: 100,
+            segment_uris: vec!["a.mp4".into()],
+            discontinuity_sequence: 0,
+            next_is_discontinuity: false,
+            prev_segments: vec![],
+            duration: 4.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            in_cue_out: false,
+            cue_out_duration: None,
+            version: None,
+            target_duration: 4.0,
+            playlist_type: None,
+            has_endlist: false,
+            part_target: Some(1.0),
+            pending_parts: vec![],
+            last_part_uri: None,
+            preload_hint_uri: None,
+            can_skip_until: None,
+            can_block_reload: false,
+            last_part: None,
+            rendition_reports: vec![],
+            map: None,
+            key_uri: None,
+            ad_break: None,
+            ad_boundaries: vec![],
+            live_edge_latency: None,
+            clock_drift: None,
+            dateranges: Default::default(),
+        }
+    }
+
+    fn part(uri: &str, duration: f64) -> PartSnapshot {
+        PartSnapshot {
+            uri: uri.into(),
+            duration,
+            independent: false,
+            gap: false,
+            byterange_length: None,
+            byterange_offset: None,
+        }
+    }
+
+    fn make_snap(part_target: Option<f64>, parts: Vec<PartSnapshot>, pending: Vec<PartSnapshot>) -> PlaylistSnapshot {
+        PlaylistSnapshot {
+            media_sequence: 100,
+            discontinuity_sequence: 0,
+            segments: vec![SegmentSnapshot {
+                uri: "a.mp4".into(),
+                duration: 4.0,
+                discontinuity: false,
+                cue_out: false,
+                cue_in: false,
+                cue_out_cont: None,
+                gap: false,
+                program_date_time: None,
+                daterange: None,
+                parts,
+                byte_range_length: None,
+                map: None,
+                scte35: vec![],
+                cue_out_duration: None,
+                ad_markers: vec![],
+            }],
+            duration: 4.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            has_cue_out: false,
+            cue_out_duration: None,
+            target_duration: 4.0,
+            playlist_type: None,
+            version: None,
+            has_gaps: false,
+            has_endlist: false,
+            i_frames_only: false,
+            has_byte_range: false,
+            has_map: false,
+            has_key_iv: false,
+            has_key_format: false,
+            keys: vec![],
+            part_target,
+            pending_parts: pending,
+            preload_hints: vec![],
+            server_control: None,
+            skipped_segments: None,
+            rendition_reports: vec![],
+            key_uri: None,
+        }
+    }
+
+    #[test]
+    fn no_error_without_parts() {
+        let snap = make_snap(None, vec![], vec![]);
+        assert!(PartDurationCheck.check(&make_prev(), &snap, &ctx()).is_empty());
+    }
+
+    #[test]
+    fn no_error_within_part_target() {
+        let snap = make_snap(
+            Some(1.0),
+            vec![part("a.p0.mp4", 1.0), part("a.p1.mp4", 1.0005)],
+            vec![part("b.p0.mp4", 0.5)],
+        );
+        assert!(PartDurationCheck.check(&make_prev(), &snap, &ctx()).is_empty());
+    }
+
+    #[test]
+    fn detects_part_over_target() {
+        let snap = make_snap(
+            Some(1.0),
+            vec![part("a.p0.mp4", 1.0)],
+            vec![part("b.p0.mp4", 1.2)],
+        );
+        let errors = PartDurationCheck.check(&make_prev(), &snap, &ctx());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error_type, ErrorType::PartTargetDurationExceeded);
+        assert!(errors[0].details.contains("b.p0.mp4"));
+    }
+
+    #[test]
+    fn detects_parts_without_part_inf() {
+        let snap = make_snap(None, vec![part("a.p0.mp4", 1.0)], vec![]);
+        let errors = PartDurationCheck.check(&make_prev(), &snap, &ctx());
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].details.contains("EXT-X-PART-INF"));
+    }
+}
@@ -0,0 +1,189 @@
+use crate::monitor::error::{ErrorType, MonitorError};
+use crate::monitor::state::{CheckContext, PlaylistSnapshot, VariantState};
+
+use super::Check;
+
+/// Detects pending parts that vanish before their parent segment is published.
+///
+/// Parts listed after the last segment on the previous poll belong to media
+/// sequence `prev.media_sequence + prev.segment_uris.len()`. Until that segment
+/// appears in the playlist, every one of those parts must still be listed.
+pub struct PartRetentionCheck;
+
+impl Check for PartRetentionCheck {
+    fn name(&self) -> &'static str {
+        "PartRetention"
+    }
+
+    fn check(
+        &self,
+        prev: &VariantState,
+        curr: &PlaylistSnapshot,
+        ctx: &CheckContext,
+    ) -> Vec<MonitorError> {
+        if prev.pending_parts.is_empty() || curr.media_sequence < prev.media_sequence {
+            return vec![];
+        }
+
+        let parent_msn = prev.media_sequence + prev.segment_uris.len() as u64;
+        let published_end = curr.media_sequence + curr.segments.len() as u64;
+        if published_end > parent_msn {
+            return vec![];
+        }
+
+        prev.pending_parts
+            .iter()
+            .filter(|uri| !curr.pending_parts.iter().any(|p| &p.uri == *uri))
+            .map(|uri| {
+                MonitorError::new(
+                    ErrorType::PartRemovedEarly,
+                    &ctx.media_type,
+                    &ctx.variant_key,
+                    format!(
+                        "Part '{}' removed before its parent segment mseq({}) was published",
+                        uri, parent_msn
+                    ),
+                    &ctx.stream_url,
+                    &ctx.stream_id,
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::state::{PartSnapshot, SegmentSnapshot};
+
+    fn ctx() -> CheckContext {
+        CheckContext {
+            stream_url: "http://example.com/".to_string(),
+            stream_id: "stream_1".to_string(),
+            media_type: "VIDEO".to_string(),
+            variant_key: "1200000".to_string(),
+        }
+    }
+
+    fn make_prev(pending: &[&str]) -> VariantState {
+        VariantState {
+            media_type: "VIDEO".to_string(),
+            media_sequence: 100,
+            segment_uris: vec!["s100.mp4".into()],
+            discontinuity_sequence: 0,
+            next_is_discontinuity: false,
+            prev_segments: vec![],
+            duration: 2.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            in_cue_out: false,
+            cue_out_duration: None,
+            version: None,
+            target_duration: 2.0,
+            playlist_type: None,
+            has_endlist: false,
+            part_target: Some(0.5),
+            pending_parts: pending.iter().map(|s| s.to_string()).collect(),
+            last_part_uri: pending.last().map(|s| s.to_string()),
+            preload_hint_uri: None,
+            can_skip_until: None,
+            can_block_reload: false,
+            last_part: None,
+            rendition_reports: vec![],
+            map: None,
+            key_uri: None,
+            ad_break: None,
+            ad_boundaries: vec![],
+            live_edge_latency: None,
+            clock_drift: None,
+            dateranges: Default::default(),
+        }
+    }
+
+    fn part(uri: &str) -> PartSnapshot {
+        PartSnapshot {
+            uri: uri.into(),
+            duration: 0.5,
+            independent: false,
+            gap: false,
+            byterange_length: None,
+            byterange_offset: None,
+        }
+    }
+
+    fn segment(uri: &str) -> SegmentSnapshot {
+        SegmentSnapshot {
+            uri: uri.into(),
+            duration: 2.0,
+            discontinuity: false,
+            cue_out: false,
+            cue_in: false,
+            cue_out_cont: None,
+            gap: false,
+            program_date_time: None,
+            daterange: None,
+            parts: vec![],
+            byte_range_length: None,
+            map: None,
+            scte35: vec![],
+            cue_out_duration: None,
+            ad_markers: vec![],
+        }
+    }
+
+    fn make_snap(segments: Vec<SegmentSnapshot>, pending: &[&str]) -> PlaylistSnapshot {
+        PlaylistSnapshot {
+            media_sequence: 100,
+            discontinuity_sequence: 0,
+            segments,
+            duration: 2.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            has_cue_out: false,
+            cue_out_duration: None,
+            target_duration: 2.0,
+            playlist_type: None,
+            version: None,
+            has_gaps: false,
+            has_endlist: false,
+            i_frames_only: false,
+            has_byte_range: false,
+            has_map: false,
+            has_key_iv: false,
+            has_key_format: false,
+            keys: vec![],
+            part_target: Some(0.5),
+            pending_parts: pending.iter().map(|u| part(u)).collect(),
+            preload_hints: vec![],
+            server_control: None,
+            skipped_segments: None,
+            rendition_reports: vec![],
+            key_uri: None,
+        }
+    }
+
+    #[test]
+    fn no_error_when_parts_grow() {
+        let prev = make_prev(&["s101.p0.mp4"]);
+        let snap = make_snap(vec![segment("s100.mp4")], &["s101.p0.mp4", "s101.p1.mp4"]);
+        assert!(PartRetentionCheck.check(&prev, &snap, &ctx()).is_empty());
+    }
+
+    #[test]
+    fn no_error_when_parent_published() {
+        let prev = make_prev(&["s101.p0.mp4", "s101.p1.mp4"]);
+        let snap = make_snap(vec![segment("s100.mp4"), segment("s101.mp4")], &[]);
+        assert!(PartRetentionCheck.check(&prev, &snap, &ctx()).is_empty());
+    }
+
+    #[test]
+    fn detects_part_removed_early() {
+        let prev = make_prev(&["s101.p0.mp4", "s101.p1.mp4"]);
+        let snap = make_snap(vec![segment("s100.mp4")], &["s101.p1.mp4"]);
+        let errors = PartRetentionCheck.check(&prev, &snap, &ctx());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error_type, ErrorType::PartRemovedEarly);
+        assert!(errors[0].details.contains("s101.p0.mp4"));
+        assert!(errors[0].details.contains("mseq(101)"));
+    }
+}
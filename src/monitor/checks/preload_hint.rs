@@ -0,0 +1,212 @@
+use crate::monitor::error::{ErrorType, MonitorError};
+use crate::monitor::state::{CheckContext, PlaylistSnapshot, VariantState};
+
+use super::Check;
+
+/// Validates that the previous `EXT-X-PRELOAD-HINT:TYPE=PART` URI names the
+/// part that was actually published next.
+pub struct PreloadHintCheck;
+
+impl Check for PreloadHintCheck {
+    fn name(&self) -> &'static str {
+        "PreloadHint"
+    }
+
+    fn check(
+        &self,
+        prev: &VariantState,
+        curr: &PlaylistSnapshot,
+        ctx: &CheckContext,
+    ) -> Vec<MonitorError> {
+        let (Some(hint_uri), Some(last_part_uri), Some(last_position)) =
+            (&prev.preload_hint_uri, &prev.last_part_uri, prev.last_part_position())
+        else {
+            return vec![];
+        };
+
+        // Parts are located by position rather than URI, since byte-range
+        // parts of a segment all share one. If the last known part has rolled
+        // out of the window there is nothing to compare.
+        let mut parts = curr.positioned_parts().skip_while(|(position, _)| *position != last_position);
+        if parts.next().is_none() {
+            return vec![];
+        }
+        let Some((_, next_part)) = parts.next() else {
+            return vec![];
+        };
+
+        if &next_part.uri == hint_uri {
+            return vec![];
+        }
+
+        vec![MonitorError::new(
+            ErrorType::PreloadHintMismatch,
+            &ctx.media_type,
+            &ctx.variant_key,
+            format!(
+                "PRELOAD-HINT '{}' did not match the next part '{}' after '{}'",
+                hint_uri, next_part.uri, last_part_uri
+            ),
+            &ctx.stream_url,
+            &ctx.stream_id,
+        )]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::state::{PartSnapshot, SegmentSnapshot};
+
+    fn ctx() -> CheckContext {
+        CheckContext {
+            stream_url: "http://example.com/".to_string(),
+            stream_id: "stream_1".to_string(),
+            media_type: "VIDEO".to_string(),
+            variant_key: "1200000".to_string(),
+        }
+    }
+
+    fn make_prev(last_part: &str, hint: &str) -> VariantState {
+        VariantState {
+            media_type: "VIDEO".to_string(),
+            media_sequence: 100,
+            segment_uris: vec![],
+            discontinuity_sequence: 0,
+            next_is_discontinuity: false,
+            prev_segments: vec![],
+            duration: 0.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            in_cue_out: false,
+            cue_out_duration: None,
+            version: None,
+            target_duration: 2.0,
+            playlist_type: None,
+            has_endlist: false,
+            part_target: Some(0.5),
+            pending_parts: vec![last_part.to_string()],
+            last_part_uri: Some(last_part.to_string()),
+            preload_hint_uri: Some(hint.to_string()),
+            can_skip_until: None,
+            can_block_reload: false,
+            last_part: Some(0),
+            rendition_reports: vec![],
+            map: None,
+            key_uri: None,
+            ad_break: None,
+            ad_boundaries: vec![],
+            live_edge_latency: None,
+            clock_drift: None,
+            dateranges: Default::default(),
+        }
+    }
+
+    fn part(uri: &str, offset: Option<u64>) -> PartSnapshot {
+        PartSnapshot {
+            uri: uri.to_string(),
+            duration: 0.5,
+            independent: false,
+            gap: false,
+            byterange_length: offset.map(|_| 1000),
+            byterange_offset: offset,
+        }
+    }
+
+    fn make_snap(pending: &[&str]) -> PlaylistSnapshot {
+        PlaylistSnapshot {
+            media_sequence: 100,
+            discontinuity_sequence: 0,
+            segments: vec![],
+            duration: 0.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            has_cue_out: false,
+            cue_out_duration: None,
+            target_duration: 2.0,
+            playlist_type: None,
+            version: None,
+            has_gaps: false,
+            has_endlist: false,
+            i_frames_only: false,
+            has_byte_range: false,
+            has_map: false,
+            has_key_iv: false,
+            has_key_format: false,
+            keys: vec![],
+            part_target: Some(0.5),
+            pending_parts: pending.iter().map(|u| part(u, None)).collect(),
+            preload_hints: vec![],
+            server_control: None,
+            skipped_segments: None,
+            rendition_reports: vec![],
+            key_uri: None,
+        }
+    }
+
+    #[test]
+    fn no_error_when_hint_matches() {
+        let prev = make_prev("p0.mp4", "p1.mp4");
+        let errors = PreloadHintCheck.check(&prev, &make_snap(&["p0.mp4", "p1.mp4"]), &ctx());
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn no_error_when_next_part_not_published() {
+        let prev = make_prev("p0.mp4", "p1.mp4");
+        let errors = PreloadHintCheck.check(&prev, &make_snap(&["p0.mp4"]), &ctx());
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn detects_mismatched_hint() {
+        let prev = make_prev("p0.mp4", "p1.mp4");
+        let errors = PreloadHintCheck.check(&prev, &make_snap(&["p0.mp4", "other.mp4"]), &ctx());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error_type, ErrorType::PreloadHintMismatch);
+        assert!(errors[0].details.contains("other.mp4"));
+    }
+
+    #[test]
+    fn skips_when_last_part_rolled_out() {
+        let prev = make_prev("gone.mp4", "p1.mp4");
+        let mut snap = make_snap(&["x.mp4", "y.mp4"]);
+        snap.media_sequence = 101;
+        let errors = PreloadHintCheck.check(&prev, &snap, &ctx());
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn byte_range_parts_are_located_by_position() {
+        // Two parts of segment 100 were out, hinting at the first part of 101.
+        let mut prev = make_prev("s100.mp4", "s101.mp4");
+        prev.pending_parts = vec!["s100.mp4".to_string(), "s100.mp4".to_string()];
+        prev.last_part = Some(1);
+
+        let mut snap = make_snap(&["s101.mp4"]);
+        snap.segments = vec![SegmentSnapshot {
+            uri: "s100.mp4".to_string(),
+            duration: 1.0,
+            discontinuity: false,
+            cue_out: false,
+            cue_in: false,
+            cue_out_cont: None,
+            gap: false,
+            program_date_time: None,
+            daterange: None,
+            parts: vec![part("s100.mp4", Some(0)), part("s100.mp4", Some(1000))],
+            byte_range_length: None,
+            map: None,
+            scte35: vec![],
+            ad_markers: vec![],
+            cue_out_duration: None,
+        }];
+        assert!(PreloadHintCheck.check(&prev, &snap, &ctx()).is_empty());
+
+        // A third part of 100 published instead of the hinted one.
+        snap.segments[0].parts.push(part("s100.mp4", Some(2000)));
+        let errors = PreloadHintCheck.check(&prev, &snap, &ctx());
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].details.contains("next part 's100.mp4'"), "{}", errors[0].details);
+    }
+}
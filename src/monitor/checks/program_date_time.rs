@@ -1,8 +1,26 @@
+use chrono::{DateTime, FixedOffset};
+
 use crate::monitor::error::{ErrorType, MonitorError};
 use crate::monitor::state::{CheckContext, PlaylistSnapshot, VariantState};
 
 use super::Check;
 
+/// Milliseconds a PDT may differ from the previous PDT plus `EXTINF`.
+const PDT_TOLERANCE_MS: i64 = 1000;
+
+/// `(expected, actual, drift)` in milliseconds when `next` is not `duration`
+/// seconds after `prev`.
+fn jump(prev: DateTime<FixedOffset>, duration: f64, next: DateTime<FixedOffset>) -> Option<(i64, i64, i64)> {
+    let expected_ms = (duration * 1000.0) as i64;
+    let actual_ms = (next - prev).num_milliseconds();
+    let drift = (actual_ms - expected_ms).abs();
+    (drift > PDT_TOLERANCE_MS).then_some((expected_ms, actual_ms, drift))
+}
+
+/// Checks that each PDT follows the previous one by its `EXTINF`, within the
+/// playlist and, for the first segment once the window has slid past its
+/// predecessor, against the previous poll. A PDT that changed between polls
+/// for a segment still in the window is also reported.
 pub struct ProgramDateTimeCheck;
 
 impl Check for ProgramDateTimeCheck {
@@ -12,11 +30,57 @@ impl Check for ProgramDateTimeCheck {
 
     fn check(
         &self,
-        _prev: &VariantState,
+        prev: &VariantState,
         curr: &PlaylistSnapshot,
         ctx: &CheckContext,
     ) -> Vec<MonitorError> {
         let mut errors = Vec::new();
+        let error = |details: String| {
+            MonitorError::new(
+                ErrorType::ProgramDateTimeJump,
+                &ctx.media_type,
+                &ctx.variant_key,
+                details,
+                &ctx.stream_url,
+                &ctx.stream_id,
+            )
+        };
+
+        // Segments of the previous poll by media sequence number.
+        let prev_at = |mseq: u64| {
+            mseq.checked_sub(prev.media_sequence)
+                .and_then(|i| prev.prev_segments.get(i as usize))
+        };
+
+        if let Some(first) = curr.segments.first().filter(|s| !s.discontinuity) {
+            let before = curr.media_sequence.checked_sub(1).and_then(prev_at);
+            let is_new = curr.media_sequence >= prev.segment_window().end;
+            if let (true, Some(before), Some(next_pdt)) = (is_new, before, first.program_date_time) {
+                if let Some((expected_ms, actual_ms, drift)) =
+                    before.program_date_time.and_then(|p| jump(p, before.duration, next_pdt))
+                {
+                    errors.push(error(format!(
+                        "PDT discontinuity across polls at mseq({}): expected +{expected_ms}ms after '{}', actual diff {actual_ms}ms (drift {drift}ms)",
+                        curr.media_sequence, before.uri
+                    )));
+                }
+            }
+        }
+
+        let rewritten = curr.segments.iter().enumerate().find_map(|(i, seg)| {
+            let mseq = curr.media_sequence + i as u64;
+            let was = prev_at(mseq).filter(|p| p.uri == seg.uri)?.program_date_time?;
+            let now = seg.program_date_time?;
+            ((now - was).num_milliseconds().abs() > PDT_TOLERANCE_MS).then_some((mseq, was, now))
+        });
+        if let Some((mseq, was, now)) = rewritten {
+            errors.push(error(format!(
+                "PDT of mseq({mseq}) changed between polls from {} to {} ({}ms)",
+                was.to_rfc3339(),
+                now.to_rfc3339(),
+                (now - was).num_milliseconds()
+            )));
+        }
 
         for (idx, pair) in curr.segments.windows(2).enumerate() {
             let prev_seg = &pair[0];
@@ -33,22 +97,11 @@ impl Check for ProgramDateTimeCheck {
                 _ => continue,
             };
 
-            let expected_ms = (prev_seg.duration * 1000.0) as i64;
-            let actual_ms = (*next_pdt - *prev_pdt).num_milliseconds();
-            let drift = (actual_ms - expected_ms).abs();
-
-            if drift > 1000 {
+            if let Some((expected_ms, actual_ms, drift)) = jump(*prev_pdt, prev_seg.duration, *next_pdt) {
                 let mseq = curr.media_sequence + seg_idx as u64;
-                errors.push(MonitorError::new(
-                    ErrorType::ProgramDateTimeJump,
-                    &ctx.media_type,
-                    &ctx.variant_key,
-                    format!(
-                        "PDT discontinuity at index({seg_idx}) in mseq({mseq}): expected +{expected_ms}ms, actual diff {actual_ms}ms (drift {drift}ms)"
-                    ),
-                    &ctx.stream_url,
-                    &ctx.stream_id,
-                ));
+                errors.push(error(format!(
+                    "PDT discontinuity at index({seg_idx}) in mseq({mseq}): expected +{expected_ms}ms, actual diff {actual_ms}ms (drift {drift}ms)"
+                )));
             }
         }
 
@@ -59,7 +112,7 @@ impl Check for ProgramDateTimeCheck {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::monitor::state::SegmentSnapshot;
+    use crate::monitor::state::{SegmentInfo, SegmentSnapshot};
     use chrono::{DateTime, Duration, FixedOffset};
 
     fn ctx() -> CheckContext {
@@ -88,6 +141,21 @@ mod tests {
             target_duration: 10.0,
             playlist_type: None,
             has_endlist: false,
+            part_target: None,
+            pending_parts: vec![],
+            last_part_uri: None,
+            preload_hint_uri: None,
+            can_skip_until: None,
+            can_block_reload: false,
+            last_part: None,
+            rendition_reports: vec![],
+            map: None,
+            key_uri: None,
+            ad_break: None,
+            ad_boundaries: vec![],
+            live_edge_latency: None,
+            clock_drift: None,
+            dateranges: Default::default(),
         }
     }
 
@@ -102,6 +170,12 @@ mod tests {
             gap: false,
             program_date_time: pdt,
             daterange: None,
+            parts: vec![],
+            byte_range_length: None,
+            map: None,
+            scte35: vec![],
+            cue_out_duration: None,
+            ad_markers: vec![],
         }
     }
 
@@ -127,6 +201,13 @@ mod tests {
             has_key_iv: false,
             has_key_format: false,
             keys: vec![],
+            part_target: None,
+            pending_parts: vec![],
+            preload_hints: vec![],
+            server_control: None,
+            skipped_segments: None,
+            rendition_reports: vec![],
+            key_uri: None,
         }
     }
 
@@ -200,4 +281,71 @@ mod tests {
         let errors = check.check(&make_prev(), &snap, &ctx());
         assert!(errors.is_empty());
     }
+
+    /// Previous poll at mseq 100 with 10s segments `uris`, stamped from `t0`.
+    fn prev_with(uris: &[&str], t0: DateTime<FixedOffset>) -> VariantState {
+        let mut prev = make_prev();
+        prev.media_sequence = 100;
+        prev.segment_uris = uris.iter().map(|u| u.to_string()).collect();
+        prev.prev_segments = uris
+            .iter()
+            .enumerate()
+            .map(|(i, u)| SegmentInfo {
+                uri: u.to_string(),
+                discontinuity: false,
+                duration: 10.0,
+                program_date_time: Some(t0 + Duration::seconds(10 * i as i64)),
+                tags: Default::default(),
+            })
+            .collect();
+        prev
+    }
+
+    #[test]
+    fn detects_jump_at_window_boundary() {
+        let t0 = base_pdt();
+        let prev = prev_with(&["a.ts", "b.ts"], t0);
+        // The window slid past b.ts; c.ts should start at t0 + 20s.
+        let snap = make_snap(102, vec![
+            make_segment("c.ts", 10.0, Some(t0 + Duration::seconds(25)), false),
+            make_segment("d.ts", 10.0, Some(t0 + Duration::seconds(35)), false),
+        ]);
+        let errors = ProgramDateTimeCheck.check(&prev, &snap, &ctx());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(
+            errors[0].details,
+            "PDT discontinuity across polls at mseq(102): expected +10000ms after 'b.ts', actual diff 15000ms (drift 5000ms)"
+        );
+
+        let snap = make_snap(102, vec![make_segment("c.ts", 10.0, Some(t0 + Duration::seconds(20)), false)]);
+        assert!(ProgramDateTimeCheck.check(&prev, &snap, &ctx()).is_empty());
+        let snap = make_snap(102, vec![make_segment("c.ts", 10.0, Some(t0 + Duration::seconds(25)), true)]);
+        assert!(ProgramDateTimeCheck.check(&prev, &snap, &ctx()).is_empty());
+    }
+
+    #[test]
+    fn detects_jump_in_single_file_playlist() {
+        // Every segment of a single-file playlist shares one URI.
+        let t0 = base_pdt();
+        let prev = prev_with(&["main.ts", "main.ts"], t0);
+        let snap = make_snap(102, vec![make_segment("main.ts", 10.0, Some(t0 + Duration::seconds(25)), false)]);
+        let errors = ProgramDateTimeCheck.check(&prev, &snap, &ctx());
+        assert_eq!(errors.len(), 1, "{:#?}", errors);
+        assert!(errors[0].details.starts_with("PDT discontinuity across polls at mseq(102)"));
+    }
+
+    #[test]
+    fn detects_pdt_rewritten_between_polls() {
+        let t0 = base_pdt();
+        let prev = prev_with(&["a.ts", "b.ts", "c.ts"], t0);
+        let shifted = t0 + Duration::seconds(3);
+        let snap = make_snap(101, vec![
+            make_segment("b.ts", 10.0, Some(shifted + Duration::seconds(10)), false),
+            make_segment("c.ts", 10.0, Some(shifted + Duration::seconds(20)), false),
+        ]);
+        let errors = ProgramDateTimeCheck.check(&prev, &snap, &ctx());
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].details.starts_with("PDT of mseq(101) changed between polls"), "{}", errors[0].details);
+        assert!(errors[0].details.ends_with("(3000ms)"));
+    }
 }
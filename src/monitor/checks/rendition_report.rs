@@ -0,0 +1,211 @@
+use std::collections::HashMap;
+
+use crate::monitor::checks::stream_check::{StreamCheck, StreamCheckContext};
+use crate::monitor::error::{ErrorType, MonitorError};
+use crate::monitor::state::VariantState;
+
+/// How far a report's LAST-MSN may differ from the fetched rendition. The
+/// renditions are fetched concurrently, so one may advance in between.
+const LAST_MSN_TOLERANCE: u64 = 1;
+
+/// Compares every `EXT-X-RENDITION-REPORT` LAST-MSN/LAST-PART with the state
+/// the monitor fetched from the reported rendition on the same poll.
+pub struct RenditionReportCheck;
+
+impl StreamCheck for RenditionReportCheck {
+    fn name(&self) -> &'static str {
+        "RenditionReport"
+    }
+
+    fn check(
+        &self,
+        variants: &HashMap<String, VariantState>,
+        ctx: &StreamCheckContext,
+    ) -> Vec<MonitorError> {
+        let mut errors = Vec::new();
+
+        let mut keys: Vec<&String> = variants.keys().collect();
+        keys.sort();
+
+        for key in keys {
+            let state = &variants[key];
+            for report in &state.rendition_reports {
+                let mut violation = |details: String| {
+                    errors.push(MonitorError::new(
+                        ErrorType::RenditionReportMismatch,
+                        &state.media_type,
+                        key.as_str(),
+                        details,
+                        &ctx.stream_url,
+                        &ctx.stream_id,
+                    ));
+                };
+
+                let Some(target_key) = ctx.variant_urls.get(&report.uri) else {
+                    // A bare media playlist URL has no master to resolve renditions against.
+                    if ctx.variant_urls.len() > 1 {
+                        violation(format!(
+                            "RENDITION-REPORT URI '{}' does not match any rendition in the master playlist",
+                            report.uri
+                        ));
+                    }
+                    continue;
+                };
+                let Some(target) = variants.get(target_key) else {
+                    continue;
+                };
+                let Some(actual_msn) = target.last_msn() else {
+                    continue;
+                };
+
+                let Some(reported_msn) = report.last_msn else {
+                    violation(format!(
+                        "RENDITION-REPORT for {} is missing LAST-MSN",
+                        target_key
+                    ));
+                    continue;
+                };
+
+                if reported_msn.abs_diff(actual_msn) > LAST_MSN_TOLERANCE {
+                    violation(format!(
+                        "RENDITION-REPORT for {} has LAST-MSN={} but the rendition's last segment is mseq({})",
+                        target_key, reported_msn, actual_msn
+                    ));
+                    continue;
+                }
+
+                if reported_msn == actual_msn && report.last_part.is_some() != target.last_part.is_some() {
+                    violation(format!(
+                        "RENDITION-REPORT for {} has LAST-PART={} but the rendition {}",
+                        target_key,
+                        report.last_part.map(|p| p.to_string()).unwrap_or_else(|| "<none>".to_string()),
+                        match target.last_part {
+                            Some(p) => format!("has parts up to {}", p),
+                            None => "has no parts".to_string(),
+                        }
+                    ));
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::state::RenditionReportSnapshot;
+
+    const URL_1M: &str = "https://example.com/live/1M/index.m3u8";
+    const URL_2M: &str = "https://example.com/live/2M/index.m3u8";
+
+    fn ctx() -> StreamCheckContext {
+        StreamCheckContext {
+            stream_url: "https://example.com/live/".to_string(),
+            stream_id: "stream_1".to_string(),
+            variant_failures: HashMap::new(),
+            variant_urls: HashMap::from([
+                (URL_1M.to_string(), "1000000".to_string()),
+                (URL_2M.to_string(), "2000000".to_string()),
+            ]),
+        }
+    }
+
+    fn make_variant(mseq: u64, segments: usize, pending: usize, reports: Vec<RenditionReportSnapshot>) -> VariantState {
+        VariantState {
+            media_type: "VIDEO".to_string(),
+            media_sequence: mseq,
+            segment_uris: (0..segments).map(|i| format!("s{}.mp4", i)).collect(),
+            discontinuity_sequence: 0,
+            next_is_discontinuity: false,
+            prev_segments: vec![],
+            duration: 0.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            in_cue_out: false,
+            cue_out_duration: None,
+            version: None,
+            target_duration: 2.0,
+            playlist_type: None,
+            has_endlist: false,
+            part_target: Some(0.5),
+            pending_parts: (0..pending).map(|i| format!("p{}.mp4", i)).collect(),
+            last_part_uri: None,
+            preload_hint_uri: None,
+            can_skip_until: None,
+            can_block_reload: false,
+            last_part: pending.checked_sub(1).map(|p| p as u64),
+            rendition_reports: reports,
+            map: None,
+            key_uri: None,
+            ad_break: None,
+            ad_boundaries: vec![],
+            live_edge_latency: None,
+            clock_drift: None,
+            dateranges: Default::default(),
+        }
+    }
+
+    fn report(uri: &str, last_msn: Option<u64>, last_part: Option<u64>) -> RenditionReportSnapshot {
+        RenditionReportSnapshot {
+            uri: uri.to_string(),
+            last_msn,
+            last_part,
+        }
+    }
+
+    #[test]
+    fn no_error_when_report_matches() {
+        let variants = HashMap::from([
+            ("1000000".to_string(), make_variant(10, 3, 2, vec![report(URL_2M, Some(13), Some(1))])),
+            ("2000000".to_string(), make_variant(10, 3, 2, vec![report(URL_1M, Some(13), Some(1))])),
+        ]);
+        assert!(RenditionReportCheck.check(&variants, &ctx()).is_empty());
+    }
+
+    #[test]
+    fn tolerates_one_segment_of_skew() {
+        let variants = HashMap::from([
+            ("1000000".to_string(), make_variant(10, 3, 2, vec![report(URL_2M, Some(14), Some(0))])),
+            ("2000000".to_string(), make_variant(10, 3, 2, vec![])),
+        ]);
+        assert!(RenditionReportCheck.check(&variants, &ctx()).is_empty());
+    }
+
+    #[test]
+    fn detects_stale_last_msn() {
+        let variants = HashMap::from([
+            ("1000000".to_string(), make_variant(10, 3, 2, vec![report(URL_2M, Some(9), Some(1))])),
+            ("2000000".to_string(), make_variant(10, 3, 2, vec![])),
+        ]);
+        let errors = RenditionReportCheck.check(&variants, &ctx());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error_type, ErrorType::RenditionReportMismatch);
+        assert_eq!(errors[0].variant, "1000000");
+        assert!(errors[0].details.contains("LAST-MSN=9"));
+        assert!(errors[0].details.contains("mseq(13)"));
+    }
+
+    #[test]
+    fn detects_missing_last_part() {
+        let variants = HashMap::from([
+            ("1000000".to_string(), make_variant(10, 3, 2, vec![report(URL_2M, Some(13), None)])),
+            ("2000000".to_string(), make_variant(10, 3, 2, vec![])),
+        ]);
+        let errors = RenditionReportCheck.check(&variants, &ctx());
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].details.contains("has parts up to 1"));
+    }
+
+    #[test]
+    fn detects_unknown_rendition() {
+        let variants = HashMap::from([(
+            "1000000".to_string(),
+            make_variant(10, 3, 2, vec![report("https://example.com/live/9M/index.m3u8", Some(13), Some(1))]),
+        )]);
+        let errors = RenditionReportCheck.check(&variants, &ctx());
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].details.contains("does not match any rendition"));
+    }
+}
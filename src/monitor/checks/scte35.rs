@@ -1,11 +1,49 @@
 use crate::monitor::error::{ErrorType, MonitorError};
-use crate::monitor::state::{CheckContext, PlaylistSnapshot, VariantState};
+use crate::monitor::state::{CheckContext, PlaylistSnapshot, Scte35Marker, VariantState};
 
 use super::Check;
 
+/// Seconds a tag's DURATION may differ from the signalled break duration.
+const DURATION_TOLERANCE: f64 = 0.5;
+
 /// Validates SCTE-35/CUE marker consistency in HLS playlists.
+///
+/// SCTE-35 payloads on newly published segments are also decoded and checked
+/// against their tag: the CRC, the out-of-network direction and the break
+/// duration must agree with what the playlist says.
 pub struct Scte35Check;
 
+/// Describe how a decoded payload contradicts its tag.
+fn marker_problems(marker: &Scte35Marker) -> Vec<String> {
+    let info = match &marker.signal {
+        Ok(info) => info,
+        Err(e) => return vec![format!("undecodable payload: {}", e)],
+    };
+    let mut problems = Vec::new();
+    if !info.crc_valid {
+        problems.push(format!("CRC_32 0x{:08x} does not match the section", info.crc_32));
+    }
+    if let (Some(tag_out), Some(signal_out)) = (marker.tag_out, info.out_of_network()) {
+        if tag_out != signal_out {
+            problems.push(format!(
+                "tag {} a break but {} signals out_of_network={}",
+                if tag_out { "opens" } else { "closes" },
+                info,
+                signal_out
+            ));
+        }
+    }
+    if let (Some(tag_duration), Some(signal_duration)) = (marker.tag_duration, info.duration_secs()) {
+        if (tag_duration - signal_duration).abs() > DURATION_TOLERANCE {
+            problems.push(format!(
+                "tag duration {:.3}s differs from signalled {:.3}s",
+                tag_duration, signal_duration
+            ));
+        }
+    }
+    problems
+}
+
 impl Check for Scte35Check {
     fn name(&self) -> &'static str {
         "SCTE35"
@@ -70,14 +108,38 @@ impl Check for Scte35Check {
             ));
         }
 
+        for (mseq, seg) in curr.segments_after(Some(prev.segment_window())) {
+            for marker in &seg.scte35 {
+                for problem in marker_problems(marker) {
+                    errors.push(MonitorError::new(
+                        ErrorType::Scte35Violation,
+                        &ctx.media_type,
+                        &ctx.variant_key,
+                        format!(
+                            "{} in mseq({}): {} \u{2014} segment: '{}'",
+                            marker.source,
+                            mseq,
+                            problem,
+                            seg.uri
+                        ),
+                        &ctx.stream_url,
+                        &ctx.stream_id,
+                    ));
+                }
+            }
+        }
+
         errors
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use base64::Engine;
+
     use super::*;
-    use crate::monitor::state::SegmentSnapshot;
+    use crate::media::scte35::{self, testing};
+    use crate::monitor::state::{SegmentSnapshot, Scte35Source};
 
     fn ctx() -> CheckContext {
         CheckContext {
@@ -99,6 +161,12 @@ mod tests {
             gap: false,
             program_date_time: None,
             daterange: None,
+            parts: vec![],
+            byte_range_length: None,
+            map: None,
+            scte35: vec![],
+            cue_out_duration: None,
+            ad_markers: vec![],
         }
     }
 
@@ -119,6 +187,21 @@ mod tests {
             target_duration: 10.0,
             playlist_type: None,
             has_endlist: false,
+            part_target: None,
+            pending_parts: vec![],
+            last_part_uri: None,
+            preload_hint_uri: None,
+            can_skip_until: None,
+            can_block_reload: false,
+            last_part: None,
+            rendition_reports: vec![],
+            map: None,
+            key_uri: None,
+            ad_break: None,
+            ad_boundaries: vec![],
+            live_edge_latency: None,
+            clock_drift: None,
+            dateranges: Default::default(),
         }
     }
 
@@ -146,6 +229,13 @@ mod tests {
             has_key_iv: false,
             has_key_format: false,
             keys: vec![],
+            part_target: None,
+            pending_parts: vec![],
+            preload_hints: vec![],
+            server_control: None,
+            skipped_segments: None,
+            rendition_reports: vec![],
+            key_uri: None,
         }
     }
 
@@ -215,4 +305,73 @@ mod tests {
         let errors = check.check(&prev, &curr, &ctx());
         assert!(errors.is_empty());
     }
+
+    fn with_marker(mut seg: SegmentSnapshot, payload: String, tag_out: Option<bool>, tag_duration: Option<f64>) -> SegmentSnapshot {
+        seg.scte35.push(Scte35Marker {
+            source: Scte35Source::Oatcls,
+            signal: scte35::decode(&payload),
+            payload,
+            tag_out,
+            tag_duration,
+        });
+        seg
+    }
+
+    #[test]
+    fn no_error_for_consistent_splice_insert() {
+        let prev = make_prev(10, false);
+        let seg = make_seg("b.ts", true, false, None);
+        let seg = with_marker(seg, testing::splice_insert(1, true, Some(30.0)), Some(true), Some(30.0));
+        let curr = make_snap(10, vec![make_seg("a.ts", false, false, None), seg]);
+        assert!(Scte35Check.check(&prev, &curr, &ctx()).is_empty());
+    }
+
+    #[test]
+    fn error_when_payload_contradicts_tag() {
+        let prev = make_prev(10, false);
+        let seg = make_seg("b.ts", true, false, None);
+        let seg = with_marker(seg, testing::splice_insert(1, false, Some(60.0)), Some(true), Some(30.0));
+        let curr = make_snap(10, vec![make_seg("a.ts", false, false, None), seg]);
+        let errors = Scte35Check.check(&prev, &curr, &ctx());
+        assert_eq!(errors.len(), 2, "{:#?}", errors);
+        assert!(errors[0].details.contains("opens a break but splice_insert event_id=1"));
+        assert!(errors[0].details.contains("mseq(11)"));
+        assert!(errors[1].details.contains("tag duration 30.000s differs from signalled 60.000s"));
+
+        // Segments seen on the previous poll are not validated again.
+        let mut prev = make_prev(10, true);
+        prev.segment_uris.push("b.ts".into());
+        assert!(Scte35Check.check(&prev, &curr, &ctx()).is_empty());
+    }
+
+    #[test]
+    fn byte_range_segments_are_new_by_media_sequence() {
+        // Every segment of a single-file playlist shares one URI.
+        let mut prev = make_prev(10, false);
+        prev.segment_uris = vec!["main.ts".into()];
+        let seg = make_seg("main.ts", true, false, None);
+        let seg = with_marker(seg, testing::splice_insert(1, false, Some(60.0)), Some(true), Some(30.0));
+        let curr = make_snap(10, vec![make_seg("main.ts", false, false, None), seg]);
+        let errors = Scte35Check.check(&prev, &curr, &ctx());
+        assert_eq!(errors.len(), 2, "{:#?}", errors);
+        assert!(errors[0].details.contains("mseq(11)"));
+    }
+
+    #[test]
+    fn error_for_undecodable_or_corrupt_payload() {
+        let prev = make_prev(10, false);
+        let mut corrupt = base64::engine::general_purpose::STANDARD
+            .decode(testing::splice_insert(1, false, None))
+            .unwrap();
+        let last = corrupt.len() - 1;
+        corrupt[last] ^= 0x01;
+        let corrupt = base64::engine::general_purpose::STANDARD.encode(corrupt);
+        let seg = with_marker(make_seg("b.ts", false, false, None), corrupt, None, None);
+        let seg = with_marker(seg, "not-scte35".to_string(), None, None);
+        let curr = make_snap(10, vec![make_seg("a.ts", false, false, None), seg]);
+        let errors = Scte35Check.check(&prev, &curr, &ctx());
+        assert_eq!(errors.len(), 2, "{:#?}", errors);
+        assert!(errors[0].details.contains("EXT-OATCLS-SCTE35 in mseq(11): CRC_32"));
+        assert!(errors[1].details.contains("undecodable payload"));
+    }
 }
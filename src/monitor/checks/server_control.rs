@@ -0,0 +1,192 @@
+use crate::monitor::error::{ErrorType, MonitorError};
+use crate::monitor::state::{CheckContext, PlaylistSnapshot, VariantState};
+
+use super::Check;
+
+/// Validates `EXT-X-SERVER-CONTROL` hold-back values against their spec minimums:
+/// HOLD-BACK ≥ 3× target duration, and PART-HOLD-BACK (required with parts)
+/// ≥ 2× PART-TARGET.
+pub struct ServerControlCheck;
+
+impl Check for ServerControlCheck {
+    fn name(&self) -> &'static str {
+        "ServerControl"
+    }
+
+    fn check(
+        &self,
+        _prev: &VariantState,
+        curr: &PlaylistSnapshot,
+        ctx: &CheckContext,
+    ) -> Vec<MonitorError> {
+        let mut errors = Vec::new();
+        let mut violation = |details: String| {
+            errors.push(MonitorError::new(
+                ErrorType::ServerControlViolation,
+                &ctx.media_type,
+                &ctx.variant_key,
+                details,
+                &ctx.stream_url,
+                &ctx.stream_id,
+            ));
+        };
+
+        let server_control = curr.server_control.clone().unwrap_or_default();
+
+        if let Some(hold_back) = server_control.hold_back {
+            let min = 3.0 * curr.target_duration;
+            if hold_back + 0.001 < min {
+                violation(format!(
+                    "HOLD-BACK {:.3}s is less than 3x target duration ({:.3}s)",
+                    hold_back, min
+                ));
+            }
+        }
+
+        if let Some(part_target) = curr.part_target {
+            match server_control.part_hold_back {
+                None => violation(
+                    "EXT-X-PART-INF present but EXT-X-SERVER-CONTROL is missing PART-HOLD-BACK".to_string(),
+                ),
+                Some(part_hold_back) => {
+                    let min = 2.0 * part_target;
+                    if part_hold_back + 0.001 < min {
+                        violation(format!(
+                            "PART-HOLD-BACK {:.3}s is less than 2x PART-TARGET ({:.3}s)",
+                            part_hold_back, min
+                        ));
+                    }
+                }
+            }
+        }
+
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::state::ServerControlSnapshot;
+
+    fn ctx() -> CheckContext {
+        CheckContext {
+            stream_url: "http://example.com/".to_string(),
+            stream_id: "stream_1".to_string(),
+            media_type: "VIDEO".to_string(),
+            variant_key: "1200000".to_string(),
+        }
+    }
+
+    fn make_prev() -> VariantState {
+        VariantState {
+            media_type: "VIDEO".to_string(),
+            media_sequence: 100,
+            segment_uris: vec![],
+            discontinuity_sequence: 0,
+            next_is_discontinuity: false,
+            prev_segments: vec![],
+            duration: 0.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            in_cue_out: false,
+            cue_out_duration: None,
+            version: None,
+            target_duration: 4.0,
+            playlist_type: None,
+            has_endlist: false,
+            part_target: None,
+            pending_parts: vec![],
+            last_part_uri: None,
+            preload_hint_uri: None,
+            can_skip_until: None,
+            can_block_reload: false,
+            last_part: None,
+            rendition_reports: vec![],
+            map: None,
+            key_uri: None,
+            ad_break: None,
+            ad_boundaries: vec![],
+            live_edge_latency: None,
+            clock_drift: None,
+            dateranges: Default::default(),
+        }
+    }
+
+    fn make_snap(
+        part_target: Option<f64>,
+        hold_back: Option<f64>,
+        part_hold_back: Option<f64>,
+    ) -> PlaylistSnapshot {
+        PlaylistSnapshot {
+            media_sequence: 100,
+            discontinuity_sequence: 0,
+            segments: vec![],
+            duration: 0.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            has_cue_out: false,
+            cue_out_duration: None,
+            target_duration: 4.0,
+            playlist_type: None,
+            version: None,
+            has_gaps: false,
+            has_endlist: false,
+            i_frames_only: false,
+            has_byte_range: false,
+            has_map: false,
+            has_key_iv: false,
+            has_key_format: false,
+            keys: vec![],
+            part_target,
+            pending_parts: vec![],
+            preload_hints: vec![],
+            server_control: Some(ServerControlSnapshot {
+                hold_back,
+                part_hold_back,
+                ..Default::default()
+            }),
+            skipped_segments: None,
+            rendition_reports: vec![],
+            key_uri: None,
+        }
+    }
+
+    #[test]
+    fn no_error_at_spec_minimums() {
+        let snap = make_snap(Some(1.0), Some(12.0), Some(2.0));
+        assert!(ServerControlCheck.check(&make_prev(), &snap, &ctx()).is_empty());
+    }
+
+    #[test]
+    fn no_error_without_server_control_or_parts() {
+        let mut snap = make_snap(None, None, None);
+        snap.server_control = None;
+        assert!(ServerControlCheck.check(&make_prev(), &snap, &ctx()).is_empty());
+    }
+
+    #[test]
+    fn detects_short_hold_back() {
+        let snap = make_snap(None, Some(8.0), None);
+        let errors = ServerControlCheck.check(&make_prev(), &snap, &ctx());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error_type, ErrorType::ServerControlViolation);
+        assert!(errors[0].details.contains("HOLD-BACK 8.000s"));
+    }
+
+    #[test]
+    fn detects_short_part_hold_back() {
+        let snap = make_snap(Some(1.0), None, Some(1.5));
+        let errors = ServerControlCheck.check(&make_prev(), &snap, &ctx());
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].details.contains("PART-HOLD-BACK 1.500s"));
+    }
+
+    #[test]
+    fn detects_missing_part_hold_back() {
+        let snap = make_snap(Some(1.0), None, None);
+        let errors = ServerControlCheck.check(&make_prev(), &snap, &ctx());
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].details.contains("missing PART-HOLD-BACK"));
+    }
+}
@@ -7,6 +7,8 @@ pub struct StreamCheckContext {
     pub stream_url: String,
     pub stream_id: String,
     pub variant_failures: HashMap<String, u32>,
+    /// Variant key for each playlist URL (without query or fragment) fetched this poll.
+    pub variant_urls: HashMap<String, String>,
 }
 
 pub trait StreamCheck: Send + Sync {
@@ -80,6 +80,7 @@ mod tests {
             stream_url: "http://example.com/master.m3u8".to_string(),
             stream_id: "stream_1".to_string(),
             variant_failures,
+            variant_urls: HashMap::new(),
         }
     }
 
@@ -0,0 +1,181 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::monitor::checks::stream_check::{StreamCheck, StreamCheckContext};
+use crate::monitor::error::{ErrorType, MonitorError};
+use crate::monitor::state::VariantState;
+
+/// A variant key with the PDT it gives one media sequence.
+type Stamp<'a> = (&'a str, DateTime<FixedOffset>);
+
+/// Compares the `EXT-X-PROGRAM-DATE-TIME` variants give the same media
+/// sequence number and reports the largest difference once per poll when it
+/// exceeds the threshold.
+pub struct VariantPdtDriftCheck {
+    threshold: f64,
+}
+
+impl VariantPdtDriftCheck {
+    pub fn new(threshold: f64) -> Self {
+        Self { threshold }
+    }
+}
+
+impl StreamCheck for VariantPdtDriftCheck {
+    fn name(&self) -> &'static str {
+        "VariantPdtDrift"
+    }
+
+    fn check(
+        &self,
+        variants: &HashMap<String, VariantState>,
+        ctx: &StreamCheckContext,
+    ) -> Vec<MonitorError> {
+        if variants.len() < 2 {
+            return Vec::new();
+        }
+
+        let mut by_mseq: BTreeMap<u64, Vec<Stamp>> = BTreeMap::new();
+        let sorted: BTreeMap<&str, &VariantState> = variants.iter().map(|(k, v)| (k.as_str(), v)).collect();
+        for (key, state) in sorted {
+            for (i, seg) in state.prev_segments.iter().enumerate() {
+                if let Some(pdt) = seg.program_date_time {
+                    by_mseq.entry(state.media_sequence + i as u64).or_default().push((key, pdt));
+                }
+            }
+        }
+
+        let mut worst: Option<(u64, Stamp, Stamp, f64)> = None;
+        for (mseq, group) in &by_mseq {
+            if group.len() < 2 {
+                continue;
+            }
+            let (Some(earliest), Some(latest)) = (group.iter().min_by_key(|(_, t)| *t), group.iter().max_by_key(|(_, t)| *t))
+            else {
+                continue;
+            };
+            let drift = (latest.1 - earliest.1).num_milliseconds() as f64 / 1000.0;
+            if worst.as_ref().is_none_or(|w| drift > w.3) {
+                worst = Some((*mseq, *latest, *earliest, drift));
+            }
+        }
+
+        match worst {
+            Some((mseq, (ahead_key, ahead), (behind_key, behind), drift)) if drift > self.threshold => {
+                vec![MonitorError::new(
+                    ErrorType::VariantPdtDrift,
+                    "ALL",
+                    "ALL",
+                    format!(
+                        "Variant PDT drift at mseq({}): '{}' is {:.3}s ahead of '{}' ({} vs {})",
+                        mseq,
+                        ahead_key,
+                        drift,
+                        behind_key,
+                        ahead.to_rfc3339(),
+                        behind.to_rfc3339(),
+                    ),
+                    &ctx.stream_url,
+                    &ctx.stream_id,
+                )]
+            }
+            _ => Vec::new(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::state::SegmentInfo;
+
+    fn ctx() -> StreamCheckContext {
+        StreamCheckContext {
+            stream_url: "http://example.com/master.m3u8".to_string(),
+            stream_id: "stream_1".to_string(),
+            variant_failures: HashMap::new(),
+            variant_urls: HashMap::new(),
+        }
+    }
+
+    /// Variant at `mseq` whose segments are 6s long, the first starting
+    /// `offset_ms` after the epoch of the test clock.
+    fn make_variant(mseq: u64, count: u64, offset_ms: i64) -> VariantState {
+        let base = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap();
+        VariantState {
+            media_type: "VIDEO".to_string(),
+            media_sequence: mseq,
+            segment_uris: vec![],
+            discontinuity_sequence: 0,
+            next_is_discontinuity: false,
+            prev_segments: (0..count)
+                .map(|i| SegmentInfo {
+                    uri: format!("s{}.ts", mseq + i),
+                    discontinuity: false,
+                    duration: 6.0,
+                    program_date_time: Some(
+                        base + chrono::Duration::milliseconds(((mseq + i) * 6000) as i64 + offset_ms),
+                    ),
+                    tags: Default::default(),
+                })
+                .collect(),
+            duration: 0.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            in_cue_out: false,
+            cue_out_duration: None,
+            version: None,
+            target_duration: 6.0,
+            playlist_type: None,
+            has_endlist: false,
+            part_target: None,
+            pending_parts: vec![],
+            last_part_uri: None,
+            preload_hint_uri: None,
+            can_skip_until: None,
+            can_block_reload: false,
+            last_part: None,
+            rendition_reports: vec![],
+            map: None,
+            key_uri: None,
+            ad_break: None,
+            ad_boundaries: vec![],
+            live_edge_latency: None,
+            clock_drift: None,
+            dateranges: Default::default(),
+        }
+    }
+
+    #[test]
+    fn no_error_within_threshold() {
+        let mut variants = HashMap::new();
+        variants.insert("v1".to_string(), make_variant(10, 3, 0));
+        variants.insert("v2".to_string(), make_variant(11, 3, 200));
+        assert!(VariantPdtDriftCheck::new(0.5).check(&variants, &ctx()).is_empty());
+    }
+
+    #[test]
+    fn reports_largest_drift_once() {
+        let mut variants = HashMap::new();
+        variants.insert("v1".to_string(), make_variant(10, 3, 0));
+        variants.insert("v2".to_string(), make_variant(11, 3, 2000));
+        variants.insert("audio".to_string(), make_variant(10, 3, -1000));
+        let errors = VariantPdtDriftCheck::new(0.5).check(&variants, &ctx());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error_type, ErrorType::VariantPdtDrift);
+        assert!(
+            errors[0].details.starts_with("Variant PDT drift at mseq(11): 'v2' is 3.000s ahead of 'audio'"),
+            "{}",
+            errors[0].details
+        );
+    }
+
+    #[test]
+    fn variants_without_shared_mseq_are_not_compared() {
+        let mut variants = HashMap::new();
+        variants.insert("v1".to_string(), make_variant(10, 2, 0));
+        variants.insert("v2".to_string(), make_variant(12, 2, 5000));
+        assert!(VariantPdtDriftCheck::new(0.5).check(&variants, &ctx()).is_empty());
+    }
+}
@@ -70,6 +70,21 @@ mod tests {
             target_duration: target_dur,
             playlist_type: None,
             has_endlist: false,
+            part_target: None,
+            pending_parts: vec![],
+            last_part_uri: None,
+            preload_hint_uri: None,
+            can_skip_until: None,
+            can_block_reload: false,
+            last_part: None,
+            rendition_reports: vec![],
+            map: None,
+            key_uri: None,
+            ad_break: None,
+            ad_boundaries: vec![],
+            live_edge_latency: None,
+            clock_drift: None,
+            dateranges: Default::default(),
         }
     }
 
@@ -78,6 +93,7 @@ mod tests {
             stream_url: "http://example.com/master.m3u8".to_string(),
             stream_id: "stream_1".to_string(),
             variant_failures: HashMap::new(),
+            variant_urls: HashMap::new(),
         }
     }
 
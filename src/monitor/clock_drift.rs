@@ -0,0 +1,246 @@
+//! Long-term encoder clock drift.
+//!
+//! From an anchor segment on, the `EXTINF` durations of every segment are
+//! added up as they are published across polls, and compared with how far
+//! each later `EXT-X-PROGRAM-DATE-TIME` is from the anchor's. An encoder whose
+//! wall clock runs fast or slow against its media clock shows up as a steadily
+//! growing difference, reported in milliseconds per hour.
+
+use std::ops::Range;
+
+use chrono::{DateTime, FixedOffset};
+
+use super::error::{ErrorType, MonitorError};
+use super::state::{CheckContext, PlaylistSnapshot};
+
+/// Seconds of PDT span needed before a rate is reported.
+pub const MIN_DRIFT_SPAN_SECS: f64 = 600.0;
+
+/// A single step larger than this is a PDT jump, not drift, and restarts the
+/// measurement.
+const MAX_STEP_SECS: f64 = 1.0;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClockDrift {
+    anchor_pdt: DateTime<FixedOffset>,
+    /// Media sequence number of the next segment to count.
+    next_mseq: u64,
+    /// `EXTINF` seconds from the anchor to the end of the last counted segment.
+    extinf: f64,
+    /// PDT seconds from the anchor to the last segment with a PDT.
+    pdt_span: f64,
+    /// `EXTINF` seconds from the anchor to the start of that segment.
+    extinf_at_pdt: f64,
+    /// The rate is over the threshold and has been reported.
+    pub reported: bool,
+}
+
+impl ClockDrift {
+    fn anchor(mseq: u64, pdt: DateTime<FixedOffset>, duration: f64) -> Self {
+        Self {
+            anchor_pdt: pdt,
+            next_mseq: mseq + 1,
+            extinf: duration,
+            pdt_span: 0.0,
+            extinf_at_pdt: 0.0,
+            reported: false,
+        }
+    }
+
+    /// Milliseconds the PDTs have run ahead of the `EXTINF` durations.
+    pub fn drift_ms(&self) -> f64 {
+        (self.pdt_span - self.extinf_at_pdt) * 1000.0
+    }
+
+    /// Drift per hour of PDT, once enough time has been observed.
+    pub fn rate_ms_per_hour(&self) -> Option<f64> {
+        (self.pdt_span >= MIN_DRIFT_SPAN_SECS).then(|| self.drift_ms() / (self.pdt_span / 3600.0))
+    }
+
+    /// Error when the rate first exceeds `threshold` ms/hour; cleared once it
+    /// drops back below.
+    pub fn check(&mut self, threshold: f64, ctx: &CheckContext) -> Option<MonitorError> {
+        let Some(rate) = self.rate_ms_per_hour().filter(|r| r.abs() > threshold) else {
+            self.reported = false;
+            return None;
+        };
+        if self.reported {
+            return None;
+        }
+        self.reported = true;
+        Some(MonitorError::new(
+            ErrorType::ClockDrift,
+            &ctx.media_type,
+            &ctx.variant_key,
+            format!(
+                "Encoder clock drift of {:.1}ms/hour: PDT advanced {:.3}s while EXTINF durations add up to {:.3}s",
+                rate, self.pdt_span, self.extinf_at_pdt
+            ),
+            &ctx.stream_url,
+            &ctx.stream_id,
+        ))
+    }
+}
+
+/// Count the segments of `curr` past `prev_window`. A discontinuity,
+/// segments missed between polls or a PDT jump restart the measurement from
+/// the next segment with a PDT.
+pub fn advance(open: Option<ClockDrift>, prev_window: Option<Range<u64>>, curr: &PlaylistSnapshot) -> Option<ClockDrift> {
+    let mut drift = open;
+
+    for (mseq, seg) in curr.segments_after(prev_window) {
+        let continuous = !seg.discontinuity && drift.as_ref().is_some_and(|d| d.next_mseq == mseq);
+        let Some(d) = drift.as_mut().filter(|_| continuous) else {
+            drift = seg.program_date_time.map(|pdt| ClockDrift::anchor(mseq, pdt, seg.duration));
+            continue;
+        };
+
+        if let Some(pdt) = seg.program_date_time {
+            let span = (pdt - d.anchor_pdt).num_milliseconds() as f64 / 1000.0;
+            let step = (span - d.pdt_span) - (d.extinf - d.extinf_at_pdt);
+            if step.abs() > MAX_STEP_SECS {
+                drift = Some(ClockDrift::anchor(mseq, pdt, seg.duration));
+                continue;
+            }
+            d.pdt_span = span;
+            d.extinf_at_pdt = d.extinf;
+        }
+        d.extinf += seg.duration;
+        d.next_mseq = mseq + 1;
+    }
+
+    drift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::state::SegmentSnapshot;
+
+    /// `count` segments from `mseq`, each `duration` long in EXTINF but
+    /// stamped `pdt_step` seconds apart.
+    fn snap(mseq: u64, count: u64, duration: f64, pdt_step: f64) -> PlaylistSnapshot {
+        let t0 = DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap();
+        let segments = (mseq..mseq + count)
+            .map(|n| SegmentSnapshot {
+                uri: format!("s{}.ts", n),
+                duration,
+                discontinuity: false,
+                cue_out: false,
+                cue_in: false,
+                cue_out_cont: None,
+                gap: false,
+                program_date_time: Some(t0 + chrono::Duration::milliseconds((n as f64 * pdt_step * 1000.0) as i64)),
+                daterange: None,
+                parts: vec![],
+                byte_range_length: None,
+                map: None,
+                scte35: vec![],
+                cue_out_duration: None,
+                ad_markers: vec![],
+            })
+            .collect();
+        PlaylistSnapshot {
+            media_sequence: mseq,
+            discontinuity_sequence: 0,
+            segments,
+            duration: 0.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            has_cue_out: false,
+            cue_out_duration: None,
+            target_duration: 6.0,
+            playlist_type: None,
+            version: Some(3),
+            has_gaps: false,
+            has_endlist: false,
+            i_frames_only: false,
+            has_byte_range: false,
+            has_map: false,
+            has_key_iv: false,
+            has_key_format: false,
+            keys: vec![],
+            part_target: None,
+            pending_parts: vec![],
+            preload_hints: vec![],
+            server_control: None,
+            skipped_segments: None,
+            rendition_reports: vec![],
+            key_uri: None,
+        }
+    }
+
+    fn window(snapshot: &PlaylistSnapshot) -> Option<Range<u64>> {
+        let start = snapshot.media_sequence;
+        Some(start..start + snapshot.segments.len() as u64)
+    }
+
+    fn ctx() -> CheckContext {
+        CheckContext {
+            stream_url: "http://example.com/".to_string(),
+            stream_id: "stream_1".to_string(),
+            media_type: "VIDEO".to_string(),
+            variant_key: "1200000".to_string(),
+        }
+    }
+
+    #[test]
+    fn accumulates_across_polls_and_reports_rate() {
+        // PDT runs 1ms per 6s segment ahead of EXTINF: 600ms/hour.
+        let first = snap(0, 60, 6.0, 6.001);
+        let d = advance(None, None, &first).unwrap();
+        assert!(d.rate_ms_per_hour().is_none(), "under 10 minutes of PDT");
+
+        let second = snap(50, 60, 6.0, 6.001);
+        let mut d = advance(Some(d), window(&first), &second).unwrap();
+        let rate = d.rate_ms_per_hour().unwrap();
+        assert!((rate - 600.0).abs() < 5.0, "{}", rate);
+
+        assert!(d.check(1000.0, &ctx()).is_none());
+        let error = d.check(500.0, &ctx()).unwrap();
+        assert_eq!(error.error_type, ErrorType::ClockDrift);
+        assert!(error.details.starts_with("Encoder clock drift of 599.9ms/hour"), "{}", error.details);
+        assert!(d.check(500.0, &ctx()).is_none(), "reported once");
+    }
+
+    #[test]
+    fn missed_segments_restart_measurement() {
+        let first = snap(0, 120, 6.0, 6.001);
+        let d = advance(None, None, &first).unwrap();
+        assert!(d.rate_ms_per_hour().is_some());
+
+        // mseq 120..130 were never seen.
+        let d = advance(Some(d), window(&first), &snap(130, 5, 6.0, 6.001)).unwrap();
+        assert!(d.rate_ms_per_hour().is_none());
+        assert!((d.drift_ms() - 4.0).abs() < 1e-6, "{}", d.drift_ms());
+    }
+
+    #[test]
+    fn pdt_jump_restarts_measurement() {
+        let mut pl = snap(0, 120, 6.0, 6.0);
+        pl.segments[100].program_date_time = pl.segments[100].program_date_time.map(|t| t + chrono::Duration::seconds(30));
+        for seg in &mut pl.segments[101..] {
+            seg.program_date_time = None;
+        }
+        let d = advance(None, None, &pl).unwrap();
+        assert_eq!(d.drift_ms(), 0.0);
+        assert!(d.rate_ms_per_hour().is_none());
+    }
+
+    #[test]
+    fn byte_range_segments_counted_once_each() {
+        // Every segment of a single-file playlist shares one URI.
+        let single_file = |mseq, count| {
+            let mut pl = snap(mseq, count, 6.0, 6.001);
+            for seg in &mut pl.segments {
+                seg.uri = "main.ts".to_string();
+            }
+            pl
+        };
+        let first = single_file(0, 60);
+        let d = advance(None, None, &first).unwrap();
+        let d = advance(Some(d), window(&first), &single_file(50, 60)).unwrap();
+        assert_eq!(d.next_mseq, 110);
+        assert!((d.rate_ms_per_hour().unwrap() - 600.0).abs() < 5.0);
+    }
+}
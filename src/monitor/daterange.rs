@@ -0,0 +1,397 @@
+//! `EXT-X-DATERANGE` lifecycle across polls.
+//!
+//! Every range is remembered by `ID`, also after its tag has left the
+//! playlist, so that a later tag with the same ID can be compared with what
+//! was published before: attributes may be added, such as `END-DATE` or
+//! `DURATION` once the end is known, but never changed (§4.4.5.1).
+//!
+//! A range opens when its tag first appears and closes once the live edge
+//! passes its end: `END-DATE`, `START-DATE` plus `DURATION`, or for
+//! `END-ON-NEXT` the `START-DATE` of the next range with the same `CLASS`.
+
+use std::collections::{BTreeMap, HashSet};
+
+use chrono::{DateTime, FixedOffset};
+
+use super::error::{ErrorType, MonitorError};
+use super::event::{EventKind, MonitorEvent};
+use super::latency;
+use super::state::{CheckContext, DateRangeSnapshot, PlaylistSnapshot};
+
+/// Ranges remembered per variant; the oldest closed ones are forgotten first.
+pub const MAX_TRACKED_DATERANGES: usize = 256;
+
+/// Two durations closer than this are the same value.
+const DURATION_EPSILON: f64 = 1e-3;
+
+#[derive(Debug, Clone)]
+pub struct TrackedRange {
+    /// Attributes of every tag seen with this ID, the newest value winning.
+    pub range: DateRangeSnapshot,
+    /// `START-DATE` of the range that ended this `END-ON-NEXT` range.
+    pub next_start: Option<DateTime<FixedOffset>>,
+    pub closed: bool,
+}
+
+impl TrackedRange {
+    pub fn end(&self) -> Option<DateTime<FixedOffset>> {
+        let r = &self.range;
+        r.end_date
+            .or_else(|| {
+                r.duration
+                    .map(|d| r.start_date + chrono::Duration::milliseconds((d * 1000.0).round() as i64))
+            })
+            .or(self.next_start)
+    }
+
+    /// Adopt the attributes of a later tag with the same ID.
+    fn merge(&mut self, dr: &DateRangeSnapshot) {
+        let r = &mut self.range;
+        r.class = dr.class.clone().or(r.class.take());
+        r.start_date = dr.start_date;
+        r.end_date = dr.end_date.or(r.end_date);
+        r.duration = dr.duration.or(r.duration);
+        r.planned_duration = dr.planned_duration.or(r.planned_duration);
+        r.end_on_next = dr.end_on_next;
+        r.attributes.extend(dr.attributes.iter().map(|(k, v)| (k.clone(), v.clone())));
+    }
+}
+
+/// Attributes present in both `prev` and `curr` with different values, as
+/// `(name, previous, current)`.
+pub fn mutations(prev: &DateRangeSnapshot, curr: &DateRangeSnapshot) -> Vec<(String, String, String)> {
+    let mut changed = Vec::new();
+    let mut push = |name: &str, a: String, b: String| changed.push((name.to_string(), a, b));
+
+    if let (Some(a), Some(b)) = (&prev.class, &curr.class) {
+        if a != b {
+            push("CLASS", a.clone(), b.clone());
+        }
+    }
+    if prev.start_date != curr.start_date {
+        push("START-DATE", prev.start_date.to_rfc3339(), curr.start_date.to_rfc3339());
+    }
+    if let (Some(a), Some(b)) = (prev.end_date, curr.end_date) {
+        if a != b {
+            push("END-DATE", a.to_rfc3339(), b.to_rfc3339());
+        }
+    }
+    for (name, a, b) in [
+        ("DURATION", prev.duration, curr.duration),
+        ("PLANNED-DURATION", prev.planned_duration, curr.planned_duration),
+    ] {
+        if let (Some(a), Some(b)) = (a, b) {
+            if (a - b).abs() > DURATION_EPSILON {
+                push(name, format!("{:.3}", a), format!("{:.3}", b));
+            }
+        }
+    }
+    if prev.end_on_next != curr.end_on_next {
+        let yes_no = |v: bool| if v { "YES" } else { "absent" }.to_string();
+        push("END-ON-NEXT", yes_no(prev.end_on_next), yes_no(curr.end_on_next));
+    }
+    for (name, a) in &prev.attributes {
+        if let Some(b) = curr.attributes.get(name).filter(|b| *b != a) {
+            push(name, a.clone(), b.clone());
+        }
+    }
+
+    changed
+}
+
+/// Track the ranges of `curr`. Returns the attribute mutations and the
+/// open/close events of this poll.
+pub fn advance(
+    tracked: &mut BTreeMap<String, TrackedRange>,
+    curr: &PlaylistSnapshot,
+    ctx: &CheckContext,
+) -> (Vec<MonitorError>, Vec<MonitorEvent>) {
+    let mut errors = Vec::new();
+    let mut events = Vec::new();
+    // A second tag with the same ID in one playlist is DateRangeCheck's concern.
+    let mut seen: HashSet<&str> = HashSet::new();
+
+    for (i, seg) in curr.segments.iter().enumerate() {
+        let Some(dr) = &seg.daterange else {
+            continue;
+        };
+        if !seen.insert(dr.id.as_str()) {
+            continue;
+        }
+        let mseq = curr.media_sequence + i as u64;
+
+        if let Some(t) = tracked.get_mut(&dr.id) {
+            for (name, prev, now) in mutations(&t.range, dr) {
+                errors.push(MonitorError::new(
+                    ErrorType::DateRangeMutation,
+                    &ctx.media_type,
+                    &ctx.variant_key,
+                    format!(
+                        "EXT-X-DATERANGE '{}' changed {} between polls from {} to {} at index({}) in mseq({})",
+                        dr.id, name, prev, now, i, mseq
+                    ),
+                    &ctx.stream_url,
+                    &ctx.stream_id,
+                ));
+            }
+            t.merge(dr);
+            continue;
+        }
+
+        let class = dr.class.as_deref().map(|c| format!(" CLASS '{}'", c)).unwrap_or_default();
+        events.push(MonitorEvent::new(
+            EventKind::DateRangeOpened,
+            &ctx.media_type,
+            &ctx.variant_key,
+            format!(
+                "DATERANGE '{}'{} opened at mseq {}, starting {}",
+                dr.id,
+                class,
+                mseq,
+                dr.start_date.to_rfc3339()
+            ),
+            &ctx.stream_id,
+        ));
+        tracked.insert(
+            dr.id.clone(),
+            TrackedRange {
+                range: dr.clone(),
+                next_start: None,
+                closed: false,
+            },
+        );
+    }
+
+    // An END-ON-NEXT range ends where the next range of its CLASS starts.
+    let starts: Vec<(Option<String>, DateTime<FixedOffset>)> =
+        tracked.values().map(|t| (t.range.class.clone(), t.range.start_date)).collect();
+    for t in tracked.values_mut().filter(|t| t.range.end_on_next && t.next_start.is_none()) {
+        t.next_start = starts
+            .iter()
+            .filter(|(class, start)| class.is_some() && *class == t.range.class && *start > t.range.start_date)
+            .map(|(_, start)| *start)
+            .min();
+    }
+
+    let edge = latency::live_edge(curr);
+    for (id, t) in tracked.iter_mut().filter(|(_, t)| !t.closed) {
+        if let Some(end) = t.end().filter(|end| edge.is_some_and(|edge| edge >= *end)) {
+            t.closed = true;
+            let ran = (end - t.range.start_date).num_milliseconds() as f64 / 1000.0;
+            events.push(MonitorEvent::new(
+                EventKind::DateRangeClosed,
+                &ctx.media_type,
+                &ctx.variant_key,
+                format!("DATERANGE '{}' closed at {} after {:.1}s", id, end.to_rfc3339(), ran),
+                &ctx.stream_id,
+            ));
+        } else if curr.has_endlist && t.range.end_on_next && t.next_start.is_none() {
+            t.closed = true;
+            errors.push(MonitorError::new(
+                ErrorType::DateRangeViolation,
+                &ctx.media_type,
+                &ctx.variant_key,
+                format!(
+                    "EXT-X-DATERANGE '{}': END-ON-NEXT range was not closed by a following range of CLASS '{}' before EXT-X-ENDLIST",
+                    id,
+                    t.range.class.as_deref().unwrap_or_default()
+                ),
+                &ctx.stream_url,
+                &ctx.stream_id,
+            ));
+        }
+    }
+
+    while tracked.len() > MAX_TRACKED_DATERANGES {
+        let oldest = tracked
+            .iter()
+            .min_by_key(|(_, t)| (!t.closed, t.range.start_date))
+            .map(|(id, _)| id.clone());
+        let Some(oldest) = oldest else {
+            break;
+        };
+        tracked.remove(&oldest);
+    }
+
+    (errors, events)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::state::SegmentSnapshot;
+
+    fn t(secs: i64) -> DateTime<FixedOffset> {
+        DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap() + chrono::Duration::seconds(secs)
+    }
+
+    fn range(id: &str, class: Option<&str>, start: i64) -> DateRangeSnapshot {
+        DateRangeSnapshot {
+            id: id.to_string(),
+            class: class.map(str::to_string),
+            start_date: t(start),
+            end_date: None,
+            duration: None,
+            end_on_next: false,
+            planned_duration: None,
+            attributes: BTreeMap::new(),
+        }
+    }
+
+    /// 10s segments from `mseq`, with PDTs from `mseq * 10` seconds on.
+    fn snap(mseq: u64, dateranges: Vec<Option<DateRangeSnapshot>>) -> PlaylistSnapshot {
+        let segments = dateranges
+            .into_iter()
+            .enumerate()
+            .map(|(i, daterange)| SegmentSnapshot {
+                uri: format!("s{}.ts", mseq + i as u64),
+                duration: 10.0,
+                discontinuity: false,
+                cue_out: false,
+                cue_in: false,
+                cue_out_cont: None,
+                gap: false,
+                program_date_time: Some(t((mseq as i64 + i as i64) * 10)),
+                daterange,
+                parts: vec![],
+                byte_range_length: None,
+                map: None,
+                scte35: vec![],
+                cue_out_duration: None,
+                ad_markers: vec![],
+            })
+            .collect();
+        PlaylistSnapshot {
+            media_sequence: mseq,
+            discontinuity_sequence: 0,
+            segments,
+            duration: 0.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            has_cue_out: false,
+            cue_out_duration: None,
+            target_duration: 10.0,
+            playlist_type: None,
+            version: Some(3),
+            has_gaps: false,
+            has_endlist: false,
+            i_frames_only: false,
+            has_byte_range: false,
+            has_map: false,
+            has_key_iv: false,
+            has_key_format: false,
+            keys: vec![],
+            part_target: None,
+            pending_parts: vec![],
+            preload_hints: vec![],
+            server_control: None,
+            skipped_segments: None,
+            rendition_reports: vec![],
+            key_uri: None,
+        }
+    }
+
+    fn ctx() -> CheckContext {
+        CheckContext {
+            stream_url: "http://example.com/".to_string(),
+            stream_id: "stream_1".to_string(),
+            media_type: "VIDEO".to_string(),
+            variant_key: "1200000".to_string(),
+        }
+    }
+
+    #[test]
+    fn opens_and_closes_once_live_edge_passes_end() {
+        let mut tracked = BTreeMap::new();
+        let mut ad = range("ad1", Some("com.example.ad"), 10);
+        let (errors, events) = advance(&mut tracked, &snap(0, vec![None, Some(ad.clone())]), &ctx());
+        assert!(errors.is_empty());
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, EventKind::DateRangeOpened);
+        assert_eq!(
+            events[0].details,
+            "DATERANGE 'ad1' CLASS 'com.example.ad' opened at mseq 1, starting 2024-01-01T00:00:10+00:00"
+        );
+
+        // DURATION added once known; the edge (00:00:40) has passed 00:00:30.
+        ad.duration = Some(20.0);
+        let (errors, events) = advance(&mut tracked, &snap(1, vec![Some(ad.clone()), None, None]), &ctx());
+        assert!(errors.is_empty(), "{:?}", errors);
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].kind, EventKind::DateRangeClosed);
+        assert!(events[0].details.ends_with("after 20.0s"), "{}", events[0].details);
+
+        // Remembered after leaving the window: no second open.
+        let (_, events) = advance(&mut tracked, &snap(5, vec![None]), &ctx());
+        assert!(events.is_empty());
+        assert!(tracked.contains_key("ad1"));
+    }
+
+    #[test]
+    fn flags_mutations_but_not_added_attributes() {
+        let mut tracked = BTreeMap::new();
+        let mut ad = range("ad1", Some("com.example.ad"), 10);
+        ad.planned_duration = Some(30.0);
+        advance(&mut tracked, &snap(0, vec![None, Some(ad.clone())]), &ctx());
+
+        ad.planned_duration = Some(60.0);
+        ad.attributes.insert("X-COM-EXAMPLE-AD-ID".to_string(), "42".to_string());
+        let (errors, _) = advance(&mut tracked, &snap(0, vec![None, Some(ad.clone())]), &ctx());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error_type, ErrorType::DateRangeMutation);
+        assert_eq!(
+            errors[0].details,
+            "EXT-X-DATERANGE 'ad1' changed PLANNED-DURATION between polls from 30.000 to 60.000 at index(1) in mseq(1)"
+        );
+
+        // Reported once; the new value is the one remembered.
+        let (errors, _) = advance(&mut tracked, &snap(0, vec![None, Some(ad)]), &ctx());
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn end_on_next_is_closed_by_next_range_of_same_class() {
+        let mut tracked = BTreeMap::new();
+        let mut first = range("seg1", Some("com.example.chapter"), 0);
+        first.end_on_next = true;
+        let other = range("x", Some("com.example.other"), 10);
+        let (_, events) = advance(&mut tracked, &snap(0, vec![Some(first), Some(other)]), &ctx());
+        assert_eq!(events.iter().filter(|e| e.kind == EventKind::DateRangeClosed).count(), 0);
+
+        let next = range("seg2", Some("com.example.chapter"), 20);
+        let (_, events) = advance(&mut tracked, &snap(1, vec![None, Some(next)]), &ctx());
+        let closed: Vec<_> = events.iter().filter(|e| e.kind == EventKind::DateRangeClosed).collect();
+        assert_eq!(closed.len(), 1, "{:?}", events);
+        assert!(closed[0].details.starts_with("DATERANGE 'seg1' closed at 2024-01-01T00:00:20+00:00"));
+    }
+
+    #[test]
+    fn end_on_next_left_open_at_endlist_is_reported() {
+        let mut tracked = BTreeMap::new();
+        let mut chapter = range("seg1", Some("com.example.chapter"), 0);
+        chapter.end_on_next = true;
+        let mut pl = snap(0, vec![Some(chapter), None]);
+        pl.has_endlist = true;
+        let (errors, _) = advance(&mut tracked, &pl, &ctx());
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error_type, ErrorType::DateRangeViolation);
+        assert!(errors[0].details.contains("not closed by a following range of CLASS 'com.example.chapter'"));
+        assert!(advance(&mut tracked, &pl, &ctx()).0.is_empty(), "reported once");
+    }
+
+    #[test]
+    fn forgets_oldest_closed_ranges_first() {
+        let mut tracked = BTreeMap::new();
+        let mut open = range("open", None, 0);
+        open.planned_duration = Some(10.0);
+        advance(&mut tracked, &snap(0, vec![Some(open)]), &ctx());
+        for n in 0..MAX_TRACKED_DATERANGES as i64 {
+            let mut dr = range(&format!("r{}", n), None, n);
+            dr.duration = Some(1.0);
+            advance(&mut tracked, &snap(1000, vec![Some(dr)]), &ctx());
+        }
+        assert_eq!(tracked.len(), MAX_TRACKED_DATERANGES);
+        assert!(tracked.contains_key("open"));
+        assert!(!tracked.contains_key("r0"));
+    }
+}
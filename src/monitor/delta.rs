@@ -0,0 +1,290 @@
+//! Playlist Delta Updates (`EXT-X-SKIP`, RFC 8216bis §6.2.5.1).
+//!
+//! A delta update replaces the oldest segments of the playlist with a single
+//! `EXT-X-SKIP:SKIPPED-SEGMENTS=<n>` tag. Every check compares full segment
+//! lists between polls, so the skipped segments are restored from the previous
+//! `VariantState` before the snapshot is checked.
+
+use std::time::Duration;
+
+use super::error::{ErrorType, MonitorError};
+use super::state::{CheckContext, PlaylistSnapshot, SegmentInfo, SegmentSnapshot, VariantState};
+
+/// Whether the next reload of a variant may ask for a delta update.
+///
+/// The origin must advertise CAN-SKIP-UNTIL, and the copy we hold must be
+/// younger than half of it, otherwise the skipped range may reach past it.
+pub fn wants_delta(prev: &VariantState, since_last_fetch: Duration) -> bool {
+    prev.can_skip_until
+        .is_some_and(|until| until > 0.0 && since_last_fetch.as_secs_f64() < until / 2.0)
+}
+
+/// Rebuild the full segment list of a delta update in place.
+///
+/// Returns violations that still allow the merge (a misaligned skip count,
+/// a tail shorter than CAN-SKIP-UNTIL), or `Err` when the skipped segments
+/// are not covered by the previous state and the playlist cannot be rebuilt.
+pub fn merge_delta(
+    prev: &VariantState,
+    delta: &mut PlaylistSnapshot,
+    ctx: &CheckContext,
+) -> Result<Vec<MonitorError>, Box<MonitorError>> {
+    let Some(skipped) = delta.skipped_segments else {
+        return Ok(vec![]);
+    };
+    let violation = |details: String| {
+        MonitorError::new(
+            ErrorType::SkipViolation,
+            &ctx.media_type,
+            &ctx.variant_key,
+            details,
+            &ctx.stream_url,
+            &ctx.stream_id,
+        )
+    };
+
+    let offset = delta
+        .media_sequence
+        .checked_sub(prev.media_sequence)
+        .map(|o| o as usize)
+        .filter(|o| o + skipped as usize <= prev.prev_segments.len())
+        .ok_or_else(|| {
+            Box::new(violation(format!(
+                "SKIPPED-SEGMENTS={} at mseq({}) not covered by previous playlist (mseq({}), {} segments)",
+                skipped,
+                delta.media_sequence,
+                prev.media_sequence,
+                prev.prev_segments.len()
+            )))
+        })?;
+    let end = offset + skipped as usize;
+
+    let mut errors = Vec::new();
+
+    let can_skip_until = delta
+        .server_control
+        .as_ref()
+        .and_then(|sc| sc.can_skip_until)
+        .or(prev.can_skip_until);
+    match can_skip_until {
+        None => errors.push(violation(format!(
+            "EXT-X-SKIP:SKIPPED-SEGMENTS={} without CAN-SKIP-UNTIL in EXT-X-SERVER-CONTROL",
+            skipped
+        ))),
+        Some(until) => {
+            let tail: f64 = delta.segments.iter().map(|s| s.duration).sum();
+            if tail + 0.001 < until {
+                errors.push(violation(format!(
+                    "Delta update retained {:.3}s of segments, less than CAN-SKIP-UNTIL {:.3}s",
+                    tail, until
+                )));
+            }
+        }
+    }
+
+    if let (Some(expected), Some(first)) = (prev.prev_segments.get(end), delta.segments.first()) {
+        if expected.uri != first.uri {
+            errors.push(violation(format!(
+                "SKIPPED-SEGMENTS={} does not line up with previous playlist: expected '{}' after the skip, got '{}'",
+                skipped, expected.uri, first.uri
+            )));
+        }
+    }
+
+    let mut segments: Vec<SegmentSnapshot> =
+        prev.prev_segments[offset..end].iter().map(SegmentInfo::to_snapshot).collect();
+    // EXT-X-MAP and EXT-X-KEY tags seen only before the skipped segments are
+    // not repeated in the delta update; they still apply.
+    if delta.segments.iter().all(|s| s.map.is_none()) {
+        for seg in delta.segments.iter_mut() {
+            seg.map = prev.map.clone();
+        }
+    }
+    if delta.keys.is_empty() {
+        delta.key_uri = prev.key_uri.clone();
+    }
+    segments.append(&mut delta.segments);
+    delta.segments = segments;
+    delta.update_totals();
+
+    Ok(errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::state::ServerControlSnapshot;
+
+    fn ctx() -> CheckContext {
+        CheckContext {
+            stream_url: "http://example.com/".to_string(),
+            stream_id: "stream_1".to_string(),
+            media_type: "VIDEO".to_string(),
+            variant_key: "1200000".to_string(),
+        }
+    }
+
+    fn make_prev(mseq: u64, count: u64) -> VariantState {
+        let prev_segments: Vec<SegmentInfo> = (mseq..mseq + count)
+            .map(|i| SegmentInfo {
+                uri: format!("s{}.ts", i),
+                discontinuity: false,
+                duration: 2.0,
+                program_date_time: None,
+                tags: Default::default(),
+            })
+            .collect();
+        VariantState {
+            media_type: "VIDEO".to_string(),
+            media_sequence: mseq,
+            segment_uris: prev_segments.iter().map(|s| s.uri.clone()).collect(),
+            discontinuity_sequence: 0,
+            next_is_discontinuity: false,
+            prev_segments,
+            duration: 2.0 * count as f64,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            in_cue_out: false,
+            cue_out_duration: None,
+            version: None,
+            target_duration: 2.0,
+            playlist_type: None,
+            has_endlist: false,
+            part_target: None,
+            pending_parts: vec![],
+            last_part_uri: None,
+            preload_hint_uri: None,
+            can_skip_until: Some(6.0),
+            can_block_reload: false,
+            last_part: None,
+            rendition_reports: vec![],
+            map: None,
+            key_uri: None,
+            ad_break: None,
+            ad_boundaries: vec![],
+            live_edge_latency: None,
+            clock_drift: None,
+            dateranges: Default::default(),
+        }
+    }
+
+    fn make_delta(mseq: u64, skipped: u64, tail: &[u64]) -> PlaylistSnapshot {
+        PlaylistSnapshot {
+            media_sequence: mseq,
+            discontinuity_sequence: 0,
+            segments: tail
+                .iter()
+                .map(|i| SegmentSnapshot {
+                    uri: format!("s{}.ts", i),
+                    duration: 2.0,
+                    discontinuity: false,
+                    cue_out: false,
+                    cue_in: false,
+                    cue_out_cont: None,
+                    gap: false,
+                    program_date_time: None,
+                    daterange: None,
+                    parts: vec![],
+                    byte_range_length: None,
+                    map: None,
+                    scte35: vec![],
+                    cue_out_duration: None,
+                    ad_markers: vec![],
+                })
+                .collect(),
+            duration: 2.0 * tail.len() as f64,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            has_cue_out: false,
+            cue_out_duration: None,
+            target_duration: 2.0,
+            playlist_type: None,
+            version: None,
+            has_gaps: false,
+            has_endlist: false,
+            i_frames_only: false,
+            has_byte_range: false,
+            has_map: false,
+            has_key_iv: false,
+            has_key_format: false,
+            keys: vec![],
+            part_target: None,
+            pending_parts: vec![],
+            preload_hints: vec![],
+            server_control: Some(ServerControlSnapshot {
+                can_skip_until: Some(6.0),
+                ..Default::default()
+            }),
+            skipped_segments: Some(skipped),
+            rendition_reports: vec![],
+            key_uri: None,
+        }
+    }
+
+    #[test]
+    fn wants_delta_within_half_skip_boundary() {
+        let prev = make_prev(0, 6);
+        assert!(wants_delta(&prev, Duration::from_secs(2)));
+        assert!(!wants_delta(&prev, Duration::from_secs(3)));
+
+        let mut no_skip = make_prev(0, 6);
+        no_skip.can_skip_until = None;
+        assert!(!wants_delta(&no_skip, Duration::ZERO));
+    }
+
+    #[test]
+    fn merges_skipped_segments_from_previous_state() {
+        let prev = make_prev(10, 6);
+        let mut delta = make_delta(11, 2, &[13, 14, 15, 16]);
+        let errors = merge_delta(&prev, &mut delta, &ctx()).unwrap();
+        assert!(errors.is_empty(), "{:?}", errors);
+        let uris: Vec<_> = delta.segments.iter().map(|s| s.uri.as_str()).collect();
+        assert_eq!(uris, ["s11.ts", "s12.ts", "s13.ts", "s14.ts", "s15.ts", "s16.ts"]);
+        assert_eq!(delta.duration, 12.0);
+    }
+
+    #[test]
+    fn restores_markers_of_skipped_segments() {
+        let mut prev = make_prev(10, 6);
+        prev.prev_segments[1].tags.cue_out = true;
+        prev.prev_segments[1].tags.cue_out_duration = Some(30.0);
+        prev.prev_segments[1].tags.byte_range_length = Some(1000);
+        let mut delta = make_delta(11, 2, &[13, 14, 15, 16]);
+        merge_delta(&prev, &mut delta, &ctx()).unwrap();
+
+        let restored = &delta.segments[0];
+        assert!(restored.cue_out);
+        assert_eq!(restored.byte_range_length, Some(1000));
+        assert_eq!(delta.cue_out_count, 1);
+        assert!(delta.has_cue_out);
+        assert_eq!(delta.cue_out_duration, Some(30.0));
+    }
+
+    #[test]
+    fn detects_misaligned_skip_count() {
+        let prev = make_prev(10, 6);
+        let mut delta = make_delta(11, 3, &[13, 14, 15, 16]);
+        let errors = merge_delta(&prev, &mut delta, &ctx()).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error_type, ErrorType::SkipViolation);
+        assert!(errors[0].details.contains("expected 's14.ts'"));
+    }
+
+    #[test]
+    fn detects_short_retained_tail() {
+        let prev = make_prev(10, 6);
+        let mut delta = make_delta(11, 4, &[15, 16]);
+        let errors = merge_delta(&prev, &mut delta, &ctx()).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert!(errors[0].details.contains("CAN-SKIP-UNTIL"));
+    }
+
+    #[test]
+    fn rejects_skip_outside_previous_state() {
+        let prev = make_prev(10, 6);
+        let mut delta = make_delta(14, 4, &[18, 19, 20]);
+        let err = merge_delta(&prev, &mut delta, &ctx()).unwrap_err();
+        assert_eq!(err.error_type, ErrorType::SkipViolation);
+        assert!(err.details.contains("not covered"));
+    }
+}
@@ -1,23 +1,33 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
 use chrono::Utc;
 use futures::stream::{self, StreamExt};
 use m3u8_rs::Playlist;
 use rand::Rng;
 use tokio::sync::mpsc::UnboundedSender;
-use tokio::sync::RwLock;
+use tokio::sync::{Mutex, RwLock, Semaphore};
+use tokio::task::JoinHandle;
 use tracing::{debug, info, warn};
 use uuid::Uuid;
 
-use crate::config::MonitorConfig;
-use crate::loader::ManifestLoader;
+use crate::config::{AdDialect, MonitorConfig, PollMode};
+use crate::loader::{DeliveryDirectives, ManifestLoader};
+use crate::media::fmp4::Fmp4Error;
+use crate::media::scte35;
+use crate::monitor::checks::master;
 use crate::monitor::checks::stale_manifest::check_stale;
 use crate::monitor::checks::stream_check;
+use crate::monitor::checks::interstitial::is_interstitial;
 use crate::monitor::checks::{default_checks, default_stream_checks, Check};
 use crate::monitor::error::{ErrorType, MonitorError};
 use crate::monitor::event::{EventKind, MonitorEvent};
+use crate::monitor::identity::VariantKeys;
 use crate::monitor::state::*;
+use crate::monitor::probe::{self, ProbeTarget};
+use crate::monitor::ad_markers::{self, AdSignal};
+use crate::monitor::{ad_break, bitrate, blocking_reload, clock_drift, daterange, delta, inspect, interstitial, keys, latency, master_diff, tags};
 use crate::webhook::Notification;
 
 pub struct Monitor {
@@ -25,6 +35,7 @@ pub struct Monitor {
     monitor_id: String,
     config: MonitorConfig,
     streams: Arc<RwLock<Vec<StreamItem>>>,
+    /// Per-stream state, keyed by stream URL.
     stream_data: Arc<RwLock<HashMap<String, StreamData>>>,
     state: Arc<RwLock<MonitorState>>,
     loader: Arc<dyn ManifestLoader>,
@@ -36,6 +47,15 @@ pub struct Monitor {
     last_error_time_per_stream: Arc<RwLock<HashMap<String, chrono::DateTime<Utc>>>>,
     manifest_error_count: Arc<RwLock<u64>>,
     notification_tx: Option<UnboundedSender<Notification>>,
+    poll_permits: Arc<Semaphore>,
+    /// Tasks of the current run. Held across `start`/`stop` so the two never interleave.
+    run: Mutex<Option<Run>>,
+}
+
+/// The supervisor and stream poll tasks spawned by one `start`.
+struct Run {
+    supervisor: JoinHandle<()>,
+    stream_tasks: Arc<std::sync::Mutex<HashMap<String, JoinHandle<()>>>>,
 }
 
 impl Monitor {
@@ -48,6 +68,7 @@ impl Monitor {
         let checks = default_checks(&config);
         let stream_checks = default_stream_checks(&config);
         let id = Uuid::new_v4();
+        let poll_permits = Arc::new(Semaphore::new(config.max_concurrent_streams.max(1)));
         Self {
             monitor_id: id.to_string(),
             id,
@@ -64,6 +85,8 @@ impl Monitor {
             last_error_time_per_stream: Arc::new(RwLock::new(HashMap::new())),
             manifest_error_count: Arc::new(RwLock::new(0)),
             notification_tx,
+            poll_permits,
+            run: Mutex::new(None),
         }
     }
 
@@ -100,6 +123,7 @@ impl Monitor {
         self.streams.read().await.clone()
     }
 
+    #[allow(clippy::unnecessary_sort_by)]
     pub async fn get_errors(&self) -> Vec<MonitorError> {
         let data = self.stream_data.read().await;
         let mut all_errors = Vec::new();
@@ -136,8 +160,7 @@ impl Monitor {
         let mut result = Vec::with_capacity(streams.len());
 
         for stream in streams.iter() {
-            let base_url = get_base_url(&stream.url);
-            if let Some(sd) = data.get(&base_url) {
+            if let Some(sd) = data.get(&stream.url) {
                 let mut variants: Vec<VariantStatus> = sd
                     .variants
                     .iter()
@@ -157,6 +180,9 @@ impl Monitor {
                             .get(key)
                             .copied()
                             .unwrap_or(0),
+                        blocking_hold_ms: sd.blocking_hold_ms.get(key).copied(),
+                        live_edge_latency_secs: vs.live_edge_latency,
+                        clock_drift_ms_per_hour: vs.clock_drift.as_ref().and_then(|d| d.rate_ms_per_hour()),
                     })
                     .collect();
 
@@ -175,6 +201,9 @@ impl Monitor {
                             cue_out_count: 0,
                             cue_in_count: 0,
                             consecutive_failures: failures,
+                            blocking_hold_ms: None,
+                            live_edge_latency_secs: None,
+                            clock_drift_ms_per_hour: None,
                         });
                     }
                 }
@@ -192,6 +221,7 @@ impl Monitor {
         result
     }
 
+    #[allow(clippy::unnecessary_sort_by)]
     pub async fn get_events(&self) -> Vec<MonitorEvent> {
         let data = self.stream_data.read().await;
         let mut all_events = Vec::new();
@@ -216,8 +246,7 @@ impl Monitor {
                 let removed = streams.remove(i);
                 drop(streams);
 
-                let base_url = get_base_url(&removed.url);
-                self.stream_data.write().await.remove(&base_url);
+                self.stream_data.write().await.remove(&removed.url);
                 self.total_errors_per_stream.write().await.remove(stream_id);
                 self.last_error_time_per_stream.write().await.remove(stream_id);
                 Ok(())
@@ -226,7 +255,11 @@ impl Monitor {
         }
     }
 
+    /// Start polling every stream on its own task.
+    ///
+    /// A start issued while the monitor is stopping waits for the stop to finish.
     pub async fn start(&self) -> Result<(), String> {
+        let mut run = self.run.lock().await;
         {
             let mut state = self.state.write().await;
             if *state == MonitorState::Active {
@@ -239,118 +272,265 @@ impl Monitor {
 
         let state = Arc::clone(&self.state);
         let streams = Arc::clone(&self.streams);
-        let stream_data = Arc::clone(&self.stream_data);
-        let loader = Arc::clone(&self.loader);
-        let checks = Arc::clone(&self.checks);
-        let stream_checks = Arc::clone(&self.stream_checks);
-        let config = self.config.clone();
-        let last_checked = Arc::clone(&self.last_checked);
-        let total_errors = Arc::clone(&self.total_errors_per_stream);
-        let last_error_times = Arc::clone(&self.last_error_time_per_stream);
-        let manifest_err_count = Arc::clone(&self.manifest_error_count);
-        let notification_tx = self.notification_tx.clone();
-        let monitor_id = self.monitor_id.clone();
-
-        tokio::spawn(async move {
-            loop {
-                {
-                    let current_state = *state.read().await;
-                    if current_state != MonitorState::Active {
-                        let mut s = state.write().await;
-                        *s = MonitorState::Stopped;
-                        info!("Monitor stopped");
-                        break;
-                    }
-                }
+        let poller = self.poller();
+        let tick = self.config.poll_interval.min(SUPERVISOR_TICK);
+        let stream_tasks: Arc<std::sync::Mutex<HashMap<String, JoinHandle<()>>>> = Arc::default();
+        let tasks = Arc::clone(&stream_tasks);
 
+        let supervisor = tokio::spawn(async move {
+            loop {
+                // Every stream runs on its own task and schedule, so a slow
+                // origin only ever delays its own polls.
                 let current_streams = streams.read().await.clone();
-                *last_checked.write().await = Some(Utc::now());
-
-                for stream in &current_streams {
-                    let errors = poll_stream(
-                        stream,
-                        &loader,
-                        &checks,
-                        &stream_checks,
-                        &stream_data,
-                        &config,
-                        &notification_tx,
-                        &monitor_id,
-                    )
-                    .await;
-
-                    if !errors.is_empty() {
-                        let now = Utc::now();
-                        let mut totals = total_errors.write().await;
-                        let mut times = last_error_times.write().await;
-                        let mut mfcount = manifest_err_count.write().await;
-
-                        for e in &errors {
-                            *totals.entry(e.stream_id.clone()).or_insert(0) += 1;
-                            times.insert(e.stream_id.clone(), now);
-                            if e.error_type == ErrorType::ManifestRetrieval {
-                                *mfcount += 1;
-                            }
+                {
+                    let mut tasks = tasks.lock().expect("stream task map poisoned");
+                    tasks.retain(|_, handle| !handle.is_finished());
+                    for stream in current_streams {
+                        if !tasks.contains_key(&stream.id) {
+                            let id = stream.id.clone();
+                            let handle = tokio::spawn(run_stream_loop(
+                                stream,
+                                poller.clone(),
+                                Arc::clone(&state),
+                                Arc::clone(&streams),
+                            ));
+                            tasks.insert(id, handle);
                         }
                     }
                 }
 
-                let base_ms = config.poll_interval.as_millis() as u64;
-                let jitter_range = base_ms / 7;
-                let jitter = if jitter_range > 0 {
-                    rand::thread_rng().gen_range(0..jitter_range * 2) as i64 - jitter_range as i64
-                } else {
-                    0
-                };
-                let sleep_ms = (base_ms as i64 + jitter).max(1) as u64;
-                tokio::time::sleep(tokio::time::Duration::from_millis(sleep_ms)).await;
+                tokio::time::sleep(tick).await;
             }
         });
 
+        *run = Some(Run { supervisor, stream_tasks });
         Ok(())
     }
 
+    /// Stop polling. Stream tasks are aborted rather than awaited, so an
+    /// in-flight blocking reload does not hold up the stop.
     pub async fn stop(&self) {
-        let mut state = self.state.write().await;
-        if *state == MonitorState::Active {
+        let mut run = self.run.lock().await;
+        {
+            let mut state = self.state.write().await;
+            if *state != MonitorState::Active {
+                return;
+            }
             *state = MonitorState::Stopping;
-            info!(monitor_id = %self.id, "Stopping monitor");
         }
+        info!(monitor_id = %self.id, "Stopping monitor");
+
+        if let Some(run) = run.take() {
+            run.supervisor.abort();
+            let _ = run.supervisor.await;
+            let tasks: Vec<JoinHandle<()>> = run
+                .stream_tasks
+                .lock()
+                .expect("stream task map poisoned")
+                .drain()
+                .map(|(_, handle)| handle)
+                .collect();
+            for handle in tasks {
+                handle.abort();
+                let _ = handle.await;
+            }
+        }
+
+        *self.state.write().await = MonitorState::Stopped;
+        info!(monitor_id = %self.id, "Monitor stopped");
     }
 
     pub async fn poll_once(&self) {
         let current_streams = self.streams.read().await.clone();
+        let poller = self.poller();
+        futures::future::join_all(current_streams.iter().map(|stream| poller.poll(stream))).await;
+    }
+
+    fn poller(&self) -> StreamPoller {
+        StreamPoller {
+            loader: Arc::clone(&self.loader),
+            checks: Arc::clone(&self.checks),
+            stream_checks: Arc::clone(&self.stream_checks),
+            stream_data: Arc::clone(&self.stream_data),
+            config: Arc::new(self.config.clone()),
+            notification_tx: self.notification_tx.clone(),
+            monitor_id: self.monitor_id.clone(),
+            last_checked: Arc::clone(&self.last_checked),
+            total_errors_per_stream: Arc::clone(&self.total_errors_per_stream),
+            last_error_time_per_stream: Arc::clone(&self.last_error_time_per_stream),
+            manifest_error_count: Arc::clone(&self.manifest_error_count),
+            poll_permits: Arc::clone(&self.poll_permits),
+        }
+    }
+}
+
+/// How often the monitor looks for streams that were added while it is running.
+const SUPERVISOR_TICK: Duration = Duration::from_secs(1);
+
+/// Shared handles needed to poll a single stream, cloned into each stream task.
+#[derive(Clone)]
+struct StreamPoller {
+    loader: Arc<dyn ManifestLoader>,
+    checks: Arc<Vec<Box<dyn Check>>>,
+    stream_checks: Arc<Vec<Box<dyn stream_check::StreamCheck>>>,
+    stream_data: Arc<RwLock<HashMap<String, StreamData>>>,
+    config: Arc<MonitorConfig>,
+    notification_tx: Option<UnboundedSender<Notification>>,
+    monitor_id: String,
+    last_checked: Arc<RwLock<Option<chrono::DateTime<Utc>>>>,
+    total_errors_per_stream: Arc<RwLock<HashMap<String, u64>>>,
+    last_error_time_per_stream: Arc<RwLock<HashMap<String, chrono::DateTime<Utc>>>>,
+    manifest_error_count: Arc<RwLock<u64>>,
+    poll_permits: Arc<Semaphore>,
+}
+
+impl StreamPoller {
+    /// Poll one stream, waiting for a free slot under `max_concurrent_streams`.
+    async fn poll(&self, stream: &StreamItem) {
+        let _permit = self
+            .poll_permits
+            .acquire()
+            .await
+            .expect("poll semaphore is never closed");
         *self.last_checked.write().await = Some(Utc::now());
 
-        for stream in &current_streams {
-            let errors = poll_stream(
-                stream,
-                &self.loader,
-                &self.checks,
-                &self.stream_checks,
-                &self.stream_data,
-                &self.config,
-                &self.notification_tx,
-                &self.monitor_id,
-            )
-            .await;
-
-            if !errors.is_empty() {
-                let now = Utc::now();
-                let mut totals = self.total_errors_per_stream.write().await;
-                let mut times = self.last_error_time_per_stream.write().await;
-                let mut mfcount = self.manifest_error_count.write().await;
-
-                for e in &errors {
-                    *totals.entry(e.stream_id.clone()).or_insert(0) += 1;
-                    times.insert(e.stream_id.clone(), now);
-                    if e.error_type == ErrorType::ManifestRetrieval {
-                        *mfcount += 1;
+        let errors = poll_stream(
+            stream,
+            &self.loader,
+            &self.checks,
+            &self.stream_checks,
+            &self.stream_data,
+            &self.config,
+            &self.notification_tx,
+            &self.monitor_id,
+        )
+        .await;
+
+        if !errors.is_empty() {
+            let now = Utc::now();
+            let mut totals = self.total_errors_per_stream.write().await;
+            let mut times = self.last_error_time_per_stream.write().await;
+            let mut mfcount = self.manifest_error_count.write().await;
+
+            for e in &errors {
+                *totals.entry(e.stream_id.clone()).or_insert(0) += 1;
+                times.insert(e.stream_id.clone(), now);
+                if e.error_type == ErrorType::ManifestRetrieval {
+                    *mfcount += 1;
+                }
+            }
+        }
+    }
+
+    /// Delay before the next reload of `stream`, according to the configured poll mode.
+    async fn next_poll_delay(&self, stream: &StreamItem) -> Duration {
+        match self.config.poll_mode {
+            PollMode::Fixed => {}
+            PollMode::Adaptive => {
+                let data = self.stream_data.read().await;
+                if let Some(sd) = data.get(&stream.url) {
+                    if let Some(delay) = adaptive_poll_interval(min_target_duration(sd), sd.last_reload_changed) {
+                        return delay;
+                    }
+                }
+            }
+            PollMode::Blocking => {
+                let data = self.stream_data.read().await;
+                if let Some(sd) = data.get(&stream.url) {
+                    // Without CAN-BLOCK-RELOAD on every variant no directives
+                    // are sent, so reload as in adaptive mode.
+                    if sd.variants.is_empty() || !sd.variants.values().all(|v| v.can_block_reload) {
+                        if let Some(delay) = adaptive_poll_interval(min_target_duration(sd), sd.last_reload_changed) {
+                            return delay;
+                        }
+                        return jittered_interval(self.config.poll_interval);
+                    }
+                    // The origin holds the next request, so reissue right away
+                    // after progress; otherwise back off by half a part (or
+                    // target) duration so a misbehaving origin isn't hammered.
+                    if sd.last_reload_changed {
+                        return Duration::ZERO;
+                    }
+                    let min_unit = sd
+                        .variants
+                        .values()
+                        .map(|v| v.part_target.unwrap_or(v.target_duration))
+                        .filter(|d| *d > 0.0)
+                        .fold(f64::INFINITY, f64::min);
+                    if let Some(delay) = adaptive_poll_interval(min_unit, false) {
+                        return delay;
                     }
                 }
             }
         }
+        jittered_interval(self.config.poll_interval)
+    }
+}
+
+/// Shortest target duration among the stream's variants, infinite if none is known.
+fn min_target_duration(sd: &StreamData) -> f64 {
+    sd.variants
+        .values()
+        .map(|v| v.target_duration)
+        .filter(|td| *td > 0.0)
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Reload interval per RFC 8216 §6.3.4: one target duration after a reload
+/// that changed the playlist, half of it after an unchanged one.
+///
+/// Returns `None` when no usable target duration is known yet.
+fn adaptive_poll_interval(target_duration: f64, last_reload_changed: bool) -> Option<Duration> {
+    if !target_duration.is_finite() || target_duration <= 0.0 {
+        return None;
     }
+    let secs = if last_reload_changed {
+        target_duration
+    } else {
+        target_duration / 2.0
+    };
+    Some(Duration::from_secs_f64(secs))
+}
+
+/// Poll a single stream on its own schedule until the monitor stops or the
+/// stream is removed.
+async fn run_stream_loop(
+    stream: StreamItem,
+    poller: StreamPoller,
+    state: Arc<RwLock<MonitorState>>,
+    streams: Arc<RwLock<Vec<StreamItem>>>,
+) {
+    loop {
+        if *state.read().await != MonitorState::Active {
+            break;
+        }
+        let still_present = streams
+            .read()
+            .await
+            .iter()
+            .any(|s| s.id == stream.id && s.url == stream.url);
+        if !still_present {
+            debug!(stream_id = %stream.id, "Stream removed, stopping its poll loop");
+            break;
+        }
+
+        poller.poll(&stream).await;
+
+        let delay = poller.next_poll_delay(&stream).await;
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Apply ±1/7 random jitter to a poll interval so streams don't poll in lockstep.
+fn jittered_interval(base: Duration) -> Duration {
+    let base_ms = base.as_millis() as u64;
+    let jitter_range = base_ms / 7;
+    let jitter = if jitter_range > 0 {
+        rand::thread_rng().gen_range(0..jitter_range * 2) as i64 - jitter_range as i64
+    } else {
+        0
+    };
+    Duration::from_millis((base_ms as i64 + jitter).max(1) as u64)
 }
 
 pub fn get_base_url(url: &str) -> String {
@@ -367,6 +547,74 @@ fn build_playlist_url(base_url: &str, path: &str) -> String {
     format!("{}{}", base_url, path)
 }
 
+/// Canonical form of a playlist URL for matching, without query or fragment.
+fn normalize_playlist_url(url: &str) -> String {
+    match url::Url::parse(url) {
+        Ok(mut u) => {
+            u.set_query(None);
+            u.set_fragment(None);
+            u.to_string()
+        }
+        Err(_) => url.to_string(),
+    }
+}
+
+/// Resolve a URI found inside a media playlist against that playlist's URL.
+fn resolve_playlist_uri(playlist_url: &str, uri: &str) -> String {
+    url::Url::parse(playlist_url)
+        .and_then(|base| base.join(uri))
+        .map(|u| u.to_string())
+        .unwrap_or_else(|_| build_playlist_url(&get_base_url(playlist_url), uri))
+}
+
+/// Report a master playlist change and forget the variants it removed.
+fn record_master_diff(
+    sd: &mut StreamData,
+    diff: &master_diff::MasterDiff,
+    stream: &StreamItem,
+    tx: &Option<UnboundedSender<Notification>>,
+    monitor_id: &str,
+    all_errors: &mut Vec<MonitorError>,
+) {
+    record_event(sd, tx, monitor_id, MonitorEvent::new(
+        EventKind::MasterChanged,
+        "MASTER",
+        "master",
+        diff.describe(),
+        stream.id.as_str(),
+    ));
+    for (key, entry) in &diff.added {
+        record_event(sd, tx, monitor_id, MonitorEvent::new(
+            EventKind::VariantAdded,
+            entry.media_type.as_str(),
+            key.as_str(),
+            format!("Variant '{}' added: {}", key, entry.description),
+            stream.id.as_str(),
+        ));
+    }
+    for (key, entry) in &diff.removed {
+        sd.forget_variant(key);
+        record_event(sd, tx, monitor_id, MonitorEvent::new(
+            EventKind::VariantRemoved,
+            entry.media_type.as_str(),
+            key.as_str(),
+            format!("Variant '{}' removed: {}", key, entry.description),
+            stream.id.as_str(),
+        ));
+    }
+    for flap in &diff.flapping {
+        let error = MonitorError::new(
+            ErrorType::LadderFlapping,
+            "MASTER",
+            flap.variant_key.as_str(),
+            flap.describe(),
+            &stream.url,
+            &stream.id,
+        );
+        record_error(sd, all_errors, tx, monitor_id, error);
+    }
+}
+
 fn record_error(
     sd: &mut StreamData,
     all_errors: &mut Vec<MonitorError>,
@@ -399,18 +647,14 @@ fn record_event(
     }
 }
 
-fn segment_to_snapshot(seg: &m3u8_rs::MediaSegment) -> SegmentSnapshot {
-    let cue_out = seg.unknown_tags.iter().any(|t| {
-        t.tag.contains("X-CUE-OUT") && !t.tag.contains("X-CUE-OUT-CONT")
-    });
-    let cue_in = seg.unknown_tags.iter().any(|t| t.tag.contains("X-CUE-IN"));
-    let cue_out_cont = seg.unknown_tags.iter().find_map(|t| {
-        if t.tag.contains("X-CUE-OUT-CONT") {
-            t.rest.clone()
-        } else {
-            None
-        }
-    });
+fn segment_to_snapshot(seg: &m3u8_rs::MediaSegment, dialect: AdDialect) -> SegmentSnapshot {
+    let scte35 = ad_markers::scte35_markers(seg);
+    let ad_markers = ad_markers::from_segment(seg, &scte35, dialect);
+    let signalled = |signal| ad_markers.iter().filter(move |m| m.signal == signal);
+    let cue_out = signalled(AdSignal::Out).next().is_some();
+    let cue_in = signalled(AdSignal::In).next().is_some();
+    let cue_out_cont = signalled(AdSignal::Cont).find_map(|m| m.text.clone());
+    let cue_out_duration = signalled(AdSignal::Out).find_map(|m| m.duration);
     let gap = seg.unknown_tags.iter().any(|t| t.tag == "X-GAP");
     let daterange = seg.daterange.as_ref().map(|dr| DateRangeSnapshot {
         id: dr.id.clone(),
@@ -420,6 +664,13 @@ fn segment_to_snapshot(seg: &m3u8_rs::MediaSegment) -> SegmentSnapshot {
         duration: dr.duration,
         end_on_next: dr.end_on_next,
         planned_duration: dr.planned_duration,
+        attributes: dr
+            .x_prefixed
+            .iter()
+            .chain(dr.other_attributes.iter())
+            .flatten()
+            .map(|(k, v)| (k.clone(), v.as_str().to_string()))
+            .collect(),
     });
 
     SegmentSnapshot {
@@ -432,57 +683,73 @@ fn segment_to_snapshot(seg: &m3u8_rs::MediaSegment) -> SegmentSnapshot {
         gap,
         program_date_time: seg.program_date_time,
         daterange,
+        parts: Vec::new(),
+        byte_range_length: seg.byte_range.as_ref().map(|br| br.length),
+        map: seg.map.as_ref().map(|m| MapSnapshot {
+            uri: m.uri.clone(),
+            byte_range_length: m.byte_range.as_ref().map(|br| br.length),
+            byte_range_offset: m.byte_range.as_ref().and_then(|br| br.offset),
+        }),
+        scte35,
+        ad_markers,
+        cue_out_duration,
     }
 }
 
-fn segment_to_info(seg: &m3u8_rs::MediaSegment) -> SegmentInfo {
-    SegmentInfo {
-        uri: seg.uri.clone(),
-        discontinuity: seg.discontinuity,
+/// Build a snapshot from the parsed playlist, filling in the LL-HLS parts and
+/// preload hints that `m3u8_rs` does not model from the raw body.
+fn playlist_to_snapshot(pl: &m3u8_rs::MediaPlaylist, body: &str, dialect: AdDialect) -> PlaylistSnapshot {
+    let ll = tags::scan_low_latency(body);
+    let mut segments: Vec<SegmentSnapshot> = pl.segments.iter().map(|s| segment_to_snapshot(s, dialect)).collect();
+    for (seg, parts) in segments.iter_mut().zip(ll.segment_parts) {
+        seg.parts = parts;
+    }
+    // EXT-X-MAP applies to every segment after it until the next one.
+    let mut map: Option<MapSnapshot> = None;
+    for seg in segments.iter_mut() {
+        match seg.map {
+            Some(ref m) => map = Some(m.clone()),
+            None => seg.map = map.clone(),
+        }
     }
-}
-
-fn playlist_to_snapshot(pl: &m3u8_rs::MediaPlaylist) -> PlaylistSnapshot {
-    let segments: Vec<SegmentSnapshot> = pl.segments.iter().map(segment_to_snapshot).collect();
-    let duration: f64 = segments.iter().map(|s| s.duration).sum();
-    let cue_out_count = segments.iter().filter(|s| s.cue_out).count();
-    let cue_in_count = segments.iter().filter(|s| s.cue_in).count();
-    let has_cue_out = cue_out_count > 0;
-    let has_gaps = segments.iter().any(|s| s.gap);
 
     let mut keys = Vec::new();
     let mut seen_keys = std::collections::HashSet::new();
+    let mut key_uri = None;
     for seg in &pl.segments {
         if let Some(ref key) = seg.key {
             let method = key.method.to_string();
             let has_uri = key.uri.is_some();
             let has_iv = key.iv.is_some();
             let has_keyformat = key.keyformat.is_some();
-            let sig = format!("{}:{}:{}:{}", method, has_uri, has_iv, has_keyformat);
+            key_uri = if method == "NONE" { None } else { key.uri.clone() };
+            let sig = format!("{}:{:?}:{:?}:{}", method, key.uri, key.iv, has_keyformat);
             if seen_keys.insert(sig) {
                 keys.push(KeySnapshot {
                     method: method.clone(),
                     has_uri,
                     has_iv,
                     has_keyformat,
+                    uri: key.uri.clone(),
+                    iv: key.iv.clone(),
                 });
             }
         }
     }
 
-    PlaylistSnapshot {
+    let mut snapshot = PlaylistSnapshot {
         media_sequence: pl.media_sequence,
         discontinuity_sequence: pl.discontinuity_sequence,
         segments,
-        duration,
-        cue_out_count,
-        cue_in_count,
-        has_cue_out,
+        duration: 0.0,
+        cue_out_count: 0,
+        cue_in_count: 0,
+        has_cue_out: false,
         cue_out_duration: None,
         target_duration: pl.target_duration as f64,
         version: pl.version.and_then(|v| u16::try_from(v).ok()),
         playlist_type: pl.playlist_type.as_ref().map(|pt| pt.to_string()),
-        has_gaps,
+        has_gaps: false,
         has_endlist: pl.end_list,
         i_frames_only: pl.i_frames_only,
         has_byte_range: pl.segments.iter().any(|s| s.byte_range.is_some()),
@@ -490,127 +757,203 @@ fn playlist_to_snapshot(pl: &m3u8_rs::MediaPlaylist) -> PlaylistSnapshot {
         has_key_iv: pl.segments.iter().any(|s| s.key.as_ref().is_some_and(|k| k.iv.is_some())),
         has_key_format: pl.segments.iter().any(|s| s.key.as_ref().is_some_and(|k| k.keyformat.is_some())),
         keys,
+        part_target: ll.part_target,
+        pending_parts: ll.pending_parts,
+        preload_hints: ll.preload_hints,
+        server_control: ll.server_control,
+        skipped_segments: ll.skipped_segments,
+        rendition_reports: ll.rendition_reports,
+        key_uri,
+    };
+    snapshot.update_totals();
+    snapshot
+}
+
+/// Break starts and returns in the playlist window, with their PDT and the
+/// announced duration of each start.
+fn ad_boundaries(snapshot: &PlaylistSnapshot) -> Vec<AdBoundary> {
+    let mut boundaries = Vec::new();
+    for (i, seg) in snapshot.segments.iter().enumerate() {
+        let mseq = snapshot.media_sequence + i as u64;
+        // A segment can close one break and open the next.
+        if seg.cue_in {
+            boundaries.push(AdBoundary {
+                mseq,
+                signal: AdSignal::In,
+                program_date_time: seg.program_date_time,
+                duration: None,
+            });
+        }
+        if seg.cue_out {
+            boundaries.push(AdBoundary {
+                mseq,
+                signal: AdSignal::Out,
+                program_date_time: seg.program_date_time,
+                duration: seg.cue_out_duration,
+            });
+        }
     }
+    boundaries
 }
 
-fn variant_key(variant: &m3u8_rs::VariantStream) -> String {
-    if variant.is_i_frame {
-        format!("iframe_{}", variant.bandwidth)
+/// Build the state carried to the next poll from a freshly-fetched snapshot.
+fn variant_state_from_snapshot(
+    media_type: &str,
+    snapshot: &PlaylistSnapshot,
+    in_cue_out: bool,
+) -> VariantState {
+    // FIX: TS version always set next_is_discontinuity to false (missing else-block)
+    let next_is_discontinuity = if let Some(first) = snapshot.segments.first() {
+        if first.discontinuity {
+            true
+        } else {
+            snapshot.segments.get(1).is_some_and(|s| s.discontinuity)
+        }
+    } else {
+        false
+    };
+
+    let last_part_uri = snapshot
+        .pending_parts
+        .last()
+        .or_else(|| snapshot.segments.last().and_then(|s| s.parts.last()))
+        .map(|p| p.uri.clone());
+    let last_part = if snapshot.pending_parts.is_empty() {
+        snapshot.segments.last().map(|s| s.parts.len())
     } else {
-        variant.bandwidth.to_string()
+        Some(snapshot.pending_parts.len())
+    }
+    .filter(|n| *n > 0)
+    .map(|n| n as u64 - 1);
+
+    VariantState {
+        media_type: media_type.to_string(),
+        media_sequence: snapshot.media_sequence,
+        segment_uris: snapshot.segments.iter().map(|s| s.uri.clone()).collect(),
+        discontinuity_sequence: snapshot.discontinuity_sequence,
+        next_is_discontinuity,
+        prev_segments: snapshot.segments.iter().map(SegmentInfo::of).collect(),
+        duration: snapshot.duration,
+        cue_out_count: snapshot.cue_out_count,
+        cue_in_count: snapshot.cue_in_count,
+        in_cue_out,
+        cue_out_duration: snapshot.cue_out_duration,
+        version: snapshot.version,
+        target_duration: snapshot.target_duration,
+        playlist_type: snapshot.playlist_type.clone(),
+        has_endlist: snapshot.has_endlist,
+        part_target: snapshot.part_target,
+        pending_parts: snapshot.pending_parts.iter().map(|p| p.uri.clone()).collect(),
+        last_part_uri,
+        preload_hint_uri: snapshot
+            .preload_hints
+            .iter()
+            .find(|h| h.hint_type == "PART")
+            .map(|h| h.uri.clone()),
+        can_skip_until: snapshot.server_control.as_ref().and_then(|sc| sc.can_skip_until),
+        can_block_reload: snapshot.server_control.as_ref().is_some_and(|sc| sc.can_block_reload),
+        last_part,
+        rendition_reports: snapshot.rendition_reports.clone(),
+        map: snapshot.segments.last().and_then(|s| s.map.clone()),
+        key_uri: snapshot.key_uri.clone(),
+        ad_break: None,
+        ad_boundaries: ad_boundaries(snapshot),
+        live_edge_latency: None,
+        clock_drift: None,
+        dateranges: BTreeMap::new(),
     }
 }
 
-fn media_key(media: &m3u8_rs::AlternativeMedia) -> String {
-    let group = &media.group_id;
-    let lang = media.language.as_deref().unwrap_or(&media.name);
-    format!("{};{}", group, lang)
+/// Advertised BANDWIDTH / AVERAGE-BANDWIDTH of every non-I-frame variant, by variant key.
+fn master_advertised_bitrates(master: &m3u8_rs::MasterPlaylist) -> HashMap<String, bitrate::Advertised> {
+    let keys = VariantKeys::of(master);
+    master
+        .variants
+        .iter()
+        .zip(keys.variants)
+        .filter(|(v, _)| !v.is_i_frame)
+        .map(|(v, key)| {
+            let advertised = bitrate::Advertised {
+                bandwidth: v.bandwidth,
+                average_bandwidth: v.average_bandwidth,
+            };
+            (key, advertised)
+        })
+        .collect()
 }
 
-fn is_valid_hls_content_type(ct: &str) -> bool {
-    let ct_lower = ct.to_lowercase();
-    ct_lower.starts_with("application/vnd.apple.mpegurl")
-        || ct_lower.starts_with("audio/mpegurl")
-        || ct_lower.starts_with("application/x-mpegurl")
+/// Delivery directives for reloading a variant seen before: the next expected
+/// segment/part in blocking mode when the origin advertises CAN-BLOCK-RELOAD,
+/// and a delta update when it advertises CAN-SKIP-UNTIL.
+fn reload_directives(
+    prev: &VariantState,
+    config: &MonitorConfig,
+    since_last_fetch: Duration,
+) -> Option<DeliveryDirectives> {
+    if prev.has_endlist {
+        return None;
+    }
+    let mut d = if config.poll_mode == PollMode::Blocking && prev.can_block_reload {
+        blocking_reload::next_directives(prev)
+    } else {
+        DeliveryDirectives::default()
+    };
+    d.skip = delta::wants_delta(prev, since_last_fetch);
+    Some(d).filter(|d| !d.is_empty())
 }
 
-fn validate_master(
-    master: &m3u8_rs::MasterPlaylist,
-    stream_url: &str,
-    stream_id: &str,
-    config: &MonitorConfig,
-) -> Vec<MonitorError> {
-    let mut errors = Vec::new();
+/// Time since the stream's playlists were last fetched.
+fn since_last_fetch(sd: &StreamData) -> Duration {
+    (Utc::now() - sd.last_fetch).to_std().unwrap_or_default()
+}
 
-    let mut groups: HashMap<(String, String), Vec<&m3u8_rs::AlternativeMedia>> = HashMap::new();
-    for media in &master.alternatives {
-        let key = (media.media_type.to_string(), media.group_id.clone());
-        groups.entry(key).or_default().push(media);
-    }
+/// Variant key used when a stream URL points directly at a media playlist.
+const MEDIA_PLAYLIST_KEY: &str = "media";
 
-    for ((media_type, group_id), members) in &groups {
-        let mut names = std::collections::HashSet::new();
-        let mut default_count = 0u32;
+/// A variant playlist fetch: index into the targets, the response, how long
+/// the request took and when it completed.
+type VariantFetch = (usize, Result<crate::loader::LoadResponse, crate::loader::LoadError>, Duration, chrono::DateTime<Utc>);
 
-        for member in members {
-            if !names.insert(&member.name) {
-                errors.push(MonitorError::new(
-                    ErrorType::RenditionGroupViolation,
-                    media_type.as_str(),
-                    group_id.as_str(),
-                    format!(
-                        "Rendition group '{}' has duplicate NAME '{}'",
-                        group_id, member.name
-                    ),
-                    stream_url,
-                    stream_id,
-                ));
-            }
-            if member.default {
-                default_count += 1;
-            }
-        }
+/// Media type reported for a stream URL that points directly at a media playlist.
+const MEDIA_PLAYLIST_TYPE: &str = "MEDIA";
 
-        if default_count > 1 {
-            errors.push(MonitorError::new(
-                ErrorType::RenditionGroupViolation,
-                media_type.as_str(),
-                group_id.as_str(),
-                format!(
-                    "Rendition group '{}' has {} members with DEFAULT=YES (max 1 allowed)",
-                    group_id, default_count
-                ),
-                stream_url,
-                stream_id,
-            ));
-        }
-    }
+/// Media type of the playlists referenced by `EXT-X-I-FRAME-STREAM-INF`.
+const I_FRAME_TYPE: &str = "I-FRAME";
 
-    if config.authoring_spec {
-        let missing_avg_bw = master
-            .variants
-            .iter()
-            .filter(|v| !v.is_i_frame && v.average_bandwidth.is_none())
-            .count();
-        if missing_avg_bw > 0 {
-            errors.push(MonitorError::new(
-                ErrorType::AuthoringSpecViolation,
-                "MASTER",
-                "master",
-                format!("{} variant(s) missing AVERAGE-BANDWIDTH attribute", missing_avg_bw),
-                stream_url,
-                stream_id,
-            ));
-        }
+/// Collect `(url, variant_key, media_type)` for every playlist referenced by a master.
+fn master_variant_targets(
+    master: &m3u8_rs::MasterPlaylist,
+    base_url: &str,
+) -> Vec<(String, String, String)> {
+    let keys = VariantKeys::of(master);
+    let mut variant_targets = Vec::new();
 
-        if !master.independent_segments {
-            errors.push(MonitorError::new(
-                ErrorType::AuthoringSpecViolation,
-                "MASTER",
-                "master",
-                "Master playlist missing EXT-X-INDEPENDENT-SEGMENTS tag",
-                stream_url,
-                stream_id,
-            ));
-        }
+    for (variant, key) in master.variants.iter().zip(keys.variants) {
+        let url = build_playlist_url(base_url, &variant.uri);
+        let media_type = if variant.is_i_frame {
+            I_FRAME_TYPE.to_string()
+        } else {
+            "VIDEO".to_string()
+        };
+        variant_targets.push((url, key, media_type));
+    }
 
-        let has_cellular = master
-            .variants
-            .iter()
-            .any(|v| !v.is_i_frame && v.bandwidth <= 192_000);
-        if !has_cellular {
-            errors.push(MonitorError::new(
-                ErrorType::AuthoringSpecViolation,
-                "MASTER",
-                "master",
-                "No variant with BANDWIDTH ≤ 192 kb/s for cellular delivery",
-                stream_url,
-                stream_id,
-            ));
+    for (media, key) in master.alternatives.iter().zip(keys.media) {
+        if let (Some(uri), Some(key)) = (&media.uri, key) {
+            let url = build_playlist_url(base_url, uri);
+            let mt = media.media_type.to_string();
+            variant_targets.push((url, key, mt));
         }
     }
 
-    errors
+    variant_targets
+}
+
+fn is_valid_hls_content_type(ct: &str) -> bool {
+    let ct_lower = ct.to_lowercase();
+    ct_lower.starts_with("application/vnd.apple.mpegurl")
+        || ct_lower.starts_with("audio/mpegurl")
+        || ct_lower.starts_with("application/x-mpegurl")
 }
 
 #[allow(clippy::too_many_arguments)]
@@ -627,7 +970,28 @@ async fn poll_stream(
     let base_url = get_base_url(&stream.url);
     let mut all_errors = Vec::new();
 
-    let master_resp = match loader.load(&stream.url).await {
+    // A stream URL that pointed at a media playlist on the last poll is
+    // requested with its delivery directives straight away, so it is only
+    // fetched once per poll.
+    let media_directives = {
+        let data = stream_data.read().await;
+        data.get(&stream.url).and_then(|sd| {
+            let prev = sd
+                .variants
+                .get(MEDIA_PLAYLIST_KEY)
+                .filter(|prev| prev.media_type == MEDIA_PLAYLIST_TYPE)?;
+            reload_directives(prev, config, since_last_fetch(sd))
+        })
+    };
+
+    let started = Instant::now();
+    let master_result = match media_directives {
+        Some(ref d) => loader.load_with_directives(&stream.url, d).await,
+        None => loader.load(&stream.url).await,
+    };
+    let master_hold = blocking_reload::hold_time(&master_result, started);
+    let master_fetched_at = Utc::now();
+    let master_resp = match master_result {
         Ok(resp) => resp,
         Err(e) => {
             if e.is_last_retry() {
@@ -643,7 +1007,7 @@ async fn poll_stream(
 
                 let mut data = stream_data.write().await;
                 let sd = data
-                    .entry(base_url.clone())
+                    .entry(stream.url.clone())
                     .or_insert_with(|| StreamData::new(config.error_limit, config.event_limit));
                 record_error(sd, &mut all_errors, notification_tx, monitor_id, error);
             } else {
@@ -653,11 +1017,46 @@ async fn poll_stream(
         }
     };
 
-    let master = match m3u8_rs::parse_playlist(master_resp.body.as_bytes()) {
-        Ok((_, Playlist::MasterPlaylist(pl))) => pl,
+    let (variant_targets, advertised, prefetched) = match m3u8_rs::parse_playlist(master_resp.body.as_bytes()) {
+        Ok((_, Playlist::MasterPlaylist(master))) => {
+            let mut master_errors = Vec::new();
+            if config.authoring_spec {
+                if let Some(ref ct) = master_resp.content_type {
+                    if !is_valid_hls_content_type(ct) {
+                        master_errors.push(MonitorError::new(
+                            ErrorType::AuthoringSpecViolation,
+                            "MASTER",
+                            "master",
+                            format!("Master playlist served with invalid Content-Type: {}", ct),
+                            &stream.url,
+                            &stream.id,
+                        ));
+                    }
+                }
+            }
+            master_errors.extend(master::validate_master(&master, &master_resp.body, &stream.url, &stream.id, config));
+
+            let mut data = stream_data.write().await;
+            let sd = data
+                .entry(stream.url.clone())
+                .or_insert_with(|| StreamData::new(config.error_limit, config.event_limit));
+            for e in master_errors {
+                record_error(sd, &mut all_errors, notification_tx, monitor_id, e);
+            }
+            if let Some(diff) = sd.master.update(master_diff::MasterFingerprint::of(&master)) {
+                record_master_diff(sd, &diff, stream, notification_tx, monitor_id, &mut all_errors);
+            }
+            drop(data);
+            (master_variant_targets(&master, &base_url), master_advertised_bitrates(&master), None)
+        }
         Ok((_, Playlist::MediaPlaylist(_))) => {
-            debug!(stream_url = %stream.url, "URL points to media playlist, not master");
-            return all_errors;
+            debug!(stream_url = %stream.url, "URL points to media playlist, monitoring it as a single variant");
+            let target = (
+                stream.url.clone(),
+                MEDIA_PLAYLIST_KEY.to_string(),
+                MEDIA_PLAYLIST_TYPE.to_string(),
+            );
+            (vec![target], HashMap::new(), Some(master_resp))
         }
         Err(e) => {
             let error = MonitorError::new(
@@ -670,91 +1069,74 @@ async fn poll_stream(
             );
             let mut data = stream_data.write().await;
             let sd = data
-                .entry(base_url.clone())
+                .entry(stream.url.clone())
                 .or_insert_with(|| StreamData::new(config.error_limit, config.event_limit));
             record_error(sd, &mut all_errors, notification_tx, monitor_id, error);
             return all_errors;
         }
     };
 
-    if config.authoring_spec {
-        if let Some(ref ct) = master_resp.content_type {
-            if !is_valid_hls_content_type(ct) {
-                let error = MonitorError::new(
-                    ErrorType::AuthoringSpecViolation,
-                    "MASTER",
-                    "master",
-                    format!("Master playlist served with invalid Content-Type: {}", ct),
-                    &stream.url,
-                    &stream.id,
-                );
-                let mut data = stream_data.write().await;
-                let sd = data
-                    .entry(base_url.clone())
-                    .or_insert_with(|| StreamData::new(config.error_limit, config.event_limit));
-                record_error(sd, &mut all_errors, notification_tx, monitor_id, error);
-            }
-        }
-    }
+    // A media playlist URL has already been fetched, with the directives
+    // it was due; reuse that response instead of requesting it again.
+    let directives: Vec<Option<DeliveryDirectives>> = if prefetched.is_some() {
+        vec![media_directives]
+    } else {
+        let data = stream_data.read().await;
+        let sd = data.get(&stream.url);
+        variant_targets
+            .iter()
+            .map(|(_, key, _)| {
+                let sd = sd?;
+                reload_directives(sd.variants.get(key)?, config, since_last_fetch(sd))
+            })
+            .collect()
+    };
 
-    {
-        let master_errors = validate_master(&master, &stream.url, &stream.id, config);
-        if !master_errors.is_empty() {
-            let mut data = stream_data.write().await;
-            let sd = data
-                .entry(base_url.clone())
-                .or_insert_with(|| StreamData::new(config.error_limit, config.event_limit));
-            for e in master_errors {
-                record_error(sd, &mut all_errors, notification_tx, monitor_id, e);
+    let results: Vec<VariantFetch> =
+        match prefetched {
+            Some(resp) => vec![(0, Ok(resp), master_hold, master_fetched_at)],
+            None => {
+                let concurrency = config.max_concurrent_fetches.max(1);
+                let fetch_futures: Vec<_> = variant_targets
+                    .iter()
+                    .enumerate()
+                    .map(|(i, (url, _, _))| {
+                        let loader = Arc::clone(loader);
+                        let url = url.clone();
+                        let directives = directives[i];
+                        async move {
+                            let started = Instant::now();
+                            let result = match directives {
+                                Some(d) => loader.load_with_directives(&url, &d).await,
+                                None => loader.load(&url).await,
+                            };
+                            let hold = blocking_reload::hold_time(&result, started);
+                            (i, result, hold, Utc::now())
+                        }
+                    })
+                    .collect();
+                stream::iter(fetch_futures)
+                    .buffer_unordered(concurrency)
+                    .collect()
+                    .await
             }
-        }
-    }
-
-    let mut variant_targets: Vec<(String, String, String)> = Vec::new();
-
-    for variant in &master.variants {
-        let url = build_playlist_url(&base_url, &variant.uri);
-        let key = variant_key(variant);
-        let media_type = if variant.is_i_frame {
-            "I-FRAME".to_string()
-        } else {
-            "VIDEO".to_string()
         };
-        variant_targets.push((url, key, media_type));
-    }
-
-    for media in &master.alternatives {
-        if let Some(ref uri) = media.uri {
-            let url = build_playlist_url(&base_url, uri);
-            let key = media_key(media);
-            let mt = media.media_type.to_string();
-            variant_targets.push((url, key, mt));
-        }
-    }
-
-    let concurrency = config.max_concurrent_fetches.max(1);
-    let fetch_futures: Vec<_> = variant_targets
-        .iter()
-        .enumerate()
-        .map(|(i, (url, _, _))| {
-            let loader = Arc::clone(loader);
-            let url = url.clone();
-            async move { (i, loader.load(&url).await) }
-        })
-        .collect();
-    let results: Vec<(usize, Result<crate::loader::LoadResponse, crate::loader::LoadError>)> =
-        stream::iter(fetch_futures)
-            .buffer_unordered(concurrency)
-            .collect()
-            .await;
 
     let mut content_changed = false;
     let mut mime_error_emitted = false;
+    let mut probe_targets: Vec<ProbeTarget> = Vec::new();
+    let mut measure_targets: Vec<bitrate::MeasureTarget> = Vec::new();
+    let mut inspect_targets: Vec<inspect::InspectTarget> = Vec::new();
+    // `(variant_key, url)` of every init segment the inspected playlists reference.
+    let mut init_refs: Vec<(String, String)> = Vec::new();
+    let mut key_targets: Vec<keys::KeyTarget> = Vec::new();
+    let mut loaded_variants: Vec<String> = Vec::new();
+    let mut asset_targets: Vec<interstitial::AssetTarget> = Vec::new();
 
     {
         let mut data = stream_data.write().await;
         let sd = data
-            .entry(base_url.clone())
+            .entry(stream.url.clone())
             .or_insert_with(|| StreamData::new(config.error_limit, config.event_limit));
 
         for (_, key, media_type) in &variant_targets {
@@ -763,17 +1145,25 @@ async fn poll_stream(
                 .or_insert_with(|| media_type.clone());
         }
 
-        for (i, result) in results.into_iter() {
+        for (i, result, hold, fetched_at) in results.into_iter() {
             let (variant_url, variant_key_str, media_type) = &variant_targets[i];
 
             let variant_resp = match result {
                 Ok(resp) => resp,
                 Err(e) => {
+                    let (error_type, details) = if media_type == I_FRAME_TYPE {
+                        (
+                            ErrorType::UnresolvableIFrameUri,
+                            format!("EXT-X-I-FRAME-STREAM-INF '{}' could not be fetched: {}", variant_url, e),
+                        )
+                    } else {
+                        (ErrorType::ManifestRetrieval, format!("Failed to fetch variant manifest: {}", e))
+                    };
                     let error = MonitorError::new(
-                        ErrorType::ManifestRetrieval,
+                        error_type,
                         media_type.as_str(),
                         variant_key_str.as_str(),
-                        format!("Failed to fetch variant manifest: {}", e),
+                        details,
                         base_url.as_str(),
                         stream.id.as_str(),
                     )
@@ -820,43 +1210,213 @@ async fn poll_stream(
 
             sd.variant_failures.remove(variant_key_str);
 
-            let snapshot = playlist_to_snapshot(&media_playlist);
+            if media_type == I_FRAME_TYPE && !media_playlist.i_frames_only {
+                let error = MonitorError::new(
+                    ErrorType::UnresolvableIFrameUri,
+                    media_type.as_str(),
+                    variant_key_str.as_str(),
+                    format!(
+                        "EXT-X-I-FRAME-STREAM-INF '{}' does not point at an I-frame playlist (no EXT-X-I-FRAMES-ONLY)",
+                        variant_url
+                    ),
+                    base_url.as_str(),
+                    stream.id.as_str(),
+                );
+                record_error(sd, &mut all_errors, notification_tx, monitor_id, error);
+            }
+
+            let mut snapshot = playlist_to_snapshot(&media_playlist, &variant_resp.body, config.ad_dialect);
+            for report in &mut snapshot.rendition_reports {
+                report.uri = normalize_playlist_url(&resolve_playlist_uri(variant_url, &report.uri));
+            }
+
+            let ctx = CheckContext {
+                stream_url: base_url.clone(),
+                stream_id: stream.id.clone(),
+                media_type: media_type.clone(),
+                variant_key: variant_key_str.clone(),
+            };
+
+            // Restore the segments a delta update skipped before anything
+            // compares segment lists; an unmergeable delta drops the stored
+            // state so the next reload fetches the full playlist.
+            if snapshot.skipped_segments.is_some() {
+                let merged = match sd.variants.get(variant_key_str.as_str()) {
+                    Some(prev_state) => delta::merge_delta(prev_state, &mut snapshot, &ctx),
+                    None => Err(Box::new(MonitorError::new(
+                        ErrorType::SkipViolation,
+                        media_type.as_str(),
+                        variant_key_str.as_str(),
+                        "Delta update (EXT-X-SKIP) received without a previous playlist to merge into",
+                        base_url.as_str(),
+                        stream.id.as_str(),
+                    ))),
+                };
+                match merged {
+                    Ok(errors) => {
+                        for e in errors {
+                            record_error(sd, &mut all_errors, notification_tx, monitor_id, e);
+                        }
+                    }
+                    Err(e) => {
+                        record_error(sd, &mut all_errors, notification_tx, monitor_id, *e);
+                        sd.variants.remove(variant_key_str.as_str());
+                        continue;
+                    }
+                }
+            }
+
+            if config.probe_segments {
+                let prev_window = sd.variants.get(variant_key_str.as_str()).map(VariantState::segment_window);
+                probe_targets.extend(
+                    probe::select_new_segments(prev_window, &snapshot, config, &mut rand::thread_rng())
+                        .into_iter()
+                        .map(|uri| ProbeTarget {
+                            url: resolve_playlist_uri(variant_url, uri),
+                            media_type: media_type.clone(),
+                            variant_key: variant_key_str.clone(),
+                            init_segment: false,
+                        }),
+                );
+
+                // Each init segment a variant switches to is probed once.
+                let prev = sd.variants.get(variant_key_str.as_str());
+                let prev_map = prev.and_then(|p| p.map.as_ref());
+                let mut maps: Vec<&MapSnapshot> = Vec::new();
+                for seg in probe::new_segments(prev.map(VariantState::segment_window), &snapshot) {
+                    if let Some(map) = seg.map.as_ref().filter(|m| Some(*m) != prev_map && !maps.contains(m)) {
+                        maps.push(map);
+                    }
+                }
+                probe_targets.extend(maps.into_iter().map(|map| ProbeTarget {
+                    url: resolve_playlist_uri(variant_url, &map.uri),
+                    media_type: media_type.clone(),
+                    variant_key: variant_key_str.clone(),
+                    init_segment: true,
+                }));
+            }
+            if config.bitrate_check && advertised.contains_key(variant_key_str) {
+                let prev_window = sd.variants.get(variant_key_str.as_str()).map(VariantState::segment_window);
+                let new = probe::new_segments(prev_window, &snapshot);
+                let skip = new.len().saturating_sub(bitrate::BITRATE_WINDOW);
+                measure_targets.extend(new[skip..].iter().map(|seg| bitrate::MeasureTarget {
+                    url: resolve_playlist_uri(variant_url, &seg.uri),
+                    duration: seg.duration,
+                    byte_range_length: seg.byte_range_length,
+                    variant_key: variant_key_str.clone(),
+                }));
+            }
+            if config.segment_inspection {
+                // Byte-range segments would need ranged downloads; they are not inspected.
+                let prev_window = sd.variants.get(variant_key_str.as_str()).map(VariantState::segment_window);
+                let new: Vec<(u64, &SegmentSnapshot)> = snapshot
+                    .segments_after(prev_window)
+                    .filter(|(_, seg)| seg.byte_range_length.is_none())
+                    .collect();
+                for map in snapshot.segments.iter().filter_map(|seg| seg.map.as_ref()) {
+                    let url = resolve_playlist_uri(variant_url, &map.uri);
+                    if !init_refs.contains(&(variant_key_str.clone(), url.clone())) {
+                        init_refs.push((variant_key_str.clone(), url));
+                    }
+                }
+                let skip = new.len().saturating_sub(inspect::INSPECT_NEWEST);
+                inspect_targets.extend(new[skip..].iter().map(|(msn, seg)| inspect::InspectTarget {
+                    url: resolve_playlist_uri(variant_url, &seg.uri),
+                    variant_key: variant_key_str.clone(),
+                    media_sequence: *msn,
+                    duration: seg.duration,
+                    discontinuity: seg.discontinuity,
+                    init_url: seg.map.as_ref().map(|m| resolve_playlist_uri(variant_url, &m.uri)),
+                }));
+            }
+
+            loaded_variants.push(variant_key_str.clone());
+            if config.key_check {
+                for key in snapshot.keys.iter().filter(|k| k.method == "AES-128") {
+                    let Some(ref uri) = key.uri else {
+                        continue;
+                    };
+                    let url = if uri.starts_with("data:") {
+                        uri.clone()
+                    } else {
+                        resolve_playlist_uri(variant_url, uri)
+                    };
+                    key_targets.push(keys::KeyTarget {
+                        url,
+                        media_type: media_type.clone(),
+                        variant_key: variant_key_str.clone(),
+                    });
+                }
+            }
+
+            if config.interstitial_fetch {
+                for dr in snapshot.segments.iter().filter_map(|s| s.daterange.as_ref()).filter(|dr| is_interstitial(dr)) {
+                    let (uri, list) = match (dr.attributes.get("X-ASSET-URI"), dr.attributes.get("X-ASSET-LIST")) {
+                        (Some(uri), _) => (uri, false),
+                        (None, Some(list)) => (list, true),
+                        (None, None) => continue,
+                    };
+                    asset_targets.push(interstitial::AssetTarget {
+                        url: resolve_playlist_uri(variant_url, uri),
+                        list,
+                        id: dr.id.clone(),
+                        media_type: media_type.clone(),
+                        variant_key: variant_key_str.clone(),
+                    });
+                }
+            }
+
+            let live_edge_latency = latency::measure(&snapshot, fetched_at);
+            if let Some((error_type, problem)) =
+                live_edge_latency.and_then(|l| latency::evaluate(l, config.max_live_latency))
+            {
+                let error = MonitorError::new(
+                    error_type,
+                    media_type.as_str(),
+                    variant_key_str.as_str(),
+                    problem,
+                    base_url.as_str(),
+                    stream.id.as_str(),
+                );
+                record_error(sd, &mut all_errors, notification_tx, monitor_id, error);
+            }
 
             if let Some(prev_state) = sd.variants.get(variant_key_str.as_str()) {
                 if snapshot.media_sequence != prev_state.media_sequence
                     || snapshot.segments.len() != prev_state.segment_uris.len()
+                    || snapshot.pending_parts.len() != prev_state.pending_parts.len()
                 {
                     content_changed = true;
                 }
 
-                let ctx = CheckContext {
-                    stream_url: base_url.clone(),
-                    stream_id: stream.id.clone(),
-                    media_type: media_type.clone(),
-                    variant_key: variant_key_str.clone(),
-                };
-
                 let mut check_errors_batch = Vec::new();
                 for check in checks.iter() {
                     check_errors_batch.extend(check.check(prev_state, &snapshot, &ctx));
                 }
-
-                // FIX: TS version always set next_is_discontinuity to false (missing else-block)
-                let next_is_disc = if let Some(first) = snapshot.segments.first() {
-                    if first.discontinuity {
-                        true
-                    } else {
-                        snapshot.segments.get(1).is_some_and(|s| s.discontinuity)
-                    }
-                } else {
-                    false
-                };
+                if let Some(ref d) = directives[i].filter(|d| d.msn.is_some()) {
+                    check_errors_batch.extend(blocking_reload::check_blocking_reload(
+                        d, hold, prev_state, &snapshot, &ctx,
+                    ));
+                    sd.blocking_hold_ms.insert(variant_key_str.clone(), hold.as_millis() as u64);
+                }
 
                 let has_cue_out = snapshot.has_cue_out;
                 let has_cue_in = snapshot.cue_in_count > 0;
                 let was_in_cue_out = prev_state.in_cue_out;
                 let prev_mseq = prev_state.media_sequence;
                 let prev_dseq = prev_state.discontinuity_sequence;
+                let prev_map = prev_state.map.clone();
+                let prev_key_uri = prev_state.key_uri.clone();
+                let new_signals: Vec<(u64, &Scte35Marker, &scte35::SpliceInfo)> = if config.scte35_enabled {
+                    snapshot
+                        .segments_after(Some(prev_state.segment_window()))
+                        .flat_map(|(mseq, seg)| {
+                            seg.scte35.iter().filter_map(move |m| Some((mseq, m, m.signal.as_ref().ok()?)))
+                        })
+                        .collect()
+                } else {
+                    Vec::new()
+                };
 
                 let new_in_cue_out = if has_cue_out {
                     !has_cue_in
@@ -866,23 +1426,29 @@ async fn poll_stream(
                     was_in_cue_out
                 };
 
-                let new_state = VariantState {
-                    media_type: media_type.clone(),
-                    media_sequence: snapshot.media_sequence,
-                    segment_uris: snapshot.segments.iter().map(|s| s.uri.clone()).collect(),
-                    discontinuity_sequence: snapshot.discontinuity_sequence,
-                    next_is_discontinuity: next_is_disc,
-                    prev_segments: media_playlist.segments.iter().map(segment_to_info).collect(),
-                    duration: snapshot.duration,
-                    cue_out_count: snapshot.cue_out_count,
-                    cue_in_count: snapshot.cue_in_count,
-                    in_cue_out: new_in_cue_out,
-                    cue_out_duration: snapshot.cue_out_duration,
-                    version: snapshot.version,
-                    target_duration: snapshot.target_duration,
-                    playlist_type: snapshot.playlist_type.clone(),
-                    has_endlist: snapshot.has_endlist,
-                };
+                let (ad_break, break_outcomes) = ad_break::advance(
+                    prev_state.ad_break.clone(),
+                    Some(prev_state.segment_window()),
+                    &snapshot,
+                    config.ad_break_tolerance,
+                );
+                if config.scte35_enabled {
+                    check_errors_batch.extend(break_outcomes.iter().filter_map(|o| o.to_error(&ctx)));
+                }
+
+                let mut drift = clock_drift::advance(prev_state.clock_drift.clone(), Some(prev_state.segment_window()), &snapshot);
+                check_errors_batch.extend(drift.as_mut().and_then(|d| d.check(config.clock_drift_threshold, &ctx)));
+
+                let mut dateranges = prev_state.dateranges.clone();
+                let (daterange_errors, daterange_events) = daterange::advance(&mut dateranges, &snapshot, &ctx);
+                check_errors_batch.extend(daterange_errors);
+
+                let mut new_state = variant_state_from_snapshot(media_type, &snapshot, new_in_cue_out);
+                new_state.cue_out_duration = ad_break.as_ref().and_then(|b| b.planned).or(new_state.cue_out_duration);
+                new_state.ad_break = ad_break;
+                new_state.live_edge_latency = live_edge_latency;
+                new_state.clock_drift = drift;
+                new_state.dateranges = dateranges;
                 sd.variants.insert(variant_key_str.clone(), new_state);
 
                 for e in check_errors_batch {
@@ -929,6 +1495,20 @@ async fn poll_stream(
                     ));
                 }
 
+                for details in break_outcomes.iter().filter_map(ad_break::BreakOutcome::describe) {
+                    record_event(sd, notification_tx, monitor_id, MonitorEvent::new(
+                        EventKind::AdBreakCompleted,
+                        media_type.as_str(),
+                        variant_key_str.as_str(),
+                        details,
+                        stream.id.as_str(),
+                    ));
+                }
+
+                for event in daterange_events {
+                    record_event(sd, notification_tx, monitor_id, event);
+                }
+
                 for seg in &snapshot.segments {
                     if let Some(ref cont_val) = seg.cue_out_cont {
                         record_event(sd, notification_tx, monitor_id, MonitorEvent::new(
@@ -955,38 +1535,64 @@ async fn poll_stream(
                         stream.id.as_str(),
                     ));
                 }
+
+                let map = snapshot.segments.last().and_then(|s| s.map.as_ref());
+                if map != prev_map.as_ref() {
+                    record_event(sd, notification_tx, monitor_id, MonitorEvent::new(
+                        EventKind::InitSegmentChanged,
+                        media_type.as_str(),
+                        variant_key_str.as_str(),
+                        format!(
+                            "EXT-X-MAP {} -> {}",
+                            MapSnapshot::describe(prev_map.as_ref()),
+                            MapSnapshot::describe(map),
+                        ),
+                        stream.id.as_str(),
+                    ));
+                }
+
+                if snapshot.key_uri != prev_key_uri {
+                    let describe = |uri: Option<&String>| uri.map_or_else(|| "none".to_string(), |u| format!("'{}'", u));
+                    record_event(sd, notification_tx, monitor_id, MonitorEvent::new(
+                        EventKind::KeyRotated,
+                        media_type.as_str(),
+                        variant_key_str.as_str(),
+                        format!(
+                            "EXT-X-KEY URI {} -> {}",
+                            describe(prev_key_uri.as_ref()),
+                            describe(snapshot.key_uri.as_ref()),
+                        ),
+                        stream.id.as_str(),
+                    ));
+                }
+
+                for (mseq, marker, info) in new_signals {
+                    record_event(sd, notification_tx, monitor_id, MonitorEvent::new(
+                        EventKind::Scte35Signal,
+                        media_type.as_str(),
+                        variant_key_str.as_str(),
+                        format!("{} at mseq {}: {}", marker.source, mseq, info),
+                        stream.id.as_str(),
+                    ).with_scte35(info.clone()));
+                }
             } else {
                 content_changed = true;
                 let has_cue_out = snapshot.has_cue_out;
                 let has_cue_in = snapshot.cue_in_count > 0;
 
-                let next_is_disc = if let Some(first) = snapshot.segments.first() {
-                    if first.discontinuity {
-                        true
-                    } else {
-                        snapshot.segments.get(1).is_some_and(|s| s.discontinuity)
-                    }
-                } else {
-                    false
-                };
-
-                let initial_state = VariantState {
-                    media_type: media_type.clone(),
-                    media_sequence: snapshot.media_sequence,
-                    segment_uris: snapshot.segments.iter().map(|s| s.uri.clone()).collect(),
-                    discontinuity_sequence: snapshot.discontinuity_sequence,
-                    next_is_discontinuity: next_is_disc,
-                    prev_segments: media_playlist.segments.iter().map(segment_to_info).collect(),
-                    duration: snapshot.duration,
-                    cue_out_count: snapshot.cue_out_count,
-                    cue_in_count: snapshot.cue_in_count,
-                    in_cue_out: has_cue_out && !has_cue_in,
-                    cue_out_duration: snapshot.cue_out_duration,
-                    version: snapshot.version,
-                    target_duration: snapshot.target_duration,
-                    playlist_type: snapshot.playlist_type.clone(),
-                    has_endlist: snapshot.has_endlist,
-                };
+                let mut initial_state = variant_state_from_snapshot(
+                    media_type,
+                    &snapshot,
+                    has_cue_out && !has_cue_in,
+                );
+                // Breaks that already ended in the first playlist are not reported.
+                let (ad_break, _) = ad_break::advance(None, None, &snapshot, config.ad_break_tolerance);
+                initial_state.cue_out_duration = ad_break.as_ref().and_then(|b| b.planned).or(initial_state.cue_out_duration);
+                initial_state.ad_break = ad_break;
+                initial_state.live_edge_latency = live_edge_latency;
+                initial_state.clock_drift = clock_drift::advance(None, None, &snapshot);
+                // Ranges already in the first playlist are remembered without events.
+                daterange::advance(&mut initial_state.dateranges, &snapshot, &ctx);
                 sd.variants.insert(variant_key_str.clone(), initial_state);
             }
         }
@@ -995,6 +1601,10 @@ async fn poll_stream(
             stream_url: stream.url.clone(),
             stream_id: stream.id.clone(),
             variant_failures: sd.variant_failures.clone(),
+            variant_urls: variant_targets
+                .iter()
+                .map(|(url, key, _)| (normalize_playlist_url(url), key.clone()))
+                .collect(),
         };
         for sc in stream_checks.iter() {
             for e in sc.check(&sd.variants, &stream_check_ctx) {
@@ -1005,6 +1615,7 @@ async fn poll_stream(
         if content_changed {
             sd.last_content_change = Utc::now();
         }
+        sd.last_reload_changed = content_changed;
         sd.last_fetch = Utc::now();
 
         let effective_stale_limit = if config.spec_stale {
@@ -1044,6 +1655,173 @@ async fn poll_stream(
         }
     }
 
+    if !probe_targets.is_empty() {
+        let probe_errors = probe::run_probes(loader, probe_targets, config, &base_url, &stream.id).await;
+        if !probe_errors.is_empty() {
+            let mut data = stream_data.write().await;
+            let sd = data
+                .entry(stream.url.clone())
+                .or_insert_with(|| StreamData::new(config.error_limit, config.event_limit));
+            for e in probe_errors {
+                record_error(sd, &mut all_errors, notification_tx, monitor_id, e);
+            }
+        }
+    }
+
+    if !measure_targets.is_empty() {
+        let measured = bitrate::measure(loader, measure_targets, config).await;
+        let mut by_variant: HashMap<String, Vec<bitrate::BitrateSample>> = HashMap::new();
+        for (target, sample) in measured {
+            by_variant.entry(target.variant_key).or_default().push(sample);
+        }
+
+        let mut data = stream_data.write().await;
+        let sd = data
+            .entry(stream.url.clone())
+            .or_insert_with(|| StreamData::new(config.error_limit, config.event_limit));
+        let mut keys: Vec<String> = by_variant.keys().cloned().collect();
+        keys.sort();
+        for key in keys {
+            let samples = by_variant.remove(&key).unwrap_or_default();
+            let ctx = CheckContext {
+                stream_url: base_url.clone(),
+                stream_id: stream.id.clone(),
+                media_type: sd.known_variants.get(&key).cloned().unwrap_or_default(),
+                variant_key: key.clone(),
+            };
+            let window = sd.bitrate_samples.entry(key.clone()).or_default();
+            let errors = bitrate::check_bitrate(window, samples, advertised[&key], config.bitrate_margin, &ctx);
+            for e in errors {
+                record_error(sd, &mut all_errors, notification_tx, monitor_id, e);
+            }
+        }
+    }
+
+    if config.segment_inspection {
+        let missing_inits: Vec<String> = {
+            let data = stream_data.read().await;
+            let known = data.get(&stream.url).map(|sd| &sd.init_segments);
+            let mut urls: Vec<String> = inspect_targets
+                .iter()
+                .filter_map(|t| t.init_url.clone())
+                .filter(|url| known.is_none_or(|k| k.get(url).is_none()))
+                .collect();
+            urls.sort();
+            urls.dedup();
+            urls
+        };
+        let inits = inspect::download_init_segments(loader, missing_inits, config).await;
+        let downloaded = inspect::download(loader, inspect_targets, config).await;
+
+        let mut data = stream_data.write().await;
+        let sd = data
+            .entry(stream.url.clone())
+            .or_insert_with(|| StreamData::new(config.error_limit, config.event_limit));
+        let mut parsed = HashMap::new();
+        for (url, result) in inits {
+            match result {
+                Ok(init) => {
+                    parsed.insert(url, Some(init));
+                }
+                // A transport stream init segment (PAT/PMT) is not an error.
+                Err(Fmp4Error::NotIsoBmff) => {
+                    parsed.insert(url, None);
+                }
+                Err(e) => {
+                    let variant_key = downloaded
+                        .iter()
+                        .find(|(t, _)| t.init_url.as_ref() == Some(&url))
+                        .map(|(t, _)| t.variant_key.clone())
+                        .unwrap_or_default();
+                    let error = MonitorError::new(
+                        ErrorType::InitSegmentMismatch,
+                        sd.known_variants.get(&variant_key).map(String::as_str).unwrap_or_default(),
+                        &variant_key,
+                        format!("Init segment '{}' could not be parsed: {}", url, e),
+                        &base_url,
+                        &stream.id,
+                    );
+                    record_error(sd, &mut all_errors, notification_tx, monitor_id, error);
+                }
+            }
+        }
+        let referenced = init_refs.iter().map(|(key, url)| (key.as_str(), url.as_str()));
+        sd.init_segments.update(&loaded_variants, referenced, &parsed);
+        for (target, body) in downloaded {
+            let ctx = CheckContext {
+                stream_url: base_url.clone(),
+                stream_id: stream.id.clone(),
+                media_type: sd.known_variants.get(&target.variant_key).cloned().unwrap_or_default(),
+                variant_key: target.variant_key.clone(),
+            };
+            let prev = sd.inspected.get(&target.variant_key);
+            let init = target
+                .init_url
+                .as_ref()
+                .and_then(|url| sd.init_segments.get(url))
+                .and_then(Option::as_ref);
+            let (record, errors) =
+                inspect::inspect_segment(prev, &target, &body, init, config.timestamp_tolerance, &ctx);
+            if let Some(record) = record {
+                sd.inspected.insert(target.variant_key.clone(), record);
+            }
+            for e in errors {
+                record_error(sd, &mut all_errors, notification_tx, monitor_id, e);
+            }
+        }
+    }
+
+    if config.key_check {
+        // Keys are fetched until they pass, and forgotten once the variants
+        // that referenced them stop doing so.
+        let missing: Vec<keys::KeyTarget> = {
+            let data = stream_data.read().await;
+            let fetched = data.get(&stream.url).map(|sd| &sd.fetched_keys);
+            let mut seen = std::collections::HashSet::new();
+            key_targets
+                .iter()
+                .filter(|t| fetched.is_none_or(|f| !f.contains(&t.url)) && seen.insert(t.url.clone()))
+                .cloned()
+                .collect()
+        };
+        let (passed, key_errors) = keys::fetch_keys(loader, missing, config, &base_url, &stream.id).await;
+
+        let mut data = stream_data.write().await;
+        let sd = data
+            .entry(stream.url.clone())
+            .or_insert_with(|| StreamData::new(config.error_limit, config.event_limit));
+        let referenced = key_targets.iter().map(|t| (t.variant_key.as_str(), t.url.as_str()));
+        sd.fetched_keys.update(&loaded_variants, referenced, &passed);
+        for e in key_errors {
+            record_error(sd, &mut all_errors, notification_tx, monitor_id, e);
+        }
+    }
+
+    if config.interstitial_fetch {
+        // Like keys, assets are fetched until they pass while they stay scheduled.
+        let missing: Vec<interstitial::AssetTarget> = {
+            let data = stream_data.read().await;
+            let fetched = data.get(&stream.url).map(|sd| &sd.fetched_assets);
+            let mut seen = std::collections::HashSet::new();
+            asset_targets
+                .iter()
+                .filter(|t| fetched.is_none_or(|f| !f.contains(&t.url)) && seen.insert(t.url.clone()))
+                .cloned()
+                .collect()
+        };
+        let (passed, asset_errors) = interstitial::fetch_assets(loader, missing, config, &base_url, &stream.id).await;
+
+        let mut data = stream_data.write().await;
+        let sd = data
+            .entry(stream.url.clone())
+            .or_insert_with(|| StreamData::new(config.error_limit, config.event_limit));
+        let referenced = asset_targets.iter().map(|t| (t.variant_key.as_str(), t.url.as_str()));
+        sd.fetched_assets.update(&loaded_variants, referenced, &passed);
+        for e in asset_errors {
+            record_error(sd, &mut all_errors, notification_tx, monitor_id, e);
+        }
+    }
+
     all_errors
 }
 
@@ -1060,137 +1838,63 @@ mod tests {
     }
 
     #[test]
-    fn get_base_url_handles_root() {
+    fn resolve_playlist_uri_handles_parent_paths() {
+        let resolved = resolve_playlist_uri("https://example.com/live/2M/index.m3u8?token=a", "../1M/index.m3u8");
+        assert_eq!(normalize_playlist_url(&resolved), "https://example.com/live/1M/index.m3u8");
         assert_eq!(
-            get_base_url("https://example.com/master.m3u8"),
-            "https://example.com/"
+            normalize_playlist_url("https://example.com/live/1M/index.m3u8?token=b#x"),
+            "https://example.com/live/1M/index.m3u8"
         );
     }
 
     #[test]
-    fn build_playlist_url_absolute() {
+    fn get_base_url_handles_root() {
         assert_eq!(
-            build_playlist_url("https://a.com/", "https://b.com/foo.m3u8"),
-            "https://b.com/foo.m3u8"
+            get_base_url("https://example.com/master.m3u8"),
+            "https://example.com/"
         );
     }
 
     #[test]
-    fn build_playlist_url_relative() {
-        assert_eq!(
-            build_playlist_url("https://a.com/path/", "level_0.m3u8"),
-            "https://a.com/path/level_0.m3u8"
-        );
-    }
-
-    fn parse_master(text: &str) -> m3u8_rs::MasterPlaylist {
-        let (_, playlist) = m3u8_rs::parse_playlist(text.as_bytes()).unwrap();
-        match playlist {
-            m3u8_rs::Playlist::MasterPlaylist(m) => m,
-            _ => panic!("expected master playlist"),
+    fn jittered_interval_stays_within_bounds() {
+        let base = Duration::from_millis(7000);
+        for _ in 0..100 {
+            let d = jittered_interval(base);
+            assert!(d >= Duration::from_millis(6000) && d < Duration::from_millis(8000));
         }
-    }
-
-    fn authoring_errors(errors: &[MonitorError]) -> Vec<&MonitorError> {
-        errors
-            .iter()
-            .filter(|e| e.error_type == ErrorType::AuthoringSpecViolation)
-            .collect()
-    }
-
-    #[test]
-    fn test_authoring_avg_bandwidth_missing() {
-        let text = "#EXTM3U\n\
-            #EXT-X-STREAM-INF:BANDWIDTH=500000\n\
-            low.m3u8\n\
-            #EXT-X-STREAM-INF:BANDWIDTH=1000000\n\
-            high.m3u8\n";
-        let master = parse_master(text);
-        let config = MonitorConfig::default().with_authoring_spec(true);
-        let errors = validate_master(&master, "http://x.com/m.m3u8", "s1", &config);
-        let ae = authoring_errors(&errors);
-        assert!(ae.iter().any(|e| e.details.contains("AVERAGE-BANDWIDTH")));
+        assert_eq!(jittered_interval(Duration::from_millis(3)), Duration::from_millis(3));
     }
 
     #[test]
-    fn test_authoring_avg_bandwidth_present() {
-        let text = "#EXTM3U\n\
-            #EXT-X-STREAM-INF:BANDWIDTH=500000,AVERAGE-BANDWIDTH=400000\n\
-            low.m3u8\n\
-            #EXT-X-STREAM-INF:BANDWIDTH=1000000,AVERAGE-BANDWIDTH=800000\n\
-            high.m3u8\n";
-        let master = parse_master(text);
-        let config = MonitorConfig::default().with_authoring_spec(true);
-        let errors = validate_master(&master, "http://x.com/m.m3u8", "s1", &config);
-        let ae = authoring_errors(&errors);
-        assert!(!ae.iter().any(|e| e.details.contains("AVERAGE-BANDWIDTH")));
+    fn adaptive_interval_uses_full_target_duration_after_change() {
+        assert_eq!(adaptive_poll_interval(6.0, true), Some(Duration::from_secs(6)));
     }
 
     #[test]
-    fn test_authoring_independent_segments_missing() {
-        let text = "#EXTM3U\n\
-            #EXT-X-STREAM-INF:BANDWIDTH=150000\n\
-            low.m3u8\n";
-        let master = parse_master(text);
-        let config = MonitorConfig::default().with_authoring_spec(true);
-        let errors = validate_master(&master, "http://x.com/m.m3u8", "s1", &config);
-        let ae = authoring_errors(&errors);
-        assert!(ae.iter().any(|e| e.details.contains("INDEPENDENT-SEGMENTS")));
+    fn adaptive_interval_halves_after_unchanged_reload() {
+        assert_eq!(adaptive_poll_interval(2.0, false), Some(Duration::from_secs(1)));
     }
 
     #[test]
-    fn test_authoring_independent_segments_present() {
-        let text = "#EXTM3U\n\
-            #EXT-X-INDEPENDENT-SEGMENTS\n\
-            #EXT-X-STREAM-INF:BANDWIDTH=150000\n\
-            low.m3u8\n";
-        let master = parse_master(text);
-        let config = MonitorConfig::default().with_authoring_spec(true);
-        let errors = validate_master(&master, "http://x.com/m.m3u8", "s1", &config);
-        let ae = authoring_errors(&errors);
-        assert!(!ae.iter().any(|e| e.details.contains("INDEPENDENT-SEGMENTS")));
+    fn adaptive_interval_requires_target_duration() {
+        assert_eq!(adaptive_poll_interval(0.0, true), None);
+        assert_eq!(adaptive_poll_interval(f64::INFINITY, true), None);
     }
 
     #[test]
-    fn test_authoring_no_cellular_variant() {
-        let text = "#EXTM3U\n\
-            #EXT-X-STREAM-INF:BANDWIDTH=500000\n\
-            mid.m3u8\n\
-            #EXT-X-STREAM-INF:BANDWIDTH=1000000\n\
-            high.m3u8\n";
-        let master = parse_master(text);
-        let config = MonitorConfig::default().with_authoring_spec(true);
-        let errors = validate_master(&master, "http://x.com/m.m3u8", "s1", &config);
-        let ae = authoring_errors(&errors);
-        assert!(ae.iter().any(|e| e.details.contains("192 kb/s")));
-    }
-
-    #[test]
-    fn test_authoring_has_cellular_variant() {
-        let text = "#EXTM3U\n\
-            #EXT-X-STREAM-INF:BANDWIDTH=150000\n\
-            cellular.m3u8\n\
-            #EXT-X-STREAM-INF:BANDWIDTH=1000000\n\
-            high.m3u8\n";
-        let master = parse_master(text);
-        let config = MonitorConfig::default().with_authoring_spec(true);
-        let errors = validate_master(&master, "http://x.com/m.m3u8", "s1", &config);
-        let ae = authoring_errors(&errors);
-        assert!(!ae.iter().any(|e| e.details.contains("192 kb/s")));
+    fn build_playlist_url_absolute() {
+        assert_eq!(
+            build_playlist_url("https://a.com/", "https://b.com/foo.m3u8"),
+            "https://b.com/foo.m3u8"
+        );
     }
 
     #[test]
-    fn test_authoring_disabled_no_errors() {
-        let text = "#EXTM3U\n\
-            #EXT-X-STREAM-INF:BANDWIDTH=500000\n\
-            low.m3u8\n\
-            #EXT-X-STREAM-INF:BANDWIDTH=1000000\n\
-            high.m3u8\n";
-        let master = parse_master(text);
-        let config = MonitorConfig::default();
-        let errors = validate_master(&master, "http://x.com/m.m3u8", "s1", &config);
-        let ae = authoring_errors(&errors);
-        assert!(ae.is_empty());
+    fn build_playlist_url_relative() {
+        assert_eq!(
+            build_playlist_url("https://a.com/path/", "level_0.m3u8"),
+            "https://a.com/path/level_0.m3u8"
+        );
     }
 
     #[test]
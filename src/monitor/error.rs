@@ -36,6 +36,48 @@ pub enum ErrorType {
     EncryptionViolation,
     RenditionGroupViolation,
     AuthoringSpecViolation,
+    PartTargetDurationExceeded,
+    IndependentPartCadence,
+    PartRemovedEarly,
+    PreloadHintMismatch,
+    BlockingReloadEarly,
+    BlockingReloadOverdue,
+    SkipViolation,
+    ServerControlViolation,
+    RenditionReportMismatch,
+    SegmentUnavailable,
+    BitrateExceeded,
+    AverageBitrateExceeded,
+    TimestampDiscontinuity,
+    ContinuityCounterError,
+    MissingPid,
+    InitSegmentMismatch,
+    FragmentDurationMismatch,
+    InitSegmentChange,
+    InitSegmentUnavailable,
+    KeyUnavailable,
+    InvalidKey,
+    AdBreakDurationMismatch,
+    AdBreakMisalignment,
+    VariantPdtDrift,
+    LiveEdgeLatency,
+    FuturePdt,
+    ClockDrift,
+    InterstitialViolation,
+    InterstitialAssetUnavailable,
+    InvalidInterstitialAsset,
+    DateRangeMutation,
+    UndefinedRenditionGroup,
+    MissingCodecs,
+    InvalidCodecs,
+    InvalidResolution,
+    InvalidFrameRate,
+    UnresolvableIFrameUri,
+    AutoselectViolation,
+    ForcedViolation,
+    CharacteristicsViolation,
+    LadderFlapping,
+    VariantKeyCollision,
 }
 
 impl fmt::Display for ErrorType {
@@ -69,6 +111,48 @@ impl fmt::Display for ErrorType {
             Self::EncryptionViolation => write!(f, "Encryption Violation"),
             Self::RenditionGroupViolation => write!(f, "Rendition Group Violation"),
             Self::AuthoringSpecViolation => write!(f, "Authoring Spec Violation"),
+            Self::PartTargetDurationExceeded => write!(f, "Part Target Duration Exceeded"),
+            Self::IndependentPartCadence => write!(f, "Independent Part Cadence"),
+            Self::PartRemovedEarly => write!(f, "Part Removed Early"),
+            Self::PreloadHintMismatch => write!(f, "Preload Hint Mismatch"),
+            Self::BlockingReloadEarly => write!(f, "Blocking Reload Early"),
+            Self::BlockingReloadOverdue => write!(f, "Blocking Reload Overdue"),
+            Self::SkipViolation => write!(f, "Skip Violation"),
+            Self::ServerControlViolation => write!(f, "Server Control Violation"),
+            Self::RenditionReportMismatch => write!(f, "Rendition Report Mismatch"),
+            Self::SegmentUnavailable => write!(f, "Segment Unavailable"),
+            Self::BitrateExceeded => write!(f, "Bitrate Exceeded"),
+            Self::AverageBitrateExceeded => write!(f, "Average Bitrate Exceeded"),
+            Self::TimestampDiscontinuity => write!(f, "Timestamp Discontinuity"),
+            Self::ContinuityCounterError => write!(f, "Continuity Counter Error"),
+            Self::MissingPid => write!(f, "Missing PID"),
+            Self::InitSegmentMismatch => write!(f, "Init Segment Mismatch"),
+            Self::FragmentDurationMismatch => write!(f, "Fragment Duration Mismatch"),
+            Self::InitSegmentChange => write!(f, "Init Segment Change"),
+            Self::InitSegmentUnavailable => write!(f, "Init Segment Unavailable"),
+            Self::KeyUnavailable => write!(f, "Key Unavailable"),
+            Self::InvalidKey => write!(f, "Invalid Key"),
+            Self::AdBreakDurationMismatch => write!(f, "Ad Break Duration Mismatch"),
+            Self::AdBreakMisalignment => write!(f, "Ad Break Misalignment"),
+            Self::VariantPdtDrift => write!(f, "Variant PDT Drift"),
+            Self::LiveEdgeLatency => write!(f, "Live Edge Latency"),
+            Self::FuturePdt => write!(f, "Future PDT"),
+            Self::ClockDrift => write!(f, "Encoder Clock Drift"),
+            Self::InterstitialViolation => write!(f, "Interstitial Violation"),
+            Self::InterstitialAssetUnavailable => write!(f, "Interstitial Asset Unavailable"),
+            Self::InvalidInterstitialAsset => write!(f, "Invalid Interstitial Asset"),
+            Self::DateRangeMutation => write!(f, "Date Range Mutation"),
+            Self::UndefinedRenditionGroup => write!(f, "Undefined Rendition Group"),
+            Self::MissingCodecs => write!(f, "Missing CODECS"),
+            Self::InvalidCodecs => write!(f, "Invalid CODECS"),
+            Self::InvalidResolution => write!(f, "Invalid Resolution"),
+            Self::InvalidFrameRate => write!(f, "Invalid Frame Rate"),
+            Self::UnresolvableIFrameUri => write!(f, "Unresolvable I-Frame URI"),
+            Self::AutoselectViolation => write!(f, "AUTOSELECT Violation"),
+            Self::ForcedViolation => write!(f, "FORCED Violation"),
+            Self::CharacteristicsViolation => write!(f, "CHARACTERISTICS Violation"),
+            Self::LadderFlapping => write!(f, "Ladder Flapping"),
+            Self::VariantKeyCollision => write!(f, "Variant Key Collision"),
         }
     }
 }
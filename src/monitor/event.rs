@@ -5,6 +5,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
+use crate::media::scte35::SpliceInfo;
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum EventKind {
@@ -15,6 +17,15 @@ pub enum EventKind {
     ManifestUpdated,
     StaleRecovered,
     StreamEnded,
+    InitSegmentChanged,
+    KeyRotated,
+    Scte35Signal,
+    AdBreakCompleted,
+    DateRangeOpened,
+    DateRangeClosed,
+    VariantAdded,
+    VariantRemoved,
+    MasterChanged,
 }
 
 impl fmt::Display for EventKind {
@@ -27,6 +38,15 @@ impl fmt::Display for EventKind {
             Self::ManifestUpdated => write!(f, "UPDATE"),
             Self::StaleRecovered => write!(f, "RECOVERED"),
             Self::StreamEnded => write!(f, "ENDED"),
+            Self::InitSegmentChanged => write!(f, "MAP"),
+            Self::KeyRotated => write!(f, "KEY"),
+            Self::Scte35Signal => write!(f, "SCTE35"),
+            Self::AdBreakCompleted => write!(f, "BREAK"),
+            Self::DateRangeOpened => write!(f, "DATERANGE-OPEN"),
+            Self::DateRangeClosed => write!(f, "DATERANGE-CLOSE"),
+            Self::VariantAdded => write!(f, "VARIANT-ADDED"),
+            Self::VariantRemoved => write!(f, "VARIANT-REMOVED"),
+            Self::MasterChanged => write!(f, "MASTER"),
         }
     }
 }
@@ -40,6 +60,9 @@ pub struct MonitorEvent {
     pub media_type: String,
     pub variant_key: String,
     pub details: String,
+    /// Decoded SCTE-35 payload of a `Scte35Signal` event.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scte35: Option<SpliceInfo>,
 }
 
 impl MonitorEvent {
@@ -58,8 +81,14 @@ impl MonitorEvent {
             media_type: media_type.into(),
             variant_key: variant_key.into(),
             details: details.into(),
+            scte35: None,
         }
     }
+
+    pub fn with_scte35(mut self, info: SpliceInfo) -> Self {
+        self.scte35 = Some(info);
+        self
+    }
 }
 
 /// Fixed-capacity circular buffer for recent events. O(1) insert, evicts oldest when full.
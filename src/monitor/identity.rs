@@ -0,0 +1,213 @@
+//! Variant keys for the playlists referenced by a master.
+//!
+//! A variant is keyed by its `BANDWIDTH` (`iframe_<bandwidth>` for I-frame
+//! streams), the sample entry types of its `CODECS` and the path of its URI,
+//! e.g. `2400000_hvc1+mp4a_hevc/1080`; a rendition by
+//! `<group>;<language>;<path>`. A key only depends on the playlist's own
+//! tag, so it stays the same when siblings come and go, and the query is
+//! left out so rotating tokens do not change it. Playlists that still end up
+//! with the same key are collisions; all but the first are numbered (`~2`).
+
+use std::collections::BTreeMap;
+
+use m3u8_rs::{AlternativeMedia, MasterPlaylist, VariantStream};
+use url::Url;
+
+use super::error::{ErrorType, MonitorError};
+
+/// A key shared by playlists that nothing in the master tells apart.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Collision {
+    pub key: String,
+    /// URIs of the playlists sharing `key`, in master order.
+    pub uris: Vec<String>,
+}
+
+/// Keys of the playlists in a master, in the order of its tags.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct VariantKeys {
+    /// One per `EXT-X-STREAM-INF` and `EXT-X-I-FRAME-STREAM-INF`.
+    pub variants: Vec<String>,
+    /// One per `EXT-X-MEDIA`; `None` for renditions without a URI.
+    pub media: Vec<Option<String>>,
+    pub collisions: Vec<Collision>,
+}
+
+/// Sample entry types of a `CODECS` list, e.g. `hvc1+mp4a`.
+fn codec_types(codecs: &str) -> String {
+    codecs
+        .split(',')
+        .map(|c| c.trim().split('.').next().unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join("+")
+}
+
+/// The path of the URI without its leading `/` and `.m3u8` extension,
+/// dropping the scheme and host of absolute URIs and any query or fragment.
+fn uri_label(uri: &str) -> String {
+    let path = match Url::parse(uri) {
+        Ok(url) => url.path().to_string(),
+        Err(_) => uri.split(['?', '#']).next().unwrap_or_default().to_string(),
+    };
+    let path = path.trim_start_matches('/');
+    path.strip_suffix(".m3u8").unwrap_or(path).to_string()
+}
+
+/// `parts` joined by `separator`, skipping empty ones.
+fn join_key(parts: &[&str], separator: char) -> String {
+    parts
+        .iter()
+        .filter(|part| !part.is_empty())
+        .copied()
+        .collect::<Vec<_>>()
+        .join(&separator.to_string())
+}
+
+fn variant_key(variant: &VariantStream) -> String {
+    let bandwidth = if variant.is_i_frame {
+        format!("iframe_{}", variant.bandwidth)
+    } else {
+        variant.bandwidth.to_string()
+    };
+    let codecs = variant.codecs.as_deref().map(codec_types).unwrap_or_default();
+    join_key(&[&bandwidth, &codecs, &uri_label(&variant.uri)], '_')
+}
+
+fn media_key(media: &AlternativeMedia, uri: &str) -> String {
+    let lang = media.language.as_deref().unwrap_or(&media.name);
+    join_key(&[&media.group_id, lang, &uri_label(uri)], ';')
+}
+
+impl VariantKeys {
+    pub fn of(master: &MasterPlaylist) -> Self {
+        let mut keys: Vec<(String, String)> = master.variants.iter().map(|v| (variant_key(v), v.uri.clone())).collect();
+        let media_entries: Vec<Option<usize>> = master
+            .alternatives
+            .iter()
+            .map(|m| {
+                let uri = m.uri.as_ref()?;
+                keys.push((media_key(m, uri), uri.clone()));
+                Some(keys.len() - 1)
+            })
+            .collect();
+
+        let mut by_key: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (i, (key, _)) in keys.iter().enumerate() {
+            by_key.entry(key.clone()).or_default().push(i);
+        }
+        let mut collisions = Vec::new();
+        for (key, shared) in by_key.into_iter().filter(|(_, shared)| shared.len() > 1) {
+            collisions.push(Collision {
+                key: key.clone(),
+                uris: shared.iter().map(|&i| keys[i].1.clone()).collect(),
+            });
+            for (n, &i) in shared.iter().enumerate().skip(1) {
+                keys[i].0 = format!("{}~{}", key, n + 1);
+            }
+        }
+
+        let variants = keys[..master.variants.len()].iter().map(|(key, _)| key.clone()).collect();
+        let media = media_entries.iter().map(|i| i.map(|i| keys[i].0.clone())).collect();
+        Self {
+            variants,
+            media,
+            collisions,
+        }
+    }
+
+    /// A [`ErrorType::VariantKeyCollision`] per collision.
+    pub fn collision_errors(&self, stream_url: &str, stream_id: &str) -> Vec<MonitorError> {
+        self.collisions
+            .iter()
+            .map(|c| {
+                let uris: Vec<String> = c.uris.iter().map(|uri| format!("'{}'", uri)).collect();
+                MonitorError::new(
+                    ErrorType::VariantKeyCollision,
+                    "MASTER",
+                    &c.key,
+                    format!(
+                        "Variant key '{}' is shared by {} playlists that no attribute or URI tells apart ({}); \
+                         all but the first are monitored as '{}~N'",
+                        c.key,
+                        c.uris.len(),
+                        uris.join(", "),
+                        c.key
+                    ),
+                    stream_url,
+                    stream_id,
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(text: &str) -> VariantKeys {
+        match m3u8_rs::parse_playlist_res(text.as_bytes()).unwrap() {
+            m3u8_rs::Playlist::MasterPlaylist(m) => VariantKeys::of(&m),
+            _ => panic!("expected master playlist"),
+        }
+    }
+
+    #[test]
+    fn keys_combine_own_attributes_and_uri_path() {
+        let keys = keys(
+            "#EXTM3U\n\
+             #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English\",LANGUAGE=\"en\",URI=\"audio/en.m3u8\"\n\
+             #EXT-X-MEDIA:TYPE=AUDIO,GROUP-ID=\"aac\",NAME=\"English AD\",LANGUAGE=\"en\",URI=\"audio/en_ad.m3u8\"\n\
+             #EXT-X-MEDIA:TYPE=CLOSED-CAPTIONS,GROUP-ID=\"cc\",NAME=\"CC1\",INSTREAM-ID=\"CC1\"\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=2000000,CODECS=\"avc1.640028,mp4a.40.2\"\navc/1080.m3u8\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=2000000,CODECS=\"hvc1.2.4.L123.B0,mp4a.40.2\"\nhevc/1080.m3u8\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=1000000\nhttps://cdn.example.com/low/index.m3u8?token=1\n\
+             #EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=200000,URI=\"iframe.m3u8\"\n",
+        );
+        assert_eq!(
+            keys.variants,
+            ["2000000_avc1+mp4a_avc/1080", "2000000_hvc1+mp4a_hevc/1080", "1000000_low/index", "iframe_200000_iframe"]
+        );
+        assert_eq!(
+            keys.media,
+            [Some("aac;en;audio/en".to_string()), Some("aac;en;audio/en_ad".to_string()), None]
+        );
+        assert!(keys.collisions.is_empty());
+    }
+
+    #[test]
+    fn keys_do_not_depend_on_siblings_or_query() {
+        let before = keys("#EXTM3U\n#EXT-X-STREAM-INF:BANDWIDTH=2400000,CODECS=\"avc1.640028,mp4a.40.2\"\navc.m3u8?token=1\n");
+        let after = keys(
+            "#EXTM3U\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=2400000,CODECS=\"avc1.640028,mp4a.40.2\"\navc.m3u8?token=2\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=2400000,CODECS=\"hvc1.2.4.L123.B0,mp4a.40.2\"\nhevc.m3u8\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=2400000,CODECS=\"avc1.640028,mp4a.40.2\"\navc.m3u8?token=3\n",
+        );
+        assert_eq!(before.variants, ["2400000_avc1+mp4a_avc"]);
+        assert_eq!(after.variants, ["2400000_avc1+mp4a_avc", "2400000_hvc1+mp4a_hevc", "2400000_avc1+mp4a_avc~2"]);
+        assert_eq!(after.collisions.len(), 1);
+    }
+
+    #[test]
+    fn duplicate_playlists_are_collisions() {
+        let keys = keys(
+            "#EXTM3U\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=1000000\nlow.m3u8\n\
+             #EXT-X-STREAM-INF:BANDWIDTH=1000000\nlow.m3u8\n",
+        );
+        assert_eq!(keys.variants, ["1000000_low", "1000000_low~2"]);
+        assert_eq!(
+            keys.collisions,
+            [Collision {
+                key: "1000000_low".to_string(),
+                uris: vec!["low.m3u8".to_string(), "low.m3u8".to_string()],
+            }]
+        );
+
+        let errors = keys.collision_errors("http://x.com/m.m3u8", "s1");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].error_type, ErrorType::VariantKeyCollision);
+        assert_eq!(errors[0].variant, "1000000_low");
+    }
+}
@@ -0,0 +1,527 @@
+//! Segment inspection: download newly published segments and check that
+//! the timestamps inside them follow the playlist.
+//!
+//! For MPEG-TS, the first timestamp of each elementary stream in a segment
+//! should advance by the `EXTINF` duration of the segment before it. For
+//! fMP4/CMAF, each track's `tfdt` should continue where the previous
+//! segment's samples ended, the samples should add up to the `EXTINF`
+//! duration, and the tracks should be those of the `EXT-X-MAP` init segment.
+//! Timestamps may restart at `EXT-X-DISCONTINUITY`.
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use futures::stream::{self, StreamExt};
+use tracing::debug;
+
+use crate::config::MonitorConfig;
+use crate::loader::ManifestLoader;
+use crate::media::fmp4::{self, FragmentSummary, InitSegment};
+use crate::media::ts::{self, TsSummary};
+
+use super::error::{ErrorType, MonitorError};
+use super::state::CheckContext;
+
+/// Maximum number of new segments downloaded per variant and poll.
+pub const INSPECT_NEWEST: usize = 3;
+
+/// A segment selected for inspection, with its place in the playlist.
+#[derive(Debug, Clone)]
+pub struct InspectTarget {
+    pub url: String,
+    pub variant_key: String,
+    pub media_sequence: u64,
+    pub duration: f64,
+    pub discontinuity: bool,
+    /// Resolved URL of the segment's `EXT-X-MAP`.
+    pub init_url: Option<String>,
+}
+
+/// What is kept of the last inspected segment of a variant.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InspectedSegment {
+    pub media_sequence: u64,
+    pub uri: String,
+    pub duration: f64,
+    /// First PTS per elementary stream PID.
+    pub first_pts: BTreeMap<u16, u64>,
+    pub first_pcr: Option<u64>,
+    /// End of the samples per fMP4 track ID, as `(decode time, timescale)`.
+    pub track_ends: BTreeMap<u32, (u64, u32)>,
+}
+
+/// Download every target, keeping the input order.
+///
+/// Segments that cannot be downloaded are left out; reporting unavailable
+/// segments is the job of the availability probe.
+pub async fn download(
+    loader: &Arc<dyn ManifestLoader>,
+    targets: Vec<InspectTarget>,
+    config: &MonitorConfig,
+) -> Vec<(InspectTarget, Vec<u8>)> {
+    let downloaded: Vec<_> = stream::iter(targets)
+        .map(|target| {
+            let loader = Arc::clone(loader);
+            async move {
+                let result = loader.fetch_segment(&target.url).await;
+                (target, result)
+            }
+        })
+        .buffered(config.max_concurrent_fetches.max(1))
+        .collect()
+        .await;
+
+    downloaded
+        .into_iter()
+        .filter_map(|(target, result)| match result {
+            Ok(body) => Some((target, body)),
+            Err(e) => {
+                debug!(url = %target.url, error = %e, "Could not download segment for inspection");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Download and parse the given init segments.
+///
+/// Init segments that cannot be downloaded are left out so that they are
+/// retried on the next poll.
+pub async fn download_init_segments(
+    loader: &Arc<dyn ManifestLoader>,
+    urls: Vec<String>,
+    config: &MonitorConfig,
+) -> Vec<(String, Result<InitSegment, fmp4::Fmp4Error>)> {
+    let downloaded: Vec<_> = stream::iter(urls)
+        .map(|url| {
+            let loader = Arc::clone(loader);
+            async move {
+                let result = loader.fetch_segment(&url).await;
+                (url, result)
+            }
+        })
+        .buffer_unordered(config.max_concurrent_fetches.max(1))
+        .collect()
+        .await;
+
+    downloaded
+        .into_iter()
+        .filter_map(|(url, result)| match result {
+            Ok(body) => Some((url, fmp4::parse_init(&body))),
+            Err(e) => {
+                debug!(url = %url, error = %e, "Could not download init segment for inspection");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Inspect a downloaded segment against the previously inspected one.
+///
+/// `init` is the parsed init segment of `target.init_url`, if it could be
+/// parsed. Returns `None` in place of the new record when the segment is
+/// neither MPEG-TS nor fMP4 (or is encrypted), so that the previous record
+/// stays in place.
+pub fn inspect_segment(
+    prev: Option<&InspectedSegment>,
+    target: &InspectTarget,
+    data: &[u8],
+    init: Option<&InitSegment>,
+    tolerance: f64,
+    ctx: &CheckContext,
+) -> (Option<InspectedSegment>, Vec<MonitorError>) {
+    let mut record = InspectedSegment {
+        media_sequence: target.media_sequence,
+        uri: target.url.clone(),
+        duration: target.duration,
+        first_pts: BTreeMap::new(),
+        first_pcr: None,
+        track_ends: BTreeMap::new(),
+    };
+
+    if let Ok(summary) = ts::parse(data) {
+        let findings = check_ts(prev, target, &summary, tolerance);
+        record.first_pts = summary
+            .streams
+            .iter()
+            .filter_map(|s| Some((s.pid, s.first_pts?)))
+            .collect();
+        record.first_pcr = summary.first_pcr;
+        return (Some(record), to_errors(findings, target, ctx));
+    }
+
+    match fmp4::parse_fragment(data) {
+        Ok(summary) => {
+            let Some(init_url) = target.init_url.as_deref() else {
+                let finding = (ErrorType::InitSegmentMismatch, "fMP4 segment has no EXT-X-MAP".to_string());
+                return (None, to_errors(vec![finding], target, ctx));
+            };
+            // An init segment that cannot be parsed is reported when it is downloaded.
+            let Some(init) = init else {
+                return (None, vec![]);
+            };
+            let findings = check_fmp4(prev, target, &summary, init, init_url, tolerance);
+            for (&track_id, run) in &summary.tracks {
+                let Some(track) = init.tracks.get(&track_id) else {
+                    continue;
+                };
+                if let (Some(start), Some(duration)) =
+                    (run.base_media_decode_time, run.total_duration(track.default_sample_duration))
+                {
+                    record.track_ends.insert(track_id, (start + duration, track.timescale));
+                }
+            }
+            (Some(record), to_errors(findings, target, ctx))
+        }
+        Err(e) => {
+            debug!(url = %target.url, error = %e, "Segment not inspected");
+            (None, vec![])
+        }
+    }
+}
+
+fn check_ts(
+    prev: Option<&InspectedSegment>,
+    target: &InspectTarget,
+    summary: &TsSummary,
+    tolerance: f64,
+) -> Vec<(ErrorType, String)> {
+    let mut findings: Vec<(ErrorType, String)> = Vec::new();
+
+    if !summary.has_pat || !summary.has_pmt {
+        findings.push((ErrorType::MissingPid, "no PAT/PMT in segment".to_string()));
+    }
+    for stream in summary.streams.iter().filter(|s| s.packets == 0) {
+        findings.push((
+            ErrorType::MissingPid,
+            format!(
+                "PID 0x{:04x} (stream type 0x{:02x}) listed in PMT has no packets",
+                stream.pid, stream.stream_type
+            ),
+        ));
+    }
+
+    if let Some(first) = summary.continuity_errors.first() {
+        findings.push((
+            ErrorType::ContinuityCounterError,
+            format!(
+                "{} continuity counter error(s), first on PID 0x{:04x} at packet {}: expected {}, got {}",
+                summary.continuity_errors.len(),
+                first.pid,
+                first.packet,
+                first.expected,
+                first.found
+            ),
+        ));
+    }
+
+    // Timestamps may restart and PIDs may change at a discontinuity.
+    if let Some(prev) = prev.filter(|p| p.media_sequence + 1 == target.media_sequence && !target.discontinuity) {
+        let mut timestamps: Vec<(String, u64, u64)> = Vec::new();
+        for (&pid, &prev_pts) in &prev.first_pts {
+            match summary.stream(pid) {
+                None => findings.push((
+                    ErrorType::MissingPid,
+                    format!(
+                        "PID 0x{:04x} present in mseq({}) is missing without EXT-X-DISCONTINUITY",
+                        pid, prev.media_sequence
+                    ),
+                )),
+                Some(stream) => {
+                    if let Some(pts) = stream.first_pts {
+                        timestamps.push((format!("PTS on PID 0x{:04x}", pid), prev_pts, pts));
+                    }
+                }
+            }
+        }
+        if let (Some(prev_pcr), Some(pcr)) = (prev.first_pcr, summary.first_pcr) {
+            timestamps.push(("PCR".to_string(), prev_pcr, pcr));
+        }
+
+        for (what, from, to) in timestamps {
+            let advanced = ts::timestamp_delta(from, to) as f64 / ts::CLOCK_HZ as f64;
+            let off = advanced - prev.duration;
+            if off.abs() > tolerance {
+                findings.push((
+                    ErrorType::TimestampDiscontinuity,
+                    format!(
+                        "{} advanced {:.3}s since mseq({}) but its EXTINF is {:.3}s ({} of {:.3}s) without EXT-X-DISCONTINUITY",
+                        what,
+                        advanced,
+                        prev.media_sequence,
+                        prev.duration,
+                        if off > 0.0 { "gap" } else { "overlap" },
+                        off.abs()
+                    ),
+                ));
+            }
+        }
+    }
+
+    findings
+}
+
+fn check_fmp4(
+    prev: Option<&InspectedSegment>,
+    target: &InspectTarget,
+    summary: &FragmentSummary,
+    init: &InitSegment,
+    init_url: &str,
+    tolerance: f64,
+) -> Vec<(ErrorType, String)> {
+    let mut findings: Vec<(ErrorType, String)> = Vec::new();
+
+    if let Some((reference_id, timescale)) = summary.sidx {
+        if let Some(track) = init.tracks.get(&reference_id).filter(|t| t.timescale != timescale) {
+            findings.push((
+                ErrorType::InitSegmentMismatch,
+                format!(
+                    "sidx timescale {} differs from timescale {} of track {} in init segment '{}'",
+                    timescale, track.timescale, reference_id, init_url
+                ),
+            ));
+        }
+    }
+
+    let consecutive = prev.filter(|p| p.media_sequence + 1 == target.media_sequence && !target.discontinuity);
+    for (&track_id, run) in &summary.tracks {
+        let Some(track) = init.tracks.get(&track_id) else {
+            findings.push((
+                ErrorType::InitSegmentMismatch,
+                format!("track_ID {} is not declared in init segment '{}'", track_id, init_url),
+            ));
+            continue;
+        };
+        if track.timescale == 0 {
+            continue;
+        }
+        let timescale = track.timescale as f64;
+
+        if let Some(duration) = run.total_duration(track.default_sample_duration) {
+            let seconds = duration as f64 / timescale;
+            if (seconds - target.duration).abs() > tolerance {
+                findings.push((
+                    ErrorType::FragmentDurationMismatch,
+                    format!(
+                        "track {} samples last {:.3}s but EXTINF is {:.3}s",
+                        track_id, seconds, target.duration
+                    ),
+                ));
+            }
+        }
+
+        let Some(prev) = consecutive else {
+            continue;
+        };
+        if let (Some(start), Some(&(prev_end, prev_timescale))) =
+            (run.base_media_decode_time, prev.track_ends.get(&track_id))
+        {
+            if prev_timescale == 0 {
+                continue;
+            }
+            let off = start as f64 / timescale - prev_end as f64 / prev_timescale as f64;
+            if off.abs() > tolerance {
+                findings.push((
+                    ErrorType::TimestampDiscontinuity,
+                    format!(
+                        "track {} tfdt {} does not continue from the end of mseq({}) ({} of {:.3}s) without EXT-X-DISCONTINUITY",
+                        track_id,
+                        start,
+                        prev.media_sequence,
+                        if off > 0.0 { "gap" } else { "overlap" },
+                        off.abs()
+                    ),
+                ));
+            }
+        }
+    }
+
+    findings
+}
+
+fn to_errors(findings: Vec<(ErrorType, String)>, target: &InspectTarget, ctx: &CheckContext) -> Vec<MonitorError> {
+    findings
+        .into_iter()
+        .map(|(error_type, details)| {
+            MonitorError::new(
+                error_type,
+                &ctx.media_type,
+                &ctx.variant_key,
+                format!("Segment '{}': {}", target.url, details),
+                &ctx.stream_url,
+                &ctx.stream_id,
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::media::fmp4::testing as fmp4_testing;
+    use crate::media::ts::testing::*;
+
+    fn ctx() -> CheckContext {
+        CheckContext {
+            stream_url: "http://example.com/".to_string(),
+            stream_id: "stream_1".to_string(),
+            media_type: "VIDEO".to_string(),
+            variant_key: "1000000".to_string(),
+        }
+    }
+
+    fn target(mseq: u64, discontinuity: bool) -> InspectTarget {
+        InspectTarget {
+            url: format!("http://example.com/s{}.ts", mseq),
+            variant_key: "1000000".to_string(),
+            media_sequence: mseq,
+            duration: 2.0,
+            discontinuity,
+            init_url: None,
+        }
+    }
+
+    fn fmp4_target(mseq: u64, discontinuity: bool) -> InspectTarget {
+        InspectTarget {
+            url: format!("http://example.com/s{}.m4s", mseq),
+            init_url: Some("http://example.com/init.mp4".to_string()),
+            ..target(mseq, discontinuity)
+        }
+    }
+
+    fn fmp4_init() -> InitSegment {
+        fmp4::parse_init(&fmp4_testing::init(&[(1, 90_000, b"vide"), (2, 48_000, b"soun")])).unwrap()
+    }
+
+    /// 2s fragment: 50 video samples of 3600 ticks, 94 audio samples of 1024 ticks (~2.005s).
+    fn two_second_fragment(video_tfdt: u64, audio_tfdt: u64) -> Vec<u8> {
+        fmp4_testing::fragment(&[(1, video_tfdt, &[3600; 50]), (2, audio_tfdt, &[1024; 94])])
+    }
+
+    /// 2s segment: 50 frames of 3600 ticks (25 fps).
+    fn two_seconds(start: u64) -> Vec<u8> {
+        segment(start, 50, 3600, 0)
+    }
+
+    fn inspect(prev: Option<&InspectedSegment>, t: &InspectTarget, data: &[u8]) -> (Option<InspectedSegment>, Vec<MonitorError>) {
+        inspect_segment(prev, t, data, None, 0.1, &ctx())
+    }
+
+    fn inspect_fmp4(
+        prev: Option<&InspectedSegment>,
+        t: &InspectTarget,
+        data: &[u8],
+    ) -> (Option<InspectedSegment>, Vec<MonitorError>) {
+        inspect_segment(prev, t, data, Some(&fmp4_init()), 0.1, &ctx())
+    }
+
+    #[test]
+    fn consecutive_segments_pass() {
+        let (first, errors) = inspect(None, &target(10, false), &two_seconds(0));
+        assert!(errors.is_empty(), "{:?}", errors);
+        let first = first.unwrap();
+        assert_eq!(first.first_pts.get(&VIDEO_PID), Some(&0));
+
+        let (_, errors) = inspect(Some(&first), &target(11, false), &two_seconds(180_000));
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn detects_gap_and_overlap() {
+        let (first, _) = inspect(None, &target(10, false), &two_seconds(0));
+
+        let (_, errors) = inspect(first.as_ref(), &target(11, false), &two_seconds(270_000));
+        assert_eq!(errors.len(), 3, "{:?}", errors); // video, audio, PCR
+        assert!(errors.iter().all(|e| e.error_type == ErrorType::TimestampDiscontinuity));
+        assert!(errors[0].details.contains("gap of 1.000s"), "{}", errors[0].details);
+
+        let (_, errors) = inspect(first.as_ref(), &target(11, false), &two_seconds(90_000));
+        assert!(errors[0].details.contains("overlap of 1.000s"), "{}", errors[0].details);
+    }
+
+    #[test]
+    fn discontinuity_allows_timestamp_reset() {
+        let (first, _) = inspect(None, &target(10, false), &two_seconds(5_000_000));
+        let (_, errors) = inspect(first.as_ref(), &target(11, true), &two_seconds(0));
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn non_consecutive_segments_are_not_compared() {
+        let (first, _) = inspect(None, &target(10, false), &two_seconds(0));
+        let (_, errors) = inspect(first.as_ref(), &target(12, false), &two_seconds(900_000));
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn detects_missing_pid_and_continuity_errors() {
+        let (first, _) = inspect(None, &target(10, false), &two_seconds(0));
+
+        let mut data = pat();
+        data.extend(pmt(&[(VIDEO_PID, 0x1b)]));
+        data.extend(pes(VIDEO_PID, 0, 180_000, Some(180_000)));
+        data.extend(pes(VIDEO_PID, 3, 183_600, Some(183_600)));
+        let (_, errors) = inspect(first.as_ref(), &target(11, false), &data);
+
+        let types: Vec<_> = errors.iter().map(|e| e.error_type).collect();
+        assert!(types.contains(&ErrorType::ContinuityCounterError), "{:?}", errors);
+        assert!(types.contains(&ErrorType::MissingPid), "{:?}", errors);
+        assert!(errors.iter().any(|e| e.details.contains("PID 0x0101 present in mseq(10)")));
+    }
+
+    #[test]
+    fn non_ts_segment_is_skipped() {
+        let (record, errors) = inspect(None, &target(10, false), b"\x00\x00\x00\x18ftypiso6");
+        assert!(record.is_none());
+        assert!(errors.is_empty());
+    }
+
+    #[test]
+    fn consecutive_fragments_pass() {
+        let (first, errors) = inspect_fmp4(None, &fmp4_target(10, false), &two_second_fragment(0, 0));
+        assert!(errors.is_empty(), "{:?}", errors);
+        let first = first.unwrap();
+        assert_eq!(first.track_ends.get(&1), Some(&(180_000, 90_000)));
+        assert_eq!(first.track_ends.get(&2), Some(&(96_256, 48_000)));
+
+        let (_, errors) = inspect_fmp4(Some(&first), &fmp4_target(11, false), &two_second_fragment(180_000, 96_256));
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn detects_tfdt_gap() {
+        let (first, _) = inspect_fmp4(None, &fmp4_target(10, false), &two_second_fragment(0, 0));
+
+        let (_, errors) = inspect_fmp4(first.as_ref(), &fmp4_target(11, false), &two_second_fragment(270_000, 96_256));
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert_eq!(errors[0].error_type, ErrorType::TimestampDiscontinuity);
+        assert!(errors[0].details.contains("track 1 tfdt 270000"), "{}", errors[0].details);
+        assert!(errors[0].details.contains("gap of 1.000s"), "{}", errors[0].details);
+
+        let (_, errors) = inspect_fmp4(first.as_ref(), &fmp4_target(11, true), &two_second_fragment(0, 0));
+        assert!(errors.is_empty(), "{:?}", errors);
+    }
+
+    #[test]
+    fn detects_fragment_duration_mismatch() {
+        let data = fmp4_testing::fragment(&[(1, 0, &[3600; 75]), (2, 0, &[1024; 94])]);
+        let (_, errors) = inspect_fmp4(None, &fmp4_target(10, false), &data);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert_eq!(errors[0].error_type, ErrorType::FragmentDurationMismatch);
+        assert!(errors[0].details.contains("track 1 samples last 3.000s but EXTINF is 2.000s"));
+    }
+
+    #[test]
+    fn detects_init_segment_mismatch() {
+        let data = fmp4_testing::fragment(&[(1, 0, &[3600; 50]), (3, 0, &[1024; 94])]);
+        let (_, errors) = inspect_fmp4(None, &fmp4_target(10, false), &data);
+        assert_eq!(errors.len(), 1, "{:?}", errors);
+        assert_eq!(errors[0].error_type, ErrorType::InitSegmentMismatch);
+        assert!(errors[0].details.contains("track_ID 3 is not declared"), "{}", errors[0].details);
+
+        let (record, errors) = inspect(None, &target(10, false), &two_second_fragment(0, 0));
+        assert!(record.is_none());
+        assert_eq!(errors[0].error_type, ErrorType::InitSegmentMismatch);
+        assert!(errors[0].details.contains("no EXT-X-MAP"));
+    }
+}
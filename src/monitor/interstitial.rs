@@ -0,0 +1,186 @@
+//! Interstitial asset checks for `CLASS="com.apple.hls.interstitial"`.
+//!
+//! Each `X-ASSET-URI` playlist, and each `X-ASSET-LIST` together with the
+//! playlists of the assets it lists, must load and parse. A target is fetched
+//! again on every poll until it does.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use futures::stream::{self, StreamExt};
+use serde::Deserialize;
+
+use crate::config::MonitorConfig;
+use crate::loader::{LoadError, ManifestLoader};
+
+use super::error::{ErrorType, MonitorError};
+
+/// An interstitial asset to fetch, with the variant whose playlist schedules it.
+#[derive(Debug, Clone)]
+pub struct AssetTarget {
+    pub url: String,
+    /// `url` is an `X-ASSET-LIST` rather than an `X-ASSET-URI`.
+    pub list: bool,
+    /// `ID` of the DATERANGE.
+    pub id: String,
+    pub media_type: String,
+    pub variant_key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct AssetList {
+    #[serde(rename = "ASSETS")]
+    assets: Vec<Asset>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Asset {
+    #[serde(rename = "URI")]
+    uri: String,
+    #[serde(rename = "DURATION")]
+    duration: f64,
+}
+
+/// URIs of the assets in an `X-ASSET-LIST` JSON body.
+pub fn parse_asset_list(body: &str) -> Result<Vec<String>, String> {
+    let list: AssetList = serde_json::from_str(body).map_err(|e| format!("asset list is not valid JSON: {}", e))?;
+    if let Some(asset) = list.assets.iter().find(|a| !(a.duration.is_finite() && a.duration >= 0.0)) {
+        return Err(format!("asset '{}' has invalid DURATION {}", asset.uri, asset.duration));
+    }
+    Ok(list.assets.into_iter().map(|a| a.uri).collect())
+}
+
+/// Describe what is wrong with an asset playlist body, or `None` if it is usable.
+pub fn evaluate_playlist(body: &str) -> Option<String> {
+    match m3u8_rs::parse_playlist_res(body.as_bytes()) {
+        Err(_) => Some("asset playlist could not be parsed".to_string()),
+        Ok(m3u8_rs::Playlist::MediaPlaylist(pl)) if pl.segments.is_empty() => {
+            Some("asset playlist has no segments".to_string())
+        }
+        Ok(_) => None,
+    }
+}
+
+/// Resolve an asset URI found in an asset list against the list's URL.
+fn resolve(list_url: &str, uri: &str) -> String {
+    url::Url::parse(list_url)
+        .and_then(|base| base.join(uri))
+        .map(|u| u.to_string())
+        .unwrap_or_else(|_| uri.to_string())
+}
+
+/// Problems with one target: the error type, the URL at fault, what went
+/// wrong and the HTTP status if any.
+async fn check_target(
+    loader: &dyn ManifestLoader,
+    target: &AssetTarget,
+) -> Vec<(ErrorType, String, String, Option<u16>)> {
+    let unavailable = |url: &str, e: LoadError| {
+        let status = e.status_code();
+        (ErrorType::InterstitialAssetUnavailable, url.to_string(), format!("request failed: {}", e), status)
+    };
+
+    let playlists = if target.list {
+        let body = match loader.load(&target.url).await {
+            Ok(resp) => resp.body,
+            Err(e) => return vec![unavailable(&target.url, e)],
+        };
+        match parse_asset_list(&body) {
+            Ok(uris) => uris.iter().map(|uri| resolve(&target.url, uri)).collect(),
+            Err(problem) => return vec![(ErrorType::InvalidInterstitialAsset, target.url.clone(), problem, None)],
+        }
+    } else {
+        vec![target.url.clone()]
+    };
+
+    let mut problems = Vec::new();
+    for url in playlists {
+        match loader.load(&url).await {
+            Ok(resp) => {
+                if let Some(problem) = evaluate_playlist(&resp.body) {
+                    problems.push((ErrorType::InvalidInterstitialAsset, url, problem, None));
+                }
+            }
+            Err(e) => problems.push(unavailable(&url, e)),
+        }
+    }
+    problems
+}
+
+/// Fetch `targets` with at most `max_concurrent_fetches` requests in flight.
+///
+/// Returns the URLs of the targets that passed, and errors for the others.
+pub async fn fetch_assets(
+    loader: &Arc<dyn ManifestLoader>,
+    targets: Vec<AssetTarget>,
+    config: &MonitorConfig,
+    stream_url: &str,
+    stream_id: &str,
+) -> (HashSet<String>, Vec<MonitorError>) {
+    let results: Vec<_> = stream::iter(targets)
+        .map(|target| {
+            let loader = Arc::clone(loader);
+            async move {
+                let problems = check_target(loader.as_ref(), &target).await;
+                (target, problems)
+            }
+        })
+        .buffer_unordered(config.max_concurrent_fetches.max(1))
+        .collect()
+        .await;
+
+    let passed = results
+        .iter()
+        .filter(|(_, problems)| problems.is_empty())
+        .map(|(target, _)| target.url.clone())
+        .collect();
+    let errors = results
+        .into_iter()
+        .flat_map(|(target, problems)| {
+            problems.into_iter().map(move |(error_type, url, problem, status)| {
+                let error = MonitorError::new(
+                    error_type,
+                    &target.media_type,
+                    &target.variant_key,
+                    format!("Interstitial '{}' asset '{}': {}", target.id, url, problem),
+                    stream_url,
+                    stream_id,
+                );
+                match status {
+                    Some(status) => error.with_status_code(status),
+                    None => error,
+                }
+            })
+        })
+        .collect();
+    (passed, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_asset_lists() {
+        let body = r#"{"ASSETS":[{"URI":"ad1.m3u8","DURATION":15.0},{"URI":"https://cdn/ad2.m3u8","DURATION":15}]}"#;
+        assert_eq!(parse_asset_list(body).unwrap(), vec!["ad1.m3u8", "https://cdn/ad2.m3u8"]);
+
+        assert!(parse_asset_list("<html>").unwrap_err().starts_with("asset list is not valid JSON"));
+        assert!(parse_asset_list(r#"{"ASSETS":[{"URI":"a.m3u8"}]}"#).is_err());
+        assert_eq!(
+            parse_asset_list(r#"{"ASSETS":[{"URI":"a.m3u8","DURATION":-1}]}"#).unwrap_err(),
+            "asset 'a.m3u8' has invalid DURATION -1"
+        );
+    }
+
+    #[test]
+    fn evaluates_asset_playlists() {
+        assert_eq!(evaluate_playlist("#EXTM3U\n#EXT-X-TARGETDURATION:6\n#EXTINF:6,\na.ts\n#EXT-X-ENDLIST\n"), None);
+        assert_eq!(
+            evaluate_playlist("#EXTM3U\n#EXT-X-TARGETDURATION:6\n#EXT-X-ENDLIST\n").as_deref(),
+            Some("asset playlist has no segments")
+        );
+        assert_eq!(evaluate_playlist("not a playlist").as_deref(), Some("asset playlist could not be parsed"));
+        assert_eq!(resolve("https://ads.example.com/list/a.json", "../ad1.m3u8"), "https://ads.example.com/ad1.m3u8");
+    }
+}
@@ -0,0 +1,138 @@
+//! Key server checks for `EXT-X-KEY:METHOD=AES-128`.
+//!
+//! Each key URL is fetched with the configured `key_headers` until it returns
+//! exactly 16 bytes. Keys given inline as base64 `data:` URIs are
+//! decoded instead of fetched.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use base64::Engine;
+use futures::stream::{self, StreamExt};
+
+use crate::config::MonitorConfig;
+use crate::loader::{LoadError, ManifestLoader};
+
+use super::error::{ErrorType, MonitorError};
+
+/// Length of an AES-128 key in bytes.
+pub const AES_128_KEY_LEN: usize = 16;
+
+/// A key to fetch, with the variant whose playlist references it.
+#[derive(Debug, Clone)]
+pub struct KeyTarget {
+    pub url: String,
+    pub media_type: String,
+    pub variant_key: String,
+}
+
+/// Payload of a base64 `data:` URI.
+fn decode_data_uri(uri: &str) -> Option<Result<Vec<u8>, String>> {
+    let (header, data) = uri.strip_prefix("data:")?.split_once(',')?;
+    if !header.ends_with(";base64") {
+        return Some(Ok(data.as_bytes().to_vec()));
+    }
+    Some(
+        base64::engine::general_purpose::STANDARD
+            .decode(data)
+            .map_err(|e| format!("invalid base64 in data: URI: {}", e)),
+    )
+}
+
+/// Describe what is wrong with a fetched key, or `None` if it is usable.
+pub fn evaluate(result: &Result<Vec<u8>, LoadError>) -> Option<(ErrorType, String)> {
+    match result {
+        Err(e) => Some((ErrorType::KeyUnavailable, format!("key request failed: {}", e))),
+        Ok(key) if key.len() != AES_128_KEY_LEN => Some((
+            ErrorType::InvalidKey,
+            format!("AES-128 key is {} bytes, expected {}", key.len(), AES_128_KEY_LEN),
+        )),
+        Ok(_) => None,
+    }
+}
+
+/// Fetch `targets` with at most `max_concurrent_fetches` requests in flight.
+///
+/// Returns the URLs of the keys that passed, and errors for the others.
+pub async fn fetch_keys(
+    loader: &Arc<dyn ManifestLoader>,
+    targets: Vec<KeyTarget>,
+    config: &MonitorConfig,
+    stream_url: &str,
+    stream_id: &str,
+) -> (HashSet<String>, Vec<MonitorError>) {
+    let results: Vec<_> = stream::iter(targets)
+        .map(|target| {
+            let loader = Arc::clone(loader);
+            async move {
+                let result = match decode_data_uri(&target.url) {
+                    Some(decoded) => decoded.map_err(|message| LoadError::Parse {
+                        url: target.url.clone(),
+                        message,
+                    }),
+                    None => loader.fetch_key(&target.url, &config.key_headers).await,
+                };
+                (target, result)
+            }
+        })
+        .buffer_unordered(config.max_concurrent_fetches.max(1))
+        .collect()
+        .await;
+
+    let mut passed = HashSet::new();
+    let errors = results
+        .into_iter()
+        .filter_map(|(target, result)| {
+            let Some((error_type, problem)) = evaluate(&result) else {
+                passed.insert(target.url);
+                return None;
+            };
+            let error = MonitorError::new(
+                error_type,
+                &target.media_type,
+                &target.variant_key,
+                format!("Key '{}': {}", target.url, problem),
+                stream_url,
+                stream_id,
+            );
+            Some(match result.as_ref().err().and_then(LoadError::status_code) {
+                Some(status) => error.with_status_code(status),
+                None => error,
+            })
+        })
+        .collect();
+    (passed, errors)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn evaluates_key_length() {
+        assert_eq!(evaluate(&Ok(vec![0u8; 16])), None);
+        let (error_type, details) = evaluate(&Ok(vec![0u8; 32])).unwrap();
+        assert_eq!(error_type, ErrorType::InvalidKey);
+        assert!(details.contains("32 bytes"));
+
+        let err = Err(LoadError::Http {
+            url: "key.bin".into(),
+            status: 403,
+            message: "Forbidden".into(),
+            is_last_retry: true,
+        });
+        let (error_type, details) = evaluate(&err).unwrap();
+        assert_eq!(error_type, ErrorType::KeyUnavailable);
+        assert!(details.contains("403"));
+    }
+
+    #[test]
+    fn decodes_data_uris() {
+        assert_eq!(decode_data_uri("https://example.com/key"), None);
+        assert_eq!(
+            decode_data_uri("data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAAAAAA=="),
+            Some(Ok(vec![0u8; 16]))
+        );
+        assert!(decode_data_uri("data:;base64,@@@").unwrap().is_err());
+    }
+}
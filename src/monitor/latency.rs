@@ -0,0 +1,143 @@
+//! Live-edge latency.
+//!
+//! The live edge is the end of the newest segment on the wall clock: the
+//! last `EXT-X-PROGRAM-DATE-TIME` in the playlist plus the `EXTINF`
+//! durations from that segment on. Its latency is how far it lies behind the
+//! time the playlist was fetched.
+
+use chrono::{DateTime, FixedOffset, Utc};
+
+use super::error::ErrorType;
+use super::state::PlaylistSnapshot;
+
+/// Seconds the live edge may lie ahead of the fetch time before its PDT is
+/// reported as in the future, to absorb small clock differences.
+pub const FUTURE_PDT_TOLERANCE: f64 = 1.0;
+
+/// Seconds between the live edge and `fetched_at`; negative when the live
+/// edge is in the future. `None` for playlists without a PDT and for ended
+/// playlists, which have no live edge.
+pub fn measure(snapshot: &PlaylistSnapshot, fetched_at: DateTime<Utc>) -> Option<f64> {
+    if snapshot.has_endlist {
+        return None;
+    }
+    let edge = live_edge(snapshot)?;
+    Some((fetched_at - edge.with_timezone(&Utc)).num_milliseconds() as f64 / 1000.0)
+}
+
+/// End of the newest segment by its PDT, or `None` without a PDT.
+pub fn live_edge(snapshot: &PlaylistSnapshot) -> Option<DateTime<FixedOffset>> {
+    let last = snapshot.segments.iter().rposition(|s| s.program_date_time.is_some())?;
+    let pdt = snapshot.segments[last].program_date_time?;
+    let after: f64 = snapshot.segments[last..].iter().map(|s| s.duration).sum();
+    Some(pdt + chrono::Duration::milliseconds((after * 1000.0).round() as i64))
+}
+
+/// Describe what is wrong with a measured latency, or `None` if it is fine.
+pub fn evaluate(latency: f64, max_latency: Option<f64>) -> Option<(ErrorType, String)> {
+    if latency < -FUTURE_PDT_TOLERANCE {
+        return Some((
+            ErrorType::FuturePdt,
+            format!(
+                "Live edge PDT is {:.3}s ahead of the wall clock at fetch; encoder clock is skewed",
+                -latency
+            ),
+        ));
+    }
+    let max = max_latency.filter(|max| latency > *max)?;
+    Some((
+        ErrorType::LiveEdgeLatency,
+        format!("Live edge is {:.3}s behind the wall clock, over the {:.3}s limit", latency, max),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monitor::state::SegmentSnapshot;
+
+    fn seg(uri: &str, pdt: Option<&str>) -> SegmentSnapshot {
+        SegmentSnapshot {
+            uri: uri.to_string(),
+            duration: 6.0,
+            discontinuity: false,
+            cue_out: false,
+            cue_in: false,
+            cue_out_cont: None,
+            gap: false,
+            program_date_time: pdt.map(|t| DateTime::parse_from_rfc3339(t).unwrap()),
+            daterange: None,
+            parts: vec![],
+            byte_range_length: None,
+            map: None,
+            scte35: vec![],
+            cue_out_duration: None,
+            ad_markers: vec![],
+        }
+    }
+
+    fn snap(segments: Vec<SegmentSnapshot>, has_endlist: bool) -> PlaylistSnapshot {
+        PlaylistSnapshot {
+            media_sequence: 0,
+            discontinuity_sequence: 0,
+            segments,
+            duration: 0.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            has_cue_out: false,
+            cue_out_duration: None,
+            target_duration: 6.0,
+            playlist_type: None,
+            version: Some(3),
+            has_gaps: false,
+            has_endlist,
+            i_frames_only: false,
+            has_byte_range: false,
+            has_map: false,
+            has_key_iv: false,
+            has_key_format: false,
+            keys: vec![],
+            part_target: None,
+            pending_parts: vec![],
+            preload_hints: vec![],
+            server_control: None,
+            skipped_segments: None,
+            rendition_reports: vec![],
+            key_uri: None,
+        }
+    }
+
+    fn at(t: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(t).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn measures_from_last_pdt_plus_following_durations() {
+        let pl = snap(
+            vec![seg("a.ts", Some("2024-01-01T00:00:00Z")), seg("b.ts", None), seg("c.ts", None)],
+            false,
+        );
+        // Live edge at 00:00:18.
+        assert_eq!(measure(&pl, at("2024-01-01T00:00:25.500Z")), Some(7.5));
+        assert_eq!(measure(&pl, at("2024-01-01T00:00:15Z")), Some(-3.0));
+
+        assert_eq!(measure(&snap(vec![seg("a.ts", None)], false), at("2024-01-01T00:00:25Z")), None);
+        let ended = snap(vec![seg("a.ts", Some("2024-01-01T00:00:00Z"))], true);
+        assert_eq!(measure(&ended, at("2024-01-01T00:00:25Z")), None);
+    }
+
+    #[test]
+    fn evaluates_latency_bounds() {
+        assert_eq!(evaluate(7.5, None), None);
+        assert_eq!(evaluate(7.5, Some(10.0)), None);
+        assert_eq!(evaluate(-0.5, None), None);
+
+        let (error_type, details) = evaluate(12.0, Some(10.0)).unwrap();
+        assert_eq!(error_type, ErrorType::LiveEdgeLatency);
+        assert!(details.contains("12.000s behind"), "{}", details);
+
+        let (error_type, details) = evaluate(-3.0, Some(10.0)).unwrap();
+        assert_eq!(error_type, ErrorType::FuturePdt);
+        assert!(details.contains("3.000s ahead"), "{}", details);
+    }
+}
@@ -0,0 +1,298 @@
+//! Master playlist changes between polls.
+//!
+//! Each master is reduced to a fingerprint: one line of attributes per
+//! variant and rendition playlist, by variant key, plus the master-level
+//! tags. Comparing it with the previous poll's fingerprint gives the
+//! variants that were added, removed or changed. A variant that is added or
+//! removed again within a few polls of its last change is flapping.
+
+use std::collections::{BTreeMap, HashMap};
+
+use m3u8_rs::{AlternativeMedia, ClosedCaptionGroupId, MasterPlaylist, VariantStream};
+
+use super::identity::VariantKeys;
+
+/// A variant added or removed again within this many polls is flapping.
+pub const FLAP_WINDOW_POLLS: u64 = 5;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MasterEntry {
+    pub media_type: String,
+    /// URI and attributes of the `EXT-X-STREAM-INF`, `EXT-X-I-FRAME-STREAM-INF`
+    /// or `EXT-X-MEDIA` tag.
+    pub description: String,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MasterFingerprint {
+    /// Variant and rendition playlists by variant key.
+    pub entries: BTreeMap<String, MasterEntry>,
+    /// Master-level tags and renditions without a playlist of their own.
+    pub header: String,
+}
+
+fn push_attr<T: std::fmt::Display>(out: &mut Vec<String>, name: &str, value: Option<T>) {
+    if let Some(value) = value {
+        out.push(format!("{}={}", name, value));
+    }
+}
+
+fn describe_variant(v: &VariantStream) -> String {
+    let mut attrs = vec![format!("BANDWIDTH={}", v.bandwidth)];
+    push_attr(&mut attrs, "AVERAGE-BANDWIDTH", v.average_bandwidth);
+    push_attr(&mut attrs, "CODECS", v.codecs.as_ref());
+    push_attr(&mut attrs, "RESOLUTION", v.resolution.map(|r| format!("{}x{}", r.width, r.height)));
+    push_attr(&mut attrs, "FRAME-RATE", v.frame_rate);
+    push_attr(&mut attrs, "AUDIO", v.audio.as_ref());
+    push_attr(&mut attrs, "VIDEO", v.video.as_ref());
+    push_attr(&mut attrs, "SUBTITLES", v.subtitles.as_ref());
+    push_attr(
+        &mut attrs,
+        "CLOSED-CAPTIONS",
+        v.closed_captions.as_ref().map(|cc| match cc {
+            ClosedCaptionGroupId::None => "NONE",
+            ClosedCaptionGroupId::GroupId(id) | ClosedCaptionGroupId::Other(id) => id.as_str(),
+        }),
+    );
+    format!("{} {}", v.uri, attrs.join(","))
+}
+
+fn describe_media(m: &AlternativeMedia) -> String {
+    let mut attrs = vec![
+        format!("TYPE={}", m.media_type),
+        format!("GROUP-ID={}", m.group_id),
+        format!("NAME={}", m.name),
+    ];
+    push_attr(&mut attrs, "LANGUAGE", m.language.as_ref());
+    push_attr(&mut attrs, "ASSOC-LANGUAGE", m.assoc_language.as_ref());
+    push_attr(&mut attrs, "DEFAULT", m.default.then_some("YES"));
+    push_attr(&mut attrs, "AUTOSELECT", m.autoselect.then_some("YES"));
+    push_attr(&mut attrs, "FORCED", m.forced.then_some("YES"));
+    push_attr(&mut attrs, "CHARACTERISTICS", m.characteristics.as_ref());
+    push_attr(&mut attrs, "CHANNELS", m.channels.as_ref());
+    format!("{} {}", m.uri.as_deref().unwrap_or_default(), attrs.join(","))
+}
+
+impl MasterFingerprint {
+    pub fn of(master: &MasterPlaylist) -> Self {
+        let keys = VariantKeys::of(master);
+        let mut entries = BTreeMap::new();
+        for (v, key) in master.variants.iter().zip(keys.variants) {
+            let media_type = if v.is_i_frame { "I-FRAME" } else { "VIDEO" };
+            entries.insert(
+                key,
+                MasterEntry {
+                    media_type: media_type.to_string(),
+                    description: describe_variant(v),
+                },
+            );
+        }
+        let mut header = vec![
+            format!("VERSION={}", master.version.unwrap_or(1)),
+            format!("INDEPENDENT-SEGMENTS={}", master.independent_segments),
+            format!("SESSION-KEYS={}", master.session_key.len()),
+            format!("SESSION-DATA={}", master.session_data.len()),
+        ];
+        for (m, key) in master.alternatives.iter().zip(keys.media) {
+            if let Some(key) = key {
+                entries.insert(
+                    key,
+                    MasterEntry {
+                        media_type: m.media_type.to_string(),
+                        description: describe_media(m),
+                    },
+                );
+            } else {
+                header.push(format!("EXT-X-MEDIA:{}", describe_media(m).trim_start()));
+            }
+        }
+        Self {
+            entries,
+            header: header.join(" "),
+        }
+    }
+}
+
+/// A variant that reversed its last addition or removal within
+/// [`FLAP_WINDOW_POLLS`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Flap {
+    pub variant_key: String,
+    pub added: bool,
+    pub polls_since: u64,
+}
+
+impl Flap {
+    pub fn describe(&self) -> String {
+        let (now, before) = if self.added {
+            ("reappeared in", "removed")
+        } else {
+            ("disappeared from", "added")
+        };
+        format!(
+            "Variant '{}' {} the master playlist {} poll(s) after it was {}",
+            self.variant_key, now, self.polls_since, before
+        )
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MasterDiff {
+    pub added: Vec<(String, MasterEntry)>,
+    pub removed: Vec<(String, MasterEntry)>,
+    /// Key, previous and current description.
+    pub changed: Vec<(String, String, String)>,
+    /// Previous and current master-level tags.
+    pub header: Option<(String, String)>,
+    pub flapping: Vec<Flap>,
+}
+
+impl MasterDiff {
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if !self.added.is_empty() {
+            let keys: Vec<String> = self.added.iter().map(|(k, _)| format!("'{}'", k)).collect();
+            parts.push(format!("added {}", keys.join(", ")));
+        }
+        if !self.removed.is_empty() {
+            let keys: Vec<String> = self.removed.iter().map(|(k, _)| format!("'{}'", k)).collect();
+            parts.push(format!("removed {}", keys.join(", ")));
+        }
+        for (key, before, after) in &self.changed {
+            parts.push(format!("changed '{}' from [{}] to [{}]", key, before, after));
+        }
+        if let Some((before, after)) = &self.header {
+            parts.push(format!("master tags changed from [{}] to [{}]", before, after));
+        }
+        format!("Master playlist changed: {}", parts.join("; "))
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct MasterHistory {
+    pub fingerprint: Option<MasterFingerprint>,
+    /// Master playlists compared so far.
+    polls: u64,
+    /// Poll at which each variant key was last added or removed.
+    last_change: HashMap<String, u64>,
+}
+
+impl MasterHistory {
+    /// Store `next` and return how it differs from the previous master, or
+    /// `None` for the first master and for an unchanged one.
+    pub fn update(&mut self, next: MasterFingerprint) -> Option<MasterDiff> {
+        self.polls += 1;
+        let prev = self.fingerprint.replace(next);
+        let (prev, next) = (prev?, self.fingerprint.as_ref()?);
+        if prev == *next {
+            return None;
+        }
+
+        let mut diff = MasterDiff::default();
+        for (key, entry) in &next.entries {
+            match prev.entries.get(key) {
+                None => diff.added.push((key.clone(), entry.clone())),
+                Some(before) if before != entry => {
+                    diff.changed.push((key.clone(), before.description.clone(), entry.description.clone()))
+                }
+                Some(_) => {}
+            }
+        }
+        for (key, entry) in &prev.entries {
+            if !next.entries.contains_key(key) {
+                diff.removed.push((key.clone(), entry.clone()));
+            }
+        }
+        if prev.header != next.header {
+            diff.header = Some((prev.header.clone(), next.header.clone()));
+        }
+
+        let toggled = diff.added.iter().map(|(k, _)| (k, true)).chain(diff.removed.iter().map(|(k, _)| (k, false)));
+        for (key, added) in toggled {
+            if let Some(at) = self.last_change.insert(key.clone(), self.polls) {
+                let polls_since = self.polls - at;
+                if polls_since <= FLAP_WINDOW_POLLS {
+                    diff.flapping.push(Flap {
+                        variant_key: key.clone(),
+                        added,
+                        polls_since,
+                    });
+                }
+            }
+        }
+        let polls = self.polls;
+        self.last_change.retain(|_, at| polls - *at <= FLAP_WINDOW_POLLS);
+
+        Some(diff)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn master(variants: &[(u64, &str)]) -> MasterFingerprint {
+        let mut text = "#EXTM3U\n".to_string();
+        for (bandwidth, codecs) in variants {
+            text.push_str(&format!(
+                "#EXT-X-STREAM-INF:BANDWIDTH={},CODECS=\"{}\"\nv{}.m3u8\n",
+                bandwidth, codecs, bandwidth
+            ));
+        }
+        match m3u8_rs::parse_playlist_res(text.as_bytes()).unwrap() {
+            m3u8_rs::Playlist::MasterPlaylist(m) => MasterFingerprint::of(&m),
+            _ => panic!("expected master playlist"),
+        }
+    }
+
+    #[test]
+    fn first_and_unchanged_masters_have_no_diff() {
+        let mut history = MasterHistory::default();
+        assert_eq!(history.update(master(&[(1000, "avc1.64001f")])), None);
+        assert_eq!(history.update(master(&[(1000, "avc1.64001f")])), None);
+    }
+
+    #[test]
+    fn diffs_added_removed_and_changed_variants() {
+        let mut history = MasterHistory::default();
+        history.update(master(&[(1000, "avc1.64001f"), (2000, "avc1.64001f")]));
+        let diff = history.update(master(&[(2000, "avc1.640028"), (3000, "avc1.64001f")])).unwrap();
+        assert_eq!(diff.added.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>(), ["3000_avc1_v3000"]);
+        assert_eq!(diff.removed.iter().map(|(k, _)| k.as_str()).collect::<Vec<_>>(), ["1000_avc1_v1000"]);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(
+            diff.describe(),
+            "Master playlist changed: added '3000_avc1_v3000'; removed '1000_avc1_v1000'; changed '2000_avc1_v2000' from \
+             [v2000.m3u8 BANDWIDTH=2000,CODECS=avc1.64001f] to [v2000.m3u8 BANDWIDTH=2000,CODECS=avc1.640028]"
+        );
+        assert!(diff.flapping.is_empty());
+    }
+
+    #[test]
+    fn flags_variants_that_come_back_quickly() {
+        let mut history = MasterHistory::default();
+        let full = || master(&[(1000, "avc1.64001f"), (2000, "avc1.64001f")]);
+        let short = || master(&[(1000, "avc1.64001f")]);
+        history.update(full());
+        assert!(history.update(short()).unwrap().flapping.is_empty(), "first removal");
+        let diff = history.update(full()).unwrap();
+        assert_eq!(
+            diff.flapping,
+            [Flap {
+                variant_key: "2000_avc1_v2000".to_string(),
+                added: true,
+                polls_since: 1
+            }]
+        );
+        assert_eq!(
+            diff.flapping[0].describe(),
+            "Variant '2000_avc1_v2000' reappeared in the master playlist 1 poll(s) after it was removed"
+        );
+
+        // A change long after the last one is not a flap.
+        for _ in 0..FLAP_WINDOW_POLLS {
+            history.update(full());
+        }
+        assert!(history.update(short()).unwrap().flapping.is_empty());
+    }
+}
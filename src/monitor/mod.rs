@@ -1,8 +1,23 @@
+pub mod ad_break;
+pub mod ad_markers;
+pub mod bitrate;
+pub mod blocking_reload;
 pub mod checks;
+pub mod clock_drift;
+pub mod daterange;
+pub mod delta;
 pub mod engine;
 pub mod error;
 pub mod event;
+pub mod identity;
+pub mod inspect;
+pub mod interstitial;
+pub mod keys;
+pub mod latency;
+pub mod master_diff;
+pub mod probe;
 pub mod state;
+pub mod tags;
 
 pub use engine::Monitor;
 pub use error::{ErrorRing, ErrorType, MonitorError};
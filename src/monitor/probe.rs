@@ -0,0 +1,233 @@
+//! Segment availability probing.
+//!
+//! A playlist can keep advertising segments the origin or CDN cannot serve.
+//! When enabled, newly appeared segment URIs are checked with a HEAD or
+//! single-byte ranged GET request after each poll, along with the `EXT-X-MAP`
+//! init segment whenever a variant starts using a new one.
+
+use std::ops::Range;
+use std::sync::Arc;
+
+use futures::stream::{self, StreamExt};
+use rand::Rng;
+
+use crate::config::{MonitorConfig, ProbeMethod};
+use crate::loader::{LoadError, ManifestLoader, ProbeResponse};
+
+use super::error::{ErrorType, MonitorError};
+use super::state::{PlaylistSnapshot, SegmentSnapshot};
+
+/// A segment selected for probing, with the variant it was published in.
+#[derive(Debug, Clone)]
+pub struct ProbeTarget {
+    pub url: String,
+    pub media_type: String,
+    pub variant_key: String,
+    /// Whether the target is an `EXT-X-MAP` init segment.
+    pub init_segment: bool,
+}
+
+/// [New segments](PlaylistSnapshot::segments_after) of `curr`, skipping GAP
+/// segments since they are declared unavailable.
+pub fn new_segments(prev_window: Option<Range<u64>>, curr: &PlaylistSnapshot) -> Vec<&SegmentSnapshot> {
+    curr.segments_after(prev_window)
+        .map(|(_, s)| s)
+        .filter(|s| !s.gap)
+        .collect()
+}
+
+/// Pick the segment URIs of `curr` to probe on this poll.
+///
+/// With `probe_fraction` set each [new segment](new_segments) is sampled at
+/// that rate, otherwise the newest `probe_newest` are taken.
+pub fn select_new_segments<'a>(
+    prev_window: Option<Range<u64>>,
+    curr: &'a PlaylistSnapshot,
+    config: &MonitorConfig,
+    rng: &mut impl Rng,
+) -> Vec<&'a str> {
+    let new: Vec<&str> = new_segments(prev_window, curr).into_iter().map(|s| s.uri.as_str()).collect();
+
+    match config.probe_fraction {
+        Some(fraction) => new.into_iter().filter(|_| rng.gen_bool(fraction)).collect(),
+        None => {
+            let skip = new.len().saturating_sub(config.probe_newest);
+            new[skip..].to_vec()
+        }
+    }
+}
+
+/// Describe what is wrong with a probe result, or `None` if the segment is available.
+pub fn evaluate(method: ProbeMethod, result: &Result<ProbeResponse, LoadError>) -> Option<String> {
+    let resp = match result {
+        Ok(resp) => resp,
+        Err(e) => return Some(format!("{} probe failed: {}", method, e)),
+    };
+    let status_ok = match method {
+        ProbeMethod::Head => resp.status == 200,
+        ProbeMethod::RangedGet => resp.status == 200 || resp.status == 206,
+    };
+    if !status_ok {
+        return Some(format!("{} probe returned HTTP {}", method, resp.status));
+    }
+    if resp.content_length == Some(0) {
+        return Some(format!("{} probe reported zero length", method));
+    }
+    None
+}
+
+/// Probe `targets` with at most `max_concurrent_fetches` requests in flight.
+pub async fn run_probes(
+    loader: &Arc<dyn ManifestLoader>,
+    targets: Vec<ProbeTarget>,
+    config: &MonitorConfig,
+    stream_url: &str,
+    stream_id: &str,
+) -> Vec<MonitorError> {
+    let method = config.probe_method;
+    let results: Vec<_> = stream::iter(targets)
+        .map(|target| {
+            let loader = Arc::clone(loader);
+            async move {
+                let result = loader.probe(&target.url, method).await;
+                (target, result)
+            }
+        })
+        .buffer_unordered(config.max_concurrent_fetches.max(1))
+        .collect()
+        .await;
+
+    results
+        .into_iter()
+        .filter_map(|(target, result)| {
+            let problem = evaluate(method, &result)?;
+            let (error_type, label) = if target.init_segment {
+                (ErrorType::InitSegmentUnavailable, "Init segment")
+            } else {
+                (ErrorType::SegmentUnavailable, "Segment")
+            };
+            let error = MonitorError::new(
+                error_type,
+                &target.media_type,
+                &target.variant_key,
+                format!("{} '{}': {}", label, target.url, problem),
+                stream_url,
+                stream_id,
+            );
+            Some(match result {
+                Ok(ProbeResponse { status, .. }) | Err(LoadError::Http { status, .. }) => {
+                    error.with_status_code(status)
+                }
+                Err(_) => error,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::mock::StepRng;
+
+    fn segment(uri: &str, gap: bool) -> SegmentSnapshot {
+        SegmentSnapshot {
+            uri: uri.to_string(),
+            duration: 2.0,
+            discontinuity: false,
+            cue_out: false,
+            cue_in: false,
+            cue_out_cont: None,
+            gap,
+            program_date_time: None,
+            daterange: None,
+            parts: vec![],
+            byte_range_length: None,
+            map: None,
+            scte35: vec![],
+            cue_out_duration: None,
+            ad_markers: vec![],
+        }
+    }
+
+    fn make_snap(segments: Vec<SegmentSnapshot>) -> PlaylistSnapshot {
+        PlaylistSnapshot {
+            media_sequence: 0,
+            discontinuity_sequence: 0,
+            segments,
+            duration: 0.0,
+            cue_out_count: 0,
+            cue_in_count: 0,
+            has_cue_out: false,
+            cue_out_duration: None,
+            target_duration: 2.0,
+            playlist_type: None,
+            version: None,
+            has_gaps: false,
+            has_endlist: false,
+            i_frames_only: false,
+            has_byte_range: false,
+            has_map: false,
+            has_key_iv: false,
+            has_key_format: false,
+            keys: vec![],
+            part_target: None,
+            pending_parts: vec![],
+            preload_hints: vec![],
+            server_control: None,
+            skipped_segments: None,
+            rendition_reports: vec![],
+            key_uri: None,
+        }
+    }
+
+    #[test]
+    fn selects_newest_new_segments() {
+        let snap = make_snap(vec![
+            segment("s1.ts", false),
+            segment("s2.ts", false),
+            segment("s3.ts", false),
+            segment("s4.ts", false),
+        ]);
+        let config = MonitorConfig::default().with_probe_newest(1);
+        let mut rng = StepRng::new(0, 1);
+        assert_eq!(select_new_segments(Some(0..2), &snap, &config, &mut rng), ["s4.ts"]);
+
+        let config = MonitorConfig::default().with_probe_newest(5);
+        assert_eq!(select_new_segments(Some(0..2), &snap, &config, &mut rng), ["s3.ts", "s4.ts"]);
+    }
+
+    #[test]
+    fn new_segments_follow_media_sequence() {
+        let mut snap = make_snap(vec![segment("main.ts", false), segment("main.ts", false)]);
+        snap.media_sequence = 1;
+        let new = new_segments(Some(0..2), &snap);
+        assert_eq!(new.len(), 1);
+        assert!(std::ptr::eq(new[0], &snap.segments[1]));
+
+        // A media sequence that went back starts over.
+        assert_eq!(new_segments(Some(5..7), &snap).len(), 2);
+    }
+
+    #[test]
+    fn skips_gap_segments_and_samples_fraction() {
+        let snap = make_snap(vec![segment("s1.ts", false), segment("s2.ts", true), segment("s3.ts", false)]);
+        let config = MonitorConfig::default().with_probe_fraction(1.0);
+        let mut rng = StepRng::new(0, 1);
+        assert_eq!(select_new_segments(None, &snap, &config, &mut rng), ["s1.ts", "s3.ts"]);
+    }
+
+    #[test]
+    fn evaluates_probe_responses() {
+        let ok = |status, len| Ok(ProbeResponse { status, content_length: len });
+        assert_eq!(evaluate(ProbeMethod::Head, &ok(200, Some(1000))), None);
+        assert_eq!(evaluate(ProbeMethod::RangedGet, &ok(206, Some(1000))), None);
+        assert_eq!(evaluate(ProbeMethod::Head, &ok(200, None)), None);
+
+        assert!(evaluate(ProbeMethod::Head, &ok(206, Some(1000))).unwrap().contains("HTTP 206"));
+        assert!(evaluate(ProbeMethod::Head, &ok(404, None)).unwrap().contains("HTTP 404"));
+        assert!(evaluate(ProbeMethod::RangedGet, &ok(206, Some(0))).unwrap().contains("zero length"));
+
+        let err = Err(LoadError::Timeout { url: "s1.ts".into(), is_last_retry: true });
+        assert!(evaluate(ProbeMethod::Head, &err).unwrap().contains("probe failed"));
+    }
+}
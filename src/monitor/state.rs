@@ -1,10 +1,22 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::ops::Range;
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 
+use crate::media::fmp4::InitSegment;
+use crate::media::scte35::{Scte35Error, SpliceInfo};
+
+use super::ad_break::AdBreak;
+use super::ad_markers::{AdMarker, AdSignal};
+use super::clock_drift::ClockDrift;
+use super::daterange::TrackedRange;
+use super::master_diff::MasterHistory;
+use super::bitrate::BitrateSample;
 use super::error::ErrorRing;
 use super::event::EventRing;
+use super::inspect::InspectedSegment;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -55,12 +67,138 @@ pub struct VariantState {
     pub target_duration: f64,
     pub playlist_type: Option<String>,
     pub has_endlist: bool,
+    pub part_target: Option<f64>,
+    /// URIs of the parts listed after the last published segment.
+    pub pending_parts: Vec<String>,
+    /// URI of the last part in the playlist, published or pending.
+    pub last_part_uri: Option<String>,
+    /// URI of the `EXT-X-PRELOAD-HINT:TYPE=PART` hint, if any.
+    pub preload_hint_uri: Option<String>,
+    /// CAN-SKIP-UNTIL from `EXT-X-SERVER-CONTROL`; enables delta updates.
+    pub can_skip_until: Option<f64>,
+    /// CAN-BLOCK-RELOAD=YES from `EXT-X-SERVER-CONTROL`; enables blocking reloads.
+    pub can_block_reload: bool,
+    /// Index of the last part of the last segment, if the playlist has parts.
+    pub last_part: Option<u64>,
+    /// `EXT-X-RENDITION-REPORT`s with URIs resolved against this playlist's URL.
+    pub rendition_reports: Vec<RenditionReportSnapshot>,
+    /// `EXT-X-MAP` of the last segment.
+    pub map: Option<MapSnapshot>,
+    /// URI of the `EXT-X-KEY` in effect for the last segment.
+    pub key_uri: Option<String>,
+    /// Ad break that has not seen its `CUE-IN` yet.
+    pub ad_break: Option<AdBreak>,
+    /// Break starts and returns on the segments in the playlist window.
+    pub ad_boundaries: Vec<AdBoundary>,
+    /// Seconds the live edge was behind the wall clock at the last fetch.
+    pub live_edge_latency: Option<f64>,
+    /// `EXTINF` time against PDT time since the last discontinuity.
+    pub clock_drift: Option<ClockDrift>,
+    /// `EXT-X-DATERANGE`s seen so far by `ID`, including ones that have left
+    /// the playlist.
+    pub dateranges: BTreeMap<String, TrackedRange>,
+}
+
+impl VariantState {
+    /// Media sequence numbers of the published segments, `first..end`.
+    pub fn segment_window(&self) -> Range<u64> {
+        self.media_sequence..self.media_sequence + self.segment_uris.len() as u64
+    }
+
+    /// Media sequence number of the last segment in the playlist, counting a
+    /// segment that only has parts so far (the value of a rendition report's
+    /// LAST-MSN).
+    pub fn last_msn(&self) -> Option<u64> {
+        let count = self.segment_uris.len() as u64 + u64::from(!self.pending_parts.is_empty());
+        (count > 0).then(|| self.media_sequence + count - 1)
+    }
+
+    /// `(msn, index)` of the last part in the playlist, published or pending.
+    pub fn last_part_position(&self) -> Option<(u64, u64)> {
+        Some((self.last_msn()?, self.last_part?))
+    }
+}
+
+/// A `CUE-OUT` or `CUE-IN` on a segment, kept so renditions can be compared.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AdBoundary {
+    pub mseq: u64,
+    /// `Out` or `In`.
+    pub signal: AdSignal,
+    pub program_date_time: Option<chrono::DateTime<chrono::FixedOffset>>,
+    /// Announced break duration, for a break start.
+    pub duration: Option<f64>,
 }
 
 #[derive(Debug, Clone)]
 pub struct SegmentInfo {
     pub uri: String,
     pub discontinuity: bool,
+    pub duration: f64,
+    pub program_date_time: Option<chrono::DateTime<chrono::FixedOffset>>,
+    /// The rest of the segment's tags, restored when a delta update skips it.
+    pub tags: SegmentTags,
+}
+
+/// Per-segment tags beyond those in [`SegmentInfo`], as parsed into a
+/// [`SegmentSnapshot`].
+#[derive(Debug, Clone, Default)]
+pub struct SegmentTags {
+    pub cue_out: bool,
+    pub cue_in: bool,
+    pub cue_out_cont: Option<String>,
+    pub cue_out_duration: Option<f64>,
+    pub gap: bool,
+    pub daterange: Option<DateRangeSnapshot>,
+    pub byte_range_length: Option<u64>,
+    pub map: Option<MapSnapshot>,
+    pub scte35: Vec<Scte35Marker>,
+    pub ad_markers: Vec<AdMarker>,
+}
+
+impl SegmentInfo {
+    pub fn of(seg: &SegmentSnapshot) -> Self {
+        Self {
+            uri: seg.uri.clone(),
+            discontinuity: seg.discontinuity,
+            duration: seg.duration,
+            program_date_time: seg.program_date_time,
+            tags: SegmentTags {
+                cue_out: seg.cue_out,
+                cue_in: seg.cue_in,
+                cue_out_cont: seg.cue_out_cont.clone(),
+                cue_out_duration: seg.cue_out_duration,
+                gap: seg.gap,
+                daterange: seg.daterange.clone(),
+                byte_range_length: seg.byte_range_length,
+                map: seg.map.clone(),
+                scte35: seg.scte35.clone(),
+                ad_markers: seg.ad_markers.clone(),
+            },
+        }
+    }
+
+    /// The segment as it was parsed, without its parts.
+    pub fn to_snapshot(&self) -> SegmentSnapshot {
+        let tags = self.tags.clone();
+        SegmentSnapshot {
+            uri: self.uri.clone(),
+            duration: self.duration,
+            discontinuity: self.discontinuity,
+            cue_out: tags.cue_out,
+            cue_in: tags.cue_in,
+            cue_out_cont: tags.cue_out_cont,
+            gap: tags.gap,
+            program_date_time: self.program_date_time,
+            daterange: tags.daterange,
+            parts: vec![],
+            byte_range_length: tags.byte_range_length,
+            map: tags.map,
+            scte35: tags.scte35,
+            ad_markers: tags.ad_markers,
+            cue_out_duration: tags.cue_out_duration,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -84,6 +222,79 @@ pub struct PlaylistSnapshot {
     pub has_key_iv: bool,
     pub has_key_format: bool,
     pub keys: Vec<KeySnapshot>,
+    pub part_target: Option<f64>,
+    /// Parts of the segment that has not been published yet.
+    pub pending_parts: Vec<PartSnapshot>,
+    pub preload_hints: Vec<PreloadHintSnapshot>,
+    pub server_control: Option<ServerControlSnapshot>,
+    /// SKIPPED-SEGMENTS of an `EXT-X-SKIP` delta update. The engine merges the
+    /// skipped segments back in from the previous state before running checks.
+    pub skipped_segments: Option<u64>,
+    pub rendition_reports: Vec<RenditionReportSnapshot>,
+    /// URI of the `EXT-X-KEY` in effect for the last segment (`None` when
+    /// unencrypted or `METHOD=NONE`).
+    pub key_uri: Option<String>,
+}
+
+impl PlaylistSnapshot {
+    /// Segments past the end of the previous playlist window, with their media
+    /// sequence numbers; all of them on the first poll or after the media
+    /// sequence went back.
+    ///
+    /// Newness follows the media sequence rather than the URI: every segment of
+    /// a single-file `EXT-X-BYTERANGE` playlist shares one URI.
+    pub fn segments_after(&self, prev_window: Option<Range<u64>>) -> impl Iterator<Item = (u64, &SegmentSnapshot)> {
+        let first_new = match prev_window {
+            Some(window) if self.media_sequence >= window.start => window.end,
+            _ => 0,
+        };
+        let mseq = self.media_sequence;
+        self.segments
+            .iter()
+            .enumerate()
+            .map(move |(i, s)| (mseq + i as u64, s))
+            .filter(move |(msn, _)| *msn >= first_new)
+    }
+
+    /// Recompute the totals derived from `segments`: duration, cue counts and gaps.
+    pub fn update_totals(&mut self) {
+        self.duration = self.segments.iter().map(|s| s.duration).sum();
+        self.cue_out_count = self.segments.iter().filter(|s| s.cue_out).count();
+        self.cue_in_count = self.segments.iter().filter(|s| s.cue_in).count();
+        self.has_cue_out = self.cue_out_count > 0;
+        self.cue_out_duration = self.segments.iter().rev().find(|s| s.cue_out).and_then(|s| s.cue_out_duration);
+        self.has_gaps = self.segments.iter().any(|s| s.gap);
+    }
+
+    /// Every part in playlist order: published segments' parts, then pending parts.
+    pub fn all_parts(&self) -> impl Iterator<Item = &PartSnapshot> {
+        self.segments
+            .iter()
+            .flat_map(|s| s.parts.iter())
+            .chain(self.pending_parts.iter())
+    }
+
+    /// Every part in playlist order with its `(msn, index)` position, so parts
+    /// sharing a URI (byte-range parts) can be told apart.
+    pub fn positioned_parts(&self) -> impl Iterator<Item = ((u64, u64), &PartSnapshot)> {
+        let mseq = self.media_sequence;
+        let pending_msn = mseq + self.segments.len() as u64;
+        self.segments
+            .iter()
+            .enumerate()
+            .flat_map(move |(i, s)| {
+                s.parts
+                    .iter()
+                    .enumerate()
+                    .map(move |(j, p)| ((mseq + i as u64, j as u64), p))
+            })
+            .chain(
+                self.pending_parts
+                    .iter()
+                    .enumerate()
+                    .map(move |(j, p)| ((pending_msn, j as u64), p)),
+            )
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -91,12 +302,61 @@ pub struct SegmentSnapshot {
     pub uri: String,
     pub duration: f64,
     pub discontinuity: bool,
+    /// A break starts here, in any recognized ad dialect (see `ad_markers`).
     pub cue_out: bool,
+    /// The break ends here, in any recognized ad dialect.
     pub cue_in: bool,
+    /// Continuation tag value of a segment inside a break.
     pub cue_out_cont: Option<String>,
     pub gap: bool,
     pub program_date_time: Option<chrono::DateTime<chrono::FixedOffset>>,
     pub daterange: Option<DateRangeSnapshot>,
+    pub parts: Vec<PartSnapshot>,
+    /// `EXT-X-BYTERANGE` length, when the segment is a sub-range of its URI.
+    pub byte_range_length: Option<u64>,
+    /// `EXT-X-MAP` in effect for this segment.
+    pub map: Option<MapSnapshot>,
+    /// SCTE-35 payloads carried by the tags of this segment.
+    pub scte35: Vec<Scte35Marker>,
+    /// Ad markers of the configured dialect(s), normalized.
+    pub ad_markers: Vec<AdMarker>,
+    /// Break duration announced on a `CUE-OUT` segment, from the tag itself,
+    /// a DATERANGE SCTE35-OUT or the SCTE-35 payload.
+    pub cue_out_duration: Option<f64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PartSnapshot {
+    pub uri: String,
+    pub duration: f64,
+    pub independent: bool,
+    pub gap: bool,
+    pub byterange_length: Option<u64>,
+    pub byterange_offset: Option<u64>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ServerControlSnapshot {
+    pub can_skip_until: Option<f64>,
+    pub can_skip_dateranges: bool,
+    pub hold_back: Option<f64>,
+    pub part_hold_back: Option<f64>,
+    pub can_block_reload: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct RenditionReportSnapshot {
+    pub uri: String,
+    pub last_msn: Option<u64>,
+    pub last_part: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PreloadHintSnapshot {
+    pub hint_type: String,
+    pub uri: String,
+    pub byterange_start: Option<u64>,
+    pub byterange_length: Option<u64>,
 }
 
 #[derive(Debug, Clone)]
@@ -105,6 +365,70 @@ pub struct KeySnapshot {
     pub has_uri: bool,
     pub has_iv: bool,
     pub has_keyformat: bool,
+    pub uri: Option<String>,
+    pub iv: Option<String>,
+}
+
+/// An `EXT-X-MAP` init segment reference.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MapSnapshot {
+    pub uri: String,
+    pub byte_range_length: Option<u64>,
+    pub byte_range_offset: Option<u64>,
+}
+
+impl MapSnapshot {
+    /// Display form of an optional map, `none` when a segment has no map.
+    pub fn describe(map: Option<&Self>) -> String {
+        map.map_or_else(|| "none".to_string(), ToString::to_string)
+    }
+}
+
+impl fmt::Display for MapSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "'{}'", self.uri)?;
+        match (self.byte_range_length, self.byte_range_offset) {
+            (Some(length), Some(offset)) => write!(f, " BYTERANGE={}@{}", length, offset),
+            (Some(length), None) => write!(f, " BYTERANGE={}", length),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Tag that carried an SCTE-35 payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scte35Source {
+    DateRangeOut,
+    DateRangeIn,
+    DateRangeCmd,
+    Oatcls,
+    XScte35,
+}
+
+impl fmt::Display for Scte35Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::DateRangeOut => write!(f, "EXT-X-DATERANGE SCTE35-OUT"),
+            Self::DateRangeIn => write!(f, "EXT-X-DATERANGE SCTE35-IN"),
+            Self::DateRangeCmd => write!(f, "EXT-X-DATERANGE SCTE35-CMD"),
+            Self::Oatcls => write!(f, "EXT-OATCLS-SCTE35"),
+            Self::XScte35 => write!(f, "EXT-X-SCTE35"),
+        }
+    }
+}
+
+/// An SCTE-35 payload found in a playlist, with what its tag claims about it.
+#[derive(Debug, Clone)]
+pub struct Scte35Marker {
+    pub source: Scte35Source,
+    pub payload: String,
+    pub signal: Result<SpliceInfo, Scte35Error>,
+    /// `Some(true)` when the tag opens a break (SCTE35-OUT, CUE-OUT),
+    /// `Some(false)` when it closes one (SCTE35-IN, CUE-IN).
+    pub tag_out: Option<bool>,
+    /// Break duration stated by the tag (DURATION, PLANNED-DURATION or the
+    /// `EXT-X-CUE-OUT` duration).
+    pub tag_duration: Option<f64>,
 }
 
 #[derive(Debug, Clone)]
@@ -116,6 +440,83 @@ pub struct DateRangeSnapshot {
     pub duration: Option<f64>,
     pub end_on_next: bool,
     pub planned_duration: Option<f64>,
+    /// `X-` client attributes and any others not parsed above (such as
+    /// SCTE35-OUT), with quotes removed.
+    pub attributes: BTreeMap<String, String>,
+}
+
+/// URLs that passed a check, per variant whose playlist references them.
+///
+/// A URL is fetched until it passes, and forgotten once no variant that
+/// loaded references it any more. Variants that failed to load keep theirs.
+#[derive(Debug, Default)]
+pub struct CheckedUrls(HashMap<String, HashSet<String>>);
+
+impl CheckedUrls {
+    pub fn contains(&self, url: &str) -> bool {
+        self.0.values().any(|urls| urls.contains(url))
+    }
+
+    /// Replace what each of the `loaded` variants references with the
+    /// `(variant_key, url)` pairs in `referenced` that passed before or are in `passed`.
+    pub fn update<'a>(
+        &mut self,
+        loaded: &[String],
+        referenced: impl IntoIterator<Item = (&'a str, &'a str)>,
+        passed: &HashSet<String>,
+    ) {
+        let mut current: HashMap<String, HashSet<String>> = HashMap::new();
+        for (variant_key, url) in referenced {
+            if passed.contains(url) || self.contains(url) {
+                current.entry(variant_key.to_string()).or_default().insert(url.to_string());
+            }
+        }
+        for key in loaded {
+            self.0.remove(key);
+        }
+        self.0.extend(current);
+    }
+
+    pub fn forget_variant(&mut self, key: &str) {
+        self.0.remove(key);
+    }
+}
+
+/// Parsed `EXT-X-MAP` init segments by URL, per variant whose playlist
+/// references them, pruned like [`CheckedUrls`]. `None` marks one that is not
+/// ISO-BMFF (an MPEG-TS PAT/PMT). Init segments that failed to download or
+/// parse are not kept, so they are fetched again.
+#[derive(Debug, Default)]
+pub struct InitSegments(HashMap<String, HashMap<String, Option<InitSegment>>>);
+
+impl InitSegments {
+    pub fn get(&self, url: &str) -> Option<&Option<InitSegment>> {
+        self.0.values().find_map(|inits| inits.get(url))
+    }
+
+    /// Replace what each of the `loaded` variants references with the
+    /// `(variant_key, url)` pairs in `referenced` that are known or in `parsed`.
+    pub fn update<'a>(
+        &mut self,
+        loaded: &[String],
+        referenced: impl IntoIterator<Item = (&'a str, &'a str)>,
+        parsed: &HashMap<String, Option<InitSegment>>,
+    ) {
+        let mut current: HashMap<String, HashMap<String, Option<InitSegment>>> = HashMap::new();
+        for (variant_key, url) in referenced {
+            if let Some(init) = parsed.get(url).or_else(|| self.get(url)) {
+                current.entry(variant_key.to_string()).or_default().insert(url.to_string(), init.clone());
+            }
+        }
+        for key in loaded {
+            self.0.remove(key);
+        }
+        self.0.extend(current);
+    }
+
+    pub fn forget_variant(&mut self, key: &str) {
+        self.0.remove(key);
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -136,6 +537,22 @@ pub struct StreamData {
     pub events: EventRing,
     pub was_stale: bool,
     pub variant_failures: HashMap<String, u32>,
+    /// Whether any variant's content changed on the most recent poll.
+    pub last_reload_changed: bool,
+    /// How long the origin held the most recent blocking reload, per variant.
+    pub blocking_hold_ms: HashMap<String, u64>,
+    /// Most recent measured segment sizes, per variant.
+    pub bitrate_samples: HashMap<String, VecDeque<BitrateSample>>,
+    /// Last segment inspected per variant.
+    pub inspected: HashMap<String, InspectedSegment>,
+    /// Parsed `EXT-X-MAP` init segments of the inspected variants.
+    pub init_segments: InitSegments,
+    /// AES-128 key URLs that were fetched and passed.
+    pub fetched_keys: CheckedUrls,
+    /// Interstitial asset and asset list URLs that were fetched and passed.
+    pub fetched_assets: CheckedUrls,
+    /// Fingerprint of the last master playlist and recent ladder changes.
+    pub master: MasterHistory,
 }
 
 impl StreamData {
@@ -150,8 +567,29 @@ impl StreamData {
             events: EventRing::new(event_capacity),
             was_stale: false,
             variant_failures: HashMap::new(),
+            last_reload_changed: false,
+            blocking_hold_ms: HashMap::new(),
+            bitrate_samples: HashMap::new(),
+            inspected: HashMap::new(),
+            init_segments: InitSegments::default(),
+            fetched_keys: CheckedUrls::default(),
+            fetched_assets: CheckedUrls::default(),
+            master: MasterHistory::default(),
         }
     }
+
+    /// Drop everything kept for a variant that left the master playlist.
+    pub fn forget_variant(&mut self, key: &str) {
+        self.variants.remove(key);
+        self.known_variants.remove(key);
+        self.variant_failures.remove(key);
+        self.blocking_hold_ms.remove(key);
+        self.bitrate_samples.remove(key);
+        self.inspected.remove(key);
+        self.init_segments.forget_variant(key);
+        self.fetched_keys.forget_variant(key);
+        self.fetched_assets.forget_variant(key);
+    }
 }
 
 /// Per-stream live status.
@@ -179,6 +617,17 @@ pub struct VariantStatus {
     pub cue_out_count: usize,
     pub cue_in_count: usize,
     pub consecutive_failures: u32,
+    /// Hold time of the last blocking reload (`poll_mode = "blocking"` only).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub blocking_hold_ms: Option<u64>,
+    /// Seconds the newest segment's end, by its PDT, was behind the wall
+    /// clock at the last fetch.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub live_edge_latency_secs: Option<f64>,
+    /// Milliseconds per hour the PDTs run ahead of (or, negative, behind)
+    /// the `EXTINF` durations, once ten minutes have been observed.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub clock_drift_ms_per_hour: Option<f64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -199,6 +648,38 @@ mod tests {
         assert!(MonitorState::Stopped.can_transition_to(MonitorState::Active));
     }
 
+    #[test]
+    fn checked_urls_kept_for_variants_that_did_not_load() {
+        let mut checked = CheckedUrls::default();
+        let passed = HashSet::from(["k1".to_string(), "k2".to_string()]);
+        checked.update(&["a".into(), "b".into()], [("a", "k1"), ("b", "k2"), ("b", "k3")], &passed);
+        assert!(checked.contains("k1") && checked.contains("k2") && !checked.contains("k3"));
+
+        // "b" failed to load: its key is kept. "a" moved on to a new key.
+        checked.update(&["a".into()], [("a", "k4")], &HashSet::new());
+        assert!(!checked.contains("k1") && checked.contains("k2") && !checked.contains("k4"));
+
+        checked.forget_variant("b");
+        assert!(!checked.contains("k2"));
+    }
+
+    #[test]
+    fn init_segments_pruned_per_variant() {
+        let mut inits = InitSegments::default();
+        let parsed = HashMap::from([("i1".to_string(), Some(InitSegment::default())), ("i2".to_string(), None)]);
+        inits.update(&["a".into(), "b".into()], [("a", "i1"), ("b", "i2"), ("b", "i3")], &parsed);
+        assert_eq!(inits.get("i1"), Some(&Some(InitSegment::default())));
+        assert_eq!(inits.get("i2"), Some(&None));
+        assert_eq!(inits.get("i3"), None);
+
+        // "a" rotated to a new init segment; "b" did not load and keeps its own.
+        inits.update(&["a".into()], [("a", "i4")], &HashMap::new());
+        assert!(inits.get("i1").is_none() && inits.get("i2").is_some() && inits.get("i4").is_none());
+
+        inits.forget_variant("b");
+        assert!(inits.get("i2").is_none());
+    }
+
     #[test]
     fn invalid_state_transitions() {
         assert!(!MonitorState::Idle.can_transition_to(MonitorState::Stopping));
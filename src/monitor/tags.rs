@@ -0,0 +1,242 @@
+//! Raw tag scanning for media playlist features that `m3u8_rs` does not model.
+//!
+//! `m3u8_rs` hands unknown tags to the *following* segment and silently drops
+//! anything after the last segment URI, which is exactly where LL-HLS puts the
+//! parts of the in-progress segment and the preload hint. These helpers walk
+//! the playlist text directly instead.
+
+use std::collections::HashMap;
+
+use super::state::{PartSnapshot, PreloadHintSnapshot, RenditionReportSnapshot, ServerControlSnapshot};
+
+/// Parse an HLS attribute list (`KEY=VALUE,KEY="quoted,value"`) into a map.
+///
+/// Quoted values are returned without their surrounding quotes. Keys are
+/// kept as written (attribute names are upper-case by spec).
+pub fn parse_attribute_list(input: &str) -> HashMap<String, String> {
+    let mut attrs = HashMap::new();
+    let mut rest = input.trim();
+
+    while !rest.is_empty() {
+        let Some(eq) = rest.find('=') else {
+            break;
+        };
+        let key = rest[..eq].trim().to_string();
+        rest = &rest[eq + 1..];
+
+        let value;
+        if let Some(stripped) = rest.strip_prefix('"') {
+            match stripped.find('"') {
+                Some(end) => {
+                    value = stripped[..end].to_string();
+                    rest = &stripped[end + 1..];
+                }
+                None => {
+                    value = stripped.to_string();
+                    rest = "";
+                }
+            }
+            rest = rest.trim_start_matches(|c: char| c != ',');
+        } else {
+            let end = rest.find(',').unwrap_or(rest.len());
+            value = rest[..end].trim().to_string();
+            rest = &rest[end..];
+        }
+        rest = rest.strip_prefix(',').unwrap_or(rest).trim_start();
+
+        if !key.is_empty() {
+            attrs.insert(key, value);
+        }
+    }
+
+    attrs
+}
+
+/// LL-HLS tags found in a media playlist, positioned relative to its segments.
+#[derive(Debug, Clone, Default)]
+pub struct LowLatencyTags {
+    /// PART-TARGET from `EXT-X-PART-INF`.
+    pub part_target: Option<f64>,
+    /// Parts of each published segment, indexed like the playlist's segments.
+    pub segment_parts: Vec<Vec<PartSnapshot>>,
+    /// Parts listed after the last segment URI (the in-progress segment).
+    pub pending_parts: Vec<PartSnapshot>,
+    pub preload_hints: Vec<PreloadHintSnapshot>,
+    pub server_control: Option<ServerControlSnapshot>,
+    /// SKIPPED-SEGMENTS from `EXT-X-SKIP` when the playlist is a delta update.
+    pub skipped_segments: Option<u64>,
+    pub rendition_reports: Vec<RenditionReportSnapshot>,
+}
+
+/// Scan a media playlist body for `EXT-X-PART-INF`, `EXT-X-PART`,
+/// `EXT-X-PRELOAD-HINT`, `EXT-X-SERVER-CONTROL`, `EXT-X-SKIP` and
+/// `EXT-X-RENDITION-REPORT` tags.
+pub fn scan_low_latency(body: &str) -> LowLatencyTags {
+    let mut tags = LowLatencyTags::default();
+    let mut current_parts = Vec::new();
+
+    for line in body.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix("#EXT-X-PART-INF:") {
+            let attrs = parse_attribute_list(rest);
+            tags.part_target = attrs.get("PART-TARGET").and_then(|v| v.parse().ok());
+        } else if let Some(rest) = line.strip_prefix("#EXT-X-PART:") {
+            let attrs = parse_attribute_list(rest);
+            let (byterange_length, byterange_offset) = attrs
+                .get("BYTERANGE")
+                .map(|v| parse_byterange(v))
+                .unwrap_or((None, None));
+            current_parts.push(PartSnapshot {
+                uri: attrs.get("URI").cloned().unwrap_or_default(),
+                duration: attrs
+                    .get("DURATION")
+                    .and_then(|v| v.parse().ok())
+                    .unwrap_or(0.0),
+                independent: attrs.get("INDEPENDENT").is_some_and(|v| v == "YES"),
+                gap: attrs.get("GAP").is_some_and(|v| v == "YES"),
+                byterange_length,
+                byterange_offset,
+            });
+        } else if let Some(rest) = line.strip_prefix("#EXT-X-PRELOAD-HINT:") {
+            let attrs = parse_attribute_list(rest);
+            tags.preload_hints.push(PreloadHintSnapshot {
+                hint_type: attrs.get("TYPE").cloned().unwrap_or_default(),
+                uri: attrs.get("URI").cloned().unwrap_or_default(),
+                byterange_start: attrs.get("BYTERANGE-START").and_then(|v| v.parse().ok()),
+                byterange_length: attrs.get("BYTERANGE-LENGTH").and_then(|v| v.parse().ok()),
+            });
+        } else if let Some(rest) = line.strip_prefix("#EXT-X-SERVER-CONTROL:") {
+            let attrs = parse_attribute_list(rest);
+            tags.server_control = Some(ServerControlSnapshot {
+                can_skip_until: attrs.get("CAN-SKIP-UNTIL").and_then(|v| v.parse().ok()),
+                can_skip_dateranges: attrs.get("CAN-SKIP-DATERANGES").is_some_and(|v| v == "YES"),
+                hold_back: attrs.get("HOLD-BACK").and_then(|v| v.parse().ok()),
+                part_hold_back: attrs.get("PART-HOLD-BACK").and_then(|v| v.parse().ok()),
+                can_block_reload: attrs.get("CAN-BLOCK-RELOAD").is_some_and(|v| v == "YES"),
+            });
+        } else if let Some(rest) = line.strip_prefix("#EXT-X-SKIP:") {
+            let attrs = parse_attribute_list(rest);
+            tags.skipped_segments = attrs.get("SKIPPED-SEGMENTS").and_then(|v| v.parse().ok());
+        } else if let Some(rest) = line.strip_prefix("#EXT-X-RENDITION-REPORT:") {
+            let attrs = parse_attribute_list(rest);
+            tags.rendition_reports.push(RenditionReportSnapshot {
+                uri: attrs.get("URI").cloned().unwrap_or_default(),
+                last_msn: attrs.get("LAST-MSN").and_then(|v| v.parse().ok()),
+                last_part: attrs.get("LAST-PART").and_then(|v| v.parse().ok()),
+            });
+        } else if !line.starts_with('#') {
+            tags.segment_parts.push(std::mem::take(&mut current_parts));
+        }
+    }
+
+    tags.pending_parts = current_parts;
+    tags
+}
+
+/// Parse an `<n>[@<o>]` byte range value.
+fn parse_byterange(value: &str) -> (Option<u64>, Option<u64>) {
+    match value.split_once('@') {
+        Some((len, off)) => (len.parse().ok(), off.parse().ok()),
+        None => (value.parse().ok(), None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn attribute_list_handles_quoted_commas() {
+        let attrs = parse_attribute_list(r#"DURATION=0.33334,URI="part,1.mp4",INDEPENDENT=YES"#);
+        assert_eq!(attrs.get("DURATION").map(String::as_str), Some("0.33334"));
+        assert_eq!(attrs.get("URI").map(String::as_str), Some("part,1.mp4"));
+        assert_eq!(attrs.get("INDEPENDENT").map(String::as_str), Some("YES"));
+    }
+
+    #[test]
+    fn attribute_list_ignores_garbage() {
+        assert!(parse_attribute_list("").is_empty());
+        assert!(parse_attribute_list("NOVALUE").is_empty());
+    }
+
+    #[test]
+    fn scan_assigns_parts_to_segments_and_pending() {
+        let body = "#EXTM3U\n\
+            #EXT-X-TARGETDURATION:4\n\
+            #EXT-X-PART-INF:PART-TARGET=1.0\n\
+            #EXT-X-MEDIA-SEQUENCE:10\n\
+            #EXT-X-PART:DURATION=1.0,URI=\"s10.p0.mp4\",INDEPENDENT=YES\n\
+            #EXT-X-PART:DURATION=1.0,URI=\"s10.p1.mp4\"\n\
+            #EXTINF:2.0,\n\
+            s10.mp4\n\
+            #EXT-X-PART:DURATION=1.0,URI=\"s11.p0.mp4\",INDEPENDENT=YES\n\
+            #EXT-X-PRELOAD-HINT:TYPE=PART,URI=\"s11.p1.mp4\"\n";
+        let tags = scan_low_latency(body);
+        assert_eq!(tags.part_target, Some(1.0));
+        assert_eq!(tags.segment_parts.len(), 1);
+        assert_eq!(tags.segment_parts[0].len(), 2);
+        assert!(tags.segment_parts[0][0].independent);
+        assert!(!tags.segment_parts[0][1].independent);
+        assert_eq!(tags.pending_parts.len(), 1);
+        assert_eq!(tags.pending_parts[0].uri, "s11.p0.mp4");
+        assert_eq!(tags.preload_hints.len(), 1);
+        assert_eq!(tags.preload_hints[0].hint_type, "PART");
+        assert_eq!(tags.preload_hints[0].uri, "s11.p1.mp4");
+    }
+
+    #[test]
+    fn scan_parses_part_byterange() {
+        let body = "#EXT-X-PART:DURATION=0.5,URI=\"seg.mp4\",BYTERANGE=\"1000@2000\"\n";
+        let tags = scan_low_latency(body);
+        assert_eq!(tags.pending_parts[0].byterange_length, Some(1000));
+        assert_eq!(tags.pending_parts[0].byterange_offset, Some(2000));
+    }
+
+    #[test]
+    fn scan_parses_server_control_and_skip() {
+        let body = "#EXTM3U\n\
+            #EXT-X-SERVER-CONTROL:CAN-BLOCK-RELOAD=YES,CAN-SKIP-UNTIL=12.0,PART-HOLD-BACK=1.5,HOLD-BACK=6\n\
+            #EXT-X-MEDIA-SEQUENCE:10\n\
+            #EXT-X-SKIP:SKIPPED-SEGMENTS=3\n\
+            #EXTINF:2.0,\n\
+            s13.mp4\n";
+        let tags = scan_low_latency(body);
+        let sc = tags.server_control.expect("server control");
+        assert!(sc.can_block_reload);
+        assert!(!sc.can_skip_dateranges);
+        assert_eq!(sc.can_skip_until, Some(12.0));
+        assert_eq!(sc.hold_back, Some(6.0));
+        assert_eq!(sc.part_hold_back, Some(1.5));
+        assert_eq!(tags.skipped_segments, Some(3));
+    }
+
+    #[test]
+    fn scan_parses_trailing_rendition_reports() {
+        let body = "#EXTINF:2.0,\n\
+            s10.mp4\n\
+            #EXT-X-RENDITION-REPORT:URI=\"../1M/live.m3u8\",LAST-MSN=10,LAST-PART=3\n\
+            #EXT-X-RENDITION-REPORT:URI=\"../4M/live.m3u8\",LAST-MSN=10\n";
+        let tags = scan_low_latency(body);
+        assert_eq!(tags.rendition_reports.len(), 2);
+        assert_eq!(tags.rendition_reports[0].uri, "../1M/live.m3u8");
+        assert_eq!(tags.rendition_reports[0].last_msn, Some(10));
+        assert_eq!(tags.rendition_reports[0].last_part, Some(3));
+        assert_eq!(tags.rendition_reports[1].last_part, None);
+    }
+
+    #[test]
+    fn scan_plain_playlist_has_no_parts() {
+        let body = "#EXTM3U\n#EXT-X-TARGETDURATION:10\n#EXTINF:10.0,\na.ts\n#EXTINF:10.0,\nb.ts\n";
+        let tags = scan_low_latency(body);
+        assert_eq!(tags.part_target, None);
+        assert_eq!(tags.segment_parts.len(), 2);
+        assert!(tags.segment_parts.iter().all(|p| p.is_empty()));
+        assert!(tags.pending_parts.is_empty());
+        assert!(tags.server_control.is_none());
+        assert_eq!(tags.skipped_segments, None);
+    }
+}
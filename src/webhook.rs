@@ -82,6 +82,15 @@ impl Notification {
                 EventKind::ManifestUpdated => "manifest_updated",
                 EventKind::StaleRecovered => "stale_recovered",
                 EventKind::StreamEnded => "stream_ended",
+                EventKind::InitSegmentChanged => "init_segment_changed",
+                EventKind::KeyRotated => "key_rotated",
+                EventKind::Scte35Signal => "scte35_signal",
+                EventKind::AdBreakCompleted => "ad_break_completed",
+                EventKind::DateRangeOpened => "daterange_opened",
+                EventKind::DateRangeClosed => "daterange_closed",
+                EventKind::VariantAdded => "variant_added",
+                EventKind::VariantRemoved => "variant_removed",
+                EventKind::MasterChanged => "master_changed",
             },
         }
     }
@@ -119,20 +128,26 @@ impl WebhookPayload {
                     "status_code": error.status_code,
                 }),
             },
-            Notification::Event { monitor_id, event } => Self {
-                version: 1,
-                id: Uuid::new_v4().to_string(),
-                timestamp: event.timestamp,
-                notification_type: notification.notification_type().to_string(),
-                monitor_id: monitor_id.clone(),
-                stream_id: event.stream_id.clone(),
-                data: serde_json::json!({
+            Notification::Event { monitor_id, event } => {
+                let mut data = serde_json::json!({
                     "kind": event.kind,
                     "media_type": event.media_type,
                     "variant_key": event.variant_key,
                     "details": event.details,
-                }),
-            },
+                });
+                if let Some(ref info) = event.scte35 {
+                    data["scte35"] = serde_json::json!(info);
+                }
+                Self {
+                    version: 1,
+                    id: Uuid::new_v4().to_string(),
+                    timestamp: event.timestamp,
+                    notification_type: notification.notification_type().to_string(),
+                    monitor_id: monitor_id.clone(),
+                    stream_id: event.stream_id.clone(),
+                    data,
+                }
+            }
         }
     }
 }
@@ -372,6 +387,30 @@ mod tests {
         assert_eq!(payload.data["details"], "Ad break started at mseq 42");
     }
 
+    #[test]
+    fn payload_carries_decoded_scte35() {
+        let info = crate::media::scte35::decode(&crate::media::scte35::testing::splice_insert(9, true, Some(30.0))).unwrap();
+        let n = Notification::Event {
+            monitor_id: "live-1".into(),
+            event: MonitorEvent::new(EventKind::Scte35Signal, "VIDEO", "1200000", "signal", "stream_1")
+                .with_scte35(info),
+        };
+        assert_eq!(n.notification_type(), "scte35_signal");
+        let payload = WebhookPayload::from_notification(&n);
+        let command = &payload.data["scte35"]["command"];
+        assert_eq!(command["type"], "splice_insert");
+        assert_eq!(command["event_id"], 9);
+        assert_eq!(command["out_of_network"], true);
+        assert_eq!(command["break_duration"]["duration"], 2_700_000);
+        assert_eq!(payload.data["scte35"]["crc_valid"], true);
+
+        let plain = Notification::Event {
+            monitor_id: "live-1".into(),
+            event: MonitorEvent::new(EventKind::CueOutStarted, "VIDEO", "1200000", "d", "stream_1"),
+        };
+        assert!(WebhookPayload::from_notification(&plain).data.get("scte35").is_none());
+    }
+
     #[test]
     fn hmac_signature_is_deterministic() {
         let body = b"test payload";
@@ -1,10 +1,13 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::Write;
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use async_trait::async_trait;
-use hls_monitor::{ErrorType, EventKind, LoadError, LoadResponse, ManifestLoader, Monitor, MonitorConfig, MonitorEvent, StreamItem};
+use hls_monitor::{
+    AdDialect, ErrorType, EventKind, LoadError, LoadResponse, ManifestLoader, Monitor, MonitorConfig, MonitorEvent,
+    MonitorState, PollMode, ProbeMethod, ProbeResponse, StreamItem,
+};
 
 const MASTER_URL: &str = "https://mock.mock.com/channels/1xx/master.m3u8";
 const LEVEL0_URL: &str = "https://mock.mock.com/channels/1xx/level_0.m3u8";
@@ -119,6 +122,7 @@ impl ManifestLoader for SequenceLoader {
             body: responses[idx].clone(),
             content_type: None,
             content_encoding: None,
+            elapsed: None,
         })
     }
 }
@@ -786,3 +790,1730 @@ async fn test_media_sequence_gap() {
         errors.iter().map(|e| (&e.error_type, &e.details)).collect::<Vec<_>>()
     );
 }
+
+#[tokio::test]
+async fn test_media_playlist_url_monitored_as_single_variant() {
+    let step = Arc::new(AtomicUsize::new(0));
+
+    let mut responses = HashMap::new();
+    responses.insert(
+        LEVEL0_URL.to_string(),
+        vec![
+            mp(0, None, &[s("a0.ts"), s("a1.ts")]),
+            mp(1, None, &[s("a1.ts"), s("a2.ts")]),
+            mp(0, None, &[s("a0.ts"), s("a1.ts")]),
+        ],
+    );
+
+    let loader = Arc::new(SequenceLoader {
+        step: Arc::clone(&step),
+        responses,
+    });
+
+    let config = MonitorConfig::default().with_stale_limit(8000);
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: LEVEL0_URL.to_string(),
+    };
+
+    let monitor = Monitor::new(vec![stream], config, loader, None);
+
+    for poll in 0..3 {
+        step.store(poll, Ordering::SeqCst);
+        monitor.poll_once().await;
+    }
+
+    let statuses = monitor.get_stream_status().await;
+    assert_eq!(statuses.len(), 1);
+    assert_eq!(statuses[0].variants.len(), 1);
+    assert_eq!(statuses[0].variants[0].variant_key, "media");
+    assert_eq!(statuses[0].variants[0].media_type, "MEDIA");
+    assert_eq!(statuses[0].variants[0].segment_count, 2);
+
+    let events = monitor.get_events().await;
+    assert_eq!(count_events(&events, EventKind::ManifestUpdated), 2);
+
+    let errors = monitor.get_errors().await;
+    assert_any_error_contains(&errors, "Expected mediaSequence >= 1. Got: 0");
+}
+
+#[tokio::test]
+async fn test_sibling_media_playlist_streams_keep_separate_state() {
+    let step = Arc::new(AtomicUsize::new(0));
+    let loader = Arc::new(SequenceLoader {
+        step: Arc::clone(&step),
+        responses: HashMap::from([
+            (
+                LEVEL0_URL.to_string(),
+                vec![
+                    mp(0, None, &[s("a0.ts"), s("a1.ts"), s("a2.ts")]),
+                    mp(1, None, &[s("a1.ts"), s("a2.ts"), s("a3.ts")]),
+                    mp(2, None, &[s("a2.ts"), s("a3.ts"), s("a4.ts")]),
+                ],
+            ),
+            (
+                LEVEL1_URL.to_string(),
+                vec![
+                    mp(100, None, &[s("v100.ts"), s("v101.ts"), s("v102.ts")]),
+                    mp(101, None, &[s("v101.ts"), s("v102.ts"), s("v103.ts")]),
+                    mp(102, None, &[s("v102.ts"), s("v103.ts"), s("v104.ts")]),
+                ],
+            ),
+        ]),
+    });
+    let streams = vec![
+        StreamItem {
+            id: "audio".to_string(),
+            url: LEVEL0_URL.to_string(),
+        },
+        StreamItem {
+            id: "video".to_string(),
+            url: LEVEL1_URL.to_string(),
+        },
+    ];
+    let monitor = Monitor::new(streams, MonitorConfig::default().with_stale_limit(8000), loader, None);
+
+    for poll in 0..3 {
+        step.store(poll, Ordering::SeqCst);
+        monitor.poll_once().await;
+    }
+
+    let errors = monitor.get_errors().await;
+    assert!(errors.is_empty(), "{:#?}", errors);
+
+    let statuses = monitor.get_stream_status().await;
+    assert_eq!(statuses.len(), 2);
+    let status = |id: &str| statuses.iter().find(|s| s.stream_id == id).unwrap();
+    assert_eq!(status("audio").variants.len(), 1);
+    assert_eq!(status("audio").variants[0].media_sequence, 2);
+    assert_eq!(status("audio").variants[0].segment_count, 3);
+    assert_eq!(status("video").variants.len(), 1);
+    assert_eq!(status("video").variants[0].media_sequence, 102);
+    assert_eq!(status("video").variants[0].segment_count, 3);
+}
+
+/// Serves an ever-advancing media playlist per URL, stalling on `slow_url`.
+struct LatencyLoader {
+    slow_url: String,
+    delay: std::time::Duration,
+    loads: Arc<std::sync::Mutex<HashMap<String, u64>>>,
+}
+
+#[async_trait]
+impl ManifestLoader for LatencyLoader {
+    async fn load(&self, uri: &str) -> Result<LoadResponse, LoadError> {
+        if uri == self.slow_url {
+            tokio::time::sleep(self.delay).await;
+        }
+        let mseq = {
+            let mut loads = self.loads.lock().unwrap();
+            let n = loads.entry(uri.to_string()).or_insert(0);
+            *n += 1;
+            *n
+        };
+        Ok(LoadResponse {
+            body: mp_flex(mseq, 10, &[fs("a.ts", 10.0)]),
+            content_type: None,
+            content_encoding: None,
+            elapsed: None,
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_slow_stream_does_not_delay_other_streams() {
+    let fast_url = "https://fast.mock.com/live/media.m3u8";
+    let slow_url = "https://slow.mock.com/live/media.m3u8";
+    let loads = Arc::new(std::sync::Mutex::new(HashMap::new()));
+    let loader = Arc::new(LatencyLoader {
+        slow_url: slow_url.to_string(),
+        delay: std::time::Duration::from_secs(5),
+        loads: Arc::clone(&loads),
+    });
+
+    let config = MonitorConfig::default().with_poll_interval(20);
+    let streams = vec![
+        StreamItem { id: "slow".to_string(), url: slow_url.to_string() },
+        StreamItem { id: "fast".to_string(), url: fast_url.to_string() },
+    ];
+
+    let monitor = Monitor::new(streams, config, loader, None);
+    monitor.start().await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+    monitor.stop().await;
+
+    let fast_loads = loads.lock().unwrap().get(fast_url).copied().unwrap_or(0);
+    assert!(
+        fast_loads >= 3,
+        "Expected the fast stream to keep polling while the slow one stalls, got {} loads",
+        fast_loads
+    );
+    assert_eq!(loads.lock().unwrap().get(slow_url).copied(), None);
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_restart_after_stop_polls_each_stream_once() {
+    let fast_url = "https://fast.mock.com/live/media.m3u8";
+    let slow_url = "https://slow.mock.com/live/media.m3u8";
+    let loads = Arc::new(std::sync::Mutex::new(HashMap::new()));
+    let loader = Arc::new(LatencyLoader {
+        slow_url: slow_url.to_string(),
+        delay: std::time::Duration::from_secs(60),
+        loads: Arc::clone(&loads),
+    });
+
+    let config = MonitorConfig::default().with_poll_interval(1000);
+    let streams = vec![
+        StreamItem { id: "slow".to_string(), url: slow_url.to_string() },
+        StreamItem { id: "fast".to_string(), url: fast_url.to_string() },
+    ];
+    let monitor = Monitor::new(streams, config, loader, None);
+
+    monitor.start().await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+    // The slow stream is mid-request; stopping must not wait for it.
+    let stopping = tokio::time::Instant::now();
+    monitor.stop().await;
+    assert!(stopping.elapsed() < std::time::Duration::from_secs(1));
+    assert_eq!(monitor.state().await, MonitorState::Stopped);
+
+    loads.lock().unwrap().clear();
+    monitor.start().await.unwrap();
+    assert_eq!(monitor.state().await, MonitorState::Active);
+    tokio::time::sleep(std::time::Duration::from_millis(3500)).await;
+    monitor.stop().await;
+
+    // Polls at ~0s, ~1s, ~2s and ~3s (±1/7 jitter); a second loop per
+    // stream would double this.
+    let fast_loads = loads.lock().unwrap().get(fast_url).copied().unwrap_or(0);
+    assert!((3..=5).contains(&fast_loads), "Expected one poll loop per stream, got {} loads", fast_loads);
+    assert_eq!(monitor.state().await, MonitorState::Stopped);
+}
+
+/// Serves `responses` in order (repeating the last) and records when each load happened.
+struct TimedLoader {
+    responses: Vec<String>,
+    loads: std::sync::Mutex<Vec<tokio::time::Instant>>,
+}
+
+#[async_trait]
+impl ManifestLoader for TimedLoader {
+    async fn load(&self, _uri: &str) -> Result<LoadResponse, LoadError> {
+        let mut loads = self.loads.lock().unwrap();
+        let idx = loads.len().min(self.responses.len() - 1);
+        loads.push(tokio::time::Instant::now());
+        Ok(LoadResponse {
+            body: self.responses[idx].clone(),
+            content_type: None,
+            content_encoding: None,
+            elapsed: None,
+        })
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn test_adaptive_polling_follows_target_duration() {
+    let loader = Arc::new(TimedLoader {
+        responses: vec![
+            mp_flex(0, 4, &[fs("a0.ts", 4.0), fs("a1.ts", 4.0)]),
+            mp_flex(0, 4, &[fs("a0.ts", 4.0), fs("a1.ts", 4.0)]),
+            mp_flex(1, 4, &[fs("a1.ts", 4.0), fs("a2.ts", 4.0)]),
+            mp_flex(1, 4, &[fs("a1.ts", 4.0), fs("a2.ts", 4.0)]),
+        ],
+        loads: std::sync::Mutex::new(Vec::new()),
+    });
+
+    let config = MonitorConfig::default()
+        .with_poll_interval(60_000)
+        .with_poll_mode(PollMode::Adaptive);
+    let stream = StreamItem { id: "stream_1".to_string(), url: LEVEL0_URL.to_string() };
+    let monitor = Monitor::new(vec![stream], config, loader.clone(), None);
+
+    monitor.start().await.unwrap();
+    tokio::time::sleep(std::time::Duration::from_millis(11_000)).await;
+    monitor.stop().await;
+
+    // New playlist: reload after a target duration. Unchanged: after half of it.
+    let loads = loader.loads.lock().unwrap().clone();
+    let gaps: Vec<u64> = loads.windows(2).map(|w| (w[1] - w[0]).as_millis() as u64).collect();
+    assert_eq!(gaps, [4000, 2000, 4000], "{:?}", gaps);
+}
+
+fn ll(mseq: u64, pending: &[&str], hint: &str) -> String {
+    let mut out = String::new();
+    writeln!(out, "#EXTM3U").unwrap();
+    writeln!(out, "#EXT-X-VERSION:9").unwrap();
+    writeln!(out, "#EXT-X-TARGETDURATION:2").unwrap();
+    writeln!(out, "#EXT-X-SERVER-CONTROL:CAN-BLOCK-RELOAD=YES,PART-HOLD-BACK=1.5").unwrap();
+    writeln!(out, "#EXT-X-PART-INF:PART-TARGET=0.5").unwrap();
+    writeln!(out, "#EXT-X-MEDIA-SEQUENCE:{}", mseq).unwrap();
+    for i in mseq..mseq + 3 {
+        for p in 0..4 {
+            writeln!(out, "#EXT-X-PART:DURATION=0.5,URI=\"s{}.p{}.mp4\"{}", i, p, if p == 0 { ",INDEPENDENT=YES" } else { "" }).unwrap();
+        }
+        writeln!(out, "#EXTINF:2.0,").unwrap();
+        writeln!(out, "s{}.mp4", i).unwrap();
+    }
+    for (p, uri) in pending.iter().enumerate() {
+        writeln!(out, "#EXT-X-PART:DURATION=0.5,URI=\"{}\"{}", uri, if p == 0 { ",INDEPENDENT=YES" } else { "" }).unwrap();
+    }
+    writeln!(out, "#EXT-X-PRELOAD-HINT:TYPE=PART,URI=\"{}\"", hint).unwrap();
+    out
+}
+
+#[tokio::test]
+async fn test_low_latency_parts_and_preload_hints() {
+    let clean = vec![
+        ll(0, &["s3.p0.mp4"], "s3.p1.mp4"),
+        ll(0, &["s3.p0.mp4", "s3.p1.mp4"], "s3.p2.mp4"),
+        ll(0, &["s3.p0.mp4", "s3.p1.mp4", "s3.p2.mp4"], "s3.p3.mp4"),
+    ];
+    let broken = vec![
+        ll(0, &["s3.p0.mp4"], "s3.p1.mp4"),
+        ll(0, &["s3.p0.mp4", "s3.p9.mp4"], "s3.p2.mp4"),
+        ll(0, &["s3.p9.mp4"], "s3.p3.mp4"),
+    ];
+
+    let errors = run_sequence(broken, clean, 3).await;
+
+    assert!(errors.iter().all(|e| e.variant == "1212000_level_0"), "{:#?}", errors);
+    assert!(errors.iter().any(|e| e.error_type == ErrorType::PreloadHintMismatch));
+    assert!(errors.iter().any(|e| e.error_type == ErrorType::PartRemovedEarly));
+    assert_any_error_contains(&errors, "s3.p0.mp4");
+}
+
+struct DirectiveLoader {
+    step: Arc<AtomicUsize>,
+    responses: Vec<String>,
+    requested: std::sync::Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl ManifestLoader for DirectiveLoader {
+    async fn load(&self, uri: &str) -> Result<LoadResponse, LoadError> {
+        self.requested.lock().unwrap().push(uri.to_string());
+        let idx = self.step.load(Ordering::SeqCst).min(self.responses.len() - 1);
+        Ok(LoadResponse {
+            body: self.responses[idx].clone(),
+            content_type: None,
+            content_encoding: None,
+            elapsed: None,
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_blocking_reload_sends_directives_and_flags_stale_answer() {
+    let step = Arc::new(AtomicUsize::new(0));
+    let loader = Arc::new(DirectiveLoader {
+        step: Arc::clone(&step),
+        responses: vec![
+            ll(0, &["s3.p0.mp4"], "s3.p1.mp4"),
+            ll(0, &["s3.p0.mp4", "s3.p1.mp4"], "s3.p2.mp4"),
+            ll(0, &["s3.p0.mp4", "s3.p1.mp4"], "s3.p2.mp4"),
+        ],
+        requested: std::sync::Mutex::new(Vec::new()),
+    });
+
+    let config = MonitorConfig::default()
+        .with_stale_limit(8000)
+        .with_poll_mode(PollMode::Blocking);
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: LEVEL0_URL.to_string(),
+    };
+    let monitor = Monitor::new(vec![stream], config, loader.clone(), None);
+
+    for poll in 0..3 {
+        step.store(poll, Ordering::SeqCst);
+        monitor.poll_once().await;
+    }
+
+    // The media playlist URL is requested once per poll, with its directives.
+    let requested = loader.requested.lock().unwrap().clone();
+    assert_eq!(
+        requested,
+        [
+            LEVEL0_URL.to_string(),
+            format!("{}?_HLS_msn=3&_HLS_part=1", LEVEL0_URL),
+            format!("{}?_HLS_msn=3&_HLS_part=2", LEVEL0_URL),
+        ]
+    );
+
+    let errors = monitor.get_errors().await;
+    let early: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::BlockingReloadEarly).collect();
+    assert_eq!(early.len(), 1, "{:#?}", errors);
+    assert!(early[0].details.contains("msn=3 part=2"));
+
+    let statuses = monitor.get_stream_status().await;
+    assert!(statuses[0].variants[0].blocking_hold_ms.is_some());
+}
+
+#[tokio::test]
+async fn test_blocking_mode_sends_no_directives_without_can_block_reload() {
+    let no_block = |pending: &[&str], hint: &str| ll(0, pending, hint).replace("CAN-BLOCK-RELOAD=YES,", "");
+    let step = Arc::new(AtomicUsize::new(0));
+    let loader = Arc::new(DirectiveLoader {
+        step: Arc::clone(&step),
+        responses: vec![
+            no_block(&["s3.p0.mp4"], "s3.p1.mp4"),
+            no_block(&["s3.p0.mp4"], "s3.p1.mp4"),
+            no_block(&["s3.p0.mp4", "s3.p1.mp4"], "s3.p2.mp4"),
+        ],
+        requested: std::sync::Mutex::new(Vec::new()),
+    });
+
+    let config = MonitorConfig::default()
+        .with_stale_limit(8000)
+        .with_poll_mode(PollMode::Blocking);
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: LEVEL0_URL.to_string(),
+    };
+    let monitor = Monitor::new(vec![stream], config, loader.clone(), None);
+
+    for poll in 0..3 {
+        step.store(poll, Ordering::SeqCst);
+        monitor.poll_once().await;
+    }
+
+    let requested = loader.requested.lock().unwrap().clone();
+    assert_eq!(requested, [LEVEL0_URL; 3]);
+
+    let errors = monitor.get_errors().await;
+    assert!(
+        !errors
+            .iter()
+            .any(|e| matches!(e.error_type, ErrorType::BlockingReloadEarly | ErrorType::BlockingReloadOverdue)),
+        "{:#?}",
+        errors
+    );
+    let statuses = monitor.get_stream_status().await;
+    assert_eq!(statuses[0].variants[0].blocking_hold_ms, None);
+}
+
+fn skip_playlist(mseq: u64, skipped: u64, tail: std::ops::Range<u64>) -> String {
+    let mut out = String::new();
+    writeln!(out, "#EXTM3U").unwrap();
+    writeln!(out, "#EXT-X-VERSION:9").unwrap();
+    writeln!(out, "#EXT-X-TARGETDURATION:2").unwrap();
+    writeln!(out, "#EXT-X-SERVER-CONTROL:CAN-SKIP-UNTIL=6.0").unwrap();
+    writeln!(out, "#EXT-X-MEDIA-SEQUENCE:{}", mseq).unwrap();
+    if skipped > 0 {
+        writeln!(out, "#EXT-X-SKIP:SKIPPED-SEGMENTS={}", skipped).unwrap();
+    }
+    for i in tail {
+        writeln!(out, "#EXTINF:2.0,").unwrap();
+        writeln!(out, "s{}.ts", i).unwrap();
+    }
+    out
+}
+
+#[tokio::test]
+async fn test_delta_update_is_merged_with_previous_state() {
+    let step = Arc::new(AtomicUsize::new(0));
+    let loader = Arc::new(DirectiveLoader {
+        step: Arc::clone(&step),
+        responses: vec![
+            skip_playlist(0, 0, 0..6),
+            skip_playlist(1, 2, 3..7),
+            skip_playlist(2, 2, 4..8),
+        ],
+        requested: std::sync::Mutex::new(Vec::new()),
+    });
+
+    let config = MonitorConfig::default().with_stale_limit(8000);
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: LEVEL0_URL.to_string(),
+    };
+    let monitor = Monitor::new(vec![stream], config, loader.clone(), None);
+
+    for poll in 0..3 {
+        step.store(poll, Ordering::SeqCst);
+        monitor.poll_once().await;
+    }
+
+    let requested = loader.requested.lock().unwrap().clone();
+    assert!(requested.contains(&format!("{}?_HLS_skip=YES", LEVEL0_URL)), "{:#?}", requested);
+
+    let errors = monitor.get_errors().await;
+    assert!(errors.is_empty(), "{:#?}", errors);
+
+    let statuses = monitor.get_stream_status().await;
+    assert_eq!(statuses[0].variants[0].segment_count, 6);
+    assert_eq!(statuses[0].variants[0].media_sequence, 2);
+}
+
+#[tokio::test]
+async fn test_delta_update_with_wrong_skip_count_is_reported() {
+    let step = Arc::new(AtomicUsize::new(0));
+    let loader = Arc::new(DirectiveLoader {
+        step: Arc::clone(&step),
+        responses: vec![skip_playlist(0, 0, 0..6), skip_playlist(1, 3, 3..7)],
+        requested: std::sync::Mutex::new(Vec::new()),
+    });
+
+    let config = MonitorConfig::default().with_stale_limit(8000);
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: LEVEL0_URL.to_string(),
+    };
+    let monitor = Monitor::new(vec![stream], config, loader, None);
+
+    for poll in 0..2 {
+        step.store(poll, Ordering::SeqCst);
+        monitor.poll_once().await;
+    }
+
+    let errors = monitor.get_errors().await;
+    assert!(errors.iter().any(|e| e.error_type == ErrorType::SkipViolation), "{:#?}", errors);
+    assert_any_error_contains(&errors, "SKIPPED-SEGMENTS=3");
+}
+
+#[tokio::test]
+async fn test_rendition_report_compared_with_fetched_rendition() {
+    let with_report = |last_msn: u64| {
+        let mut pl = ll(0, &["s3.p0.mp4"], "s3.p1.mp4");
+        writeln!(pl, "#EXT-X-RENDITION-REPORT:URI=\"level_1.m3u8\",LAST-MSN={},LAST-PART=0", last_msn).unwrap();
+        pl
+    };
+
+    let errors = run_sequence(vec![with_report(3)], vec![ll(0, &["s3.p0.mp4"], "s3.p1.mp4")], 1).await;
+    assert!(errors.is_empty(), "{:#?}", errors);
+
+    let errors = run_sequence(vec![with_report(0)], vec![ll(0, &["s3.p0.mp4"], "s3.p1.mp4")], 1).await;
+    assert!(errors.iter().any(|e| e.error_type == ErrorType::RenditionReportMismatch), "{:#?}", errors);
+    assert_any_error_contains(&errors, "LAST-MSN=0");
+}
+
+/// Serves a fixed media playlist per step and answers segment probes from a
+/// table of statuses (200 with a length for anything not listed).
+struct ProbeLoader {
+    step: Arc<AtomicUsize>,
+    responses: Vec<String>,
+    statuses: HashMap<&'static str, (u16, Option<u64>)>,
+    probed: std::sync::Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl ManifestLoader for ProbeLoader {
+    async fn load(&self, _uri: &str) -> Result<LoadResponse, LoadError> {
+        let idx = self.step.load(Ordering::SeqCst).min(self.responses.len() - 1);
+        Ok(LoadResponse {
+            body: self.responses[idx].clone(),
+            content_type: None,
+            content_encoding: None,
+            elapsed: None,
+        })
+    }
+
+    async fn probe(&self, uri: &str, _method: ProbeMethod) -> Result<ProbeResponse, LoadError> {
+        self.probed.lock().unwrap().push(uri.to_string());
+        let name = uri.rsplit('/').next().unwrap_or(uri);
+        let (status, content_length) = self.statuses.get(name).copied().unwrap_or((200, Some(1000)));
+        Ok(ProbeResponse { status, content_length })
+    }
+}
+
+#[tokio::test]
+async fn test_segment_probe_reports_missing_and_empty_segments() {
+    let step = Arc::new(AtomicUsize::new(0));
+    let loader = Arc::new(ProbeLoader {
+        step: Arc::clone(&step),
+        responses: vec![
+            mp_flex(0, 2, &[fs("seg0.ts", 2.0), fs("seg1.ts", 2.0)]),
+            mp_flex(1, 2, &[fs("seg1.ts", 2.0), fs("seg2.ts", 2.0), fs("seg3.ts", 2.0)]),
+        ],
+        statuses: HashMap::from([("seg2.ts", (404, None)), ("seg3.ts", (200, Some(0)))]),
+        probed: std::sync::Mutex::new(Vec::new()),
+    });
+
+    let config = MonitorConfig::default()
+        .with_stale_limit(8000)
+        .with_probe_segments(true)
+        .with_probe_newest(2);
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: LEVEL0_URL.to_string(),
+    };
+    let monitor = Monitor::new(vec![stream], config, loader.clone(), None);
+
+    for poll in 0..2 {
+        step.store(poll, Ordering::SeqCst);
+        monitor.poll_once().await;
+    }
+
+    let mut probed = loader.probed.lock().unwrap().clone();
+    probed.sort();
+    let base = LEVEL0_URL.trim_end_matches("level_0.m3u8");
+    let expected: Vec<String> = ["seg0.ts", "seg1.ts", "seg2.ts", "seg3.ts"]
+        .iter()
+        .map(|name| format!("{}{}", base, name))
+        .collect();
+    assert_eq!(probed, expected);
+
+    let errors = monitor.get_errors().await;
+    let unavailable: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::SegmentUnavailable).collect();
+    assert_eq!(unavailable.len(), 2, "{:#?}", errors);
+    assert!(unavailable.iter().any(|e| e.details.contains("seg2.ts") && e.details.contains("HTTP 404")));
+    assert!(unavailable.iter().any(|e| e.details.contains("seg3.ts") && e.details.contains("zero length")));
+}
+
+/// Serves playlists like [`SequenceLoader`] and reports a Content-Length per
+/// segment file name when probed.
+struct SizedSegmentLoader {
+    playlists: SequenceLoader,
+    sizes: HashMap<&'static str, u64>,
+}
+
+#[async_trait]
+impl ManifestLoader for SizedSegmentLoader {
+    async fn load(&self, uri: &str) -> Result<LoadResponse, LoadError> {
+        self.playlists.load(uri).await
+    }
+
+    async fn probe(&self, uri: &str, _method: ProbeMethod) -> Result<ProbeResponse, LoadError> {
+        let name = uri.rsplit('/').next().unwrap_or(uri);
+        Ok(ProbeResponse { status: 200, content_length: self.sizes.get(name).copied() })
+    }
+}
+
+#[tokio::test]
+async fn test_measured_bitrate_above_bandwidth_is_reported() {
+    let step = Arc::new(AtomicUsize::new(0));
+    let mut responses = HashMap::new();
+    responses.insert(MASTER_URL.to_string(), vec![MASTER_PLAYLIST.to_string()]);
+    responses.insert(LEVEL0_URL.to_string(), vec![mp(0, None, &[s("l0_a.ts"), s("l0_b.ts")])]);
+    responses.insert(LEVEL1_URL.to_string(), vec![mp(0, None, &[s("l1_a.ts"), s("l1_b.ts")])]);
+
+    // 10s segments: level_0 (BANDWIDTH=1212000) carries 1.5 Mb/s in its newest
+    // segment, level_1 (BANDWIDTH=2424000) stays at 2 Mb/s.
+    let loader = Arc::new(SizedSegmentLoader {
+        playlists: SequenceLoader { step: Arc::clone(&step), responses },
+        sizes: HashMap::from([
+            ("l0_a.ts", 1_250_000),
+            ("l0_b.ts", 1_875_000),
+            ("l1_a.ts", 2_500_000),
+            ("l1_b.ts", 2_500_000),
+        ]),
+    });
+
+    let config = MonitorConfig::default().with_stale_limit(8000).with_bitrate_check(true);
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: MASTER_URL.to_string(),
+    };
+    let monitor = Monitor::new(vec![stream], config, loader, None);
+    monitor.poll_once().await;
+
+    let errors = monitor.get_errors().await;
+    let exceeded: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::BitrateExceeded).collect();
+    assert_eq!(exceeded.len(), 1, "{:#?}", errors);
+    assert_eq!(exceeded[0].variant, "1212000_level_0");
+    assert!(exceeded[0].details.contains("l0_b.ts"));
+    assert!(exceeded[0].details.contains("1500000 bps"));
+}
+
+/// A minimal MPEG-TS segment: PAT, PMT (one H.264 stream on PID 0x100, also
+/// the PCR PID) and one PES packet per frame with a PTS and PCR.
+/// A single-file media playlist: 10s segments of `lengths` bytes, back to back in `main.ts`.
+fn byterange_playlist(mseq: u64, first_offset: u64, lengths: &[u64]) -> String {
+    let mut out = format!(
+        "#EXTM3U\n#EXT-X-VERSION:4\n#EXT-X-TARGETDURATION:10\n#EXT-X-MEDIA-SEQUENCE:{}\n",
+        mseq
+    );
+    let mut offset = first_offset;
+    for len in lengths {
+        writeln!(out, "#EXTINF:10.000,\n#EXT-X-BYTERANGE:{}@{}\nmain.ts", len, offset).unwrap();
+        offset += len;
+    }
+    out
+}
+
+#[tokio::test]
+async fn test_byterange_segment_bitrate_measured_on_every_poll() {
+    let step = Arc::new(AtomicUsize::new(0));
+    let mut responses = HashMap::new();
+    responses.insert(MASTER_URL.to_string(), vec![MASTER_PLAYLIST.to_string()]);
+    // level_0 (BANDWIDTH=1212000) runs at 1 Mb/s, then publishes a 2 Mb/s segment.
+    responses.insert(
+        LEVEL0_URL.to_string(),
+        vec![
+            byterange_playlist(0, 0, &[1_250_000, 1_250_000]),
+            byterange_playlist(1, 1_250_000, &[1_250_000, 2_500_000]),
+        ],
+    );
+    responses.insert(LEVEL1_URL.to_string(), vec![mp(0, None, &[s("l1_a.ts"), s("l1_b.ts")])]);
+
+    let loader = Arc::new(SizedSegmentLoader {
+        playlists: SequenceLoader { step: Arc::clone(&step), responses },
+        sizes: HashMap::from([("l1_a.ts", 2_500_000), ("l1_b.ts", 2_500_000)]),
+    });
+
+    let config = MonitorConfig::default().with_stale_limit(8000).with_bitrate_check(true);
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: MASTER_URL.to_string(),
+    };
+    let monitor = Monitor::new(vec![stream], config, loader, None);
+    for poll in 0..2 {
+        step.store(poll, Ordering::SeqCst);
+        monitor.poll_once().await;
+    }
+
+    let errors = monitor.get_errors().await;
+    let exceeded: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::BitrateExceeded).collect();
+    assert_eq!(exceeded.len(), 1, "{:#?}", errors);
+    assert_eq!(exceeded[0].variant, "1212000_level_0");
+    assert!(exceeded[0].details.contains("main.ts"));
+    assert!(exceeded[0].details.contains("2000000 bps"));
+}
+
+fn ts_segment(start_pts: u64, frames: u64, frame_ticks: u64) -> Vec<u8> {
+    fn packet(pid: u16, pusi: bool, cc: u8, adaptation: &[u8], payload: &[u8]) -> Vec<u8> {
+        let afc = if adaptation.is_empty() { 0x10 } else { 0x30 };
+        let mut p = vec![0x47, ((pusi as u8) << 6) | (pid >> 8) as u8, pid as u8, afc | (cc & 0x0f)];
+        if !adaptation.is_empty() {
+            p.push(adaptation.len() as u8);
+            p.extend_from_slice(adaptation);
+        }
+        p.extend_from_slice(payload);
+        p.resize(188, 0xff);
+        p
+    }
+
+    let pat = [0x00, 0x00, 0xb0, 0x0d, 0x00, 0x01, 0xc1, 0x00, 0x00, 0x00, 0x01, 0xf0, 0x00, 0, 0, 0, 0];
+    let pmt = [
+        0x00, 0x02, 0xb0, 0x12, 0x00, 0x01, 0xc1, 0x00, 0x00, 0xe1, 0x00, 0xf0, 0x00, 0x1b, 0xe1, 0x00, 0xf0, 0x00, 0, 0,
+        0, 0,
+    ];
+    let mut out = packet(0x0000, true, 0, &[], &pat);
+    out.extend(packet(0x1000, true, 0, &[], &pmt));
+    for i in 0..frames {
+        let ts = start_pts + i * frame_ticks;
+        let pcr = [0x10, (ts >> 25) as u8, (ts >> 17) as u8, (ts >> 9) as u8, (ts >> 1) as u8, ((ts & 1) << 7) as u8 | 0x7e, 0];
+        let pes = [
+            0x00, 0x00, 0x01, 0xe0, 0x00, 0x00, 0x80, 0x80, 0x05,
+            0x21 | ((ts >> 29) & 0x0e) as u8,
+            (ts >> 22) as u8,
+            0x01 | ((ts >> 14) & 0xfe) as u8,
+            (ts >> 7) as u8,
+            0x01 | ((ts << 1) & 0xfe) as u8,
+        ];
+        out.extend(packet(0x0100, true, i as u8, &pcr, &pes));
+    }
+    out
+}
+
+/// Serves one media playlist and raw segment bodies by file name.
+struct SegmentBodyLoader {
+    playlist: String,
+    segments: HashMap<&'static str, Vec<u8>>,
+}
+
+#[async_trait]
+impl ManifestLoader for SegmentBodyLoader {
+    async fn load(&self, _uri: &str) -> Result<LoadResponse, LoadError> {
+        Ok(LoadResponse {
+            body: self.playlist.clone(),
+            content_type: None,
+            content_encoding: None,
+            elapsed: None,
+        })
+    }
+
+    async fn fetch_segment(&self, uri: &str) -> Result<Vec<u8>, LoadError> {
+        let name = uri.rsplit('/').next().unwrap_or(uri);
+        self.segments.get(name).cloned().ok_or_else(|| LoadError::Http {
+            url: uri.to_string(),
+            status: 404,
+            message: "Not Found".to_string(),
+            is_last_retry: true,
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_segment_inspection_flags_untagged_timestamp_jump() {
+    // 2s segments of 50 frames; ts2 starts 10s late without a discontinuity,
+    // ts3 jumps back after one.
+    let playlist = format!(
+        "{}#EXT-X-DISCONTINUITY\n#EXTINF:2.000,\nts3.ts\n",
+        mp_flex(0, 2, &[fs("ts0.ts", 2.0), fs("ts1.ts", 2.0), fs("ts2.ts", 2.0)])
+    );
+    let loader = Arc::new(SegmentBodyLoader {
+        playlist,
+        segments: HashMap::from([
+            ("ts0.ts", ts_segment(0, 50, 3600)),
+            ("ts1.ts", ts_segment(180_000, 50, 3600)),
+            ("ts2.ts", ts_segment(1_260_000, 50, 3600)),
+            ("ts3.ts", ts_segment(0, 50, 3600)),
+        ]),
+    });
+
+    let config = MonitorConfig::default().with_stale_limit(8000).with_segment_inspection(true);
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: LEVEL0_URL.to_string(),
+    };
+    let monitor = Monitor::new(vec![stream], config, loader, None);
+    monitor.poll_once().await;
+
+    let errors = monitor.get_errors().await;
+    let jumps: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::TimestampDiscontinuity).collect();
+    assert_eq!(jumps.len(), 2, "{:#?}", errors); // PTS and PCR of ts2.ts
+    assert!(jumps.iter().all(|e| e.details.contains("ts2.ts") && e.details.contains("gap of 10.000s")));
+}
+
+fn mp4_box(kind: &[u8; 4], version_flags: Option<u32>, body: &[u8]) -> Vec<u8> {
+    let header = if version_flags.is_some() { 12 } else { 8 };
+    let mut out = ((body.len() + header) as u32).to_be_bytes().to_vec();
+    out.extend_from_slice(kind);
+    if let Some(vf) = version_flags {
+        out.extend_from_slice(&vf.to_be_bytes());
+    }
+    out.extend_from_slice(body);
+    out
+}
+
+/// A minimal fMP4 init segment with one video track.
+fn fmp4_init(track_id: u32, timescale: u32) -> Vec<u8> {
+    let tkhd = [&[0u8; 8][..], &track_id.to_be_bytes(), &[0u8; 68]].concat();
+    let mdhd = [&[0u8; 8][..], &timescale.to_be_bytes(), &[0u8; 8]].concat();
+    let hdlr = [&[0u8; 4][..], b"vide", &[0u8; 13]].concat();
+    let mdia = [mp4_box(b"mdhd", Some(0), &mdhd), mp4_box(b"hdlr", Some(0), &hdlr)].concat();
+    let trak = [mp4_box(b"tkhd", Some(3), &tkhd), mp4_box(b"mdia", None, &mdia)].concat();
+    let trex = [&track_id.to_be_bytes()[..], &[0u8; 16]].concat();
+    let moov = [mp4_box(b"trak", None, &trak), mp4_box(b"mvex", None, &mp4_box(b"trex", Some(0), &trex))].concat();
+    [mp4_box(b"ftyp", None, b"iso6\0\0\0\0"), mp4_box(b"moov", None, &moov)].concat()
+}
+
+/// A minimal fMP4 media segment: one `moof` with `tfhd`, `tfdt` and a `trun`
+/// of `frames` samples of `frame_ticks` each.
+fn fmp4_fragment(track_id: u32, tfdt: u64, frames: u32, frame_ticks: u32) -> Vec<u8> {
+    let mut trun = frames.to_be_bytes().to_vec();
+    for _ in 0..frames {
+        trun.extend_from_slice(&frame_ticks.to_be_bytes());
+    }
+    let traf = [
+        mp4_box(b"tfhd", Some(0x020000), &track_id.to_be_bytes()),
+        mp4_box(b"tfdt", Some(0x01000000), &tfdt.to_be_bytes()),
+        mp4_box(b"trun", Some(0x100), &trun),
+    ]
+    .concat();
+    let moof = [mp4_box(b"mfhd", Some(0), &1u32.to_be_bytes()), mp4_box(b"traf", None, &traf)].concat();
+    [mp4_box(b"moof", None, &moof), mp4_box(b"mdat", None, &[0u8; 16])].concat()
+}
+
+#[tokio::test]
+async fn test_fmp4_inspection_checks_tfdt_and_init_tracks() {
+    // s1.m4s starts 3s after s0.m4s ends; s2.m4s carries a track the init
+    // segment does not declare.
+    let playlist = "#EXTM3U\n#EXT-X-VERSION:7\n#EXT-X-TARGETDURATION:2\n#EXT-X-MEDIA-SEQUENCE:0\n\
+                    #EXT-X-MAP:URI=\"init.mp4\"\n\
+                    #EXTINF:2.000,\ns0.m4s\n#EXTINF:2.000,\ns1.m4s\n#EXTINF:2.000,\ns2.m4s\n"
+        .to_string();
+    let loader = Arc::new(SegmentBodyLoader {
+        playlist,
+        segments: HashMap::from([
+            ("init.mp4", fmp4_init(1, 90_000)),
+            ("s0.m4s", fmp4_fragment(1, 0, 50, 3600)),
+            ("s1.m4s", fmp4_fragment(1, 450_000, 50, 3600)),
+            ("s2.m4s", fmp4_fragment(2, 630_000, 50, 3600)),
+        ]),
+    });
+
+    let config = MonitorConfig::default().with_stale_limit(8000).with_segment_inspection(true);
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: LEVEL0_URL.to_string(),
+    };
+    let monitor = Monitor::new(vec![stream], config, loader, None);
+    monitor.poll_once().await;
+
+    let errors = monitor.get_errors().await;
+    let jumps: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::TimestampDiscontinuity).collect();
+    assert_eq!(jumps.len(), 1, "{:#?}", errors);
+    assert!(jumps[0].details.contains("s1.m4s") && jumps[0].details.contains("gap of 3.000s"));
+    let mismatches: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::InitSegmentMismatch).collect();
+    assert_eq!(mismatches.len(), 1, "{:#?}", errors);
+    assert!(mismatches[0].details.contains("s2.m4s") && mismatches[0].details.contains("track_ID 2"));
+}
+
+#[tokio::test]
+async fn test_init_segment_change_without_discontinuity_and_unavailable_map() {
+    let header = "#EXTM3U\n#EXT-X-VERSION:7\n#EXT-X-TARGETDURATION:2\n";
+    let step = Arc::new(AtomicUsize::new(0));
+    let loader = |step: &Arc<AtomicUsize>| {
+        Arc::new(ProbeLoader {
+            step: Arc::clone(step),
+            responses: vec![
+                format!(
+                    "{}#EXT-X-MEDIA-SEQUENCE:0\n#EXT-X-MAP:URI=\"init.mp4\"\n#EXTINF:2.000,\ns0.m4s\n#EXTINF:2.000,\ns1.m4s\n",
+                    header
+                ),
+                format!(
+                    "{}#EXT-X-MEDIA-SEQUENCE:1\n#EXT-X-MAP:URI=\"init.mp4\"\n#EXTINF:2.000,\ns1.m4s\n\
+                     #EXT-X-MAP:URI=\"init2.mp4\"\n#EXTINF:2.000,\ns2.m4s\n",
+                    header
+                ),
+            ],
+            statuses: HashMap::from([("init2.mp4", (404, None))]),
+            probed: std::sync::Mutex::new(Vec::new()),
+        })
+    };
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: LEVEL0_URL.to_string(),
+    };
+
+    // Init segments are only probed when probing is enabled.
+    let unprobed = loader(&step);
+    let monitor = Monitor::new(vec![stream.clone()], MonitorConfig::default().with_stale_limit(8000), unprobed.clone(), None);
+    for poll in 0..2 {
+        step.store(poll, Ordering::SeqCst);
+        monitor.poll_once().await;
+    }
+    assert!(unprobed.probed.lock().unwrap().is_empty());
+
+    let probing = loader(&step);
+    let config = MonitorConfig::default().with_stale_limit(8000).with_probe_segments(true);
+    let monitor = Monitor::new(vec![stream], config, probing.clone(), None);
+    for poll in 0..2 {
+        step.store(poll, Ordering::SeqCst);
+        monitor.poll_once().await;
+    }
+
+    let probed: Vec<String> = probing.probed.lock().unwrap().iter().filter(|u| u.contains("init")).cloned().collect();
+    assert_eq!(probed.len(), 2, "{:?}", probed);
+    assert!(probed[0].ends_with("/init.mp4") && probed[1].ends_with("/init2.mp4"));
+
+    let errors = monitor.get_errors().await;
+    let changes: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::InitSegmentChange).collect();
+    assert_eq!(changes.len(), 1, "{:#?}", errors);
+    assert!(changes[0].details.contains("s2.m4s"));
+    let unavailable: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::InitSegmentUnavailable).collect();
+    assert_eq!(unavailable.len(), 1, "{:#?}", errors);
+    assert!(unavailable[0].details.contains("init2.mp4") && unavailable[0].details.contains("HTTP 404"));
+
+    let events = monitor.get_events().await;
+    assert_eq!(count_events(&events, EventKind::InitSegmentChanged), 1);
+}
+
+/// Serves playlists by poll step and AES-128 keys by file name, recording
+/// each key request with its Authorization header.
+struct KeyLoader {
+    step: Arc<AtomicUsize>,
+    responses: Vec<String>,
+    keys: HashMap<&'static str, Vec<u8>>,
+    fetched: std::sync::Mutex<Vec<(String, Option<String>)>>,
+}
+
+#[async_trait]
+impl ManifestLoader for KeyLoader {
+    async fn load(&self, _uri: &str) -> Result<LoadResponse, LoadError> {
+        let idx = self.step.load(Ordering::SeqCst).min(self.responses.len() - 1);
+        Ok(LoadResponse {
+            body: self.responses[idx].clone(),
+            content_type: None,
+            content_encoding: None,
+            elapsed: None,
+        })
+    }
+
+    async fn fetch_key(&self, uri: &str, headers: &BTreeMap<String, String>) -> Result<Vec<u8>, LoadError> {
+        self.fetched.lock().unwrap().push((uri.to_string(), headers.get("Authorization").cloned()));
+        let name = uri.rsplit('/').next().unwrap_or(uri);
+        self.keys.get(name).cloned().ok_or_else(|| LoadError::Http {
+            url: uri.to_string(),
+            status: 403,
+            message: "Forbidden".to_string(),
+            is_last_retry: true,
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_key_check_refetches_failing_keys_and_reports_rotation() {
+    let playlist = |mseq: u64, segs: &[(&str, &str)]| {
+        let mut out = format!(
+            "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:2\n#EXT-X-MEDIA-SEQUENCE:{}\n",
+            mseq
+        );
+        for (key, uri) in segs {
+            writeln!(out, "#EXT-X-KEY:METHOD=AES-128,URI=\"{}\"\n#EXTINF:2.000,\n{}", key, uri).unwrap();
+        }
+        out
+    };
+    let step = Arc::new(AtomicUsize::new(0));
+    let loader = Arc::new(KeyLoader {
+        step: Arc::clone(&step),
+        responses: vec![
+            playlist(0, &[("k1.bin", "s0.ts"), ("k1.bin", "s1.ts")]),
+            playlist(1, &[("k1.bin", "s1.ts"), ("k2.bin", "s2.ts")]),
+            playlist(2, &[("k2.bin", "s2.ts"), ("k3.bin", "s3.ts")]),
+        ],
+        keys: HashMap::from([("k1.bin", vec![1u8; 16]), ("k2.bin", vec![2u8; 32])]),
+        fetched: std::sync::Mutex::new(Vec::new()),
+    });
+
+    let config = MonitorConfig::default()
+        .with_stale_limit(8000)
+        .with_key_check(true)
+        .with_key_header("Authorization", "Bearer t0ken");
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: LEVEL0_URL.to_string(),
+    };
+    let monitor = Monitor::new(vec![stream], config, loader.clone(), None);
+    for poll in 0..3 {
+        step.store(poll, Ordering::SeqCst);
+        monitor.poll_once().await;
+    }
+
+    let fetched = loader.fetched.lock().unwrap().clone();
+    let names: Vec<&str> = fetched.iter().map(|(url, _)| url.rsplit('/').next().unwrap()).collect();
+    // k1 passed and is fetched once; the invalid k2 is checked again while it stays.
+    assert_eq!(names, ["k1.bin", "k2.bin", "k2.bin", "k3.bin"]);
+    assert!(fetched.iter().all(|(_, auth)| auth.as_deref() == Some("Bearer t0ken")));
+
+    let errors = monitor.get_errors().await;
+    let invalid: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::InvalidKey).collect();
+    assert_eq!(invalid.len(), 2, "{:#?}", errors);
+    assert!(invalid.iter().all(|e| e.details.contains("k2.bin") && e.details.contains("32 bytes")));
+    let unavailable: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::KeyUnavailable).collect();
+    assert_eq!(unavailable.len(), 1, "{:#?}", errors);
+    assert_eq!(unavailable[0].status_code, Some(403));
+
+    let events = monitor.get_events().await;
+    assert_eq!(count_events(&events, EventKind::KeyRotated), 2);
+}
+
+/// ANSI/SCTE 35 sample 14.2: splice_insert, out of network, 60.293 s break.
+const SPLICE_INSERT_B64: &str = "/DAvAAAAAAAA///wFAVIAACPf+/+c2nALv4AUsz1AAAAAAAKAAhDVUVJAAABNWLbowo=";
+const SPLICE_INSERT_HEX: &str =
+    "0xFC302F000000000000FFFFF014054800008F7FEFFE7369C02EFE0052CCF500000000000A0008435545490000013562DBA30A";
+
+#[tokio::test]
+async fn test_scte35_payloads_are_decoded_and_checked_against_tags() {
+    let head = "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:10\n";
+    let level0 = vec![
+        format!("{}#EXT-X-MEDIA-SEQUENCE:0\n#EXTINF:10.000,\na0.ts\n", head),
+        format!(
+            "{}#EXT-X-MEDIA-SEQUENCE:0\n#EXTINF:10.000,\na0.ts\n#EXT-OATCLS-SCTE35:{}\n#EXT-X-CUE-OUT:30\n#EXTINF:10.000,\nad0.ts\n",
+            head, SPLICE_INSERT_B64
+        ),
+    ];
+    let level1 = vec![
+        format!("{}#EXT-X-MEDIA-SEQUENCE:0\n#EXTINF:10.000,\nb0.ts\n", head),
+        format!(
+            "{}#EXT-X-MEDIA-SEQUENCE:0\n#EXTINF:10.000,\nb0.ts\n#EXT-X-DATERANGE:ID=\"1\",START-DATE=\"2024-01-01T00:00:10Z\",PLANNED-DURATION=60.293,SCTE35-OUT={}\n#EXTINF:10.000,\nbad0.ts\n",
+            head, SPLICE_INSERT_HEX
+        ),
+    ];
+
+    let (errors, events) = run_sequence_scte35_with_events(level0, level1, 2).await;
+
+    let scte: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::Scte35Violation).collect();
+    assert_eq!(scte.len(), 1, "{:#?}", scte);
+    assert!(scte[0].details.contains("EXT-OATCLS-SCTE35 in mseq(1): tag duration 30.000s differs from signalled 60.294s"), "{}", scte[0].details);
+
+    let signals: Vec<_> = events.iter().filter(|e| e.kind == EventKind::Scte35Signal).collect();
+    assert_eq!(signals.len(), 2, "{:#?}", signals);
+    for event in &signals {
+        assert!(event.details.contains("splice_insert event_id=1207959695 out_of_network=true"), "{}", event.details);
+        assert!(event.scte35.as_ref().unwrap().crc_valid);
+    }
+    assert!(signals.iter().any(|e| e.details.starts_with("EXT-X-DATERANGE SCTE35-OUT at mseq 1")));
+}
+
+#[tokio::test]
+async fn test_ad_break_actual_duration_compared_with_planned() {
+    // level0 returns after 20s of a 30s break, level1 after the full 30s.
+    let level0 = vec![
+        mp(0, None, &[s("a0.ts"), s("a1.ts")]),
+        mp(1, None, &[s("a1.ts"), ds_co("ad_0.ts", "30")]),
+        mp(2, None, &[ds_co("ad_0.ts", "30"), s("ad_1.ts")]),
+        mp(3, None, &[s("ad_1.ts"), ds_ci("a2.ts")]),
+    ];
+    let level1 = vec![
+        mp(0, None, &[s("b0.ts"), s("b1.ts")]),
+        mp(1, None, &[s("b1.ts"), ds_co("bad_0.ts", "30"), s("bad_1.ts")]),
+        mp(3, None, &[s("bad_1.ts"), s("bad_2.ts")]),
+        mp(4, None, &[s("bad_2.ts"), ds_ci("b2.ts")]),
+    ];
+
+    let (errors, events) = run_sequence_scte35_with_events(level0, level1, 4).await;
+
+    let mismatches: Vec<_> = errors
+        .iter()
+        .filter(|e| e.error_type == ErrorType::AdBreakDurationMismatch)
+        .collect();
+    assert_eq!(mismatches.len(), 1, "{:#?}", mismatches);
+    assert!(
+        mismatches[0].details.contains("under-ran: actual 20.000s, planned 30.000s"),
+        "{}",
+        mismatches[0].details
+    );
+
+    let completed: Vec<_> = events.iter().filter(|e| e.kind == EventKind::AdBreakCompleted).collect();
+    assert_eq!(completed.len(), 2, "{:#?}", completed);
+    assert!(completed.iter().any(|e| e.details == "Ad break mseq 2..4 ran 20.0s, planned 30.0s"));
+    assert!(completed.iter().any(|e| e.details == "Ad break mseq 2..5 ran 30.0s, planned 30.0s"));
+    let started = events.iter().find(|e| e.kind == EventKind::CueOutStarted).unwrap();
+    assert!(started.details.ends_with("duration=30.0s"), "{}", started.details);
+}
+
+/// Ad break signalled only with DATERANGE SCTE35-OUT/IN, 20s planned and run.
+fn daterange_break_steps() -> Vec<String> {
+    let head = "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:10\n";
+    let out = format!(
+        "#EXT-X-DATERANGE:ID=\"ad1\",START-DATE=\"2024-01-01T00:00:20Z\",PLANNED-DURATION=20,SCTE35-OUT={}\n",
+        SPLICE_INSERT_HEX
+    );
+    let back = "#EXT-X-DATERANGE:ID=\"ad1-in\",START-DATE=\"2024-01-01T00:00:40Z\",SCTE35-IN=0xFC\n";
+    vec![
+        format!("{}#EXT-X-MEDIA-SEQUENCE:0\n#EXTINF:10.000,\nc0.ts\n#EXTINF:10.000,\nc1.ts\n", head),
+        format!("{}#EXT-X-MEDIA-SEQUENCE:1\n#EXTINF:10.000,\nc1.ts\n{}#EXTINF:10.000,\nad0.ts\n", head, out),
+        format!("{}#EXT-X-MEDIA-SEQUENCE:2\n{}#EXTINF:10.000,\nad0.ts\n#EXTINF:10.000,\nad1.ts\n", head, out),
+        format!("{}#EXT-X-MEDIA-SEQUENCE:3\n#EXTINF:10.000,\nad1.ts\n{}#EXTINF:10.000,\nc2.ts\n", head, back),
+    ]
+}
+
+#[tokio::test]
+async fn test_daterange_ad_markers_drive_cue_events_per_dialect() {
+    for (dialect, expected) in [(AdDialect::Auto, 1), (AdDialect::Daterange, 1), (AdDialect::Cue, 0)] {
+        let step = Arc::new(AtomicUsize::new(0));
+        let loader = Arc::new(SequenceLoader {
+            step: Arc::clone(&step),
+            responses: HashMap::from([(LEVEL0_URL.to_string(), daterange_break_steps())]),
+        });
+        let config = MonitorConfig::default()
+            .with_stale_limit(8000)
+            .with_scte35(true)
+            .with_ad_dialect(dialect);
+        let stream = StreamItem {
+            id: "stream_1".to_string(),
+            url: LEVEL0_URL.to_string(),
+        };
+        let monitor = Monitor::new(vec![stream], config, loader, None);
+        for poll in 0..4 {
+            step.store(poll, Ordering::SeqCst);
+            monitor.poll_once().await;
+        }
+
+        let events = monitor.get_events().await;
+        assert_eq!(count_events(&events, EventKind::CueOutStarted), expected, "{}", dialect);
+        assert_eq!(count_events(&events, EventKind::CueInReturned), expected, "{}", dialect);
+        let completed: Vec<_> = events.iter().filter(|e| e.kind == EventKind::AdBreakCompleted).collect();
+        assert_eq!(completed.len(), expected, "{}", dialect);
+        if expected > 0 {
+            assert_eq!(completed[0].details, "Ad break mseq 2..4 ran 20.0s, planned 20.0s");
+        }
+
+        let errors = monitor.get_errors().await;
+        let cue_errors: Vec<_> = errors
+            .iter()
+            .filter(|e| e.error_type == ErrorType::Scte35Violation && e.details.contains("CUE"))
+            .collect();
+        assert!(cue_errors.is_empty(), "{}: {:#?}", dialect, cue_errors);
+    }
+}
+
+#[tokio::test]
+async fn test_ad_break_alignment_across_variants() {
+    let level0 = || {
+        vec![
+            mp(0, None, &[s("c0.ts"), s("c1.ts")]),
+            mp(1, None, &[s("c1.ts"), ds_co("ad0.ts", "20")]),
+            mp(2, None, &[ds_co("ad0.ts", "20"), s("ad1.ts")]),
+        ]
+    };
+    let misalignments = |errors: &[hls_monitor::MonitorError]| -> Vec<String> {
+        errors
+            .iter()
+            .filter(|e| e.error_type == ErrorType::AdBreakMisalignment)
+            .map(|e| e.details.clone())
+            .collect()
+    };
+
+    let (errors, _) = run_sequence_scte35_with_events(level0(), level0(), 3).await;
+    assert!(misalignments(&errors).is_empty(), "{:#?}", errors);
+
+    // level_1 enters the break one segment later.
+    let level1 = vec![
+        mp(0, None, &[s("c0.ts"), s("c1.ts")]),
+        mp(1, None, &[s("c1.ts"), s("c2.ts")]),
+        mp(2, None, &[s("c2.ts"), ds_co("ad0.ts", "20")]),
+    ];
+    let (errors, _) = run_sequence_scte35_with_events(level0(), level1, 3).await;
+    let details = misalignments(&errors);
+    assert!(
+        details.iter().any(|d| d == "CUE-OUT at mseq(2) in '1212000_level_0' but not in every rendition: '2424000_level_1' has none"),
+        "{:#?}",
+        details
+    );
+    assert!(
+        details.iter().any(|d| d == "CUE-OUT at mseq(2) in '1212000_level_0' but not in every rendition: '2424000_level_1' at mseq(3)"),
+        "{:#?}",
+        details
+    );
+
+    // Same boundary, different announced duration.
+    let level1 = vec![
+        mp(0, None, &[s("c0.ts"), s("c1.ts")]),
+        mp(1, None, &[s("c1.ts"), ds_co("ad0.ts", "30")]),
+    ];
+    let (errors, _) = run_sequence_scte35_with_events(level0(), level1, 2).await;
+    assert_eq!(
+        misalignments(&errors),
+        vec!["CUE-OUT at mseq(2) announces different break durations across renditions: '1212000_level_0'=20.000s, '2424000_level_1'=30.000s"]
+    );
+
+    // Without SCTE-35 validation the check does not run.
+    let level1 = vec![mp(0, None, &[s("c0.ts"), s("c1.ts")]), mp(1, None, &[s("c1.ts"), s("c2.ts")])];
+    let errors = run_sequence(level0(), level1, 2).await;
+    assert!(misalignments(&errors).is_empty(), "{:#?}", errors);
+}
+
+/// Two 10s segments from `mseq`, the first stamped `start` plus 10s per mseq.
+fn pdt_playlist(mseq: u64, start: &str) -> String {
+    let base = chrono::DateTime::parse_from_rfc3339(start).unwrap();
+    let mut out = format!(
+        "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:10\n#EXT-X-MEDIA-SEQUENCE:{}\n",
+        mseq
+    );
+    for n in mseq..mseq + 2 {
+        let pdt = base + chrono::Duration::seconds(10 * n as i64);
+        writeln!(out, "#EXT-X-PROGRAM-DATE-TIME:{}\n#EXTINF:10.000,\ns{}.ts", pdt.to_rfc3339(), n).unwrap();
+    }
+    out
+}
+
+#[tokio::test]
+async fn test_variant_pdt_drift_across_variants() {
+    let level0 = || vec![pdt_playlist(0, "2024-01-01T00:00:00Z"), pdt_playlist(1, "2024-01-01T00:00:00Z")];
+
+    let errors = run_sequence(level0(), vec![pdt_playlist(0, "2024-01-01T00:00:00.200Z")], 2).await;
+    assert!(!errors.iter().any(|e| e.error_type == ErrorType::VariantPdtDrift), "{:#?}", errors);
+
+    // level_1 maps every mseq 2s later.
+    let errors = run_sequence(level0(), vec![pdt_playlist(0, "2024-01-01T00:00:02Z")], 2).await;
+    let drift: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::VariantPdtDrift).collect();
+    assert_eq!(drift.len(), 2, "{:#?}", drift);
+    assert!(
+        drift
+            .iter()
+            .any(|e| e.details.starts_with("Variant PDT drift at mseq(0): '2424000_level_1' is 2.000s ahead of '1212000_level_0'")),
+        "{:#?}",
+        drift
+    );
+}
+
+#[tokio::test]
+async fn test_live_edge_latency_and_future_pdt() {
+    let run = |offset_secs: i64| async move {
+        // Two 10s segments, the first stamped `offset_secs` from now.
+        let start = chrono::Utc::now() + chrono::Duration::seconds(offset_secs);
+        let playlist = pdt_playlist(0, &start.to_rfc3339());
+        let loader = Arc::new(SequenceLoader {
+            step: Arc::new(AtomicUsize::new(0)),
+            responses: HashMap::from([(LEVEL0_URL.to_string(), vec![playlist])]),
+        });
+        let config = MonitorConfig::default().with_stale_limit(8000).with_max_live_latency(30.0);
+        let stream = StreamItem {
+            id: "stream_1".to_string(),
+            url: LEVEL0_URL.to_string(),
+        };
+        let monitor = Monitor::new(vec![stream], config, loader, None);
+        monitor.poll_once().await;
+        let status = monitor.get_stream_status().await;
+        (monitor.get_errors().await, status[0].variants[0].live_edge_latency_secs)
+    };
+
+    // Live edge 10s behind.
+    let (errors, latency) = run(-30).await;
+    assert!(latency.is_some_and(|l| (10.0..12.0).contains(&l)), "{:?}", latency);
+    assert!(
+        !errors
+            .iter()
+            .any(|e| matches!(e.error_type, ErrorType::LiveEdgeLatency | ErrorType::FuturePdt)),
+        "{:#?}",
+        errors
+    );
+
+    // Live edge 60s behind.
+    let (errors, _) = run(-80).await;
+    let late: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::LiveEdgeLatency).collect();
+    assert_eq!(late.len(), 1, "{:#?}", errors);
+    assert!(late[0].details.contains("over the 30.000s limit"), "{}", late[0].details);
+
+    // Encoder clock a minute fast.
+    let (errors, latency) = run(60).await;
+    assert!(latency.is_some_and(|l| l < -70.0), "{:?}", latency);
+    assert_eq!(errors.iter().filter(|e| e.error_type == ErrorType::FuturePdt).count(), 1, "{:#?}", errors);
+}
+
+/// `count` 10s segments from `mseq` whose PDTs are `pdt_step` seconds apart.
+fn drifting_playlist(mseq: u64, count: u64, pdt_step: f64) -> String {
+    let base = chrono::DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap();
+    let mut out = format!(
+        "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:10\n#EXT-X-MEDIA-SEQUENCE:{}\n",
+        mseq
+    );
+    for n in mseq..mseq + count {
+        let pdt = base + chrono::Duration::milliseconds((n as f64 * pdt_step * 1000.0).round() as i64);
+        writeln!(out, "#EXT-X-PROGRAM-DATE-TIME:{}\n#EXTINF:10.000,\ns{}.ts", pdt.to_rfc3339(), n).unwrap();
+    }
+    out
+}
+
+#[tokio::test]
+async fn test_encoder_clock_drift_tracked_across_polls() {
+    // 20ms of PDT per 10s segment too many: 7200ms/hour.
+    let steps = vec![drifting_playlist(0, 36, 10.02), drifting_playlist(30, 36, 10.02), drifting_playlist(32, 36, 10.02)];
+    let step = Arc::new(AtomicUsize::new(0));
+    let loader = Arc::new(SequenceLoader {
+        step: Arc::clone(&step),
+        responses: HashMap::from([(LEVEL0_URL.to_string(), steps)]),
+    });
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: LEVEL0_URL.to_string(),
+    };
+    let monitor = Monitor::new(vec![stream], MonitorConfig::default().with_stale_limit(8000), loader, None);
+
+    step.store(0, Ordering::SeqCst);
+    monitor.poll_once().await;
+    let status = monitor.get_stream_status().await;
+    assert_eq!(status[0].variants[0].clock_drift_ms_per_hour, None, "only 350s observed");
+
+    for poll in 1..3 {
+        step.store(poll, Ordering::SeqCst);
+        monitor.poll_once().await;
+    }
+    let status = monitor.get_stream_status().await;
+    let rate = status[0].variants[0].clock_drift_ms_per_hour.unwrap();
+    assert!((rate - 7185.6).abs() < 1.0, "{}", rate);
+
+    let errors = monitor.get_errors().await;
+    let drift: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::ClockDrift).collect();
+    assert_eq!(drift.len(), 1, "reported once: {:#?}", errors);
+    assert!(drift[0].details.contains("ms/hour"));
+    assert!(!errors.iter().any(|e| e.error_type == ErrorType::ProgramDateTimeJump), "{:#?}", errors);
+}
+
+struct AssetLoader {
+    playlist: String,
+    assets: HashMap<&'static str, String>,
+    loaded: std::sync::Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl ManifestLoader for AssetLoader {
+    async fn load(&self, uri: &str) -> Result<LoadResponse, LoadError> {
+        if uri == LEVEL0_URL {
+            return Ok(LoadResponse { body: self.playlist.clone(), content_type: None, content_encoding: None, elapsed: None });
+        }
+        self.loaded.lock().unwrap().push(uri.to_string());
+        match self.assets.get(uri) {
+            Some(body) => Ok(LoadResponse { body: body.clone(), content_type: None, content_encoding: None, elapsed: None }),
+            None => Err(LoadError::Http {
+                url: uri.to_string(),
+                status: 404,
+                message: "Not Found".to_string(),
+                is_last_retry: true,
+            }),
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_interstitials_are_validated_and_assets_refetched_until_they_pass() {
+    let interstitial = |id: &str, start: &str, attrs: &str| {
+        format!(
+            "#EXT-X-DATERANGE:ID=\"{}\",CLASS=\"com.apple.hls.interstitial\",START-DATE=\"{}\",DURATION=15.0,{}\n",
+            id, start, attrs
+        )
+    };
+    let playlist = format!(
+        "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:10\n#EXT-X-MEDIA-SEQUENCE:0\n\
+         #EXT-X-PROGRAM-DATE-TIME:2024-01-01T00:00:00Z\n{}#EXTINF:10.000,\ns0.ts\n{}#EXTINF:10.000,\ns1.ts\n{}#EXTINF:10.000,\ns2.ts\n",
+        interstitial("ad1", "2024-01-01T00:00:00Z", "X-ASSET-URI=\"https://ads.mock.com/ad1.m3u8\",X-RESTRICT=\"SKIP,JUMP\""),
+        interstitial("ad2", "2024-01-01T00:00:10Z", "X-ASSET-LIST=\"list.json\",X-RESUME-OFFSET=0"),
+        interstitial("ad3", "2024-01-01T00:00:20Z", "X-ASSET-URI=\"https://ads.mock.com/ad1.m3u8\",X-ASSET-LIST=\"list.json\",X-RESTRICT=\"SEEK\""),
+    );
+    let asset = "#EXTM3U\n#EXT-X-TARGETDURATION:5\n#EXTINF:5.000,\nad.ts\n#EXT-X-ENDLIST\n".to_string();
+    let loader = Arc::new(AssetLoader {
+        playlist,
+        assets: HashMap::from([
+            ("https://ads.mock.com/ad1.m3u8", asset.clone()),
+            (
+                "https://mock.mock.com/channels/1xx/list.json",
+                r#"{"ASSETS":[{"URI":"https://ads.mock.com/ad2.m3u8","DURATION":5.0},{"URI":"missing.m3u8","DURATION":10.0}]}"#.to_string(),
+            ),
+            ("https://ads.mock.com/ad2.m3u8", "garbage".to_string()),
+        ]),
+        loaded: std::sync::Mutex::new(Vec::new()),
+    });
+
+    let config = MonitorConfig::default().with_stale_limit(8000).with_interstitial_fetch(true);
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: LEVEL0_URL.to_string(),
+    };
+    let monitor = Monitor::new(vec![stream], config, loader.clone(), None);
+    monitor.poll_once().await;
+    monitor.poll_once().await;
+
+    // ad1 passed and is fetched once; the broken list is checked on both polls.
+    let mut loaded = loader.loaded.lock().unwrap().clone();
+    loaded.sort();
+    assert_eq!(
+        loaded,
+        [
+            "https://ads.mock.com/ad1.m3u8",
+            "https://ads.mock.com/ad2.m3u8",
+            "https://ads.mock.com/ad2.m3u8",
+            "https://mock.mock.com/channels/1xx/list.json",
+            "https://mock.mock.com/channels/1xx/list.json",
+            "https://mock.mock.com/channels/1xx/missing.m3u8",
+            "https://mock.mock.com/channels/1xx/missing.m3u8",
+        ]
+    );
+
+    let errors = monitor.get_errors().await;
+    let violations: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::InterstitialViolation).collect();
+    assert!(
+        violations.iter().any(|e| e.details.contains("'ad3'") && e.details.contains("has both X-ASSET-URI and X-ASSET-LIST")),
+        "{:#?}",
+        violations
+    );
+    assert!(violations.iter().any(|e| e.details.contains("unknown value 'SEEK'")), "{:#?}", violations);
+    assert!(violations.iter().all(|e| e.details.contains("'ad3'")), "{:#?}", violations);
+
+    let invalid: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::InvalidInterstitialAsset).collect();
+    assert_eq!(invalid.len(), 2, "{:#?}", errors);
+    assert!(invalid[0].details.contains("ad2.m3u8") && invalid[0].details.contains("could not be parsed"));
+    let unavailable: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::InterstitialAssetUnavailable).collect();
+    assert_eq!(unavailable.len(), 2, "{:#?}", errors);
+    assert!(unavailable[0].details.starts_with("Interstitial 'ad2' asset 'https://mock.mock.com/channels/1xx/missing.m3u8'"));
+    assert_eq!(unavailable[0].status_code, Some(404));
+}
+
+#[tokio::test]
+async fn test_iframe_playlists_are_fetched_and_checked() {
+    let playlist = "#EXTM3U\n\
+        #EXT-X-STREAM-INF:BANDWIDTH=2000000,RESOLUTION=1280x720\n\
+        level_1.m3u8\n\
+        #EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=200000,RESOLUTION=1280x720,URI=\"iframe_ok.m3u8\"\n\
+        #EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=150000,RESOLUTION=960x540,URI=\"iframe_regular.m3u8\"\n\
+        #EXT-X-I-FRAME-STREAM-INF:BANDWIDTH=100000,RESOLUTION=640x360,URI=\"iframe_missing.m3u8\"\n"
+        .to_string();
+    let media = "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:10\n#EXT-X-MEDIA-SEQUENCE:0\n\
+                 #EXTINF:10.000,\ns0.ts\n#EXT-X-ENDLIST\n"
+        .to_string();
+    let i_frames = "#EXTM3U\n#EXT-X-VERSION:4\n#EXT-X-TARGETDURATION:10\n#EXT-X-MEDIA-SEQUENCE:0\n#EXT-X-I-FRAMES-ONLY\n\
+                    #EXTINF:10.000,\n#EXT-X-BYTERANGE:1000@376\ns0.ts\n#EXT-X-ENDLIST\n"
+        .to_string();
+    let loader = Arc::new(AssetLoader {
+        playlist,
+        assets: HashMap::from([
+            ("https://mock.mock.com/channels/1xx/level_1.m3u8", media.clone()),
+            ("https://mock.mock.com/channels/1xx/iframe_ok.m3u8", i_frames),
+            ("https://mock.mock.com/channels/1xx/iframe_regular.m3u8", media),
+        ]),
+        loaded: std::sync::Mutex::new(Vec::new()),
+    });
+
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: LEVEL0_URL.to_string(),
+    };
+    let monitor = Monitor::new(vec![stream], MonitorConfig::default(), loader.clone(), None);
+    monitor.poll_once().await;
+
+    let loaded = loader.loaded.lock().unwrap().clone();
+    assert!(loaded.contains(&"https://mock.mock.com/channels/1xx/iframe_missing.m3u8".to_string()), "{:?}", loaded);
+
+    let errors = monitor.get_errors().await;
+    let mut i_frame_errors: Vec<_> = errors
+        .iter()
+        .filter(|e| e.error_type == ErrorType::UnresolvableIFrameUri)
+        .map(|e| (e.details.as_str(), e.status_code))
+        .collect();
+    i_frame_errors.sort();
+    assert_eq!(i_frame_errors.len(), 2, "{:#?}", errors);
+    assert!(i_frame_errors[0].0.contains("iframe_missing.m3u8' could not be fetched"), "{:?}", i_frame_errors);
+    assert_eq!(i_frame_errors[0].1, Some(404));
+    assert!(i_frame_errors[1].0.contains("iframe_regular.m3u8' does not point at an I-frame playlist"), "{:?}", i_frame_errors);
+    assert!(
+        !errors.iter().any(|e| e.error_type == ErrorType::ManifestRetrieval),
+        "{:#?}",
+        errors
+    );
+}
+
+/// `count` 10s segments from `mseq` with PDTs from 2024-01-01T00:00:00Z,
+/// each preceded by the DATERANGE attributes given for its media sequence.
+fn daterange_playlist(mseq: u64, count: u64, ranges: &[(u64, &str)]) -> String {
+    let base = chrono::DateTime::parse_from_rfc3339("2024-01-01T00:00:00Z").unwrap();
+    let mut out = format!(
+        "#EXTM3U\n#EXT-X-VERSION:3\n#EXT-X-TARGETDURATION:10\n#EXT-X-MEDIA-SEQUENCE:{}\n",
+        mseq
+    );
+    for n in mseq..mseq + count {
+        let pdt = base + chrono::Duration::seconds(10 * n as i64);
+        writeln!(out, "#EXT-X-PROGRAM-DATE-TIME:{}", pdt.to_rfc3339()).unwrap();
+        for (_, attrs) in ranges.iter().filter(|(at, _)| *at == n) {
+            writeln!(out, "#EXT-X-DATERANGE:{}", attrs).unwrap();
+        }
+        writeln!(out, "#EXTINF:10.000,\ns{}.ts", n).unwrap();
+    }
+    out
+}
+
+#[tokio::test]
+async fn test_daterange_lifecycle_across_polls() {
+    let ad1 = "ID=\"ad1\",CLASS=\"com.example.ad\",START-DATE=\"2024-01-01T00:00:10Z\",PLANNED-DURATION=20";
+    let ad1_ended = "ID=\"ad1\",CLASS=\"com.example.ad\",START-DATE=\"2024-01-01T00:00:10Z\",PLANNED-DURATION=25,DURATION=20";
+    let ch1 = "ID=\"ch1\",CLASS=\"com.example.chapter\",START-DATE=\"2024-01-01T00:00:20Z\",END-ON-NEXT=YES";
+    let ch2 = "ID=\"ch2\",CLASS=\"com.example.chapter\",START-DATE=\"2024-01-01T00:00:40Z\",END-ON-NEXT=YES";
+    let ad2 = "ID=\"ad2\",CLASS=\"com.example.ad\",START-DATE=\"2024-01-01T00:00:30Z\",DURATION=10";
+    let steps = vec![
+        daterange_playlist(0, 3, &[(1, ad1), (2, ch1)]),
+        daterange_playlist(1, 4, &[(1, ad1_ended), (3, ad2), (4, ch2)]),
+        // ad1 is published again after leaving the window.
+        daterange_playlist(3, 3, &[(5, ad1_ended)]),
+    ];
+
+    let step = Arc::new(AtomicUsize::new(0));
+    let loader = Arc::new(SequenceLoader {
+        step: Arc::clone(&step),
+        responses: HashMap::from([(LEVEL0_URL.to_string(), steps)]),
+    });
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: LEVEL0_URL.to_string(),
+    };
+    let monitor = Monitor::new(vec![stream], MonitorConfig::default().with_stale_limit(8000), loader, None);
+    for poll in 0..3 {
+        step.store(poll, Ordering::SeqCst);
+        monitor.poll_once().await;
+    }
+
+    let errors = monitor.get_errors().await;
+    let mutations: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::DateRangeMutation).collect();
+    assert_eq!(mutations.len(), 1, "{:#?}", errors);
+    assert!(
+        mutations[0].details.starts_with("EXT-X-DATERANGE 'ad1' changed PLANNED-DURATION between polls from 20.000 to 25.000"),
+        "{}",
+        mutations[0].details
+    );
+
+    let events = monitor.get_events().await;
+    let mut opened: Vec<_> = events
+        .iter()
+        .filter(|e| e.kind == EventKind::DateRangeOpened)
+        .map(|e| e.details.split('\'').nth(1).unwrap())
+        .collect();
+    opened.sort();
+    assert_eq!(opened, ["ad2", "ch2"], "ranges of the first playlist open silently and ad1 only once");
+
+    let mut closed: Vec<_> = events
+        .iter()
+        .filter(|e| e.kind == EventKind::DateRangeClosed)
+        .map(|e| e.details.as_str())
+        .collect();
+    closed.sort();
+    assert_eq!(
+        closed,
+        [
+            "DATERANGE 'ad1' closed at 2024-01-01T00:00:30+00:00 after 20.0s",
+            "DATERANGE 'ad2' closed at 2024-01-01T00:00:40+00:00 after 10.0s",
+            "DATERANGE 'ch1' closed at 2024-01-01T00:00:40+00:00 after 20.0s",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_master_changes_are_reported_and_removed_variants_pruned() {
+    let single = MASTER_PLAYLIST.replace(
+        "#EXT-X-STREAM-INF:BANDWIDTH=2424000,RESOLUTION=1280x720,FRAME-RATE=30.000\nlevel_1.m3u8\n",
+        "",
+    );
+    assert_ne!(single, MASTER_PLAYLIST);
+    let level = mp(0, None, &[s("a.ts"), s("b.ts")]);
+
+    let step = Arc::new(AtomicUsize::new(0));
+    let loader = Arc::new(SequenceLoader {
+        step: Arc::clone(&step),
+        responses: HashMap::from([
+            (MASTER_URL.to_string(), vec![MASTER_PLAYLIST.to_string(), MASTER_PLAYLIST.to_string(), single, MASTER_PLAYLIST.to_string()]),
+            (LEVEL0_URL.to_string(), vec![level.clone()]),
+            (LEVEL1_URL.to_string(), vec![level]),
+        ]),
+    });
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: MASTER_URL.to_string(),
+    };
+    let monitor = Monitor::new(vec![stream], MonitorConfig::default().with_stale_limit(8000), loader, None);
+
+    let variant_keys = |statuses: Vec<hls_monitor::StreamStatus>| {
+        let mut keys: Vec<String> = statuses[0].variants.iter().map(|v| v.variant_key.clone()).collect();
+        keys.sort();
+        keys
+    };
+    for poll in 0..3 {
+        step.store(poll, Ordering::SeqCst);
+        monitor.poll_once().await;
+    }
+    assert_eq!(variant_keys(monitor.get_stream_status().await), ["1212000_level_0"]);
+    assert_eq!(count_events(&monitor.get_events().await, EventKind::MasterChanged), 1, "unchanged masters are quiet");
+
+    step.store(3, Ordering::SeqCst);
+    monitor.poll_once().await;
+    assert_eq!(variant_keys(monitor.get_stream_status().await), ["1212000_level_0", "2424000_level_1"]);
+
+    let events = monitor.get_events().await;
+    assert_eq!(count_events(&events, EventKind::MasterChanged), 2);
+    let removed: Vec<_> = events.iter().filter(|e| e.kind == EventKind::VariantRemoved).collect();
+    assert_eq!(removed.len(), 1);
+    assert_eq!(removed[0].variant_key, "2424000_level_1");
+    assert!(removed[0].details.starts_with("Variant '2424000_level_1' removed: level_1.m3u8 BANDWIDTH=2424000"), "{}", removed[0].details);
+    assert_eq!(count_events(&events, EventKind::VariantAdded), 1);
+    assert!(events.iter().any(|e| e.kind == EventKind::MasterChanged && e.details == "Master playlist changed: removed '2424000_level_1'"));
+
+    let errors = monitor.get_errors().await;
+    let flapping: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::LadderFlapping).collect();
+    assert_eq!(flapping.len(), 1, "{:#?}", errors);
+    assert_eq!(flapping[0].details, "Variant '2424000_level_1' reappeared in the master playlist 1 poll(s) after it was removed");
+}
+
+#[tokio::test]
+async fn test_variant_key_survives_siblings_and_collisions_appearing() {
+    let before = "\
+#EXTM3U
+#EXT-X-VERSION:3
+#EXT-X-STREAM-INF:BANDWIDTH=2424000,CODECS=\"avc1.64001f,mp4a.40.2\"
+level_0.m3u8?token=a
+";
+    let after = "\
+#EXTM3U
+#EXT-X-VERSION:3
+#EXT-X-STREAM-INF:BANDWIDTH=2424000,CODECS=\"avc1.64001f,mp4a.40.2\"
+level_0.m3u8?token=b
+#EXT-X-STREAM-INF:BANDWIDTH=2424000,CODECS=\"hvc1.1.6.L93.B0,mp4a.40.2\"
+level_1.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=2424000,CODECS=\"avc1.64001f,mp4a.40.2\"
+level_0.m3u8?token=c
+";
+    let level = mp(0, None, &[s("a.ts"), s("b.ts")]);
+    let step = Arc::new(AtomicUsize::new(0));
+    let loader = Arc::new(SequenceLoader {
+        step: Arc::clone(&step),
+        responses: HashMap::from([
+            (MASTER_URL.to_string(), vec![before.to_string(), after.to_string()]),
+            (format!("{}?token=a", LEVEL0_URL), vec![level.clone()]),
+            (format!("{}?token=b", LEVEL0_URL), vec![level.clone()]),
+            (format!("{}?token=c", LEVEL0_URL), vec![level.clone()]),
+            (LEVEL1_URL.to_string(), vec![level]),
+        ]),
+    });
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: MASTER_URL.to_string(),
+    };
+    let monitor = Monitor::new(vec![stream], MonitorConfig::default().with_stale_limit(8000), loader, None);
+
+    for poll in 0..2 {
+        step.store(poll, Ordering::SeqCst);
+        monitor.poll_once().await;
+    }
+
+    let mut keys: Vec<String> = monitor.get_stream_status().await[0]
+        .variants
+        .iter()
+        .map(|v| v.variant_key.clone())
+        .collect();
+    keys.sort();
+    assert_eq!(keys, ["2424000_avc1+mp4a_level_0", "2424000_avc1+mp4a_level_0~2", "2424000_hvc1+mp4a_level_1"]);
+
+    let events = monitor.get_events().await;
+    assert_eq!(count_events(&events, EventKind::VariantRemoved), 0, "{:#?}", events);
+    let added: Vec<_> = events.iter().filter(|e| e.kind == EventKind::VariantAdded).map(|e| e.variant_key.as_str()).collect();
+    assert!(added.contains(&"2424000_hvc1+mp4a_level_1"), "{:?}", added);
+    assert!(!added.contains(&"2424000_avc1+mp4a_level_0"), "{:?}", added);
+
+    let errors = monitor.get_errors().await;
+    assert!(!errors.iter().any(|e| e.error_type == ErrorType::LadderFlapping), "{:#?}", errors);
+    let collisions: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::VariantKeyCollision).collect();
+    assert!(!collisions.is_empty(), "{:#?}", errors);
+    assert!(collisions.iter().all(|e| e.variant == "2424000_avc1+mp4a_level_0"));
+}
+
+#[tokio::test]
+async fn test_variants_with_equal_bandwidth_keep_separate_state() {
+    let master = "\
+#EXTM3U
+#EXT-X-VERSION:3
+#EXT-X-STREAM-INF:BANDWIDTH=2424000,RESOLUTION=1280x720,CODECS=\"avc1.64001f,mp4a.40.2\"
+level_0.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=2424000,RESOLUTION=1280x720,CODECS=\"hvc1.1.6.L93.B0,mp4a.40.2\"
+level_1.m3u8
+#EXT-X-STREAM-INF:BANDWIDTH=2424000,RESOLUTION=1280x720,CODECS=\"avc1.64001f,mp4a.40.2\"
+level_0.m3u8
+";
+    let step = Arc::new(AtomicUsize::new(0));
+    let loader = Arc::new(SequenceLoader {
+        step: Arc::clone(&step),
+        responses: HashMap::from([
+            (MASTER_URL.to_string(), vec![master.to_string()]),
+            (
+                LEVEL0_URL.to_string(),
+                vec![
+                    mp(0, None, &[s("avc_0.ts"), s("avc_1.ts"), s("avc_2.ts")]),
+                    mp(1, None, &[s("avc_1.ts"), s("avc_2.ts"), s("avc_3.ts")]),
+                ],
+            ),
+            (
+                LEVEL1_URL.to_string(),
+                vec![
+                    mp(0, None, &[s("hevc_0.ts"), s("hevc_1.ts"), s("hevc_2.ts")]),
+                    mp(1, None, &[s("hevc_1.ts"), s("hevc_2.ts"), s("hevc_3.ts")]),
+                ],
+            ),
+        ]),
+    });
+    let stream = StreamItem {
+        id: "stream_1".to_string(),
+        url: MASTER_URL.to_string(),
+    };
+    let monitor = Monitor::new(vec![stream], MonitorConfig::default().with_stale_limit(8000), loader, None);
+
+    for poll in 0..2 {
+        step.store(poll, Ordering::SeqCst);
+        monitor.poll_once().await;
+    }
+
+    let mut keys: Vec<String> = monitor.get_stream_status().await[0]
+        .variants
+        .iter()
+        .map(|v| v.variant_key.clone())
+        .collect();
+    keys.sort();
+    assert_eq!(keys, ["2424000_avc1+mp4a_level_0", "2424000_avc1+mp4a_level_0~2", "2424000_hvc1+mp4a_level_1"]);
+
+    let errors = monitor.get_errors().await;
+    let collisions: Vec<_> = errors.iter().filter(|e| e.error_type == ErrorType::VariantKeyCollision).collect();
+    assert!(!collisions.is_empty(), "{:#?}", errors);
+    assert!(collisions.iter().all(|e| e.variant == "2424000_avc1+mp4a_level_0"));
+    assert!(
+        errors.iter().all(|e| e.error_type == ErrorType::VariantKeyCollision),
+        "AVC and HEVC playlists must not be compared with each other: {:#?}",
+        errors
+    );
+}